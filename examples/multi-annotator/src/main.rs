@@ -40,6 +40,9 @@ struct Cli {
     /// Turns on reporting of I/L coverage by satellite ions, returns a list with a 0 (not covered) or 1 (covered) for each I or L in the peptide
     #[arg(long)]
     report_IL_satellite_coverage: bool,
+    /// Use the fragment charge ranges from the model instead of estimating them for each peptide from the precursor charge, peptide length, and number of basic residues
+    #[arg(long)]
+    model_charge_ranges: bool,
     /// To turn off loading the custom modifications database from the Annotator (if installed)
     #[arg(long)]
     no_custom_mods: bool,
@@ -105,11 +108,12 @@ fn main() {
                 .unwrap();
                 let selected_model = line
                     .index_column("fragmentation")
-                    .map_or_else(|_| model.clone(), |(text, _)| select_model(text, &model));
+                    .map_or_else(|_| model.clone(), |(text, _)| select_model(text, &model))
+                    .mobile_proton_charges(!args.model_charge_ranges);
                 if let Some(spectrum) = file.iter().find(|s| s.raw_scan_number == Some(scan_number))
                 {
-                    let fragments =
-                        peptide.generate_theoretical_fragments(Charge::new::<e>(z), &model);
+                    let fragments = peptide
+                        .generate_theoretical_fragments(Charge::new::<e>(z), &selected_model);
                     let annotated = spectrum.annotate(
                         peptide,
                         &fragments,
@@ -234,7 +238,7 @@ fn main() {
                         };
                         row.insert(format!("ion_{ion}"), format!("{}", recovered.fraction(),));
                     }
-                    Some(row)
+                    Some((line.line_index(), row))
                 } else {
                     eprintln!("Could not find scan number {scan_number} for file {file_name}");
                     None
@@ -250,5 +254,7 @@ fn main() {
         );
     }
 
-    rustyms::csv::write_csv(out_file, out_data).unwrap();
+    // Restore the order of the input file, the files are processed in arbitrary order
+    out_data.sort_unstable_by_key(|(index, _)| *index);
+    rustyms::csv::write_csv(out_file, out_data.into_iter().map(|(_, row)| row)).unwrap();
 }
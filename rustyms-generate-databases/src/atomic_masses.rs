@@ -17,7 +17,7 @@ pub fn build_atomic_masses(out_dir: &Path) {
     .unwrap();
     for line in table {
         let line = line.unwrap();
-        let (nuclide, mass, _uncertainty, year) = (&line[0], &line[1], &line[2], &line[3]);
+        let (nuclide, mass, uncertainty, year) = (&line[0], &line[1], &line[2], &line[3]);
         if nuclide.starts_with("AME")
             || nuclide.is_empty()
             || nuclide == "nuclide"
@@ -39,7 +39,11 @@ pub fn build_atomic_masses(out_dir: &Path) {
             .parse::<f64>()
             .map_err(|e| format!("{}@{}", e, mass))
             .unwrap();
-        atomic_masses[element as usize - 1].push((isotope, mass))
+        let uncertainty = uncertainty
+            .parse::<f64>()
+            .map_err(|e| format!("{}@{}", e, uncertainty))
+            .unwrap();
+        atomic_masses[element as usize - 1].push((isotope, mass, uncertainty))
     }
 
     let mut last_element = 0;
@@ -78,7 +82,7 @@ pub fn build_atomic_masses(out_dir: &Path) {
 
         let isotope = isotope.parse::<usize>().unwrap();
 
-        isotopic_abundances[element - 1].push((isotope, get_ciaaw_number(&abundance).unwrap()))
+        isotopic_abundances[element - 1].push((isotope, get_ciaaw_number(&abundance).unwrap().0))
     }
 
     let table = parse_csv(
@@ -116,7 +120,7 @@ pub fn build_atomic_masses(out_dir: &Path) {
         .map(|((isotopic_abundance, isotopic_mass), atomic_weight)| {
             let isotopes = isotopic_mass
                 .iter()
-                .map(|(i, m)| {
+                .map(|(i, m, _)| {
                     (
                         *i,
                         *m,
@@ -138,6 +142,7 @@ pub fn build_atomic_masses(out_dir: &Path) {
                     .0,
                 atomic_weight,
                 isotopes,
+                isotopic_mass,
             )
         });
 
@@ -146,13 +151,15 @@ pub fn build_atomic_masses(out_dir: &Path) {
     let mut file = std::fs::File::create(dest_path).unwrap();
     let elements = combined_data
         .into_iter()
-        .map(|(m, a, i)| {
+        .map(|(m, a, i, u)| {
             (
                 m.map(da),
-                a.map(da),
+                a.map(|(a, _)| da(a)),
                 i.into_iter()
                     .map(|(n, m, i)| (n as u16, da(m), i))
                     .collect(),
+                a.map(|(_, u)| da(u)),
+                u.into_iter().map(|(n, _, u)| (n as u16, da(u))).collect(),
             )
         })
         .collect();
@@ -160,23 +167,28 @@ pub fn build_atomic_masses(out_dir: &Path) {
         .unwrap();
 }
 
-fn get_ciaaw_number(text: &str) -> Result<f64, String> {
+/// Parse a CIAAW number, returning the value and its uncertainty. Ranges are given as the midpoint
+/// with half the range as uncertainty, concise notation (`4.002602(2)`) has the uncertainty in
+/// the last digits, and plain numbers are assumed to be precise to half the last digit.
+fn get_ciaaw_number(text: &str) -> Result<(f64, f64), String> {
     let parse = |t: &str| {
         t.parse::<f64>()
             .map_err(|_| format!("Not a valid number: {t}"))
     };
+    let last_digit =
+        |t: &str| 10.0_f64.powi(-(t.split_once('.').map_or(0, |(_, d)| d.len()) as i32));
     if text.starts_with('[') {
         let (low, high) = text[1..text.len() - 1]
             .split_once(',')
             .ok_or(format!("Not a valid range: {text}"))?;
-        Ok((parse(low)? + parse(high)?) / 2.0)
-    } else if text.ends_with(')') {
-        Ok(parse(
-            text.split_once('(')
-                .ok_or(format!("Not valid error indication: {text}"))?
-                .0,
-        )?)
+        let (low, high) = (parse(low)?, parse(high)?);
+        Ok(((low + high) / 2.0, (high - low) / 2.0))
+    } else if let Some(text) = text.strip_suffix(')') {
+        let (value, uncertainty) = text
+            .split_once('(')
+            .ok_or(format!("Not valid error indication: {text}"))?;
+        Ok((parse(value)?, parse(uncertainty)? * last_digit(value)))
     } else {
-        Ok(parse(text)?)
+        Ok((parse(text)?, last_digit(text) / 2.0))
     }
 }
@@ -20,6 +20,8 @@ mod multi;
 mod sequence_position;
 
 mod atomic_masses;
+#[path = "../../rustyms/src/shared/compression.rs"]
+mod compression;
 #[path = "../../rustyms/src/shared/csv.rs"]
 mod csv;
 #[path = "../../rustyms/src/error/mod.rs"]
@@ -3,10 +3,11 @@
 use std::fmt::Debug;
 use std::num::NonZeroU16;
 
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
 use ordered_float::OrderedFloat;
 use pyo3::{exceptions::PyValueError, prelude::*, types::PyType};
 
-use rustyms::{AnnotatableSpectrum, Chemical, Linked, MultiChemical};
+use rustyms::{spectrum::PeakSpectrum, AnnotatableSpectrum, Chemical, Linked, MultiChemical};
 
 /// Mass mode enum.
 #[pyclass(eq, eq_int)]
@@ -17,6 +18,15 @@ enum MassMode {
     MostAbundant,
 }
 
+/// Helper function to match a [`MassMode`] to a rustyms MassMode.
+fn match_mass_mode(mode: &MassMode) -> rustyms::MassMode {
+    match mode {
+        MassMode::Monoisotopic => rustyms::MassMode::Monoisotopic,
+        MassMode::Average => rustyms::MassMode::Average,
+        MassMode::MostAbundant => rustyms::MassMode::MostAbundant,
+    }
+}
+
 /// Element.
 ///
 /// A chemical element, with its isotopes and their properties.
@@ -564,6 +574,24 @@ impl SimpleModification {
     fn monoisotopic_mass(&self) -> f64 {
         self.0.formula().monoisotopic_mass().value
     }
+
+    /// The glycan structure of this modification, if it is a glycan with a known structure.
+    ///
+    /// Returns
+    /// -------
+    /// GlycanStructure | None
+    ///
+    #[getter]
+    fn glycan_structure(&self) -> Option<GlycanStructure> {
+        match self.0.as_ref() {
+            rustyms::modification::SimpleModificationInner::GlycanStructure(structure)
+            | rustyms::modification::SimpleModificationInner::Gno {
+                composition: rustyms::modification::GnoComposition::Topology(structure),
+                ..
+            } => Some(GlycanStructure(structure.clone())),
+            _ => None,
+        }
+    }
 }
 
 /// Amino acid modification.
@@ -702,6 +730,418 @@ impl Fragment {
 #[pyclass]
 pub struct FragmentType(rustyms::fragment::FragmentType);
 
+#[pymethods]
+impl FragmentType {
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("FragmentType('{}')", self.0)
+    }
+
+    /// The position on the glycan for B ions and glycan diagnostic ions.
+    ///
+    /// Returns
+    /// -------
+    /// GlycanPosition | None
+    ///
+    #[getter]
+    fn glycan_position(&self) -> Option<GlycanPosition> {
+        match &self.0 {
+            rustyms::fragment::FragmentType::B(position)
+            | rustyms::fragment::FragmentType::Diagnostic(
+                rustyms::fragment::DiagnosticPosition::Glycan(position, _),
+            ) => Some(GlycanPosition(position.clone())),
+            _ => None,
+        }
+    }
+
+    /// All glycan bonds that are broken to form this ion, with the kind of breakage ('Y', 'B',
+    /// or 'End' for a branch that is not broken). Only defined for Y and internal glycan ions.
+    ///
+    /// Returns
+    /// -------
+    /// list[tuple[str, GlycanPosition]]
+    ///
+    #[getter]
+    fn glycan_breakages(&self) -> Vec<(String, GlycanPosition)> {
+        match &self.0 {
+            rustyms::fragment::FragmentType::Y(positions) => positions
+                .iter()
+                .map(|p| ("Y".to_string(), GlycanPosition(p.clone())))
+                .collect(),
+            rustyms::fragment::FragmentType::Oxonium(breakages) => breakages
+                .iter()
+                .map(|b| (b.label().to_string(), GlycanPosition(b.position().clone())))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The amino acid and sequence index (0-based) where the glycan of this ion is attached, if
+    /// this is a glycan ion and the attachment is known.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[AminoAcid, int] | None
+    ///
+    #[getter]
+    fn glycan_attachment(&self) -> Option<(AminoAcid, usize)> {
+        match &self.0 {
+            rustyms::fragment::FragmentType::B(position)
+            | rustyms::fragment::FragmentType::Diagnostic(
+                rustyms::fragment::DiagnosticPosition::Glycan(position, _),
+            ) => position.attachment,
+            rustyms::fragment::FragmentType::Y(positions) => {
+                positions.first().and_then(|p| p.attachment)
+            }
+            rustyms::fragment::FragmentType::Oxonium(breakages) => {
+                breakages.first().and_then(|b| b.position().attachment)
+            }
+            rustyms::fragment::FragmentType::OxoniumComposition(_, attachment)
+            | rustyms::fragment::FragmentType::YComposition(_, attachment)
+            | rustyms::fragment::FragmentType::Diagnostic(
+                rustyms::fragment::DiagnosticPosition::GlycanCompositional(_, attachment),
+            ) => *attachment,
+            _ => None,
+        }
+        .map(|(aa, index)| (AminoAcid(aa), index))
+    }
+
+    /// The part of the glycan structure that is retained in this ion, for B, Y, internal glycan,
+    /// and glycan diagnostic ions. Use this with `PositionedGlycanStructure.select` to get the
+    /// monosaccharides in this ion.
+    ///
+    /// Returns
+    /// -------
+    /// GlycanSelection | None
+    ///
+    #[getter]
+    fn glycan_selection(&self) -> Option<GlycanSelection> {
+        match &self.0 {
+            rustyms::fragment::FragmentType::B(position) => Some(GlycanSelection(
+                GlycanSelectionInner::Subtree(Some(position.clone()), Vec::new()),
+            )),
+            rustyms::fragment::FragmentType::Y(positions) => Some(GlycanSelection(
+                GlycanSelectionInner::Subtree(None, positions.clone()),
+            )),
+            rustyms::fragment::FragmentType::Oxonium(breakages) => {
+                Some(GlycanSelection(GlycanSelectionInner::Subtree(
+                    breakages.iter().find_map(|b| match b {
+                        rustyms::fragment::GlycanBreakPos::B(p) => Some(p.clone()),
+                        _ => None,
+                    }),
+                    breakages
+                        .iter()
+                        .filter_map(|b| match b {
+                            rustyms::fragment::GlycanBreakPos::Y(p) => Some(p.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                )))
+            }
+            rustyms::fragment::FragmentType::Diagnostic(
+                rustyms::fragment::DiagnosticPosition::Glycan(position, _),
+            ) => Some(GlycanSelection(GlycanSelectionInner::SingleSugar(
+                position.clone(),
+            ))),
+            _ => None,
+        }
+    }
+}
+
+/// A position on a glycan structure.
+#[pyclass(eq)]
+#[derive(Clone, PartialEq)]
+pub struct GlycanPosition(rustyms::fragment::GlycanPosition);
+
+#[pymethods]
+impl GlycanPosition {
+    fn __str__(&self) -> String {
+        self.0.label()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GlycanPosition(inner_depth={}, series_number={}, branch={:?}, attachment='{}')",
+            self.0.inner_depth,
+            self.0.series_number,
+            self.0.branch,
+            self.0.attachment()
+        )
+    }
+
+    /// The depth starting at the amino acid.
+    ///
+    /// Returns
+    /// -------
+    /// int
+    ///
+    #[getter]
+    fn inner_depth(&self) -> usize {
+        self.0.inner_depth
+    }
+
+    /// The series number (from the ion series terminal).
+    ///
+    /// Returns
+    /// -------
+    /// int
+    ///
+    #[getter]
+    fn series_number(&self) -> usize {
+        self.0.series_number
+    }
+
+    /// The branch indices, one for each branching point on the path from the root.
+    ///
+    /// Returns
+    /// -------
+    /// list[int]
+    ///
+    #[getter]
+    fn branch(&self) -> Vec<usize> {
+        self.0.branch.clone()
+    }
+
+    /// The branch names, eg `α'`.
+    ///
+    /// Returns
+    /// -------
+    /// str
+    ///
+    #[getter]
+    fn branch_names(&self) -> String {
+        self.0.branch_names()
+    }
+
+    /// The amino acid and sequence index (0-based) where the glycan is attached, if known.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[AminoAcid, int] | None
+    ///
+    #[getter]
+    fn attachment(&self) -> Option<(AminoAcid, usize)> {
+        self.0.attachment.map(|(aa, index)| (AminoAcid(aa), index))
+    }
+}
+
+/// A part of a glycan structure, see `PositionedGlycanStructure.select`.
+#[pyclass]
+#[derive(Clone)]
+pub struct GlycanSelection(GlycanSelectionInner);
+
+/// The owned version of [`rustyms::glycan::GlycanSelection`].
+#[derive(Clone)]
+enum GlycanSelectionInner {
+    /// The subtree rooted at the given position (or the whole structure if None) without the
+    /// subtrees rooted at any of the given breakages.
+    Subtree(
+        Option<rustyms::fragment::GlycanPosition>,
+        Vec<rustyms::fragment::GlycanPosition>,
+    ),
+    /// Only the monosaccharide at the given position.
+    SingleSugar(rustyms::fragment::GlycanPosition),
+}
+
+impl GlycanSelection {
+    /// Get the selection as used in rustyms.
+    fn selection(&self) -> rustyms::glycan::GlycanSelection<'_> {
+        match &self.0 {
+            GlycanSelectionInner::Subtree(root, breakages) => {
+                rustyms::glycan::GlycanSelection::Subtree(root.as_ref(), breakages)
+            }
+            GlycanSelectionInner::SingleSugar(position) => {
+                rustyms::glycan::GlycanSelection::SingleSugar(position)
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl GlycanSelection {
+    /// Select the subtree rooted at the given position (or the whole structure if None) without
+    /// the subtrees rooted at any of the given breakages.
+    #[staticmethod]
+    #[pyo3(signature = (root=None, breakages=Vec::new()))]
+    fn subtree(root: Option<GlycanPosition>, breakages: Vec<GlycanPosition>) -> Self {
+        Self(GlycanSelectionInner::Subtree(
+            root.map(|r| r.0),
+            breakages.into_iter().map(|b| b.0).collect(),
+        ))
+    }
+
+    /// Select only the monosaccharide at the given position.
+    #[staticmethod]
+    fn single_sugar(position: GlycanPosition) -> Self {
+        Self(GlycanSelectionInner::SingleSugar(position.0))
+    }
+}
+
+/// A glycan structure.
+///
+/// Parameters
+/// ----------
+/// structure : str
+///     The glycan structure in the short IUPAC like notation as used in ProForma, eg `hexnac(hex(hex),fuc)`.
+///
+#[pyclass]
+#[derive(Clone)]
+pub struct GlycanStructure(rustyms::glycan::GlycanStructure);
+
+#[pymethods]
+impl GlycanStructure {
+    #[new]
+    fn new(structure: &str) -> Result<Self, CustomError> {
+        structure
+            .parse::<rustyms::glycan::GlycanStructure>()
+            .map(GlycanStructure)
+            .map_err(CustomError)
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("GlycanStructure('{}')", self.0)
+    }
+
+    /// The composition of this glycan.
+    ///
+    /// Returns
+    /// -------
+    /// list[tuple[str, int]]
+    ///
+    fn composition(&self) -> Vec<(String, isize)> {
+        self.0
+            .composition()
+            .into_iter()
+            .map(|(sugar, amount)| (sugar.to_string(), amount))
+            .collect()
+    }
+
+    /// Molecular formula of this glycan.
+    ///
+    /// Returns
+    /// -------
+    /// MolecularFormula
+    ///
+    fn formula(&self) -> MolecularFormula {
+        MolecularFormula(self.0.formula())
+    }
+
+    /// Determine the positions of all monosaccharides in this glycan, as used in the glycan fragments.
+    ///
+    /// Returns
+    /// -------
+    /// PositionedGlycanStructure
+    ///
+    fn positioned(&self) -> PositionedGlycanStructure {
+        PositionedGlycanStructure(self.0.clone().determine_positions())
+    }
+}
+
+/// A glycan structure with the positions of all monosaccharides determined, or a subtree of such
+/// a structure.
+#[pyclass]
+#[derive(Clone)]
+pub struct PositionedGlycanStructure(rustyms::glycan::PositionedGlycanStructure);
+
+#[pymethods]
+impl PositionedGlycanStructure {
+    fn __repr__(&self) -> String {
+        format!(
+            "PositionedGlycanStructure(sugar='{}', position='{}', branches={})",
+            self.0.sugar(),
+            self.0.position(None).label(),
+            self.0.branches().len()
+        )
+    }
+
+    /// The monosaccharide at the root of this (sub)tree.
+    ///
+    /// Returns
+    /// -------
+    /// str
+    ///
+    #[getter]
+    fn sugar(&self) -> String {
+        self.0.sugar().to_string()
+    }
+
+    /// The branches from this monosaccharide, sorted on decreasing mass.
+    ///
+    /// Returns
+    /// -------
+    /// list[PositionedGlycanStructure]
+    ///
+    #[getter]
+    fn branches(&self) -> Vec<PositionedGlycanStructure> {
+        self.0
+            .branches()
+            .iter()
+            .map(|b| PositionedGlycanStructure(b.clone()))
+            .collect()
+    }
+
+    /// The position of the monosaccharide at the root of this (sub)tree.
+    ///
+    /// Parameters
+    /// ----------
+    /// attachment : tuple[AminoAcid, int] | None
+    ///     The amino acid and sequence index (0-based) where the glycan is attached.
+    ///
+    /// Returns
+    /// -------
+    /// GlycanPosition
+    ///
+    #[pyo3(signature = (attachment=None))]
+    fn position(&self, attachment: Option<(PyRef<AminoAcid>, usize)>) -> GlycanPosition {
+        GlycanPosition(self.0.position(attachment.map(|(aa, index)| (aa.0, index))))
+    }
+
+    /// Get all monosaccharides in the selected part of this structure, in depth first order.
+    ///
+    /// Parameters
+    /// ----------
+    /// selection : GlycanSelection
+    ///     The part of the structure to select, eg `FragmentType.glycan_selection`.
+    ///
+    /// Returns
+    /// -------
+    /// list[tuple[GlycanPosition, str]]
+    ///
+    fn select(&self, selection: &GlycanSelection) -> Vec<(GlycanPosition, String)> {
+        self.0
+            .select(selection.selection())
+            .into_iter()
+            .map(|s| (GlycanPosition(s.position(None)), s.sugar().to_string()))
+            .collect()
+    }
+
+    /// The composition of the selected part of this structure.
+    ///
+    /// Parameters
+    /// ----------
+    /// selection : GlycanSelection
+    ///     The part of the structure to select, eg `FragmentType.glycan_selection`.
+    ///
+    /// Returns
+    /// -------
+    /// list[tuple[str, int]]
+    ///
+    fn composition(&self, selection: &GlycanSelection) -> Vec<(String, isize)> {
+        self.0
+            .composition(selection.selection())
+            .into_iter()
+            .map(|(sugar, amount)| (sugar.to_string(), amount))
+            .collect()
+    }
+}
+
 /// One block in a sequence meaning an amino acid and its accompanying modifications.
 #[pyclass]
 pub struct SequenceElement(rustyms::SequenceElement<Linked>);
@@ -1315,6 +1755,23 @@ impl std::fmt::Display for AnnotatedPeak {
     }
 }
 
+/// An array of floats, given as either a numpy array or a list.
+#[derive(FromPyObject)]
+enum FloatArray<'py> {
+    Numpy(PyReadonlyArray1<'py, f64>),
+    List(Vec<f64>),
+}
+
+impl FloatArray<'_> {
+    /// Get the values as a vector.
+    fn to_vec(&self) -> Vec<f64> {
+        match self {
+            Self::Numpy(array) => array.as_array().to_vec(),
+            Self::List(list) => list.clone(),
+        }
+    }
+}
+
 /// A raw spectrum (meaning not annotated yet)
 ///
 /// Parameters
@@ -1329,9 +1786,9 @@ impl std::fmt::Display for AnnotatedPeak {
 ///     The found precursor charge.
 /// precursor_mass : float
 ///     The found precursor mass.
-/// mz_array : list[float]
+/// mz_array : numpy.ndarray | list[float]
 ///     The m/z values of the peaks.
-/// intensity_array : list[float]
+/// intensity_array : numpy.ndarray | list[float]
 ///     The intensities of the peaks.
 ///
 /// Returns
@@ -1357,26 +1814,41 @@ impl RawSpectrum {
     ///     The found precursor charge.
     /// precursor_mass : float
     ///     The found precursor mass.
-    /// mz_array : list[float]
+    /// mz_array : numpy.ndarray | list[float]
     ///     The m/z values of the peaks.
-    /// intensity_array : list[float]
+    /// intensity_array : numpy.ndarray | list[float]
     ///     The intensities of the peaks.
     ///
     /// Returns
     /// -------
     /// RawSpectrum
     ///
+    /// Raises
+    /// ------
+    /// ValueError
+    ///     If the m/z and intensity arrays do not have the same length.
+    ///
     #[new]
     #[pyo3(signature = (title, num_scans, mz_array, intensity_array, rt=None, precursor_charge=None, precursor_mass=None))]
     fn new(
         title: &str,
         num_scans: u64,
-        mz_array: Vec<f64>,
-        intensity_array: Vec<f64>,
+        mz_array: FloatArray,
+        intensity_array: FloatArray,
         rt: Option<f64>,
         precursor_charge: Option<usize>,
         precursor_mass: Option<f64>,
-    ) -> Self {
+    ) -> PyResult<Self> {
+        let mz_array = mz_array.to_vec();
+        let intensity_array = intensity_array.to_vec();
+        if mz_array.len() != intensity_array.len() {
+            return Err(PyValueError::new_err(format!(
+                "The m/z array ({}) and intensity array ({}) do not have the same length",
+                mz_array.len(),
+                intensity_array.len()
+            )));
+        }
+
         let mut spectrum = rustyms::RawSpectrum::default();
         spectrum.title = title.to_string();
         spectrum.num_scans = num_scans;
@@ -1395,7 +1867,7 @@ impl RawSpectrum {
             .collect::<Vec<_>>();
 
         spectrum.extend(peaks);
-        RawSpectrum(spectrum)
+        Ok(RawSpectrum(spectrum))
     }
 
     fn __repr__(&self) -> String {
@@ -1479,6 +1951,36 @@ impl RawSpectrum {
         self.0.clone().into_iter().map(RawPeak).collect()
     }
 
+    /// The m/z values of the peaks, sorted ascending.
+    ///
+    /// Returns
+    /// -------
+    /// numpy.ndarray
+    ///
+    #[getter]
+    fn mz_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.0
+            .spectrum()
+            .map(|p| p.mz.value)
+            .collect::<Vec<_>>()
+            .into_pyarray(py)
+    }
+
+    /// The intensities of the peaks, in the same order as `mz_array`.
+    ///
+    /// Returns
+    /// -------
+    /// numpy.ndarray
+    ///
+    #[getter]
+    fn intensity_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.0
+            .spectrum()
+            .map(|p| *p.intensity)
+            .collect::<Vec<_>>()
+            .into_pyarray(py)
+    }
+
     /// Annotate this spectrum with the given peptide
     ///
     /// Parameters
@@ -1518,11 +2020,7 @@ impl RawSpectrum {
             peptide.0,
             &fragments,
             &rusty_model,
-            match mode {
-                MassMode::Monoisotopic => rustyms::MassMode::Monoisotopic,
-                MassMode::Average => rustyms::MassMode::Average,
-                MassMode::MostAbundant => rustyms::MassMode::MostAbundant,
-            },
+            match_mass_mode(mode),
         )))
     }
 }
@@ -1613,12 +2111,491 @@ impl AnnotatedSpectrum {
     fn spectrum(&self) -> Vec<AnnotatedPeak> {
         self.0.clone().into_iter().map(AnnotatedPeak).collect()
     }
+
+    /// The experimental m/z values of the peaks, sorted ascending.
+    ///
+    /// Returns
+    /// -------
+    /// numpy.ndarray
+    ///
+    #[getter]
+    fn mz_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.0
+            .spectrum()
+            .map(|p| p.experimental_mz.value)
+            .collect::<Vec<_>>()
+            .into_pyarray(py)
+    }
+
+    /// The intensities of the peaks, in the same order as `mz_array`.
+    ///
+    /// Returns
+    /// -------
+    /// numpy.ndarray
+    ///
+    #[getter]
+    fn intensity_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.0
+            .spectrum()
+            .map(|p| *p.intensity)
+            .collect::<Vec<_>>()
+            .into_pyarray(py)
+    }
+
+    /// All annotations in this spectrum as a flat list of fragments with a parallel array containing
+    /// for each fragment the index of the peak it annotates (an index into `mz_array`). A peak with
+    /// multiple annotations occurs multiple times in the index array, a peak without annotations
+    /// does not occur at all.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[numpy.ndarray, list[Fragment]]
+    ///
+    fn annotation_arrays<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> (Bound<'py, PyArray1<usize>>, Vec<Fragment>) {
+        let (indices, fragments): (Vec<usize>, Vec<Fragment>) = self
+            .0
+            .spectrum()
+            .enumerate()
+            .flat_map(|(index, peak)| {
+                peak.annotation
+                    .iter()
+                    .map(move |fragment| (index, Fragment(fragment.clone())))
+            })
+            .unzip();
+        (indices.into_pyarray(py), fragments)
+    }
+}
+
+/// Helper function to turn a recovered statistic into a (found, total) tuple.
+fn recovered<T: Into<f64>>(recovered: rustyms::spectrum::Recovered<T>) -> (f64, f64) {
+    (recovered.found.into(), recovered.total.into())
+}
+
+/// The score for a single fragment series for a single peptide in an annotated spectrum.
+#[pyclass]
+#[derive(Clone)]
+pub struct Score(rustyms::spectrum::Score);
+
+#[pymethods]
+impl Score {
+    fn __repr__(&self) -> String {
+        format!(
+            "Score(fragments={:?}, peaks={:?}, intensity={:?}, theoretical_positions={}, expected_positions={}, unique_formulas={})",
+            self.fragments(),
+            self.peaks(),
+            self.intensity(),
+            self.theoretical_positions()
+                .map_or("None".to_string(), |v| format!("{v:?}")),
+            self.expected_positions()
+                .map_or("None".to_string(), |v| format!("{v:?}")),
+            self.unique_formulas()
+                .map_or("None".to_string(), |v| format!("{v:?}")),
+        )
+    }
+
+    /// The number of theoretical fragments that could be annotated and the total number of theoretical fragments.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[float, float]
+    ///
+    #[getter]
+    fn fragments(&self) -> (f64, f64) {
+        match self.0 {
+            rustyms::spectrum::Score::Position { fragments, .. }
+            | rustyms::spectrum::Score::UniqueFormulas { fragments, .. } => recovered(fragments),
+        }
+    }
+
+    /// The number of peaks that could be annotated and the total number of peaks.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[float, float]
+    ///
+    #[getter]
+    fn peaks(&self) -> (f64, f64) {
+        match self.0 {
+            rustyms::spectrum::Score::Position { peaks, .. }
+            | rustyms::spectrum::Score::UniqueFormulas { peaks, .. } => recovered(peaks),
+        }
+    }
+
+    /// The intensity that could be annotated and the total intensity.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[float, float]
+    ///
+    #[getter]
+    fn intensity(&self) -> (f64, f64) {
+        match self.0 {
+            rustyms::spectrum::Score::Position { intensity, .. }
+            | rustyms::spectrum::Score::UniqueFormulas { intensity, .. } => recovered(intensity),
+        }
+    }
+
+    /// The number of positions on the peptide with at least one fragment found and the total number
+    /// of positions on the peptide. None if this score is not based on positions.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[float, float] | None
+    ///
+    #[getter]
+    fn theoretical_positions(&self) -> Option<(f64, f64)> {
+        match self.0 {
+            rustyms::spectrum::Score::Position {
+                theoretical_positions,
+                ..
+            } => Some(recovered(theoretical_positions)),
+            rustyms::spectrum::Score::UniqueFormulas { .. } => None,
+        }
+    }
+
+    /// The number of positions on the peptide with at least one fragment found and the total number
+    /// of positions that have theoretical fragments. None if this score is not based on positions.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[float, float] | None
+    ///
+    #[getter]
+    fn expected_positions(&self) -> Option<(f64, f64)> {
+        match self.0 {
+            rustyms::spectrum::Score::Position {
+                expected_positions, ..
+            } => Some(recovered(expected_positions)),
+            rustyms::spectrum::Score::UniqueFormulas { .. } => None,
+        }
+    }
+
+    /// The number of unique formulas found and the total number of unique theoretical formulas.
+    /// None if this score is based on positions.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[float, float] | None
+    ///
+    #[getter]
+    fn unique_formulas(&self) -> Option<(f64, f64)> {
+        match self.0 {
+            rustyms::spectrum::Score::UniqueFormulas {
+                unique_formulas, ..
+            } => Some(recovered(unique_formulas)),
+            rustyms::spectrum::Score::Position { .. } => None,
+        }
+    }
+}
+
+/// The scores for an annotated spectrum, for all peptides combined or for a single peptide.
+#[pyclass]
+#[derive(Clone)]
+pub struct Scores(rustyms::spectrum::Scores);
+
+#[pymethods]
+impl Scores {
+    fn __repr__(&self) -> String {
+        format!(
+            "Scores(score={}, ions=[{}])",
+            self.score().__repr__(),
+            self.ions()
+                .iter()
+                .map(|(ion, score)| format!("('{ion}', {})", score.__repr__()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// The overall score.
+    ///
+    /// Returns
+    /// -------
+    /// Score
+    ///
+    #[getter]
+    fn score(&self) -> Score {
+        Score(self.0.score.clone())
+    }
+
+    /// The scores per ion series, only ion series with theoretical fragments are included.
+    ///
+    /// Returns
+    /// -------
+    /// list[tuple[str, Score]]
+    ///
+    #[getter]
+    fn ions(&self) -> Vec<(String, Score)> {
+        self.0
+            .ions
+            .iter()
+            .map(|(kind, score)| (kind.to_string(), Score(score.clone())))
+            .collect()
+    }
+}
+
+/// Annotate a spectrum with the given compound peptidoform and score the annotation.
+///
+/// Parameters
+/// ----------
+/// spectrum : RawSpectrum
+///     The spectrum to annotate.
+/// peptidoform : CompoundPeptidoform
+///     The compound peptidoform to annotate the spectrum with.
+/// model : FragmentationModel
+///     The model to use for the fragmentation.
+/// mode : MassMode
+///     The mode to use for the mass.
+/// max_charge : int | None
+///     The maximal charge of the fragments, defaults to the precursor charge of the spectrum or 1 if
+///     that is not known.
+/// tolerance_ppm : float | None
+///     The matching tolerance in ppm, defaults to the tolerance of the model.
+///
+/// Returns
+/// -------
+/// tuple[AnnotatedSpectrum, Scores, list[list[Scores]]]
+///     The annotated spectrum, the scores for all peptidoforms combined, and the scores for each
+///     peptidoform, grouped per peptidoform ion in the same order as `CompoundPeptidoform.peptidoforms`.
+///
+/// Raises
+/// ------
+/// ValueError
+///     If the model is not one of the valid models.
+///
+#[pyfunction]
+#[pyo3(signature = (spectrum, peptidoform, model, mode=&MassMode::Monoisotopic, max_charge=None, tolerance_ppm=None))]
+fn annotate(
+    spectrum: &RawSpectrum,
+    peptidoform: CompoundPeptidoform,
+    model: &FragmentationModel,
+    mode: &MassMode,
+    max_charge: Option<usize>,
+    tolerance_ppm: Option<f64>,
+) -> PyResult<(AnnotatedSpectrum, Scores, Vec<Vec<Scores>>)> {
+    let mut rusty_model = match_model(model)?;
+    if let Some(tolerance) = tolerance_ppm {
+        rusty_model = rusty_model.tolerance(rustyms::Tolerance::new_ppm(tolerance));
+    }
+    let mode = match_mass_mode(mode);
+    let fragments = peptidoform.0.generate_theoretical_fragments(
+        max_charge.map_or_else(
+            || {
+                spectrum
+                    .0
+                    .charge
+                    .unwrap_or(rustyms::system::usize::Charge::new::<rustyms::system::e>(1))
+            },
+            rustyms::system::usize::Charge::new::<rustyms::system::e>,
+        ),
+        &rusty_model,
+    );
+    let annotated = spectrum
+        .0
+        .annotate(peptidoform.0, &fragments, &rusty_model, mode);
+    let (combined, individual) = annotated.scores(&fragments, &rusty_model, mode);
+    Ok((
+        AnnotatedSpectrum(annotated),
+        Scores(combined),
+        individual
+            .into_iter()
+            .map(|scores| scores.into_iter().map(Scores).collect())
+            .collect(),
+    ))
+}
+
+/// A substitution matrix given as the name of a built in matrix or as a full custom matrix.
+#[derive(FromPyObject)]
+enum ScoringMatrixArgument {
+    Name(String),
+    Custom(Vec<Vec<i8>>),
+}
+
+/// The scoring parameters for alignments.
+///
+/// Parameters
+/// ----------
+/// matrix : str | list[list[int]]
+///     The substitution matrix, either the name of a built in matrix (BLOSUM45, BLOSUM50,
+///     BLOSUM62, BLOSUM80, BLOSUM90, Identity, PAM30, PAM70, PAM250) or a custom matrix of 25 by
+///     25 scores, indexed by the amino acid.
+/// mismatch : int
+///     The score for a mismatch.
+/// mass_mismatch : int
+///     The score for identical amino acids with a different mass.
+/// mass_base : int
+///     The base score for mass based steps.
+/// rotated : int
+///     The per position score for a rotated step.
+/// isobaric : int
+///     The per position score for an isobaric step.
+/// gap_start : int
+///     The score for starting a gap.
+/// gap_extend : int
+///     The score for extending a gap.
+/// tolerance_ppm : float
+///     The tolerance for mass equality in ppm.
+/// mode : MassMode
+///     The mass mode for the alignment.
+///
+/// Raises
+/// ------
+/// ValueError
+///     If the matrix is not a known matrix name or does not have the correct size.
+///
+#[pyclass]
+#[derive(Clone)]
+pub struct AlignScoring(rustyms::align::RecalibratedScoring);
+
+#[pymethods]
+impl AlignScoring {
+    #[new]
+    #[pyo3(signature = (matrix=ScoringMatrixArgument::Name("BLOSUM62".to_string()), mismatch=-1, mass_mismatch=2, mass_base=1, rotated=3, isobaric=2, gap_start=-4, gap_extend=-1, tolerance_ppm=10.0, mode=&MassMode::Monoisotopic))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        matrix: ScoringMatrixArgument,
+        mismatch: i8,
+        mass_mismatch: i8,
+        mass_base: i8,
+        rotated: i8,
+        isobaric: i8,
+        gap_start: i8,
+        gap_extend: i8,
+        tolerance_ppm: f64,
+        mode: &MassMode,
+    ) -> PyResult<Self> {
+        let matrix = match matrix {
+            ScoringMatrixArgument::Name(name) => name
+                .parse::<rustyms::align::ScoringMatrix>()
+                .map_err(CustomError)?,
+            ScoringMatrixArgument::Custom(rows) => {
+                rustyms::align::ScoringMatrix::custom(&rows).map_err(CustomError)?
+            }
+        };
+        Ok(Self(rustyms::align::RecalibratedScoring {
+            mismatch,
+            mass_mismatch,
+            mass_base,
+            rotated,
+            isobaric,
+            gap_start,
+            gap_extend,
+            matrix,
+            tolerance: rustyms::Tolerance::new_ppm(tolerance_ppm),
+            mass_mode: match_mass_mode(mode),
+            ..Default::default()
+        }))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AlignScoring(matrix={}, mismatch={}, gap_start={}, gap_extend={})",
+            self.matrix(),
+            self.0.mismatch,
+            self.0.gap_start,
+            self.0.gap_extend
+        )
+    }
+
+    /// The name of the substitution matrix, or 'Custom' for a custom matrix.
+    ///
+    /// Returns
+    /// -------
+    /// str
+    ///
+    #[getter]
+    fn matrix(&self) -> String {
+        self.0.matrix.to_string()
+    }
+
+    /// The full substitution matrix, indexed by the amino acid.
+    ///
+    /// Returns
+    /// -------
+    /// list[list[int]]
+    ///
+    #[getter]
+    fn matrix_values(&self) -> Vec<Vec<i8>> {
+        self.0
+            .matrix
+            .matrix()
+            .iter()
+            .map(|row| row.to_vec())
+            .collect()
+    }
+}
+
+/// Align two peptides using mass based alignment.
+///
+/// Parameters
+/// ----------
+/// a : LinearPeptide
+///     The first peptide, this cannot contain cross-links.
+/// b : LinearPeptide
+///     The second peptide, this cannot contain cross-links.
+/// scoring : AlignScoring | None
+///     The scoring parameters, defaults to the default scoring.
+/// align_type : str
+///     The alignment type, one of 'global', 'local', 'global_a', 'global_b', 'either_global', or
+///     the symbolic representation, eg '1111' for global.
+///
+/// Returns
+/// -------
+/// tuple[float, int, str]
+///     The normalised score, the absolute score, and the path of the alignment in short form.
+///
+/// Raises
+/// ------
+/// ValueError
+///     If either peptide contains cross-links or if the alignment type is not valid.
+///
+#[pyfunction]
+#[pyo3(signature = (a, b, scoring=None, align_type="global"))]
+fn align(
+    a: &LinearPeptide,
+    b: &LinearPeptide,
+    scoring: Option<&AlignScoring>,
+    align_type: &str,
+) -> PyResult<(f64, isize, String)> {
+    let linear = |peptide: &LinearPeptide| {
+        peptide.0.clone().into_simple_linear().ok_or_else(|| {
+            PyValueError::new_err("Only peptides without cross-links can be aligned")
+        })
+    };
+    let (a, b) = (linear(a)?, linear(b)?);
+    let align_type = match align_type.to_ascii_lowercase().as_str() {
+        "global" => rustyms::align::AlignType::GLOBAL,
+        "local" => rustyms::align::AlignType::LOCAL,
+        "global_a" => rustyms::align::AlignType::GLOBAL_A,
+        "global_b" => rustyms::align::AlignType::GLOBAL_B,
+        "either_global" => rustyms::align::AlignType::EITHER_GLOBAL,
+        symbol => symbol.parse().map_err(|()| {
+            PyValueError::new_err(format!("Invalid alignment type '{align_type}'"))
+        })?,
+    };
+    let parameters = scoring.map(|s| s.0.clone()).unwrap_or_default();
+    let alignment = rustyms::align::align::<4, rustyms::SimpleLinear, rustyms::SimpleLinear>(
+        &a,
+        &b,
+        parameters.scoring(),
+        align_type,
+    );
+    let score = alignment.score();
+    Ok((
+        score.normalised.into_inner(),
+        score.absolute,
+        alignment.short(),
+    ))
 }
 
 /// Python bindings to the rustyms library.
 #[pymodule]
 #[pyo3(name = "rustyms")]
 fn rustyms_py03(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<AlignScoring>()?;
     m.add_class::<AminoAcid>()?;
     m.add_class::<AnnotatedPeak>()?;
     m.add_class::<AnnotatedSpectrum>()?;
@@ -1628,16 +2605,24 @@ fn rustyms_py03(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Fragment>()?;
     m.add_class::<FragmentationModel>()?;
     m.add_class::<FragmentType>()?;
+    m.add_class::<GlycanPosition>()?;
+    m.add_class::<GlycanSelection>()?;
+    m.add_class::<GlycanStructure>()?;
     m.add_class::<LinearPeptide>()?;
     m.add_class::<MassMode>()?;
     m.add_class::<Modification>()?;
     m.add_class::<MolecularCharge>()?;
     m.add_class::<MolecularFormula>()?;
     m.add_class::<Peptidoform>()?;
+    m.add_class::<PositionedGlycanStructure>()?;
     m.add_class::<RawPeak>()?;
     m.add_class::<RawSpectrum>()?;
+    m.add_class::<Score>()?;
+    m.add_class::<Scores>()?;
     m.add_class::<SequenceElement>()?;
     m.add_class::<SimpleModification>()?;
+    m.add_function(wrap_pyfunction!(align, m)?)?;
+    m.add_function(wrap_pyfunction!(annotate, m)?)?;
     Ok(())
 }
 
@@ -1,6 +1,10 @@
 use std::hint::black_box;
 
 use rustyms::align::*;
+use rustyms::spectrum::{
+    batch_cosine, batch_cosine_scalar, BinnedSpectrum, BinningParameters, RawPeak,
+};
+use rustyms::system::MassOverCharge;
 use rustyms::SimpleLinear;
 use rustyms::*;
 
@@ -76,5 +80,35 @@ pub fn align_unbounded(setup: (Peptidoform<SimpleLinear>, Peptidoform<SimpleLine
 
 library_benchmark_group!(name = alignment; benchmarks = align_1, align_4, align_unbounded);
 
+#[inline(never)]
+fn setup_library(size: usize) -> (BinnedSpectrum, Vec<BinnedSpectrum>) {
+    let parameters = BinningParameters::default();
+    let spectrum = |seed: usize| {
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend((0..100).map(|i| RawPeak {
+            mz: MassOverCharge::new::<rustyms::system::mz>(
+                100.0 + ((seed * 7919 + i * 104_729) % 19_000) as f64 / 10.0,
+            ),
+            intensity: (((seed + i) % 97 + 1) as f64).into(),
+        }));
+        BinnedSpectrum::new(&spectrum, &parameters)
+    };
+    (spectrum(0), (1..=size).map(spectrum).collect())
+}
+
+#[library_benchmark]
+#[bench::library_1000(setup_library(1000))]
+pub fn cosine_batch(setup: (BinnedSpectrum, Vec<BinnedSpectrum>)) -> Vec<f64> {
+    black_box(batch_cosine(&setup.0, &setup.1))
+}
+
+#[library_benchmark]
+#[bench::library_1000(setup_library(1000))]
+pub fn cosine_batch_scalar(setup: (BinnedSpectrum, Vec<BinnedSpectrum>)) -> Vec<f64> {
+    black_box(batch_cosine_scalar(&setup.0, &setup.1))
+}
+
+library_benchmark_group!(name = similarity; benchmarks = cosine_batch, cosine_batch_scalar);
+
 main!(config = LibraryBenchmarkConfig::default()
-.tool(Tool::new(ValgrindTool::DHAT)).tool(Tool::new(ValgrindTool::Massif)); library_benchmark_groups = alignment);
+.tool(Tool::new(ValgrindTool::DHAT)).tool(Tool::new(ValgrindTool::Massif)); library_benchmark_groups = alignment, similarity);
@@ -1,6 +1,6 @@
 //! Functions to generate alignments of peptides based on homology, while taking mass spectrometry errors into account.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, num::NonZeroU32, ops::Range};
 
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
@@ -11,7 +11,9 @@ use super::align_type::*;
 use super::piece::*;
 use super::scoring::*;
 
+use crate::align::mass_alignment::confidence_weight;
 use crate::align::mass_alignment::determine_final_score;
+use crate::align::mass_alignment::isobaric_score;
 use crate::align::mass_alignment::score_pair;
 use crate::helper_functions::next_num;
 use crate::peptidoform::AtMax;
@@ -21,6 +23,7 @@ use crate::system::Ratio;
 use crate::MolecularFormula;
 use crate::Multi;
 use crate::Peptidoform;
+use crate::SequenceElement;
 use crate::SequencePosition;
 use crate::SimpleLinear;
 
@@ -43,6 +46,9 @@ pub struct Alignment<'lifetime, A, B> {
     pub(super) align_type: AlignType,
     /// The maximal step size (the const generic STEPS)
     pub(super) maximal_step: u16,
+    /// If the scoring was weighted by the local confidence
+    #[serde(default)]
+    pub(super) confidence_weighted: bool,
 }
 
 impl<A, B> Clone for Alignment<'_, A, B> {
@@ -56,6 +62,7 @@ impl<A, B> Clone for Alignment<'_, A, B> {
             start_b: self.start_b,
             align_type: self.align_type,
             maximal_step: self.maximal_step,
+            confidence_weighted: self.confidence_weighted,
         }
     }
 }
@@ -70,6 +77,7 @@ impl<A, B> PartialEq for Alignment<'_, A, B> {
             && self.start_b == other.start_b
             && self.align_type == other.align_type
             && self.maximal_step == other.maximal_step
+            && self.confidence_weighted == other.confidence_weighted
     }
 }
 
@@ -83,6 +91,7 @@ impl<A, B> std::hash::Hash for Alignment<'_, A, B> {
         self.start_b.hash(state);
         self.align_type.hash(state);
         self.maximal_step.hash(state);
+        self.confidence_weighted.hash(state);
     }
 }
 
@@ -213,7 +222,7 @@ impl<'lifetime, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>> Alignment<'lifet
                                 )
                                 .0
                                 .iter()
-                                .map(MolecularFormula::monoisotopic_mass)
+                                .map(|f| scoring.labeling.apply(f.clone()).monoisotopic_mass())
                                 .collect();
                             let mass_b = seq_b[index_b]
                                 .formulas_all(
@@ -226,7 +235,7 @@ impl<'lifetime, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>> Alignment<'lifet
                                 )
                                 .0
                                 .iter()
-                                .map(MolecularFormula::monoisotopic_mass)
+                                .map(|f| scoring.labeling.apply(f.clone()).monoisotopic_mass())
                                 .collect();
                             let piece = score_pair(
                                 (&seq_a[index_a], &mass_a),
@@ -256,8 +265,19 @@ impl<'lifetime, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>> Alignment<'lifet
                     }]
                 }
                 MatchType::Isobaric => {
-                    let local_score = scoring.mass_base as isize
-                        + scoring.isobaric as isize * (a + b) as isize / 2;
+                    let local_score = isobaric_score(
+                        a as usize,
+                        b as usize,
+                        if scoring.confidence_weighting {
+                            confidence_weight(
+                                &seq_a.sequence()[index_a..index_a + a as usize],
+                                &seq_b.sequence()[index_b..index_b + b as usize],
+                            )
+                        } else {
+                            1.0
+                        },
+                        scoring,
+                    );
                     score += local_score;
                     index_a += a as usize;
                     index_b += b as usize;
@@ -281,8 +301,43 @@ impl<'lifetime, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>> Alignment<'lifet
             start_b,
             align_type,
             maximal_step,
+            confidence_weighted: scoring.confidence_weighting,
         })
     }
+
+    /// Merge the aligned region into a single consensus peptidoform that is mass faithful to
+    /// sequence A. Identical and mismatched steps take the residues of A, isobaric and rotated
+    /// steps take the residues of A as an ambiguous sequence group (`(?AA)` in ProForma) because
+    /// only the total mass is supported by both sequences, residues of A that are gapped in B are
+    /// kept, and residues only present in B are left out. Terminal and unplaced modifications of
+    /// A are not carried over.
+    pub fn consensus(&self) -> Peptidoform<SimpleLinear> {
+        let mut group = self
+            .seq_a
+            .sequence()
+            .iter()
+            .filter_map(|element| element.ambiguous)
+            .max()
+            .map_or(0, NonZeroU32::get);
+        let mut index_a = self.start_a;
+        let mut consensus = Vec::with_capacity(self.len_a());
+        for piece in &self.path {
+            let residues = self.seq_a.sequence()[index_a..index_a + piece.step_a as usize]
+                .iter()
+                .map(|element| element.clone().mark::<SimpleLinear>());
+            if matches!(piece.match_type, MatchType::Isobaric | MatchType::Rotation) {
+                group += 1;
+                consensus.extend(residues.map(|mut element| {
+                    element.ambiguous = NonZeroU32::new(group);
+                    element
+                }));
+            } else {
+                consensus.extend(residues);
+            }
+            index_a += piece.step_a as usize;
+        }
+        consensus.into()
+    }
 }
 
 impl<A, B> Alignment<'_, A, B> {
@@ -346,39 +401,108 @@ impl<A, B> Alignment<'_, A, B> {
         self.path().iter().map(|p| p.step_b as usize).sum()
     }
 
+    /// The mean local confidence of the residues covered by each piece of the path, for the first
+    /// and second sequence respectively. This is `None` for a sequence if the piece does not cover
+    /// any residues of that sequence (a gap) or if not all covered residues have a confidence.
+    /// This can be used to down weight regions of low confidence, for example in de novo peptides.
+    pub fn path_confidence(&self) -> Vec<(Option<f64>, Option<f64>)> {
+        fn mean<T>(sequence: &[SequenceElement<T>]) -> Option<f64> {
+            if sequence.is_empty() {
+                return None;
+            }
+            sequence
+                .iter()
+                .map(|s| s.confidence.map(|c| c.0))
+                .sum::<Option<f64>>()
+                .map(|sum| sum / sequence.len() as f64)
+        }
+
+        let (mut index_a, mut index_b) = self.start();
+        self.path()
+            .iter()
+            .map(|piece| {
+                let a = mean(&self.seq_a.sequence()[index_a..index_a + piece.step_a as usize]);
+                let b = mean(&self.seq_b.sequence()[index_b..index_b + piece.step_b as usize]);
+                index_a += piece.step_a as usize;
+                index_b += piece.step_b as usize;
+                (a, b)
+            })
+            .collect()
+    }
+
     /// Returns statistics for this match.
     pub fn stats(&self) -> Stats {
-        let (identical, mass_similar, similar, gaps, length) =
-            self.path().iter().fold((0, 0, 0, 0, 0), |acc, p| {
-                let m = p.match_type;
-                (
-                    acc.0
-                        + usize::from(
-                            m == MatchType::IdentityMassMismatch || m == MatchType::FullIdentity,
-                        ) * p.step_a.max(p.step_b) as usize,
-                    acc.1
-                        + usize::from(
-                            m == MatchType::FullIdentity
-                                || m == MatchType::Isobaric
-                                || m == MatchType::Rotation,
-                        ) * p.step_a.max(p.step_b) as usize,
-                    acc.2
-                        + usize::from(
-                            (m == MatchType::IdentityMassMismatch
-                                || m == MatchType::FullIdentity
-                                || m == MatchType::Mismatch)
-                                && p.local_score >= 0,
-                        ) * p.step_a.max(p.step_b) as usize,
-                    acc.3 + usize::from(m == MatchType::Gap),
-                    acc.4 + p.step_a.max(p.step_b) as usize,
+        Stats::from_path(&self.path, &self.confidence_weights())
+    }
+
+    /// Returns statistics for every window of `size` consecutive steps of the path, sliding one
+    /// step at a time. This can be used to find locally poor stretches in an alignment. If the
+    /// path is shorter than the window a single window covering the whole path is returned.
+    pub fn windowed_stats(&self, size: usize) -> Vec<Stats> {
+        let weights = self.confidence_weights();
+        let size = size.clamp(1, self.path.len().max(1));
+        (0..=self.path.len().saturating_sub(size))
+            .map(|start| {
+                Stats::from_path(
+                    &self.path[start..(start + size).min(self.path.len())],
+                    weights.get(start..start + size).unwrap_or(&[]),
                 )
-            });
-        Stats {
-            identical,
-            mass_similar,
-            similar,
-            gaps,
-            length,
+            })
+            .collect()
+    }
+
+    /// Returns statistics for each of the given regions, for example the CDRs of an antibody.
+    /// The regions are given as ranges of positions on sequence A, and every step of the path is
+    /// counted in the region that contains the first position on A of that step. Steps that fall
+    /// outside of all regions are ignored, steps in overlapping regions are counted for all.
+    pub fn region_stats(&self, regions: &[Range<usize>]) -> Vec<Stats> {
+        let weights = self.confidence_weights();
+        let mut selected = vec![Vec::new(); regions.len()];
+        let mut index_a = self.start_a;
+        for (index, piece) in self.path.iter().enumerate() {
+            for (region, selected) in regions.iter().zip(selected.iter_mut()) {
+                if region.contains(&index_a) {
+                    selected.push(index);
+                }
+            }
+            index_a += piece.step_a as usize;
+        }
+        selected
+            .into_iter()
+            .map(|indices| {
+                let path: Vec<Piece> = indices.iter().map(|i| self.path[*i].clone()).collect();
+                let weights: Vec<OrderedFloat<f64>> = indices
+                    .iter()
+                    .filter_map(|i| weights.get(*i).copied())
+                    .collect();
+                Stats::from_path(&path, &weights)
+            })
+            .collect()
+    }
+
+    /// The confidence weight for each piece in the path, see [`Stats::confidence_weights`].
+    fn confidence_weights(&self) -> Vec<OrderedFloat<f64>> {
+        if self.confidence_weighted {
+            let (mut index_a, mut index_b) = self.start();
+            self.path()
+                .iter()
+                .map(|piece| {
+                    let weight =
+                        if matches!(piece.match_type, MatchType::Mismatch | MatchType::Isobaric) {
+                            confidence_weight(
+                                &self.seq_a.sequence()[index_a..index_a + piece.step_a as usize],
+                                &self.seq_b.sequence()[index_b..index_b + piece.step_b as usize],
+                            )
+                        } else {
+                            1.0
+                        };
+                    index_a += piece.step_a as usize;
+                    index_b += piece.step_b as usize;
+                    OrderedFloat(weight)
+                })
+                .collect()
+        } else {
+            Vec::new()
         }
     }
 }
@@ -539,9 +663,51 @@ pub struct Stats {
     pub gaps: usize,
     /// The length of the alignment, the sum of the max of the step for A and B for each position.
     pub length: usize,
+    /// The confidence weight used for each piece in the path, only mismatch and isobaric pieces
+    /// are weighted, the others are always 1.0. This is empty if the alignment was not confidence
+    /// weighted, see [`AlignScoring::confidence_weighting`].
+    pub confidence_weights: Vec<OrderedFloat<f64>>,
 }
 
 impl Stats {
+    /// Calculate the statistics for (part of) a path, with the confidence weights for the same
+    /// pieces (or empty if not confidence weighted).
+    fn from_path(path: &[Piece], confidence_weights: &[OrderedFloat<f64>]) -> Self {
+        let (identical, mass_similar, similar, gaps, length) =
+            path.iter().fold((0, 0, 0, 0, 0), |acc, p| {
+                let m = p.match_type;
+                (
+                    acc.0
+                        + usize::from(
+                            m == MatchType::IdentityMassMismatch || m == MatchType::FullIdentity,
+                        ) * p.step_a.max(p.step_b) as usize,
+                    acc.1
+                        + usize::from(
+                            m == MatchType::FullIdentity
+                                || m == MatchType::Isobaric
+                                || m == MatchType::Rotation,
+                        ) * p.step_a.max(p.step_b) as usize,
+                    acc.2
+                        + usize::from(
+                            (m == MatchType::IdentityMassMismatch
+                                || m == MatchType::FullIdentity
+                                || m == MatchType::Mismatch)
+                                && p.local_score >= 0,
+                        ) * p.step_a.max(p.step_b) as usize,
+                    acc.3 + usize::from(m == MatchType::Gap),
+                    acc.4 + p.step_a.max(p.step_b) as usize,
+                )
+            });
+        Self {
+            identical,
+            mass_similar,
+            similar,
+            gaps,
+            length,
+            confidence_weights: confidence_weights.to_vec(),
+        }
+    }
+
     /// Get the identity as fraction.
     pub fn identity(&self) -> f64 {
         if self.length == 0 {
@@ -595,7 +761,7 @@ pub struct Score {
 #[allow(clippy::missing_panics_doc)]
 mod tests {
     use crate::{
-        align::{align, AlignScoring, AlignType},
+        align::{align, AlignScoring, AlignType, Alignment},
         peptidoform::SimpleLinear,
         AminoAcid, MultiChemical, Peptidoform,
     };
@@ -675,4 +841,167 @@ mod tests {
             "{mass_diff_bc} (peptides) should be equal to {mass_diff_nd} (ND)"
         );
     }
+
+    #[test]
+    fn path_confidence() {
+        let mut a = Peptidoform::pro_forma("AWGK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("AWK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        assert!(a.set_local_confidence(&[1.0, 0.5, 0.1, 0.9]));
+        assert!(!a.set_local_confidence(&[1.0]));
+        let alignment = align::<1, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring::default(),
+            AlignType::GLOBAL,
+        );
+        assert_eq!(
+            alignment.path_confidence(),
+            vec![
+                (Some(1.0), None),
+                (Some(0.5), None),
+                (Some(0.1), None),
+                (Some(0.9), None)
+            ]
+        );
+        // The confidence is only shown as INFO tags on request, which are read back when parsing
+        assert_eq!(a.to_string(), "AWGK");
+        let mut shown = String::new();
+        a.display_with_confidence(&mut shown, true).unwrap();
+        assert!(shown.contains("[INFO:confidence=0.5]"), "{shown}");
+        let back = Peptidoform::pro_forma(&shown, None).unwrap();
+        assert_eq!(back.local_confidence(), a.local_confidence());
+        assert_eq!(a.sub_peptide(1..3).local_confidence(), Some(vec![0.5, 0.1]));
+        // The confidence is serialized in human readable formats, but not in binary formats
+        let json: Peptidoform<SimpleLinear> =
+            serde_json::from_str(&serde_json::to_string(&a).unwrap()).unwrap();
+        assert_eq!(json.local_confidence(), a.local_confidence());
+        let binary: Peptidoform<SimpleLinear> =
+            bincode::deserialize(&bincode::serialize(&a).unwrap()).unwrap();
+        assert_eq!(binary, a);
+        assert_eq!(binary.local_confidence(), None);
+    }
+
+    #[test]
+    fn confidence_weighting() {
+        let mut a = Peptidoform::pro_forma("AWGKA", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("AWSKA", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        assert!(a.set_local_confidence(&[1.0, 1.0, 0.0, 1.0, 1.0]));
+        let weighted = AlignScoring {
+            mismatch: -4,
+            confidence_weighting: true,
+            ..AlignScoring::default()
+        };
+        let plain = align::<1, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring {
+                confidence_weighting: false,
+                ..weighted
+            },
+            AlignType::GLOBAL,
+        );
+        let aligned = align::<1, SimpleLinear, SimpleLinear>(&a, &b, weighted, AlignType::GLOBAL);
+        assert_eq!(plain.short(), "2=1X2=");
+        assert_eq!(aligned.short(), "2=1X2=");
+        assert_eq!(aligned.path()[2].local_score, 0);
+        assert_eq!(aligned.score().absolute, plain.score().absolute + 4);
+        assert!(plain.stats().confidence_weights.is_empty());
+        assert_eq!(
+            aligned
+                .stats()
+                .confidence_weights
+                .iter()
+                .map(|w| w.0)
+                .collect::<Vec<_>>(),
+            vec![1.0, 1.0, 0.0, 1.0, 1.0]
+        );
+        let recreated = Alignment::create_from_path(
+            &a,
+            &b,
+            0,
+            0,
+            &aligned.short(),
+            weighted,
+            AlignType::GLOBAL,
+            1,
+        )
+        .unwrap();
+        assert_eq!(recreated.score(), aligned.score());
+    }
+
+    #[test]
+    fn windowed_and_region_stats() {
+        let mut a = Peptidoform::pro_forma("AWGKAPEPTIDE", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("AWSKAPEPTIDE", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        assert!(a.set_local_confidence(&[0.5; 12]));
+        let alignment = align::<1, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring {
+                confidence_weighting: true,
+                ..AlignScoring::default()
+            },
+            AlignType::GLOBAL,
+        );
+        assert_eq!(alignment.short(), "2=1X9=");
+
+        let windows = alignment.windowed_stats(4);
+        assert_eq!(windows.len(), 9);
+        assert_eq!(windows[0].identical, 3);
+        assert_eq!(windows[0].length, 4);
+        assert_eq!(windows[3].identical, 4);
+        assert_eq!(windows[0].confidence_weights.len(), 4);
+        assert_eq!(alignment.windowed_stats(20), vec![alignment.stats()]);
+
+        let regions = alignment.region_stats(&[0..3, 3..12, 20..30]);
+        assert_eq!(regions.len(), 3);
+        assert_eq!((regions[0].identical, regions[0].length), (2, 3));
+        assert_eq!(regions[0].confidence_weights[2].0, 0.5);
+        assert_eq!((regions[1].identical, regions[1].length), (9, 9));
+        assert_eq!(regions[2].length, 0);
+    }
+
+    #[test]
+    fn consensus() {
+        let a = Peptidoform::pro_forma("ANGARSWK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("AGGQRSK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let alignment = align::<4, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring::default(),
+            AlignType::GLOBAL,
+        );
+        assert_eq!(alignment.short(), "1=1:2i2:1i2=1D1=");
+        let consensus = alignment.consensus();
+        assert_eq!(consensus.to_string(), "A(?N)(?GA)RSWK");
+        assert_eq!(
+            consensus.formulas(),
+            a.formulas(),
+            "The consensus should be mass faithful to A"
+        );
+    }
 }
@@ -37,23 +37,35 @@ impl<A: AtMax<Linear>> ConsecutiveAlignment<'_, A> {
                     let mut index_a = alignment.start_a;
                     let mut start_region_b = alignment.start_b;
                     let mut index_b = alignment.start_b;
+                    // The regions are looked up 1 based
                     let mut region = allele
-                        .get_region(index_a)
+                        .get_region(index_a + 1)
                         .map_or(&Region::Framework(1), |(r, _)| r);
                     let mut ranges = Vec::new();
 
                     for step in &alignment.path {
-                        let new_region = allele
-                            .get_region(index_a + step.step_a as usize)
-                            .map_or(&Region::Framework(1), |(r, _)| r);
-                        if region != new_region {
-                            ranges.push((b_offset + start_region_b, b_offset + index_b, region));
-                            start_region_b = index_b + 1;
-                            region = new_region;
+                        if step.step_a > 0 {
+                            let new_region = allele
+                                .get_region(index_a + 1)
+                                .map_or(&Region::Framework(1), |(r, _)| r);
+                            if region != new_region {
+                                if index_b > start_region_b {
+                                    ranges.push((
+                                        b_offset + start_region_b,
+                                        b_offset + index_b,
+                                        region,
+                                    ));
+                                }
+                                start_region_b = index_b;
+                                region = new_region;
+                            }
                         }
                         index_a += step.step_a as usize;
                         index_b += step.step_b as usize;
                     }
+                    if index_b > start_region_b {
+                        ranges.push((b_offset + start_region_b, b_offset + index_b, region));
+                    }
                     b_offset += alignment.len_b() + alignment.start_b;
 
                     ranges
@@ -66,20 +78,37 @@ impl<A: AtMax<Linear>> ConsecutiveAlignment<'_, A> {
                 let first = chunk.next().unwrap();
                 let last = chunk.last().unwrap_or(first);
                 (
-                    self.alignments[0][0]
-                        .1
-                        .seq_b()
-                        .sub_peptide(first.0..=last.1),
+                    self.alignments[0][0].1.seq_b().sub_peptide(first.0..last.1),
                     region.clone(),
                 )
             })
             .collect()
     }
+
+    /// Break up the main alignment into the regions (see [`Self::regions`]) with the IMGT unique
+    /// numbering for every residue in each region, see [`imgt_numbering`]. This can be used to
+    /// report mutations in standard numbering.
+    pub fn numbered_regions(&self) -> Vec<(Peptidoform<A>, Region, Vec<Option<ImgtPosition>>)> {
+        let regions = self.regions();
+        let lengths: Vec<(Region, usize)> = regions
+            .iter()
+            .map(|(peptide, region)| (region.clone(), peptide.len()))
+            .collect();
+        let mut numbering = imgt_numbering(&lengths).into_iter();
+        regions
+            .into_iter()
+            .map(|(peptide, region)| {
+                let positions = numbering.by_ref().take(peptide.len()).collect();
+                (peptide, region, positions)
+            })
+            .collect()
+    }
 }
 
 /// Only available if features `align` and `imgt` are turned on.
 /// Align one sequence to multiple consecutive genes. Each gene can be controlled to be global to the left or free to allow unmatched residues between it and the previous gene.
 /// If the sequence is too short to cover all genes only the genes that could be matched are returned.
+/// The options for each gene are sorted on descending score, alignments with equal scores are kept in the order of [`Selection::germlines`].
 /// # Panics
 /// If there are not two or more genes listed. If the return number is 0.
 #[allow(clippy::needless_pass_by_value)]
@@ -117,7 +146,7 @@ pub fn consecutive_align<const STEPS: u16, A: AtMax<SimpleLinear> + AtMax<Linear
             break;
         }
 
-        output.push(
+        output.push(best_alignments(
             Selection {
                 species: use_species,
                 chains: use_chains,
@@ -131,9 +160,9 @@ pub fn consecutive_align<const STEPS: u16, A: AtMax<SimpleLinear> + AtMax<Linear
                         .to_owned();
                 (seq, alignment)
             })
-            .k_largest_by(return_number, |a, b| a.1.cmp(&b.1))
             .collect_vec(),
-        );
+            return_number,
+        ));
     }
     ConsecutiveAlignment { alignments: output }
 }
@@ -141,6 +170,7 @@ pub fn consecutive_align<const STEPS: u16, A: AtMax<SimpleLinear> + AtMax<Linear
 /// Only available with if features `align`, `rayon`, and `imgt` are turned on.
 /// Align one sequence to multiple consecutive genes. Each gene can be controlled to be global to the left or free to allow unmatched residues between it and the previous gene.
 /// If the sequence is too short to cover all genes only the genes that could be matched are returned.
+/// The result is deterministic and identical to [`consecutive_align`], regardless of the number of threads used.
 /// # Panics
 /// If there are not two or more genes listed. If the return number is 0.
 #[cfg(feature = "rayon")]
@@ -184,7 +214,7 @@ pub fn par_consecutive_align<
             break;
         }
 
-        output.push(
+        output.push(best_alignments(
             Selection {
                 species: use_species,
                 chains: use_chains,
@@ -197,11 +227,21 @@ pub fn par_consecutive_align<
                     align::<STEPS, UnAmbiguous, A>(seq.sequence, &left_sequence, scoring, gene.1);
                 (seq, alignment.to_owned())
             })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .k_largest_by(return_number, |a, b| a.1.cmp(&b.1))
-            .collect_vec(),
-        );
+            .collect::<Vec<_>>(),
+            return_number,
+        ));
     }
     ConsecutiveAlignment { alignments: output }
 }
+
+/// Get the best `number` alignments, sorted on descending score. The sort is stable so alignments
+/// with equal scores stay in the given order, which keeps the parallel and sequential versions of
+/// the consecutive alignment identical.
+fn best_alignments<T, A>(
+    mut alignments: Vec<(T, Alignment<'static, UnAmbiguous, A>)>,
+    number: usize,
+) -> Vec<(T, Alignment<'static, UnAmbiguous, A>)> {
+    alignments.sort_by(|a, b| b.1.cmp(&a.1));
+    alignments.truncate(number);
+    alignments
+}
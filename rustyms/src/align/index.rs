@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    peptidoform::{AtMax, SimpleLinear},
+    spectrum::PeakIndex,
+    system::Mass,
+    AminoAcid, MassMode, MolecularFormula, Multi, Peptidoform, SequencePosition, Tolerance,
+};
+
+use super::{align, AlignScoring, AlignType, Alignment};
+
+/// An index of database sequences to align many queries against, for example all proteins from a
+/// FASTA file. Full mass based alignments are expensive, so optionally the candidates can be
+/// filtered before aligning using short sequence tags, see [`TagFilter`], and/or on mass, see
+/// [`MassFilter`]. If both filters are set a candidate has to pass both.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlignIndex<const STEPS: u16, A> {
+    sequences: Vec<Peptidoform<A>>,
+    tags: Option<(TagFilter, TagIndex)>,
+    masses: Option<(MassFilter, PeakIndex<(Mass, usize)>)>,
+}
+
+/// For each tag the indices of all database sequences that contain it
+type TagIndex = HashMap<Vec<char>, Vec<usize>>;
+
+/// The settings for filtering database candidates on shared sequence tags (k-mers) before
+/// aligning. I and L (and J) are seen as identical.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TagFilter {
+    /// The length of the tags, shorter tags give better recall but filter less.
+    ///
+    /// Default: 4.
+    pub length: usize,
+    /// The minimal number of distinct tags a candidate has to share with the query.
+    ///
+    /// Default: 1.
+    pub min_matches: usize,
+}
+
+impl Default for TagFilter {
+    fn default() -> Self {
+        Self {
+            length: 4,
+            min_matches: 1,
+        }
+    }
+}
+
+/// The settings for filtering database candidates on mass before aligning. The residue masses
+/// (without termini) of the query are compared to the residue masses of the database sequences,
+/// or of stretches of the database sequences if `max_window` is set. This is intended for
+/// alignments that are global for the query, like [`AlignType::EITHER_GLOBAL`], where the query
+/// is expected to match (a stretch of) the database sequence in full.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MassFilter {
+    /// The tolerance on the mass, a wider tolerance gives better recall but filters less.
+    ///
+    /// Default: 10 ppm.
+    pub tolerance: Tolerance<Mass>,
+    /// The mass mode used to calculate the masses.
+    ///
+    /// Default: monoisotopic.
+    pub mass_mode: MassMode,
+    /// If set, every stretch of at most this many residues of each database sequence is indexed,
+    /// so that queries can be matched to part of a longer sequence (for example a peptide to a
+    /// protein). This index grows linearly with this window, so set it to the longest expected
+    /// query. If not set only the mass of the full database sequences is indexed.
+    ///
+    /// Default: `None`.
+    pub max_window: Option<usize>,
+}
+
+impl Default for MassFilter {
+    fn default() -> Self {
+        Self {
+            tolerance: Tolerance::new_ppm(10.0),
+            mass_mode: MassMode::Monoisotopic,
+            max_window: None,
+        }
+    }
+}
+
+impl<const STEPS: u16, A: AtMax<SimpleLinear>> AlignIndex<STEPS, A> {
+    /// Create a new index, without any filtering all queries are aligned to all sequences.
+    pub fn new(sequences: impl IntoIterator<Item = Peptidoform<A>>) -> Self {
+        Self {
+            sequences: sequences.into_iter().collect(),
+            tags: None,
+            masses: None,
+        }
+    }
+
+    /// Only align queries to database sequences that share enough sequence tags with the query.
+    /// This builds the tag index, so this takes time proportional to the size of the database.
+    #[must_use]
+    pub fn with_tag_filter(mut self, filter: TagFilter) -> Self {
+        let filter = TagFilter {
+            length: filter.length.max(1),
+            ..filter
+        };
+        let mut index = TagIndex::new();
+        for (sequence_index, sequence) in self.sequences.iter().enumerate() {
+            for tag in tags(sequence, filter.length) {
+                index.entry(tag).or_default().push(sequence_index);
+            }
+        }
+        self.tags = Some((filter, index));
+        self
+    }
+
+    /// Only align queries to database sequences that (in part) match the mass of the query.
+    /// This builds the mass index, so this takes time proportional to the size of the database
+    /// (times the window if set).
+    #[must_use]
+    pub fn with_mass_filter(mut self, filter: MassFilter) -> Self {
+        let mut masses = Vec::new();
+        for (sequence_index, sequence) in self.sequences.iter().enumerate() {
+            let residues = residue_formulas(sequence);
+            if let Some(window) = filter.max_window {
+                for start in 0..residues.len() {
+                    let mut formulas = Multi::default();
+                    for residue in residues.iter().skip(start).take(window) {
+                        formulas = &formulas * residue;
+                        masses.extend(
+                            formulas
+                                .iter()
+                                .map(|f| (f.mass(filter.mass_mode), sequence_index)),
+                        );
+                    }
+                }
+            } else {
+                let formulas: Multi<MolecularFormula> = residues.into_iter().sum();
+                masses.extend(
+                    formulas
+                        .iter()
+                        .map(|f| (f.mass(filter.mass_mode), sequence_index)),
+                );
+            }
+        }
+        self.masses = Some((filter, PeakIndex::new(masses)));
+        self
+    }
+
+    /// All database sequences.
+    pub fn sequences(&self) -> &[Peptidoform<A>] {
+        &self.sequences
+    }
+
+    /// The indices of all database sequences that will be aligned to this query, in database
+    /// order. Without any filters this is all sequences.
+    pub fn candidates<B: AtMax<SimpleLinear>>(&self, query: &Peptidoform<B>) -> Vec<usize> {
+        let mut candidates = self.tag_candidates(query);
+        if let Some((filter, index)) = &self.masses {
+            let formulas: Multi<MolecularFormula> = residue_formulas(query).into_iter().sum();
+            let mut matching: Vec<usize> = formulas
+                .iter()
+                .flat_map(|f| index.within(f.mass(filter.mass_mode), filter.tolerance))
+                .map(|(_, sequence_index)| *sequence_index)
+                .collect();
+            matching.sort_unstable();
+            matching.dedup();
+            candidates.retain(|sequence_index| matching.binary_search(sequence_index).is_ok());
+        }
+        candidates
+    }
+
+    /// The candidates according to the tag filter, if the query is shorter than the tag length
+    /// this is all sequences.
+    fn tag_candidates<B>(&self, query: &Peptidoform<B>) -> Vec<usize> {
+        let Some((filter, index)) = &self.tags else {
+            return (0..self.sequences.len()).collect();
+        };
+        if query.len() < filter.length {
+            return (0..self.sequences.len()).collect();
+        }
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for tag in tags(query, filter.length) {
+            for sequence_index in index.get(&tag).into_iter().flatten() {
+                *counts.entry(*sequence_index).or_default() += 1;
+            }
+        }
+        let mut candidates: Vec<usize> = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= filter.min_matches)
+            .map(|(sequence_index, _)| sequence_index)
+            .collect();
+        candidates.sort_unstable();
+        candidates
+    }
+
+    /// Align the query to all candidate database sequences, see [`Self::candidates`]. The
+    /// alignments are returned in database order.
+    pub fn align<'lifetime, B: AtMax<SimpleLinear>>(
+        &'lifetime self,
+        query: &'lifetime Peptidoform<B>,
+        scoring: AlignScoring<'lifetime>,
+        align_type: AlignType,
+    ) -> Vec<Alignment<'lifetime, A, B>> {
+        self.candidates(query)
+            .into_iter()
+            .map(|index| align::<STEPS, A, B>(&self.sequences[index], query, scoring, align_type))
+            .collect()
+    }
+
+    /// Only available with feature `rayon`.
+    /// Align the query to all candidate database sequences in parallel, see [`Self::align`]. The
+    /// alignments are returned in database order, regardless of the number of threads used.
+    #[cfg(feature = "rayon")]
+    pub fn par_align<'lifetime, B: AtMax<SimpleLinear> + Send + Sync>(
+        &'lifetime self,
+        query: &'lifetime Peptidoform<B>,
+        scoring: AlignScoring<'lifetime>,
+        align_type: AlignType,
+    ) -> Vec<Alignment<'lifetime, A, B>>
+    where
+        A: Send + Sync,
+    {
+        use rayon::prelude::*;
+        self.candidates(query)
+            .into_par_iter()
+            .map(|index| align::<STEPS, A, B>(&self.sequences[index], query, scoring, align_type))
+            .collect()
+    }
+}
+
+/// The formulas of all residues in this sequence
+fn residue_formulas<T: AtMax<SimpleLinear>>(
+    sequence: &Peptidoform<T>,
+) -> Vec<Multi<MolecularFormula>> {
+    sequence
+        .sequence()
+        .iter()
+        .enumerate()
+        .map(|(index, element)| {
+            element
+                .formulas_all(
+                    &[],
+                    &[],
+                    &mut Vec::new(),
+                    false,
+                    SequencePosition::Index(index),
+                    0,
+                )
+                .0
+        })
+        .collect()
+}
+
+/// All distinct tags of the given length in this sequence, with I, L, and J all written as L
+fn tags<T>(sequence: &Peptidoform<T>, length: usize) -> HashSet<Vec<char>> {
+    let residues: Vec<char> = sequence
+        .sequence()
+        .iter()
+        .map(|element| match element.aminoacid.aminoacid() {
+            AminoAcid::Isoleucine | AminoAcid::AmbiguousLeucine => 'L',
+            aminoacid => aminoacid.char(),
+        })
+        .collect();
+    residues.windows(length).map(<[char]>::to_vec).collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::peptidoform::SimpleLinear;
+
+    fn linear(aa: &str) -> Peptidoform<SimpleLinear> {
+        Peptidoform::pro_forma(aa, None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn tag_filter() {
+        let database = ["MKWVTFISLLFLFSSAYS", "AGGQRSPEPTIDE", "WWWWWWWWWW"];
+        let index = AlignIndex::<4, SimpleLinear>::new(database.iter().map(|s| linear(s)));
+        let query = linear("FLSLLF");
+        assert_eq!(index.candidates(&query), vec![0, 1, 2]);
+
+        let index = index.with_tag_filter(TagFilter::default());
+        assert_eq!(index.candidates(&query), vec![0]);
+        assert_eq!(index.candidates(&linear("PEPTLDE")), vec![1]);
+        assert_eq!(index.candidates(&linear("WWW")), vec![0, 1, 2]);
+        assert!(index.candidates(&linear("CCCCC")).is_empty());
+
+        let alignments = index.align(&query, AlignScoring::default(), AlignType::EITHER_GLOBAL);
+        assert_eq!(alignments.len(), 1);
+        assert_eq!(alignments[0].seq_a(), &index.sequences()[0]);
+        #[cfg(feature = "rayon")]
+        assert_eq!(
+            index.par_align(&query, AlignScoring::default(), AlignType::EITHER_GLOBAL),
+            alignments
+        );
+
+        let strict = AlignIndex::<4, SimpleLinear>::new(database.iter().map(|s| linear(s)))
+            .with_tag_filter(TagFilter {
+                length: 3,
+                min_matches: 3,
+            });
+        assert_eq!(strict.candidates(&query), vec![0]);
+        assert_eq!(strict.candidates(&linear("PEPTLDE")), vec![1]);
+        assert!(strict.candidates(&linear("AGGWWW")).is_empty());
+    }
+
+    #[test]
+    fn mass_filter() {
+        let database = ["AGGQRSPEPTIDE", "PEPTIDE", "PEPTLDEK", "WWWWWWWWWW"];
+        let full = AlignIndex::<4, SimpleLinear>::new(database.iter().map(|s| linear(s)))
+            .with_mass_filter(MassFilter::default());
+        assert_eq!(full.candidates(&linear("PEPTIDE")), vec![1]);
+        assert_eq!(full.candidates(&linear("EDITPEP")), vec![1]);
+        assert_eq!(full.candidates(&linear("PEPTLDE")), vec![1]);
+        assert!(full.candidates(&linear("PEPT")).is_empty());
+
+        let windows = AlignIndex::<4, SimpleLinear>::new(database.iter().map(|s| linear(s)))
+            .with_mass_filter(MassFilter {
+                max_window: Some(10),
+                ..MassFilter::default()
+            });
+        assert_eq!(windows.candidates(&linear("PEPTIDE")), vec![0, 1, 2]);
+        assert_eq!(windows.candidates(&linear("PEPT")), vec![0, 1, 2]);
+        assert_eq!(
+            windows.candidates(&linear("WWWWWWWWWWW")),
+            Vec::<usize>::new()
+        );
+        let query = linear("SPEPTIDE");
+        let alignments = windows.align(&query, AlignScoring::default(), AlignType::EITHER_GLOBAL);
+        assert_eq!(alignments.len(), 1);
+        assert_eq!(alignments[0].start_a(), 5);
+        assert_eq!(alignments[0].short(), "8=");
+
+        let both = windows.with_tag_filter(TagFilter::default());
+        assert_eq!(both.candidates(&linear("EDITPEP")), Vec::<usize>::new());
+        assert_eq!(both.candidates(&linear("PEPTIDE")), vec![0, 1, 2]);
+    }
+}
@@ -3,8 +3,8 @@ use std::fmt::Debug;
 use crate::{
     peptidoform::{AtMax, SimpleLinear},
     system::Mass,
-    MassMode, MolecularFormula, Multi, Peptidoform, SequenceElement, SequencePosition,
-    WithinTolerance,
+    IsotopeLabeling, MassMode, MolecularFormula, Multi, Peptidoform, SequenceElement,
+    SequencePosition, WithinTolerance,
 };
 
 use super::{
@@ -31,8 +31,10 @@ pub fn align<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear>, B: AtMax<Simpl
 
     let mut matrix = Matrix::new(seq_a.len(), seq_b.len());
     let mut global_highest = (0, 0, 0);
-    let masses_a: DiagonalArray<Multi<Mass>> = calculate_masses::<STEPS>(seq_a, scoring.mass_mode);
-    let masses_b: DiagonalArray<Multi<Mass>> = calculate_masses::<STEPS>(seq_b, scoring.mass_mode);
+    let masses_a: DiagonalArray<Multi<Mass>> =
+        calculate_masses::<STEPS>(seq_a, scoring.mass_mode, scoring.labeling);
+    let masses_b: DiagonalArray<Multi<Mass>> =
+        calculate_masses::<STEPS>(seq_b, scoring.mass_mode, scoring.labeling);
     let zero: Multi<Mass> = Multi::default();
 
     if align_type.left.global_a() {
@@ -162,6 +164,7 @@ pub fn align<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear>, B: AtMax<Simpl
         start_b,
         align_type,
         maximal_step: STEPS,
+        confidence_weighted: scoring.confidence_weighting,
     }
 }
 
@@ -222,20 +225,90 @@ pub(super) fn score_pair<A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>>(
             let local = scoring.mass_mismatch as isize;
             Piece::new(score + local, local, MatchType::IdentityMassMismatch, 1, 1)
         }
-        (false, true) => Piece::new(
-            score + scoring.mass_base as isize + scoring.isobaric as isize,
-            scoring.mass_base as isize + scoring.isobaric as isize,
-            MatchType::Isobaric,
-            1,
-            1,
-        ),
-        (false, false) => Piece::new(
-            score + scoring.mismatch as isize,
-            scoring.mismatch as isize,
-            MatchType::Mismatch,
-            1,
-            1,
-        ),
+        (false, true) => {
+            let local = isobaric_score(
+                1,
+                1,
+                step_weight(
+                    std::slice::from_ref(a.0),
+                    std::slice::from_ref(b.0),
+                    scoring,
+                ),
+                scoring,
+            );
+            Piece::new(score + local, local, MatchType::Isobaric, 1, 1)
+        }
+        (false, false) => {
+            let local = weigh(
+                scoring.mismatch as isize,
+                0,
+                step_weight(
+                    std::slice::from_ref(a.0),
+                    std::slice::from_ref(b.0),
+                    scoring,
+                ),
+            );
+            Piece::new(score + local, local, MatchType::Mismatch, 1, 1)
+        }
+    }
+}
+
+/// The local score for an isobaric step of the given lengths with the given confidence weight
+/// (see [`step_weight`]).
+#[allow(clippy::cast_possible_wrap)]
+pub(super) fn isobaric_score(
+    len_a: usize,
+    len_b: usize,
+    weight: f64,
+    scoring: AlignScoring<'_>,
+) -> isize {
+    scoring.mass_base as isize
+        + weigh(
+            scoring.isobaric as isize * (len_a + len_b) as isize / 2,
+            scoring.rotated as isize * (len_a + len_b) as isize / 2,
+            weight,
+        )
+}
+
+/// The confidence weight for a step if confidence weighting is turned on in the scoring, see
+/// [`confidence_weight`], otherwise 1.0.
+fn step_weight<A, B>(
+    a: &[SequenceElement<A>],
+    b: &[SequenceElement<B>],
+    scoring: AlignScoring<'_>,
+) -> f64 {
+    if scoring.confidence_weighting {
+        confidence_weight(a, b)
+    } else {
+        1.0
+    }
+}
+
+/// The confidence weight for a step, the lowest of the mean local confidence of both sides.
+/// Residues without a confidence count as fully confident.
+pub(super) fn confidence_weight<A, B>(a: &[SequenceElement<A>], b: &[SequenceElement<B>]) -> f64 {
+    fn mean<T>(sequence: &[SequenceElement<T>]) -> f64 {
+        if sequence.is_empty() {
+            1.0
+        } else {
+            sequence
+                .iter()
+                .map(|s| s.confidence.map_or(1.0, |c| c.0.clamp(0.0, 1.0)))
+                .sum::<f64>()
+                / sequence.len() as f64
+        }
+    }
+    mean(a).min(mean(b))
+}
+
+/// Move a score towards the target score, a weight of 1.0 keeps the score and a weight of 0.0
+/// results in the target score. Scores that are already at or above the target are kept.
+#[allow(clippy::cast_possible_truncation)]
+fn weigh(score: isize, target: isize, weight: f64) -> isize {
+    if score < target {
+        target - ((target - score) as f64 * weight).round() as isize
+    } else {
+        score
     }
 }
 
@@ -264,12 +337,16 @@ fn score<A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>>(
             }
         };
         #[allow(clippy::cast_possible_wrap)]
-        let local = scoring.mass_base as isize
-            + if rotated {
-                scoring.rotated as isize * a.0.len() as isize
-            } else {
-                scoring.isobaric as isize * (a.0.len() + b.0.len()) as isize / 2
-            };
+        let local = if rotated {
+            scoring.mass_base as isize + scoring.rotated as isize * a.0.len() as isize
+        } else {
+            isobaric_score(
+                a.0.len(),
+                b.0.len(),
+                step_weight(a.0, b.0, scoring),
+                scoring,
+            )
+        };
         Some(Piece::new(
             score + local,
             local,
@@ -290,6 +367,7 @@ fn score<A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>>(
 fn calculate_masses<const STEPS: u16>(
     sequence: &Peptidoform<impl AtMax<SimpleLinear>>,
     mass_mode: MassMode,
+    labeling: IsotopeLabeling,
 ) -> DiagonalArray<Multi<Mass>> {
     let mut array = DiagonalArray::new(sequence.len(), STEPS);
     for i in 0..sequence.len() {
@@ -309,7 +387,7 @@ fn calculate_masses<const STEPS: u16>(
                 })
                 .sum::<Multi<MolecularFormula>>()
                 .iter()
-                .map(|f| f.mass(mass_mode))
+                .map(|f| labeling.apply(f.clone()).mass(mass_mode))
                 .collect();
         }
     }
@@ -30,10 +30,13 @@ mod alignment;
 #[cfg(test)]
 mod bad_alignments;
 mod diagonal_array;
+mod index;
 mod mass_alignment;
 mod multi_alignment;
 mod piece;
+mod recalibration;
 mod scoring;
+mod statistics;
 #[cfg(test)]
 mod test_alignments;
 
@@ -44,9 +47,12 @@ pub use consecutive::*;
 
 pub use align_type::{AlignType, Side};
 pub use alignment::{Alignment, Score, Stats};
+pub use index::{AlignIndex, MassFilter, TagFilter};
 pub use mass_alignment::align;
 pub use piece::Piece;
-pub use scoring::{AlignScoring, MatchType};
+pub use recalibration::{recalibrate_scoring, RecalibratedScoring, RecalibrationParameters};
+pub use scoring::{AlignScoring, MatchType, ScoringMatrix};
+pub use statistics::ScoreDistribution;
 
 /// Different scoring matrices that can be used.
 /// Matrices from: <https://www.ncbi.nlm.nih.gov/IEB/ToolBox/CPP_DOC/lxr/source/src/util/tables/> and <https://www.ncbi.nlm.nih.gov/IEB/ToolBox/C_DOC/lxr/source/data/>
@@ -0,0 +1,383 @@
+//! Data driven re-estimation of the alignment scoring parameters from trusted alignments.
+
+use serde::{Deserialize, Serialize};
+
+use super::{scoring::MatchType, AlignScoring, Alignment, ScoringMatrix};
+use crate::{
+    system::{Mass, OrderedMass},
+    AminoAcid, IsotopeLabeling, MassMode, MolecularFormula, Multi, SequenceElement,
+    SequencePosition, Tolerance,
+};
+
+/// The alignment scoring parameters in an owned form, so these can be (de)serialized, for example
+/// from a configuration file, or estimated from data, see [`recalibrate_scoring`]. Any missing
+/// field is set to its default value (see [`AlignScoring`]). Use [`Self::scoring`] to get an
+/// [`AlignScoring`] to use in alignments.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecalibratedScoring {
+    /// See [`AlignScoring::mismatch`]
+    pub mismatch: i8,
+    /// See [`AlignScoring::mass_mismatch`]
+    pub mass_mismatch: i8,
+    /// See [`AlignScoring::mass_base`]
+    pub mass_base: i8,
+    /// See [`AlignScoring::rotated`]
+    pub rotated: i8,
+    /// See [`AlignScoring::isobaric`]
+    pub isobaric: i8,
+    /// See [`AlignScoring::gap_start`]
+    pub gap_start: i8,
+    /// See [`AlignScoring::gap_extend`]
+    pub gap_extend: i8,
+    /// See [`AlignScoring::matrix`], a recalibrated matrix is stored as a custom matrix
+    pub matrix: ScoringMatrix,
+    /// See [`AlignScoring::tolerance`]
+    pub tolerance: Tolerance<OrderedMass>,
+    /// See [`AlignScoring::mass_mode`]
+    pub mass_mode: MassMode,
+    /// See [`AlignScoring::confidence_weighting`]
+    pub confidence_weighting: bool,
+    /// See [`AlignScoring::labeling`]
+    pub labeling: IsotopeLabeling,
+    /// The number of aligned one to one residue pairs the matrix is based on, 0 if these
+    /// parameters were not estimated from data
+    pub observed_pairs: usize,
+}
+
+impl RecalibratedScoring {
+    /// Get the scoring parameters to use in an alignment.
+    pub fn scoring(&self) -> AlignScoring<'_> {
+        AlignScoring {
+            mismatch: self.mismatch,
+            mass_mismatch: self.mass_mismatch,
+            mass_base: self.mass_base,
+            rotated: self.rotated,
+            isobaric: self.isobaric,
+            gap_start: self.gap_start,
+            gap_extend: self.gap_extend,
+            matrix: self.matrix.matrix(),
+            tolerance: self.tolerance,
+            mass_mode: self.mass_mode,
+            confidence_weighting: self.confidence_weighting,
+            labeling: self.labeling,
+        }
+    }
+}
+
+impl Default for RecalibratedScoring {
+    fn default() -> Self {
+        AlignScoring::default().into()
+    }
+}
+
+impl From<AlignScoring<'_>> for RecalibratedScoring {
+    fn from(value: AlignScoring<'_>) -> Self {
+        Self {
+            mismatch: value.mismatch,
+            mass_mismatch: value.mass_mismatch,
+            mass_base: value.mass_base,
+            rotated: value.rotated,
+            isobaric: value.isobaric,
+            gap_start: value.gap_start,
+            gap_extend: value.gap_extend,
+            matrix: ScoringMatrix::from_matrix(value.matrix),
+            tolerance: value.tolerance,
+            mass_mode: value.mass_mode,
+            confidence_weighting: value.confidence_weighting,
+            labeling: value.labeling,
+            observed_pairs: 0,
+        }
+    }
+}
+
+/// The settings for [`recalibrate_scoring`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RecalibrationParameters {
+    /// The scale of the log odds scores, scores are given in `1/scale` bits. BLOSUM62 uses half
+    /// bits, so a scale of 2.
+    ///
+    /// Default: 2.0.
+    pub scale: f64,
+    /// The minimal number of observations of an amino acid (in any aligned pair) before its row
+    /// and column are re-estimated, otherwise the scores from the prior matrix are kept.
+    ///
+    /// Default: 20.
+    pub minimal_observations: usize,
+    /// The pseudo count added to every pair of re-estimated amino acids, this prevents pairs that
+    /// are never observed from getting extreme scores.
+    ///
+    /// Default: 1.0.
+    pub pseudo_count: f64,
+    /// The fraction of the alignment mass errors that should fall within the estimated tolerance.
+    ///
+    /// Default: 0.95.
+    pub tolerance_quantile: f64,
+}
+
+impl Default for RecalibrationParameters {
+    fn default() -> Self {
+        Self {
+            scale: 2.0,
+            minimal_observations: 20,
+            pseudo_count: 1.0,
+            tolerance_quantile: 0.95,
+        }
+    }
+}
+
+/// Re-estimate the substitution matrix, the affine gap penalties, and the mass tolerance from a
+/// set of trusted alignments, for example alignments of de novo peptides against their database
+/// match for high scoring PSMs.
+///
+/// The substitution scores are calculated as log odds scores `scale * log2(p_ab / (p_a * p_b))`,
+/// similar to how the BLOSUM matrices are made, from all one to one steps in the alignments. Any
+/// amino acid that is observed less than [`RecalibrationParameters::minimal_observations`] times
+/// keeps the scores from the prior. The gap penalties are estimated from the frequency of gap
+/// openings and the average gap length. The tolerance is set to the given quantile of the ppm
+/// errors of all mass based steps (identical, isobaric, and rotated) in the alignments. Any
+/// parameter for which no data is available keeps the value from the prior.
+pub fn recalibrate_scoring<'a, A: 'a, B: 'a>(
+    alignments: impl IntoIterator<Item = &'a Alignment<'a, A, B>>,
+    prior: AlignScoring<'_>,
+    parameters: RecalibrationParameters,
+) -> RecalibratedScoring {
+    let mut result = RecalibratedScoring::from(prior);
+    let mut matrix = *prior.matrix;
+    let mut pairs = vec![[0_usize; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER];
+    let mut positions = 0_usize;
+    let mut gaps = 0_usize;
+    let mut gap_length = 0_usize;
+    let mut errors = Vec::new();
+
+    for alignment in alignments {
+        let (mut index_a, mut index_b) = alignment.start();
+        let mut previous_gap = None;
+        for piece in alignment.path() {
+            if piece.step_a == 1
+                && piece.step_b == 1
+                && matches!(
+                    piece.match_type,
+                    MatchType::FullIdentity | MatchType::IdentityMassMismatch | MatchType::Mismatch
+                )
+            {
+                let a = alignment.seq_a().sequence()[index_a].aminoacid.aminoacid() as usize;
+                let b = alignment.seq_b().sequence()[index_b].aminoacid.aminoacid() as usize;
+                // Count symmetrically as the direction of the alignment is not meaningful
+                pairs[a][b] += 1;
+                pairs[b][a] += 1;
+            }
+            if matches!(
+                piece.match_type,
+                MatchType::FullIdentity | MatchType::Isobaric | MatchType::Rotation
+            ) {
+                let a = stretch_masses(
+                    &alignment.seq_a().sequence()[index_a..index_a + piece.step_a as usize],
+                    prior.mass_mode,
+                    prior.labeling,
+                );
+                let b = stretch_masses(
+                    &alignment.seq_b().sequence()[index_b..index_b + piece.step_b as usize],
+                    prior.mass_mode,
+                    prior.labeling,
+                );
+                if let Some(error) = a
+                    .iter()
+                    .flat_map(|a| b.iter().map(move |b| a.ppm(*b)))
+                    .map(|e| e.get::<crate::system::ratio::ppm>())
+                    .min_by(f64::total_cmp)
+                {
+                    errors.push(error);
+                }
+            }
+            if piece.match_type == MatchType::Gap {
+                // Every gap piece is a single position, a new gap is only opened if the previous
+                // piece was not a gap or was a gap in the other sequence
+                let gap_in_b = piece.step_a > 0;
+                if previous_gap != Some(gap_in_b) {
+                    gaps += 1;
+                }
+                previous_gap = Some(gap_in_b);
+                gap_length += piece.step_a.max(piece.step_b) as usize;
+            } else {
+                previous_gap = None;
+            }
+            positions += piece.step_a.max(piece.step_b) as usize;
+            index_a += piece.step_a as usize;
+            index_b += piece.step_b as usize;
+        }
+    }
+
+    // Substitution matrix
+    let observations: Vec<usize> = pairs.iter().map(|row| row.iter().sum()).collect();
+    let estimated: Vec<usize> = (0..AminoAcid::TOTAL_NUMBER)
+        .filter(|aa| observations[*aa] >= parameters.minimal_observations)
+        .collect();
+    result.observed_pairs = observations.iter().sum::<usize>() / 2;
+    if !estimated.is_empty() {
+        let total: f64 = estimated
+            .iter()
+            .flat_map(|a| estimated.iter().map(move |b| (*a, *b)))
+            .map(|(a, b)| pairs[a][b] as f64 + parameters.pseudo_count)
+            .sum();
+        let background: Vec<f64> = (0..AminoAcid::TOTAL_NUMBER)
+            .map(|a| {
+                estimated
+                    .iter()
+                    .map(|b| pairs[a][*b] as f64 + parameters.pseudo_count)
+                    .sum::<f64>()
+                    / total
+            })
+            .collect();
+        for a in &estimated {
+            for b in &estimated {
+                let p = (pairs[*a][*b] as f64 + parameters.pseudo_count) / total;
+                matrix[*a][*b] =
+                    to_score(parameters.scale * (p / (background[*a] * background[*b])).log2());
+            }
+        }
+    }
+
+    result.matrix = ScoringMatrix::from_matrix(&matrix);
+
+    // Gap penalties
+    if gaps > 0 && positions > 0 {
+        let mean_length = gap_length as f64 / gaps as f64;
+        result.gap_start = to_score(parameters.scale * (gaps as f64 / positions as f64).log2());
+        if mean_length > 1.0 {
+            result.gap_extend =
+                to_score(parameters.scale * ((mean_length - 1.0) / mean_length).log2()).min(-1);
+        }
+    }
+
+    // Tolerance
+    errors.retain(|e| e.is_finite());
+    errors.sort_unstable_by(f64::total_cmp);
+    if !errors.is_empty() {
+        let index = ((errors.len() - 1) as f64 * parameters.tolerance_quantile.clamp(0.0, 1.0))
+            .round() as usize;
+        if errors[index] > 0.0 {
+            result.tolerance = Tolerance::new_ppm(errors[index]);
+        }
+    }
+
+    result
+}
+
+/// Get all possible masses for a stretch of sequence
+fn stretch_masses<T>(
+    sequence: &[SequenceElement<T>],
+    mass_mode: MassMode,
+    labeling: IsotopeLabeling,
+) -> Multi<Mass> {
+    sequence
+        .iter()
+        .enumerate()
+        .map(|(index, element)| {
+            element
+                .formulas_all(
+                    &[],
+                    &[],
+                    &mut Vec::new(),
+                    false,
+                    SequencePosition::Index(index),
+                    0,
+                )
+                .0
+        })
+        .sum::<Multi<MolecularFormula>>()
+        .iter()
+        .map(|f| labeling.apply(f.clone()).mass(mass_mode))
+        .collect()
+}
+
+/// Round a floating point score to the nearest score that fits in the matrix
+#[allow(clippy::cast_possible_truncation)]
+fn to_score(value: f64) -> i8 {
+    value.round().clamp(f64::from(i8::MIN), f64::from(i8::MAX)) as i8
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{align::AlignType, peptidoform::SimpleLinear, Peptidoform};
+
+    fn linear(aa: &str) -> Peptidoform<SimpleLinear> {
+        Peptidoform::pro_forma(aa, None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn recalibrate_favours_observed_substitutions() {
+        let pairs: Vec<_> = (0..30)
+            .map(|_| (linear("WAKEDMWDSW"), linear("WAKEDMFDSW")))
+            .collect();
+        let alignments: Vec<_> = pairs
+            .iter()
+            .map(|(a, b)| {
+                super::super::align::<1, SimpleLinear, SimpleLinear>(
+                    a,
+                    b,
+                    AlignScoring::default(),
+                    AlignType::GLOBAL,
+                )
+            })
+            .collect();
+        let recalibrated = recalibrate_scoring(
+            &alignments,
+            AlignScoring::default(),
+            RecalibrationParameters::default(),
+        );
+        assert_eq!(recalibrated.observed_pairs, 300);
+        let w = AminoAcid::Tryptophan as usize;
+        let f = AminoAcid::Phenylalanine as usize;
+        assert!(recalibrated.matrix.matrix()[w][f] > AlignScoring::default().matrix[w][f]);
+        assert_eq!(
+            recalibrated.matrix.matrix()[w][f],
+            recalibrated.matrix.matrix()[f][w]
+        );
+        // No gaps and no mass errors so these should be unchanged
+        assert_eq!(recalibrated.gap_start, AlignScoring::default().gap_start);
+        assert_eq!(recalibrated.tolerance, AlignScoring::default().tolerance);
+
+        let json = serde_json::to_string(&recalibrated).unwrap();
+        let back: RecalibratedScoring = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, recalibrated);
+        let _ = back.scoring();
+    }
+
+    #[test]
+    fn recalibrate_gaps() {
+        let pairs: Vec<_> = (0..10)
+            .flat_map(|_| {
+                [
+                    (linear("WAKEDMWDSW"), linear("WAKEDHHMWDSW")),
+                    (linear("WAKEDMWDSW"), linear("WAKEDHMWDSW")),
+                ]
+            })
+            .collect();
+        let alignments: Vec<_> = pairs
+            .iter()
+            .map(|(a, b)| {
+                super::super::align::<1, SimpleLinear, SimpleLinear>(
+                    a,
+                    b,
+                    AlignScoring::default(),
+                    AlignType::GLOBAL,
+                )
+            })
+            .collect();
+        assert_eq!(alignments[0].short(), "5=2I5=");
+        let recalibrated = recalibrate_scoring(
+            &alignments,
+            AlignScoring::default(),
+            RecalibrationParameters::default(),
+        );
+        // 2 gap openings in 23 positions and a mean gap length of 1.5
+        assert_eq!(recalibrated.gap_start, -7);
+        assert_eq!(recalibrated.gap_extend, -3);
+    }
+}
@@ -1,6 +1,13 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{system::OrderedMass, AminoAcid, MassMode, Tolerance};
+use super::RecalibratedScoring;
+use crate::{
+    error::{Context, CustomError},
+    system::OrderedMass,
+    AminoAcid, IsotopeLabeling, MassMode, Tolerance,
+};
 
 /// The type of a single match step
 #[derive(
@@ -78,6 +85,19 @@ pub struct AlignScoring<'a> {
     ///
     /// Default: Monoisotopic.
     pub mass_mode: MassMode,
+    /// Weigh mismatch and isobaric steps by the local confidence of the residues (see
+    /// [`crate::SequenceElement::confidence`]). The weight of a step is the lowest of the mean
+    /// confidence of the residues on both sides, residues without a confidence count as fully
+    /// confident. The mismatch score is scaled towards 0 and the isobaric score towards the
+    /// rotated score as the weight goes down, so errors in low confidence regions of de novo
+    /// sequences are penalised less.
+    ///
+    /// Default: false.
+    pub confidence_weighting: bool,
+    /// The full isotope labeling of both sequences, applied to all masses in the alignment.
+    ///
+    /// Default: [`IsotopeLabeling::None`].
+    pub labeling: IsotopeLabeling,
 }
 
 impl Default for AlignScoring<'static> {
@@ -93,10 +113,191 @@ impl Default for AlignScoring<'static> {
             matrix: matrices::BLOSUM62,
             tolerance: crate::Tolerance::new_ppm(10.0),
             mass_mode: MassMode::Monoisotopic,
+            confidence_weighting: false,
+            labeling: IsotopeLabeling::None,
         }
     }
 }
 
+/// A substitution matrix for [`AlignScoring::matrix`], either one of the built in matrices (see
+/// [`super::matrix`]) or a fully custom matrix. This can be (de)serialized, built in matrices are
+/// stored by name and custom matrices as the full matrix indexed by the amino acid.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ScoringMatrix {
+    /// [`matrices::BLOSUM45`]
+    Blosum45,
+    /// [`matrices::BLOSUM50`]
+    Blosum50,
+    /// [`matrices::BLOSUM62`]
+    #[default]
+    Blosum62,
+    /// [`matrices::BLOSUM80`]
+    Blosum80,
+    /// [`matrices::BLOSUM90`]
+    Blosum90,
+    /// [`matrices::IDENTITY`]
+    Identity,
+    /// [`matrices::PAM30`]
+    Pam30,
+    /// [`matrices::PAM70`]
+    Pam70,
+    /// [`matrices::PAM250`]
+    Pam250,
+    /// A custom matrix, indexed by the amino acid (see [`AminoAcid::TOTAL_NUMBER`])
+    Custom(Box<[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER]>),
+}
+
+impl ScoringMatrix {
+    /// All built in matrices
+    pub const BUILT_IN: &'static [Self] = &[
+        Self::Blosum45,
+        Self::Blosum50,
+        Self::Blosum62,
+        Self::Blosum80,
+        Self::Blosum90,
+        Self::Identity,
+        Self::Pam30,
+        Self::Pam70,
+        Self::Pam250,
+    ];
+
+    /// Get the matrix
+    pub fn matrix(&self) -> &[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER] {
+        match self {
+            Self::Blosum45 => matrices::BLOSUM45,
+            Self::Blosum50 => matrices::BLOSUM50,
+            Self::Blosum62 => matrices::BLOSUM62,
+            Self::Blosum80 => matrices::BLOSUM80,
+            Self::Blosum90 => matrices::BLOSUM90,
+            Self::Identity => matrices::IDENTITY,
+            Self::Pam30 => matrices::PAM30,
+            Self::Pam70 => matrices::PAM70,
+            Self::Pam250 => matrices::PAM250,
+            Self::Custom(matrix) => matrix,
+        }
+    }
+
+    /// Get the scoring matrix for the given matrix, if it is identical to one of the built in
+    /// matrices that one is returned, otherwise a custom matrix.
+    pub fn from_matrix(matrix: &[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER]) -> Self {
+        Self::BUILT_IN
+            .iter()
+            .find(|m| m.matrix() == matrix)
+            .cloned()
+            .unwrap_or_else(|| Self::Custom(Box::new(*matrix)))
+    }
+
+    /// Create a custom matrix from rows of scores, for example when the matrix is supplied at
+    /// runtime. The matrix is indexed by the amino acid, so it has to be square with
+    /// [`AminoAcid::TOTAL_NUMBER`] rows and columns.
+    /// # Errors
+    /// If the matrix does not have the correct size.
+    pub fn custom(rows: &[impl AsRef<[i8]>]) -> Result<Self, CustomError> {
+        let invalid = || {
+            CustomError::error(
+                "Invalid scoring matrix",
+                format!(
+                    "A scoring matrix has to have {0} rows of {0} columns",
+                    AminoAcid::TOTAL_NUMBER
+                ),
+                Context::None,
+            )
+        };
+        if rows.len() != AminoAcid::TOTAL_NUMBER {
+            return Err(invalid());
+        }
+        let mut matrix = [[0; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER];
+        for (target, row) in matrix.iter_mut().zip(rows) {
+            *target = row.as_ref().try_into().map_err(|_| invalid())?;
+        }
+        Ok(Self::Custom(Box::new(matrix)))
+    }
+}
+
+impl std::fmt::Display for ScoringMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Blosum45 => "BLOSUM45",
+                Self::Blosum50 => "BLOSUM50",
+                Self::Blosum62 => "BLOSUM62",
+                Self::Blosum80 => "BLOSUM80",
+                Self::Blosum90 => "BLOSUM90",
+                Self::Identity => "Identity",
+                Self::Pam30 => "PAM30",
+                Self::Pam70 => "PAM70",
+                Self::Pam250 => "PAM250",
+                Self::Custom(_) => "Custom",
+            }
+        )
+    }
+}
+
+impl FromStr for ScoringMatrix {
+    type Err = CustomError;
+    /// Parse the name of a built in matrix, case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::BUILT_IN
+            .iter()
+            .find(|m| m.to_string().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| {
+                CustomError::error(
+                    "Unknown scoring matrix",
+                    format!(
+                        "The scoring matrix '{s}' is not a built in matrix, use one of: {}",
+                        Self::BUILT_IN
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    Context::None,
+                )
+            })
+    }
+}
+
+impl Serialize for AlignScoring<'_> {
+    /// Serialized as [`RecalibratedScoring`], so built in matrices are stored by name.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RecalibratedScoring::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AlignScoring<'static> {
+    /// Deserialized from [`RecalibratedScoring`], this only supports the built in matrices as a
+    /// custom matrix cannot be borrowed, deserialize into [`RecalibratedScoring`] for those.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let owned = RecalibratedScoring::deserialize(deserializer)?;
+        let matrix = ScoringMatrix::BUILT_IN
+            .iter()
+            .find(|m| **m == owned.matrix)
+            .ok_or_else(|| {
+                serde::de::Error::custom(
+                    "a custom matrix cannot be deserialized into AlignScoring, use RecalibratedScoring",
+                )
+            })?
+            .matrix();
+        Ok(Self {
+            mismatch: owned.mismatch,
+            mass_mismatch: owned.mass_mismatch,
+            mass_base: owned.mass_base,
+            rotated: owned.rotated,
+            isobaric: owned.isobaric,
+            gap_start: owned.gap_start,
+            gap_extend: owned.gap_extend,
+            matrix,
+            tolerance: owned.tolerance,
+            mass_mode: owned.mass_mode,
+            confidence_weighting: owned.confidence_weighting,
+            labeling: owned.labeling,
+        })
+    }
+}
+
 /// Matrices from: <https://www.ncbi.nlm.nih.gov/IEB/ToolBox/CPP_DOC/lxr/source/src/util/tables/> and <https://www.ncbi.nlm.nih.gov/IEB/ToolBox/C_DOC/lxr/source/data/>.
 /// The UO columns are added by me (see top left for the original matrix used by me) (B/J/Z is the rounded down average of the corresponding non ambiguous AAs) (All these are exactly the same for all matrices).
 pub mod matrices {
@@ -129,3 +330,75 @@ pub mod matrices {
     pub const PAM250: &[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER] =
         include!("matrices/pam250.txt");
 }
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_names() {
+        for matrix in ScoringMatrix::BUILT_IN {
+            assert_eq!(
+                matrix.to_string().parse::<ScoringMatrix>(),
+                Ok(matrix.clone())
+            );
+            assert_eq!(&ScoringMatrix::from_matrix(matrix.matrix()), matrix);
+        }
+        assert_eq!("pam30".parse::<ScoringMatrix>(), Ok(ScoringMatrix::Pam30));
+        let error = "BLOSUM100".parse::<ScoringMatrix>().unwrap_err();
+        assert!(error.to_string().contains("BLOSUM62, BLOSUM80"));
+    }
+
+    #[test]
+    fn custom_matrix() {
+        let mut rows = vec![vec![-1_i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER];
+        for (index, row) in rows.iter_mut().enumerate() {
+            row[index] = 5;
+        }
+        let matrix = ScoringMatrix::custom(&rows).unwrap();
+        assert!(matches!(matrix, ScoringMatrix::Custom(_)));
+        assert_eq!(matrix.matrix()[3][3], 5);
+        assert_eq!(matrix.matrix()[3][4], -1);
+        assert!(ScoringMatrix::custom(&rows[1..]).is_err());
+        rows[2].pop();
+        assert!(ScoringMatrix::custom(&rows).is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let scoring = AlignScoring {
+            matrix: matrices::PAM70,
+            gap_start: -6,
+            ..AlignScoring::default()
+        };
+        let json = serde_json::to_string(&scoring).unwrap();
+        assert!(json.contains("\"Pam70\""));
+        let parsed: RecalibratedScoring = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.matrix, ScoringMatrix::Pam70);
+        assert_eq!(parsed.scoring().matrix, matrices::PAM70);
+        assert_eq!(parsed.gap_start, -6);
+        let back: AlignScoring = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, scoring);
+
+        let parsed: RecalibratedScoring =
+            serde_json::from_str(r#"{"matrix":"Identity","mismatch":-3}"#).unwrap();
+        assert_eq!(parsed.matrix, ScoringMatrix::Identity);
+        assert_eq!(parsed.mismatch, -3);
+        assert_eq!(parsed.gap_start, AlignScoring::default().gap_start);
+
+        let mut custom = *matrices::BLOSUM62;
+        custom[0][0] = 12;
+        let scoring = AlignScoring {
+            matrix: &custom,
+            ..AlignScoring::default()
+        };
+        let parameters = RecalibratedScoring::from(scoring);
+        assert!(matches!(parameters.matrix, ScoringMatrix::Custom(_)));
+        let json = serde_json::to_string(&parameters).unwrap();
+        let parsed: RecalibratedScoring = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.matrix, parameters.matrix);
+        assert_eq!(parsed.scoring(), scoring);
+        assert!(serde_json::from_str::<AlignScoring>(&json).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "rand")]
+use crate::{
+    align::{align, AlignScoring, AlignType},
+    peptidoform::{AtMax, SimpleLinear},
+    Peptidoform,
+};
+
+use super::Alignment;
+
+/// The distribution of the absolute scores of alignments of unrelated sequences. This is modelled
+/// as a Gumbel (extreme value) distribution, as in BLAST. The normalised score of an alignment
+/// depends heavily on the lengths of the sequences and the [`AlignType`](super::AlignType), so
+/// these are hard to compare and hard to set a threshold on. The bit score and E value derived
+/// from this distribution take the lengths into account and, if the distribution is estimated for
+/// the align type that is used, also the align type.
+///
+/// For a score `S` of an alignment of sequences with length `m` and `n` the bit score is
+/// `(λS - ln K) / ln 2` and the E value, the number of alignments with at least this score
+/// expected by chance, is `K m n e^(-λS)`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreDistribution {
+    /// The scale parameter λ
+    lambda: f64,
+    /// The search space parameter K
+    k: f64,
+}
+
+impl ScoreDistribution {
+    /// Create a score distribution from known parameters λ (`lambda`) and `k`.
+    pub const fn new(lambda: f64, k: f64) -> Self {
+        Self { lambda, k }
+    }
+
+    /// Fit the distribution to the given absolute scores of alignments of unrelated sequences
+    /// with length `len_a` and `len_b` with the method of moments. Returns `None` if there are
+    /// less than two scores or if all scores are identical.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fit(scores: &[isize], len_a: usize, len_b: usize) -> Option<Self> {
+        if scores.len() < 2 {
+            return None;
+        }
+        let n = scores.len() as f64;
+        let mean = scores.iter().map(|s| *s as f64).sum::<f64>() / n;
+        let variance = scores
+            .iter()
+            .map(|s| (*s as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0);
+        if variance <= 0.0 {
+            return None;
+        }
+        let lambda = std::f64::consts::PI / (variance * 6.0).sqrt();
+        // Location of the Gumbel distribution, using the Euler–Mascheroni constant
+        let mu = mean - 0.577_215_664_901_532_9 / lambda;
+        let search_space = (len_a.max(1) * len_b.max(1)) as f64;
+        Some(Self {
+            lambda,
+            k: (lambda * mu).exp() / search_space,
+        })
+    }
+
+    /// Only available with feature `rand`.
+    /// Estimate the distribution by aligning `seq_a` to `samples` random shuffles of `seq_b`,
+    /// with the same scoring and align type as the real alignments. The shuffles are generated
+    /// from the given seed, so the estimate is reproducible. Returns `None` if the scores of the
+    /// shuffles are all identical or if `samples` is less than two.
+    #[cfg(feature = "rand")]
+    pub fn estimate<const STEPS: u16, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>>(
+        seq_a: &Peptidoform<A>,
+        seq_b: &Peptidoform<B>,
+        scoring: AlignScoring<'_>,
+        align_type: AlignType,
+        samples: usize,
+        seed: u64,
+    ) -> Option<Self> {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut residues = seq_b.sequence().to_vec();
+        let scores: Vec<isize> = (0..samples)
+            .map(|_| {
+                residues.shuffle(&mut rng);
+                let shuffled: Peptidoform<B> = residues.iter().cloned().collect();
+                align::<STEPS, A, B>(seq_a, &shuffled, scoring, align_type)
+                    .score()
+                    .absolute
+            })
+            .collect();
+        Self::fit(&scores, seq_a.len(), seq_b.len())
+    }
+
+    /// The scale parameter λ.
+    pub const fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    /// The search space parameter K.
+    pub const fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// The bit score for the given absolute score, this is independent of the sequence lengths.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bit_score(&self, score: isize) -> f64 {
+        self.lambda.mul_add(score as f64, -self.k.ln()) / std::f64::consts::LN_2
+    }
+
+    /// The number of alignments with at least this score that are expected by chance when
+    /// aligning sequences of these lengths.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn e_value(&self, score: isize, len_a: usize, len_b: usize) -> f64 {
+        (len_a * len_b) as f64 * (-self.bit_score(score)).exp2()
+    }
+
+    /// The probability of finding at least one alignment with at least this score by chance when
+    /// aligning sequences of these lengths.
+    pub fn p_value(&self, score: isize, len_a: usize, len_b: usize) -> f64 {
+        -(-self.e_value(score, len_a, len_b)).exp_m1()
+    }
+
+    /// The lowest absolute score that has at most the given E value for sequences of these
+    /// lengths, this can be used as a length aware threshold.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn threshold(&self, e_value: f64, len_a: usize, len_b: usize) -> isize {
+        ((self.k * (len_a * len_b) as f64 / e_value).ln() / self.lambda).ceil() as isize
+    }
+
+    /// The bit score of this alignment, see [`Self::bit_score`].
+    pub fn alignment_bit_score<A, B>(&self, alignment: &Alignment<'_, A, B>) -> f64 {
+        self.bit_score(alignment.score().absolute)
+    }
+
+    /// The E value of this alignment, using the full lengths of both sequences as the search
+    /// space, see [`Self::e_value`].
+    pub fn alignment_e_value<A, B>(&self, alignment: &Alignment<'_, A, B>) -> f64 {
+        self.e_value(
+            alignment.score().absolute,
+            alignment.seq_a().len(),
+            alignment.seq_b().len(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit() {
+        assert_eq!(ScoreDistribution::fit(&[1], 10, 10), None);
+        assert_eq!(ScoreDistribution::fit(&[4, 4, 4], 10, 10), None);
+        let distribution =
+            ScoreDistribution::fit(&[10, 12, 9, 15, 11, 13, 10, 20], 10, 10).unwrap();
+        assert!(distribution.lambda() > 0.0);
+        let threshold = distribution.threshold(0.01, 10, 10);
+        assert!(distribution.e_value(threshold, 10, 10) <= 0.01);
+        assert!(distribution.e_value(threshold - 1, 10, 10) > 0.01);
+        assert!(distribution.e_value(threshold, 100, 100) > 0.01);
+        assert!(distribution.bit_score(threshold) > distribution.bit_score(threshold - 1));
+        let p = distribution.p_value(threshold, 10, 10);
+        assert!(p > 0.0 && p <= 0.01);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn estimate() {
+        let a = Peptidoform::pro_forma("ANGARSPEPTIDEWHKLMQ", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let distribution = ScoreDistribution::estimate::<4, SimpleLinear, SimpleLinear>(
+            &a,
+            &a,
+            AlignScoring::default(),
+            AlignType::LOCAL,
+            50,
+            42,
+        )
+        .unwrap();
+        assert_eq!(
+            ScoreDistribution::estimate::<4, SimpleLinear, SimpleLinear>(
+                &a,
+                &a,
+                AlignScoring::default(),
+                AlignType::LOCAL,
+                50,
+                42,
+            ),
+            Some(distribution)
+        );
+        let alignment = align::<4, SimpleLinear, SimpleLinear>(
+            &a,
+            &a,
+            AlignScoring::default(),
+            AlignType::LOCAL,
+        );
+        assert!(distribution.alignment_e_value(&alignment) < 1e-3);
+        assert!(distribution.alignment_bit_score(&alignment) > 10.0);
+    }
+}
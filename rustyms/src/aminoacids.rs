@@ -297,7 +297,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::a(n_pos),
                 n_term,
-                ions.a.1,
+                &ions.a.1,
                 charge_carriers,
                 ions.a.2,
             ));
@@ -310,7 +310,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::b(n_pos),
                 n_term,
-                ions.b.1,
+                &ions.b.1,
                 charge_carriers,
                 ions.b.2,
             ));
@@ -323,7 +323,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::c(n_pos),
                 n_term,
-                ions.c.1,
+                &ions.c.1,
                 charge_carriers,
                 ions.c.2,
             ));
@@ -338,7 +338,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::d(n_pos),
                 n_term,
-                ions.d.1,
+                &ions.d.1,
                 charge_carriers,
                 ions.d.2,
             ));
@@ -350,7 +350,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::v(c_pos),
                 c_term,
-                ions.v.1,
+                &ions.v.1,
                 charge_carriers,
                 ions.v.2,
             ));
@@ -365,7 +365,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::w(c_pos),
                 c_term,
-                ions.w.1,
+                &ions.w.1,
                 charge_carriers,
                 ions.w.2,
             ));
@@ -378,7 +378,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::x(c_pos),
                 c_term,
-                ions.x.1,
+                &ions.x.1,
                 charge_carriers,
                 ions.x.2,
             ));
@@ -391,7 +391,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::y(c_pos),
                 c_term,
-                ions.y.1,
+                &ions.y.1,
                 charge_carriers,
                 ions.y.2,
             ));
@@ -404,7 +404,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::z(c_pos),
                 c_term,
-                ions.z.1,
+                &ions.z.1,
                 charge_carriers,
                 ions.z.2,
             ));
@@ -415,7 +415,7 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::z·(c_pos),
                 c_term,
-                ions.z.1,
+                &ions.z.1,
                 charge_carriers,
                 ions.z.2,
             ));
@@ -0,0 +1,308 @@
+//! An on-disk cache of annotated spectra, for batch tools that repeatedly annotate the same
+//! spectrum and peptidoform pairs, for example while tuning parameters.
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::{annotation_cache::AnnotationCache, rawfile::mgf, system::{e, usize::Charge}, *};
+//! let spectra = mgf::open("data/example.mgf")?;
+//! let peptide = CompoundPeptidoformIon::pro_forma("WFWF", None)?;
+//! let model = Model::cid_hcd();
+//! let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &model);
+//! let mut cache = AnnotationCache::open("annotations.cache")?;
+//! let annotated = cache.annotate(&spectra[0], peptide, &fragments, &model, MassMode::Monoisotopic)?;
+//! cache.save()?;
+//! # Ok(()) }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{Context, CustomError},
+    AnnotatableSpectrum, AnnotatedSpectrum, CompoundPeptidoformIon, Fragment, MassMode, Model,
+};
+
+/// A cache of annotation results, keyed on a SHA-256 digest of the spectrum, peptidoform,
+/// fragments, model, and mass mode. The cache is stored on disk together with the version of this crate, if the
+/// version does not match the cache is invalidated so changes in the annotation do not result in
+/// stale results.
+#[derive(Clone, Debug)]
+pub struct AnnotationCache {
+    /// The location of the cache file
+    path: PathBuf,
+    /// All cached annotations
+    entries: HashMap<Key, AnnotatedSpectrum>,
+    /// If there are any entries that are not saved yet
+    unsaved: bool,
+    /// The number of annotations that were found in the cache
+    hits: usize,
+    /// The number of annotations that were not found in the cache
+    misses: usize,
+}
+
+/// The digest of all inputs of an annotation
+type Key = [u8; 32];
+
+/// The cache as stored on disk, the version is read first so the entries are only read if the
+/// cache was written by this version
+#[derive(Serialize, Deserialize)]
+struct StoredCache {
+    version: String,
+    entries: HashMap<Key, AnnotatedSpectrum>,
+}
+
+impl AnnotationCache {
+    /// Open a cache, if the file exists the entries are loaded from it, otherwise this starts
+    /// empty. If the file was written by another version of this crate it is ignored.
+    /// # Errors
+    /// If the file exists but could not be opened or is corrupt.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        let path = path.as_ref().to_path_buf();
+        let entries = if path.exists() {
+            let file = File::open(&path).map_err(|e| {
+                CustomError::error("Could not open annotation cache", e, context(&path))
+            })?;
+            let mut reader = BufReader::new(file);
+            let corrupt = |error: bincode::Error| {
+                CustomError::error(
+                    "Corrupt annotation cache",
+                    format!("The annotation cache could not be read, remove the file to start a new cache: {error}"),
+                    context(&path),
+                )
+            };
+            let version: String = bincode::deserialize_from(&mut reader).map_err(corrupt)?;
+            if version == env!("CARGO_PKG_VERSION") {
+                bincode::deserialize_from(&mut reader).map_err(corrupt)?
+            } else {
+                HashMap::new()
+            }
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            path,
+            entries,
+            unsaved: false,
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    /// Annotate the spectrum (see [`AnnotatableSpectrum::annotate`]), or return the cached
+    /// result if this exact annotation was done before.
+    /// # Errors
+    /// If any of the inputs could not be serialized to determine the cache key.
+    pub fn annotate<S: AnnotatableSpectrum + Serialize>(
+        &mut self,
+        spectrum: &S,
+        peptide: CompoundPeptidoformIon,
+        fragments: &[Fragment],
+        model: &Model,
+        mode: MassMode,
+    ) -> Result<AnnotatedSpectrum, CustomError> {
+        let key = key(spectrum, &peptide, fragments, model, mode).map_err(|e| {
+            CustomError::error("Could not determine annotation cache key", e, Context::None)
+        })?;
+        if let Some(annotated) = self.entries.get(&key) {
+            self.hits += 1;
+            return Ok(annotated.clone());
+        }
+        self.misses += 1;
+        let annotated = spectrum.annotate(peptide, fragments, model, mode);
+        self.entries.insert(key, annotated.clone());
+        self.unsaved = true;
+        Ok(annotated)
+    }
+
+    /// The number of cached annotations
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of annotations since opening that were found in the cache (hits) and that had
+    /// to be annotated (misses)
+    pub const fn statistics(&self) -> (usize, usize) {
+        (self.hits, self.misses)
+    }
+
+    /// Remove all cached annotations
+    pub fn clear(&mut self) {
+        self.unsaved |= !self.entries.is_empty();
+        self.entries.clear();
+    }
+
+    /// Save the cache if any annotations were added since the last save. The cache is first
+    /// written to a temporary file and synced to disk, which then replaces the cache, so a crash
+    /// while saving does not corrupt the cache.
+    /// # Errors
+    /// If the cache could not be written.
+    pub fn save(&mut self) -> Result<(), CustomError> {
+        if !self.unsaved {
+            return Ok(());
+        }
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        let file = File::create(&temporary).map_err(|e| {
+            CustomError::error("Could not save annotation cache", e, context(&temporary))
+        })?;
+        let stored = StoredCache {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: std::mem::take(&mut self.entries),
+        };
+        let mut writer = BufWriter::new(file);
+        let result = bincode::serialize_into(&mut writer, &stored);
+        self.entries = stored.entries;
+        result.map_err(|e| {
+            CustomError::error("Could not save annotation cache", e, context(&temporary))
+        })?;
+        writer
+            .into_inner()
+            .map_err(std::io::IntoInnerError::into_error)
+            .and_then(|file| file.sync_all())
+            .map_err(|e| {
+                CustomError::error("Could not save annotation cache", e, context(&temporary))
+            })?;
+        std::fs::rename(&temporary, &self.path).map_err(|e| {
+            CustomError::error("Could not save annotation cache", e, context(&self.path))
+        })?;
+        self.unsaved = false;
+        Ok(())
+    }
+}
+
+/// The digest of all inputs of an annotation
+/// # Errors
+/// If any of the inputs could not be serialized.
+fn key(
+    spectrum: &impl Serialize,
+    peptide: &CompoundPeptidoformIon,
+    fragments: &[Fragment],
+    model: &Model,
+    mode: MassMode,
+) -> Result<Key, bincode::Error> {
+    let mut hasher = Sha256::new();
+    for part in [
+        bincode::serialize(spectrum)?,
+        bincode::serialize(peptide)?,
+        bincode::serialize(fragments)?,
+        bincode::serialize(model)?,
+        bincode::serialize(&mode)?,
+    ] {
+        // Prefix the length so the boundaries between the parts are part of the digest
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Show the path of the cache as error context
+fn context(path: &Path) -> Context {
+    Context::Show {
+        line: path.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        rawfile::mgf,
+        system::{e, usize::Charge},
+    };
+
+    #[test]
+    fn cache() {
+        let path = std::env::temp_dir().join(format!(
+            "rustyms_annotation_cache_test_{}.cache",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let spectra = mgf::open("data/example.mgf").unwrap();
+        let peptide = CompoundPeptidoformIon::pro_forma("WFWF", None).unwrap();
+        let model = Model::cid_hcd();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &model);
+
+        let mut cache = AnnotationCache::open(&path).unwrap();
+        let first = cache
+            .annotate(
+                &spectra[0],
+                peptide.clone(),
+                &fragments,
+                &model,
+                MassMode::Monoisotopic,
+            )
+            .unwrap();
+        let second = cache
+            .annotate(
+                &spectra[0],
+                peptide.clone(),
+                &fragments,
+                &model,
+                MassMode::Monoisotopic,
+            )
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.statistics(), (1, 1));
+        // A different model is a different annotation
+        let other_model = Model::cid_hcd().tolerance(crate::Tolerance::new_ppm(5.0));
+        cache
+            .annotate(
+                &spectra[0],
+                peptide.clone(),
+                &fragments,
+                &other_model,
+                MassMode::Monoisotopic,
+            )
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+        cache.save().unwrap();
+
+        let mut cache = AnnotationCache::open(&path).unwrap();
+        assert_eq!(cache.len(), 2);
+        let third = cache
+            .annotate(
+                &spectra[0],
+                peptide,
+                &fragments,
+                &model,
+                MassMode::Monoisotopic,
+            )
+            .unwrap();
+        assert_eq!(first, third);
+        assert_eq!(cache.statistics(), (1, 0));
+
+        // A cache from another version is ignored
+        let file = File::create(&path).unwrap();
+        bincode::serialize_into(
+            file,
+            &StoredCache {
+                version: "0.0.0".to_string(),
+                entries: cache.entries.clone(),
+            },
+        )
+        .unwrap();
+        assert!(AnnotationCache::open(&path).unwrap().is_empty());
+
+        // A corrupt cache is reported
+        let mut file = File::create(&path).unwrap();
+        bincode::serialize_into(&mut file, env!("CARGO_PKG_VERSION")).unwrap();
+        std::io::Write::write_all(&mut file, &[0xff; 7]).unwrap();
+        drop(file);
+        assert!(AnnotationCache::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
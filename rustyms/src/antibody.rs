@@ -0,0 +1,563 @@
+//! Paired heavy and light chain antibodies, to calculate intact masses and generate the subunits
+//! for middle-down analysis.
+//!
+//! ```rust
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::{antibody::*, Peptidoform};
+//! let heavy = Peptidoform::pro_forma("EVQLVESGGGLVQPGGSLRLSCAASPELLGGPSVFEEQYNSTYRVVSVLTVLHQDWLNGKEYKC", None)?
+//!     .into_linear()
+//!     .unwrap();
+//! let light = Peptidoform::pro_forma("DIQMTQSPSSLSASVGDRVTITCRASQDVNTAVAWYQQKPGEC", None)?
+//!     .into_linear()
+//!     .unwrap();
+//! let antibody = PairedAntibody::new(heavy, light);
+//! let intact = antibody.intact_formulas();
+//! let subunits = antibody
+//!     .subunits(SubunitDigestion::HingeCleavage(HingeProtease::IdeS))
+//!     .unwrap();
+//! assert_eq!(subunits.len(), 3);
+//! # Ok(()) }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modification::SimpleModification, peptidoform::Linear, system::usize::Charge, AminoAcid,
+    Chemical, Fragment, Model, Modification, MolecularFormula, Multi, Peptidoform,
+};
+
+/// An antibody made up of two identical heavy chains and two identical light chains, like `IgG`.
+/// The masses are calculated with the given number of disulfide bonds and with all combinations
+/// of the given glycoforms on the N-glycosylation site of both heavy chains.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PairedAntibody {
+    /// The heavy chain
+    heavy: Peptidoform<Linear>,
+    /// The light chain
+    light: Peptidoform<Linear>,
+    /// The number of disulfide bonds in the intact antibody, `None` pairs up all cysteines
+    disulfides: Option<usize>,
+    /// The index of the N-glycosylation site in the heavy chain, `None` uses the last sequon
+    glycosylation_site: Option<usize>,
+    /// The glycans that can be found on the N-glycosylation site
+    glycoforms: Vec<SimpleModification>,
+}
+
+/// A subunit of an antibody, as generated by [`PairedAntibody::subunits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Subunit {
+    /// The full heavy chain
+    HeavyChain,
+    /// The full light chain
+    LightChain,
+    /// The N terminal part of the heavy chain after hinge cleavage (Fd' for `IdeS`)
+    Fd,
+    /// The C terminal part of the heavy chain after hinge cleavage (Fc/2)
+    Fc,
+    /// A single chain variable fragment, the variable domains of both chains joined by a linker
+    ScFv,
+}
+
+/// The way the antibody is split into subunits, see [`PairedAntibody::subunits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SubunitDigestion {
+    /// Only reduction of the disulfide bonds, giving the heavy and light chain
+    Reduction,
+    /// Cleavage of the heavy chain in the hinge followed by reduction, giving the light chain,
+    /// Fd, and Fc/2
+    HingeCleavage(HingeProtease),
+}
+
+/// A protease that specifically cleaves the heavy chain in the hinge region, see
+/// [`SubunitDigestion::HingeCleavage`]. The cleavage sites are defined on the human `IgG1` hinge
+/// `EPKSCDKTHTCPPCPAPELLGGPSVF`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HingeProtease {
+    /// `IdeS` (`FabRICATOR`) cleaves below the hinge at `ELLG^GPSV`, giving Fd' and Fc/2
+    IdeS,
+    /// Papain cleaves above the hinge at `DKTH^TCPP`, giving Fd and an Fc/2 with the full hinge
+    Papain,
+    /// Limited Lys-C (or `GingisKHAN`) digestion cleaves above the hinge at `SCDK^THTC`
+    LysC,
+}
+
+impl HingeProtease {
+    /// The motif that is recognised, with the index of the cleavage in this motif
+    pub const fn motif(self) -> (&'static str, usize) {
+        match self {
+            Self::IdeS => ("ELLGGPSV", 4),
+            Self::Papain => ("DKTHTCPP", 4),
+            Self::LysC => ("SCDKTHTC", 4),
+        }
+    }
+
+    /// Find the cleavage site in this heavy chain, returns the index of the first residue after
+    /// the cleavage. I and L are seen as identical.
+    pub fn cleavage_site(self, heavy: &Peptidoform<Linear>) -> Option<usize> {
+        let (motif, cut) = self.motif();
+        let motif: Vec<char> = motif.chars().map(canonical).collect();
+        heavy
+            .sequence()
+            .windows(motif.len())
+            .position(|window| {
+                window
+                    .iter()
+                    .zip(&motif)
+                    .all(|(element, m)| canonical(element.aminoacid.aminoacid().char()) == *m)
+            })
+            .map(|index| index + cut)
+    }
+}
+
+/// The default linker for scFv constructs, `(G4S)3`, see [`PairedAntibody::scfv`].
+pub const SCFV_LINKER: &str = "GGGGSGGGGSGGGGS";
+
+/// See I and J as L
+const fn canonical(aminoacid: char) -> char {
+    match aminoacid {
+        'I' | 'J' => 'L',
+        other => other,
+    }
+}
+
+/// A single subunit of an antibody, with the glycan on it if it contains the N-glycosylation site.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AntibodySubunit {
+    /// The kind of subunit
+    pub kind: Subunit,
+    /// The glycan on this subunit, if any
+    pub glycan: Option<SimpleModification>,
+    /// The sequence of the subunit, with the glycan placed on the glycosylation site
+    pub peptidoform: Peptidoform<Linear>,
+}
+
+/// A single glycoform of an intact antibody, as generated by [`PairedAntibody::intact_formulas`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IntactGlycoform {
+    /// The glycans on both heavy chains
+    pub glycans: [Option<SimpleModification>; 2],
+    /// The formulas of the intact antibody with these glycans
+    pub formulas: Multi<MolecularFormula>,
+}
+
+impl PairedAntibody {
+    /// Create a paired antibody from the heavy and light chain sequences.
+    pub const fn new(heavy: Peptidoform<Linear>, light: Peptidoform<Linear>) -> Self {
+        Self {
+            heavy,
+            light,
+            disulfides: None,
+            glycosylation_site: None,
+            glycoforms: Vec::new(),
+        }
+    }
+
+    /// Create a paired antibody from the variable domains and the given constant genes from the
+    /// IMGT germlines, eg `IGHG1` and `IGKC`. The first allele of the constant genes is used.
+    /// Returns `None` if any of the constant genes could not be found for this species.
+    /// Only available with feature `imgt`.
+    #[cfg(feature = "imgt")]
+    pub fn from_variable_domains(
+        heavy_variable: Peptidoform<Linear>,
+        light_variable: Peptidoform<Linear>,
+        species: crate::imgt::Species,
+        heavy_constant: crate::imgt::Gene,
+        light_constant: crate::imgt::Gene,
+    ) -> Option<Self> {
+        let constant = |variable: Peptidoform<Linear>, gene| {
+            crate::imgt::get_germline(species, gene, None).map(|germline| {
+                let mut chain = variable;
+                chain.sequence_mut().extend(
+                    germline
+                        .sequence
+                        .sequence()
+                        .iter()
+                        .map(|element| element.clone().mark()),
+                );
+                chain
+            })
+        };
+        Some(Self::new(
+            constant(heavy_variable, heavy_constant)?,
+            constant(light_variable, light_constant)?,
+        ))
+    }
+
+    /// Set the number of disulfide bonds in the intact antibody.
+    /// Default: all cysteines are paired up, which is 16 for `IgG1`.
+    #[must_use]
+    pub fn with_disulfides(self, disulfides: usize) -> Self {
+        Self {
+            disulfides: Some(disulfides),
+            ..self
+        }
+    }
+
+    /// Set the index of the N-glycosylation site in the heavy chain.
+    /// Default: the last N-glycosylation sequon (N-X-S/T where X is not P) in the heavy chain.
+    #[must_use]
+    pub fn with_glycosylation_site(self, index: usize) -> Self {
+        Self {
+            glycosylation_site: Some(index),
+            ..self
+        }
+    }
+
+    /// Set the glycans that can be found on the N-glycosylation site, for example G0F, G1F, and
+    /// G2F. Default: no glycans.
+    #[must_use]
+    pub fn with_glycoforms(self, glycoforms: impl IntoIterator<Item = SimpleModification>) -> Self {
+        Self {
+            glycoforms: glycoforms.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// The heavy chain
+    pub const fn heavy(&self) -> &Peptidoform<Linear> {
+        &self.heavy
+    }
+
+    /// The light chain
+    pub const fn light(&self) -> &Peptidoform<Linear> {
+        &self.light
+    }
+
+    /// The glycans that can be found on the N-glycosylation site
+    pub fn glycoforms(&self) -> &[SimpleModification] {
+        &self.glycoforms
+    }
+
+    /// The number of disulfide bonds in the intact antibody
+    pub fn disulfides(&self) -> usize {
+        self.disulfides.unwrap_or_else(|| {
+            let cysteines = |chain: &Peptidoform<Linear>| {
+                chain
+                    .sequence()
+                    .iter()
+                    .filter(|element| element.aminoacid.aminoacid() == AminoAcid::Cysteine)
+                    .count()
+            };
+            cysteines(&self.heavy) + cysteines(&self.light)
+        })
+    }
+
+    /// The index of the N-glycosylation site in the heavy chain, if any
+    pub fn glycosylation_site(&self) -> Option<usize> {
+        self.glycosylation_site.or_else(|| {
+            self.heavy.sequence().windows(3).rposition(|window| {
+                window[0].aminoacid.aminoacid() == AminoAcid::Asparagine
+                    && window[1].aminoacid.aminoacid() != AminoAcid::Proline
+                    && matches!(
+                        window[2].aminoacid.aminoacid(),
+                        AminoAcid::Serine | AminoAcid::Threonine
+                    )
+            })
+        })
+    }
+
+    /// The glycans that are placed on a single heavy chain, `None` is no glycan
+    fn single_glycans(&self) -> Vec<Option<SimpleModification>> {
+        if self.glycoforms.is_empty() || self.glycosylation_site().is_none() {
+            vec![None]
+        } else {
+            self.glycoforms.iter().cloned().map(Some).collect()
+        }
+    }
+
+    /// The formulas of the intact antibody (two heavy and two light chains) for all combinations
+    /// of glycoforms on both heavy chains, with the loss of two hydrogens for every disulfide
+    /// bond.
+    pub fn intact_formulas(&self) -> Vec<IntactGlycoform> {
+        let chains = self.heavy.formulas() * self.light.formulas();
+        let base = &(&chains * &chains)
+            - &(molecular_formula!(H 2) * i32::try_from(self.disulfides()).unwrap_or(i32::MAX));
+        let glycans = self.single_glycans();
+        let mut result = Vec::new();
+        for (index, first) in glycans.iter().enumerate() {
+            for second in &glycans[index..] {
+                let glycan_formula = first.as_ref().map(|g| g.formula()).unwrap_or_default()
+                    + second.as_ref().map(|g| g.formula()).unwrap_or_default();
+                result.push(IntactGlycoform {
+                    glycans: [first.clone(), second.clone()],
+                    formulas: &base + &glycan_formula,
+                });
+            }
+        }
+        result
+    }
+
+    /// Split the antibody into reduced subunits for middle-down analysis. The subunit containing
+    /// the N-glycosylation site is given once for every glycoform. Returns `None` if the hinge
+    /// cleavage site is not present, see [`HingeProtease::cleavage_site`].
+    pub fn subunits(&self, digestion: SubunitDigestion) -> Option<Vec<AntibodySubunit>> {
+        let site = self.glycosylation_site();
+        let mut subunits = self.glycosylated(Subunit::LightChain, &self.light, None);
+        match digestion {
+            SubunitDigestion::Reduction => {
+                subunits.extend(self.glycosylated(Subunit::HeavyChain, &self.heavy, site));
+            }
+            SubunitDigestion::HingeCleavage(protease) => {
+                let cut = protease.cleavage_site(&self.heavy)?;
+                subunits.extend(self.glycosylated(
+                    Subunit::Fd,
+                    &self.heavy.sub_peptide(..cut),
+                    site.filter(|s| *s < cut),
+                ));
+                subunits.extend(self.glycosylated(
+                    Subunit::Fc,
+                    &self.heavy.sub_peptide(cut..),
+                    site.and_then(|s| s.checked_sub(cut)),
+                ));
+            }
+        }
+        Some(subunits)
+    }
+
+    /// Build a single chain variable fragment from the variable domains of this antibody joined
+    /// by the given linker, for example [`SCFV_LINKER`]. The variable domains end 11 (heavy) or
+    /// 10 (light) residues after the start of the conserved `WGxG`/`FGxG` motif of the J region.
+    /// `heavy_first` gives the VH-linker-VL orientation, otherwise VL-linker-VH is used. If the
+    /// N-glycosylation site is in the heavy variable domain the scFv is given once for every
+    /// glycoform. Returns `None` if the J motif could not be found in either chain.
+    pub fn scfv(
+        &self,
+        linker: &Peptidoform<Linear>,
+        heavy_first: bool,
+    ) -> Option<Vec<AntibodySubunit>> {
+        let heavy_end = variable_domain_end(&self.heavy, AminoAcid::Tryptophan, 11)?;
+        let light_end = variable_domain_end(&self.light, AminoAcid::Phenylalanine, 10)?;
+        let heavy = self.heavy.sub_peptide(..heavy_end);
+        let light = self.light.sub_peptide(..light_end);
+        let site = self.glycosylation_site().filter(|s| *s < heavy_end);
+        let (first, second, site) = if heavy_first {
+            (heavy, light, site)
+        } else {
+            let offset = light.len() + linker.len();
+            (light, heavy, site.map(|s| s + offset))
+        };
+        let mut scfv = first;
+        scfv.sequence_mut()
+            .extend(linker.sequence().iter().chain(second.sequence()).cloned());
+        Some(self.glycosylated(Subunit::ScFv, &scfv, site))
+    }
+
+    /// Give the subunit once for every glycoform if it contains the glycosylation site
+    fn glycosylated(
+        &self,
+        kind: Subunit,
+        peptidoform: &Peptidoform<Linear>,
+        site: Option<usize>,
+    ) -> Vec<AntibodySubunit> {
+        let glycans = if site.is_some() {
+            self.single_glycans()
+        } else {
+            vec![None]
+        };
+        glycans
+            .into_iter()
+            .map(|glycan| {
+                let mut peptidoform = peptidoform.clone();
+                if let (Some(site), Some(glycan)) = (site, &glycan) {
+                    peptidoform.sequence_mut()[site]
+                        .modifications
+                        .push(Modification::Simple(glycan.clone()));
+                }
+                AntibodySubunit {
+                    kind,
+                    glycan,
+                    peptidoform,
+                }
+            })
+            .collect()
+    }
+
+    /// Generate the theoretical fragments for all subunits, see [`Self::subunits`].
+    pub fn subunit_fragments(
+        &self,
+        digestion: SubunitDigestion,
+        max_charge: Charge,
+        model: &Model,
+    ) -> Option<Vec<(AntibodySubunit, Vec<Fragment>)>> {
+        Some(
+            self.subunits(digestion)?
+                .into_iter()
+                .map(|subunit| {
+                    let fragments = subunit
+                        .peptidoform
+                        .generate_theoretical_fragments(max_charge, model);
+                    (subunit, fragments)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Find the end of a variable domain, as the given number of residues from the start of the
+/// first `WGxG`/`FGxG` motif
+fn variable_domain_end(
+    chain: &Peptidoform<Linear>,
+    first: AminoAcid,
+    length: usize,
+) -> Option<usize> {
+    chain
+        .sequence()
+        .windows(4)
+        .position(|window| {
+            window[0].aminoacid.aminoacid() == first
+                && window[1].aminoacid.aminoacid() == AminoAcid::Glycine
+                && window[3].aminoacid.aminoacid() == AminoAcid::Glycine
+        })
+        .map(|index| (index + length).min(chain.len()))
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{glycan::MonoSaccharide, modification::SimpleModificationInner};
+
+    fn linear(sequence: &str) -> Peptidoform<Linear> {
+        Peptidoform::pro_forma(sequence, None)
+            .unwrap()
+            .into_linear()
+            .unwrap()
+    }
+
+    fn glycan(composition: &str) -> SimpleModification {
+        SimpleModificationInner::Glycan(MonoSaccharide::from_composition(composition).unwrap())
+            .into()
+    }
+
+    #[test]
+    fn intact() {
+        let heavy = linear("CAKNSTC");
+        let light = linear("WCK");
+        let antibody = PairedAntibody::new(heavy.clone(), light.clone());
+        assert_eq!(antibody.disulfides(), 3);
+        assert_eq!(antibody.glycosylation_site(), Some(3));
+        let intact = antibody.intact_formulas();
+        assert_eq!(intact.len(), 1);
+        let chains = heavy.formulas()[0].clone() + light.formulas()[0].clone();
+        assert_eq!(
+            intact[0].formulas[0],
+            chains.clone() + chains - molecular_formula!(H 6)
+        );
+
+        let glycosylated = antibody
+            .with_glycoforms([glycan("HexNAc4Hex3Fuc1"), glycan("HexNAc4Hex4Fuc1")])
+            .with_disulfides(2);
+        let intact = glycosylated.intact_formulas();
+        assert_eq!(intact.len(), 3);
+        assert_eq!(
+            intact[1].formulas[0],
+            intact[0].formulas[0].clone() + glycan("Hex1").formula()
+        );
+        assert_eq!(
+            intact[2].glycans,
+            [
+                Some(glycan("HexNAc4Hex4Fuc1")),
+                Some(glycan("HexNAc4Hex4Fuc1"))
+            ]
+        );
+    }
+
+    #[test]
+    fn subunits() {
+        let antibody = PairedAntibody::new(linear("AAPELLGGPSVFNSTK"), linear("WCK"))
+            .with_glycoforms([glycan("HexNAc4Hex3Fuc1"), glycan("HexNAc4Hex4Fuc1")]);
+        let reduced = antibody.subunits(SubunitDigestion::Reduction).unwrap();
+        assert_eq!(reduced.len(), 3);
+        assert_eq!(reduced[1].kind, Subunit::HeavyChain);
+        assert_eq!(
+            reduced[1].peptidoform.formulas()[0],
+            antibody.heavy().formulas()[0].clone() + glycan("HexNAc4Hex3Fuc1").formula()
+        );
+        let ides = antibody
+            .subunits(SubunitDigestion::HingeCleavage(HingeProtease::IdeS))
+            .unwrap();
+        assert_eq!(
+            ides.iter().map(|s| s.kind).collect::<Vec<_>>(),
+            [Subunit::LightChain, Subunit::Fd, Subunit::Fc, Subunit::Fc]
+        );
+        assert_eq!(ides[1].peptidoform.to_string(), "AAPELLG");
+        assert!(ides[2].peptidoform.to_string().starts_with("GPSVFN["));
+        assert_eq!(ides[3].glycan, Some(glycan("HexNAc4Hex4Fuc1")));
+        assert!(PairedAntibody::new(linear("AAPELK"), linear("WCK"))
+            .subunits(SubunitDigestion::HingeCleavage(HingeProtease::IdeS))
+            .is_none());
+        let fragments = antibody
+            .subunit_fragments(
+                SubunitDigestion::HingeCleavage(HingeProtease::IdeS),
+                Charge::new::<crate::system::e>(1),
+                &Model::all(),
+            )
+            .unwrap();
+        assert_eq!(fragments.len(), 4);
+        assert!(fragments.iter().all(|(_, f)| !f.is_empty()));
+    }
+
+    #[test]
+    fn scfv() {
+        let antibody = PairedAntibody::new(
+            linear("EVQLVESGGGLVQPGGSLRLSCAASGFNIKDTYIHWVRQAPGKGLEWVARIYPTNGYTRYADSVKGRFTISADTSKNTAYLQMNSLRAEDTAVYYCSRWGGDGFYAMDYWGQGTLVTVSSASTKGPSVFPLAPSSK"),
+            linear("DIQMTQSPSSLSASVGDRVTITCRASQDVNTAVAWYQQKPGKAPKLLIYSASFLYSGVPSRFSGSRSGTDFTLTISSLQPEDFATYYCQQHYTTPPTFGQGTKVEIKRTVAAPSVFIFPPSDEQLK"),
+        )
+        .with_glycoforms([glycan("HexNAc4Hex3Fuc1")]);
+        let linker = linear(SCFV_LINKER);
+        let scfv = antibody.scfv(&linker, true).unwrap();
+        assert_eq!(scfv.len(), 1);
+        let sequence = scfv[0].peptidoform.to_string();
+        assert!(sequence.starts_with("EVQLVESGG"));
+        assert!(sequence.contains("WGQGTLVTVSSGGGGSGGGGSGGGGSDIQMTQ"));
+        assert!(sequence.ends_with("FGQGTKVEIK"));
+        let reversed = antibody.scfv(&linker, false).unwrap();
+        assert!(reversed[0]
+            .peptidoform
+            .to_string()
+            .contains("FGQGTKVEIKGGGGSGGGGSGGGGSEVQLV"));
+        assert_eq!(reversed[0].peptidoform.len(), scfv[0].peptidoform.len());
+        assert!(PairedAntibody::new(linear("EVQLVES"), linear("DIQMTQ"))
+            .scfv(&linker, true)
+            .is_none());
+    }
+
+    #[cfg(feature = "imgt")]
+    #[test]
+    fn imgt_constant() {
+        use crate::imgt::{Gene, Species};
+        let antibody = PairedAntibody::from_variable_domains(
+            linear("EVQLVESGGGLVQPGGSLRLSCAASGFNIKDTYIHWVRQAPGKGLEWVARIYPTNGYTRYADSVKGRFTISADTSKNTAYLQMNSLRAEDTAVYYCSRWGGDGFYAMDYWGQGTLVTVSS"),
+            linear("DIQMTQSPSSLSASVGDRVTITCRASQDVNTAVAWYQQKPGKAPKLLIYSASFLYSGVPSRFSGSRSGTDFTLTISSLQPEDFATYYCQQHYTTPPTFGQGTKVEIK"),
+            Species::HomoSapiens,
+            Gene::from_imgt_name("IGHG1").unwrap(),
+            Gene::from_imgt_name("IGKC").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(antibody.disulfides(), 16);
+        let site = antibody.glycosylation_site().unwrap();
+        assert_eq!(
+            antibody.heavy().sub_peptide(site - 4..site + 4).to_string(),
+            "EEQYNSTY"
+        );
+        let subunits = antibody
+            .subunits(SubunitDigestion::HingeCleavage(HingeProtease::IdeS))
+            .unwrap();
+        assert!(subunits[2]
+            .peptidoform
+            .to_string()
+            .starts_with("GPSVFLFPPKPK"));
+        let papain = antibody
+            .subunits(SubunitDigestion::HingeCleavage(HingeProtease::Papain))
+            .unwrap();
+        assert!(papain[1].peptidoform.to_string().ends_with("KSCDKTH"));
+        assert!(papain[2]
+            .peptidoform
+            .to_string()
+            .starts_with("TCPPCPAPELLGG"));
+        let lys_c = antibody
+            .subunits(SubunitDigestion::HingeCleavage(HingeProtease::LysC))
+            .unwrap();
+        assert!(lys_c[1].peptidoform.to_string().ends_with("KSCDK"));
+    }
+}
@@ -0,0 +1,244 @@
+//! Runtime introspection of the capabilities of this build of the crate, so applications that
+//! embed it can show accurate information and check if a workflow is supported.
+//!
+//! ```rust
+//! let capabilities = rustyms::capabilities::capabilities();
+//! assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::modification::Ontology;
+
+/// The capabilities of this build of the crate, see [`capabilities`]. This can be serialised to
+/// get a machine readable description.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// The version of this crate
+    pub version: String,
+    /// The enabled cargo features
+    pub features: Vec<String>,
+    /// The supported file formats
+    pub file_formats: Vec<FileFormatCapability>,
+    /// The built in ontologies
+    pub ontologies: Vec<OntologyCapability>,
+    /// The supported ProForma compliance levels, as defined by the ProForma 2.0 specification
+    pub pro_forma: Vec<String>,
+}
+
+/// A supported file format
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileFormatCapability {
+    /// The name of the format
+    pub name: String,
+    /// The supported versions of the format
+    pub versions: Vec<String>,
+    /// If this format can be read
+    pub read: bool,
+    /// If this format can be written
+    pub write: bool,
+}
+
+/// A built in ontology
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OntologyCapability {
+    /// The ontology
+    pub ontology: Ontology,
+    /// The number of entries (modifications and cross-linkers)
+    pub entries: usize,
+    /// The hash of the built in database, see [`Ontology::database_hash`]
+    pub hash: u64,
+}
+
+/// All optional cargo features, see the `Cargo.toml` for their meaning
+const FEATURES: &[(&str, bool)] = &[
+    ("align", cfg!(feature = "align")),
+    ("identification", cfg!(feature = "identification")),
+    ("imgt", cfg!(feature = "imgt")),
+    ("isotopes", cfg!(feature = "isotopes")),
+    ("mzdata", cfg!(feature = "mzdata")),
+    ("rand", cfg!(feature = "rand")),
+    ("rayon", cfg!(feature = "rayon")),
+    ("simd", cfg!(feature = "simd")),
+    ("zstd", cfg!(feature = "zstd")),
+];
+
+/// The supported ProForma compliance levels
+const PRO_FORMA: &[&str] = &[
+    "Base-ProForma",
+    "Level 2-ProForma",
+    "Top-Down",
+    "Cross-Linking",
+    "Glycans",
+    "Mass spectrum",
+];
+
+/// Get the capabilities of this build of the crate. Note that this loads all built in ontologies
+/// to count the number of entries.
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| (*name).to_string())
+            .collect(),
+        file_formats: file_formats(),
+        ontologies: [
+            Ontology::Unimod,
+            Ontology::Psimod,
+            Ontology::Gnome,
+            Ontology::Xlmod,
+            Ontology::Resid,
+        ]
+        .into_iter()
+        .map(|ontology| OntologyCapability {
+            ontology,
+            entries: ontology.lookup(None).len(),
+            hash: ontology.database_hash().unwrap_or_default(),
+        })
+        .collect(),
+        pro_forma: PRO_FORMA.iter().map(ToString::to_string).collect(),
+    }
+}
+
+/// All supported file formats given the enabled features
+fn file_formats() -> Vec<FileFormatCapability> {
+    let format =
+        |name: &str, versions: Vec<String>, read: bool, write: bool| FileFormatCapability {
+            name: name.to_string(),
+            versions,
+            read,
+            write,
+        };
+    #[allow(unused_mut)]
+    let mut formats = vec![
+        format("mgf", Vec::new(), true, false),
+        format("mzML", vec!["1.1.0".to_string()], false, true),
+        format("DIA-NN library", Vec::new(), false, true),
+        format("Spectronaut library", Vec::new(), false, true),
+    ];
+    // mzTab can be written as peptide report, see `write_peptide_report`
+    #[cfg(feature = "identification")]
+    formats.extend(
+        crate::identification::IdentifiedPeptideFileFormat::ALL
+            .iter()
+            .map(|f| {
+                format(
+                    f.name(),
+                    f.versions(),
+                    true,
+                    *f == crate::identification::IdentifiedPeptideFileFormat::MZTab,
+                )
+            }),
+    );
+    #[cfg(feature = "identification")]
+    formats.push(format("Generic", Vec::new(), true, false));
+    formats
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities() {
+        let capabilities = super::capabilities();
+        assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            capabilities
+                .features
+                .contains(&"identification".to_string()),
+            cfg!(feature = "identification")
+        );
+        assert_eq!(capabilities.ontologies.len(), 5);
+        assert!(capabilities.ontologies.iter().all(|o| o.entries > 0));
+        assert_eq!(
+            Ontology::Unimod.database_hash(),
+            Ontology::Unimod.database_hash()
+        );
+        assert_ne!(
+            Ontology::Unimod.database_hash(),
+            Ontology::Psimod.database_hash()
+        );
+        assert!(capabilities.file_formats.iter().any(|f| f.name == "mgf"));
+        #[cfg(feature = "identification")]
+        assert!(capabilities
+            .file_formats
+            .iter()
+            .any(|f| f.name == "Sage" && f.versions == ["v0.14"]));
+    }
+
+    /// A writer that writes a file of a certain format into the given buffer
+    type Writer<'a> = Box<dyn Fn(&mut Vec<u8>) -> Result<(), crate::error::CustomError> + 'a>;
+
+    #[test]
+    fn writable_formats() {
+        // All formats reported as writable, with a writer that writes an (empty) file
+        let spectra: Vec<crate::spectrum::RawSpectrum> = Vec::new();
+        #[allow(unused_mut)]
+        let mut writers: Vec<(&str, Writer)> = vec![
+            (
+                "mzML",
+                Box::new(|output: &mut Vec<u8>| {
+                    crate::rawfile::mzml::write(output, &spectra, true)
+                }),
+            ),
+            (
+                "DIA-NN library",
+                Box::new(|output: &mut Vec<u8>| {
+                    crate::spectrum::write_dia_library(
+                        [],
+                        crate::spectrum::DiaLibraryOptions::new(
+                            crate::spectrum::DiaLibraryFormat::DiaNn,
+                        ),
+                        output,
+                    )
+                }),
+            ),
+            (
+                "Spectronaut library",
+                Box::new(|output: &mut Vec<u8>| {
+                    crate::spectrum::write_dia_library(
+                        [],
+                        crate::spectrum::DiaLibraryOptions::new(
+                            crate::spectrum::DiaLibraryFormat::Spectronaut,
+                        ),
+                        output,
+                    )
+                }),
+            ),
+        ];
+        #[cfg(feature = "identification")]
+        writers.push((
+            "mzTab",
+            Box::new(|output: &mut Vec<u8>| {
+                crate::identification::write_peptide_report(
+                    &[],
+                    crate::identification::PeptideReportFormat::MzTab,
+                    None,
+                    output,
+                )
+            }),
+        ));
+        let mut writable = super::capabilities()
+            .file_formats
+            .into_iter()
+            .filter(|f| f.write)
+            .map(|f| f.name)
+            .collect::<Vec<_>>();
+        writable.sort();
+        let mut names = writers
+            .iter()
+            .map(|(name, _)| (*name).to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(writable, names);
+        for (name, writer) in writers {
+            let mut output = Vec::new();
+            writer(&mut output).unwrap();
+            assert!(!output.is_empty(), "Nothing written for {name}");
+        }
+    }
+}
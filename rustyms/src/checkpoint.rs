@@ -0,0 +1,273 @@
+//! Checkpointing for long running batch jobs, so that a crashed run can be resumed.
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::checkpoint::Checkpoint;
+//! let mut checkpoint = Checkpoint::<usize, String>::open("job.checkpoint", 1000)?;
+//! let outputs = checkpoint.run(
+//!     (0..100_000).map(|index| (index, index * 2)),
+//!     |value| Some(value.to_string()),
+//! )?;
+//! # Ok(()) }
+//! ```
+
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::{Context, CustomError};
+
+/// The progress of a batch job, the keys of all processed items together with all outputs so far.
+/// This is periodically saved to disk, and when opened again with the same path the job resumes
+/// from the last save. Any item that was processed after the last save is processed again, so the
+/// processing should not have side effects outside of the returned outputs.
+///
+/// The checkpoint file is only ever appended to, every save adds the items processed since the
+/// last save as one length prefixed block. A block that is cut short by a crash while saving is
+/// dropped when the checkpoint is opened again.
+#[derive(Clone, Debug)]
+pub struct Checkpoint<K: Ord, O> {
+    /// The location of the checkpoint file
+    path: PathBuf,
+    /// The number of processed items between saves
+    interval: usize,
+    /// The serialized items processed since the last save
+    unsaved: Vec<u8>,
+    /// The number of processed items since the last save
+    unsaved_items: usize,
+    /// The keys of all processed items
+    processed: BTreeSet<K>,
+    /// All outputs so far
+    outputs: Vec<O>,
+}
+
+impl<K: Ord + Serialize + DeserializeOwned, O: Serialize + DeserializeOwned> Checkpoint<K, O> {
+    /// Open a checkpoint, if the file exists the progress is loaded from it, otherwise this starts
+    /// empty. The progress is saved every `interval` processed items (with a minimum of 1).
+    /// # Errors
+    /// If the file exists but could not be read as a checkpoint with these key and output types.
+    pub fn open(path: impl AsRef<Path>, interval: usize) -> Result<Self, CustomError> {
+        let mut checkpoint = Self {
+            path: path.as_ref().to_path_buf(),
+            interval: interval.max(1),
+            unsaved: Vec::new(),
+            unsaved_items: 0,
+            processed: BTreeSet::new(),
+            outputs: Vec::new(),
+        };
+        if checkpoint.path.exists() {
+            checkpoint.load()?;
+        }
+        Ok(checkpoint)
+    }
+
+    /// Load all blocks from the checkpoint file, and drop a trailing block that was cut short.
+    /// # Errors
+    /// If the file could not be read, or a complete block could not be read as items with these
+    /// key and output types.
+    fn load(&mut self) -> Result<(), CustomError> {
+        let mut bytes = Vec::new();
+        File::open(&self.path)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .map_err(|e| CustomError::error("Could not open checkpoint", e, context(&self.path)))?;
+        let mut offset = 0;
+        while let Some(length) = bytes
+            .get(offset..offset + 8)
+            .and_then(|length| <[u8; 8]>::try_from(length).ok())
+            .and_then(|length| usize::try_from(u64::from_le_bytes(length)).ok())
+            .filter(|length| *length <= bytes.len() - offset - 8)
+        {
+            let mut block = &bytes[offset + 8..offset + 8 + length];
+            while !block.is_empty() {
+                let (key, outputs): (K, Vec<O>) =
+                    bincode::deserialize_from(&mut block).map_err(|e| {
+                        CustomError::error("Could not read checkpoint", e, context(&self.path))
+                    })?;
+                self.processed.insert(key);
+                self.outputs.extend(outputs);
+            }
+            offset += 8 + length;
+        }
+        if offset < bytes.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(&self.path)
+                .and_then(|file| {
+                    file.set_len(offset as u64)?;
+                    file.sync_all()
+                })
+                .map_err(|e| {
+                    CustomError::error("Could not repair checkpoint", e, context(&self.path))
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Check if the item with this key has already been processed.
+    pub fn is_processed(&self, key: &K) -> bool {
+        self.processed.contains(key)
+    }
+
+    /// The number of processed items.
+    pub fn len(&self) -> usize {
+        self.processed.len()
+    }
+
+    /// Check if no items have been processed yet.
+    pub fn is_empty(&self) -> bool {
+        self.processed.is_empty()
+    }
+
+    /// All outputs so far.
+    pub fn outputs(&self) -> &[O] {
+        &self.outputs
+    }
+
+    /// Get all outputs so far, the checkpoint file is left as is.
+    pub fn into_outputs(self) -> Vec<O> {
+        self.outputs
+    }
+
+    /// Record that the item with this key is processed with the given outputs, this saves the
+    /// checkpoint if the interval is reached.
+    /// # Errors
+    /// If the item could not be serialized or the checkpoint could not be saved.
+    pub fn record(
+        &mut self,
+        key: K,
+        outputs: impl IntoIterator<Item = O>,
+    ) -> Result<(), CustomError> {
+        let outputs: Vec<O> = outputs.into_iter().collect();
+        bincode::serialize_into(&mut self.unsaved, &(&key, &outputs))
+            .map_err(|e| CustomError::error("Could not save checkpoint", e, context(&self.path)))?;
+        self.processed.insert(key);
+        self.outputs.extend(outputs);
+        self.unsaved_items += 1;
+        if self.unsaved_items >= self.interval {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Save the checkpoint. All items processed since the last save are appended to the
+    /// checkpoint file as one block, which is synced to disk before this returns.
+    /// # Errors
+    /// If the checkpoint could not be written.
+    pub fn save(&mut self) -> Result<(), CustomError> {
+        if self.unsaved.is_empty() {
+            return Ok(());
+        }
+        let mut block = Vec::with_capacity(8 + self.unsaved.len());
+        block.extend_from_slice(&(self.unsaved.len() as u64).to_le_bytes());
+        block.extend_from_slice(&self.unsaved);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| {
+                file.write_all(&block)?;
+                file.sync_all()
+            })
+            .map_err(|e| CustomError::error("Could not save checkpoint", e, context(&self.path)))?;
+        self.unsaved.clear();
+        self.unsaved_items = 0;
+        Ok(())
+    }
+
+    /// Process all items that are not processed yet, saving the progress periodically. Returns
+    /// all outputs, including those from previous runs, in the order they were processed. Use
+    /// [`Self::into_outputs`] to take ownership of the outputs.
+    /// # Errors
+    /// If the checkpoint could not be saved.
+    pub fn run<T, R: IntoIterator<Item = O>>(
+        &mut self,
+        items: impl IntoIterator<Item = (K, T)>,
+        mut process: impl FnMut(T) -> R,
+    ) -> Result<&[O], CustomError> {
+        for (key, item) in items {
+            if !self.is_processed(&key) {
+                let outputs = process(item);
+                self.record(key, outputs)?;
+            }
+        }
+        self.save()?;
+        Ok(&self.outputs)
+    }
+
+    /// Remove the checkpoint file, for example after the job finished and the outputs are
+    /// stored elsewhere.
+    /// # Errors
+    /// If the checkpoint file exists but could not be removed.
+    pub fn remove(self) -> Result<Vec<O>, CustomError> {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path).map_err(|e| {
+                CustomError::error("Could not remove checkpoint", e, context(&self.path))
+            })?;
+        }
+        Ok(self.outputs)
+    }
+}
+
+/// Show the path of the checkpoint as error context
+fn context(path: &Path) -> Context {
+    Context::Show {
+        line: path.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume() {
+        let path = std::env::temp_dir().join(format!(
+            "rustyms_checkpoint_test_{}.checkpoint",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        // Crash halfway through (after the checkpoint at 5)
+        let mut checkpoint = Checkpoint::<usize, String>::open(&path, 5).unwrap();
+        for index in 0..7 {
+            checkpoint.record(index, Some(index.to_string())).unwrap();
+        }
+        drop(checkpoint);
+
+        let mut checkpoint = Checkpoint::<usize, String>::open(&path, 5).unwrap();
+        assert_eq!(checkpoint.len(), 5);
+        let mut processed = Vec::new();
+        let outputs = checkpoint
+            .run((0..10).map(|i| (i, i)), |i| {
+                processed.push(i);
+                Some(i.to_string())
+            })
+            .unwrap();
+        assert_eq!(processed, vec![5, 6, 7, 8, 9]);
+        assert_eq!(outputs, (0..10).map(|i| i.to_string()).collect::<Vec<_>>());
+        drop(checkpoint);
+
+        // A crash while saving leaves a block that is cut short, which is dropped
+        let length = std::fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&100_u64.to_le_bytes()).unwrap();
+        file.write_all(&[1, 2, 3]).unwrap();
+        drop(file);
+        let checkpoint = Checkpoint::<usize, String>::open(&path, 5).unwrap();
+        assert_eq!(checkpoint.len(), 10);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), length);
+        assert_eq!(checkpoint.remove().unwrap().len(), 10);
+        assert!(!path.exists());
+
+        // A complete block that cannot be read is an error
+        std::fs::write(&path, [&3_u64.to_le_bytes()[..], &[1, 2, 3]].concat()).unwrap();
+        assert!(Checkpoint::<usize, String>::open(&path, 5).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
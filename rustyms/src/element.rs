@@ -64,6 +64,36 @@ impl Element {
         )
     }
 
+    /// The uncertainty of the mass of the specified isotope of this element, or of the
+    /// monoisotopic mass if no isotope is specified (if that isotope exists)
+    pub fn mass_uncertainty(self, isotope: Option<NonZeroU16>) -> Option<Mass> {
+        if self == Self::Electron {
+            return Some(da(1.6e-14));
+        }
+        let data = &elemental_data()[self as usize - 1];
+        let isotope = isotope.map_or_else(
+            || {
+                data.2
+                    .iter()
+                    .find(|(_, m, _)| Some(*m) == data.0)
+                    .map(|(n, _, _)| *n)
+            },
+            |isotope| Some(isotope.get()),
+        )?;
+        data.4.iter().find(|(n, _)| *n == isotope).map(|(_, u)| *u)
+    }
+
+    /// The uncertainty of the average weight of the specified isotope of this element (if that
+    /// isotope exists). For elements with a natural variation in isotopic composition this is
+    /// half the range of the atomic weight as given by CIAAW.
+    pub fn average_weight_uncertainty(self, isotope: Option<NonZeroU16>) -> Option<Mass> {
+        if isotope.is_some() || self == Self::Electron {
+            self.mass_uncertainty(isotope)
+        } else {
+            elemental_data()[self as usize - 1].3
+        }
+    }
+
     /// Gives the most abundant mass based on the number of this isotope
     pub fn most_abundant_mass(self, isotope: Option<NonZeroU16>, n: i32) -> Option<Mass> {
         if self == Self::Electron {
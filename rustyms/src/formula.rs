@@ -1,6 +1,7 @@
 use crate::{
     system::{da, fraction, Mass, OrderedMass, Ratio},
-    MassMode,
+    uncertain_mass::stated_precision,
+    MassMode, UncertainMass,
 };
 use std::fmt::Write;
 
@@ -70,6 +71,31 @@ impl MolecularFormula {
         }
     }
 
+    /// The uncertainty of the mass in the given mode. This combines the uncertainties of the
+    /// masses of the elements (as given by IUPAC) and the precision of any additional mass (see
+    /// [`UncertainMass::from_stated`]). Multiple atoms of the same element have fully correlated
+    /// uncertainties, all other uncertainties are assumed to be independent. For the most
+    /// abundant mass the uncertainty of the monoisotopic mass is given.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn mass_uncertainty(&self, mode: MassMode) -> Mass {
+        let mut variance = stated_precision(*self.additional_mass).powi(2);
+        for (e, i, n) in &self.elements {
+            let uncertainty = if mode == MassMode::Average {
+                e.average_weight_uncertainty(*i)
+            } else {
+                e.mass_uncertainty(*i)
+            }
+            .expect("An invalid molecular formula was created, please report this crash");
+            variance += (uncertainty.value * f64::from(*n)).powi(2);
+        }
+        da(variance.sqrt())
+    }
+
+    /// Get the mass in the given mode together with its uncertainty, see [`Self::mass_uncertainty`]
+    pub fn mass_with_uncertainty(&self, mode: MassMode) -> UncertainMass {
+        UncertainMass::new(self.mass(mode), self.mass_uncertainty(mode))
+    }
+
     /// Create a [Hill notation](https://en.wikipedia.org/wiki/Chemical_formula#Hill_system) from this collections of elements merged with the ProForma notation for specific isotopes
     pub fn hill_notation(&self) -> String {
         self.hill_notation_generic(|element, buffer| {
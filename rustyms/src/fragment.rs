@@ -56,6 +56,16 @@ impl Fragment {
         })
     }
 
+    /// Get the uncertainty of the mz, see [`MolecularFormula::mass_uncertainty`]
+    pub fn mz_uncertainty(&self, mode: MassMode) -> Option<MassOverCharge> {
+        self.formula.as_ref().map(|f| {
+            f.mass_uncertainty(mode)
+                / crate::system::f64::Charge::new::<crate::system::charge::e>(
+                    self.charge.value as f64,
+                )
+        })
+    }
+
     /// Get the ppm difference between two fragments
     pub fn ppm(&self, other: &Self, mode: MassMode) -> Option<Ratio> {
         self.mz(mode)
@@ -63,6 +73,21 @@ impl Fragment {
             .map(|(mz, omz)| mz.ppm(omz))
     }
 
+    /// Create a copy of this fragment with its m/z shifted by the given offset, useful to generate
+    /// decoy fragments. The shift is applied as additional mass on the formula so it scales with
+    /// the charge of this fragment.
+    #[must_use]
+    pub fn with_mz_shift(&self, shift: MassOverCharge) -> Self {
+        let mut formula = self.formula.clone();
+        if let Some(formula) = &mut formula {
+            formula.add_mass(OrderedFloat(shift.value * self.charge.value as f64));
+        }
+        Self {
+            formula,
+            ..self.clone()
+        }
+    }
+
     /// Create a new fragment
     #[must_use]
     pub fn new(
@@ -103,37 +128,45 @@ impl Fragment {
         termini
             .iter()
             .cartesian_product(theoretical_mass.iter())
-            .cartesian_product(charge_carriers.range(charge_range))
-            .cartesian_product(std::iter::once(None).chain(neutral_losses.iter().map(Some)))
-            .map(|(((term, mass), charge), loss)| Self {
-                formula: Some(
-                    term + mass
-                        + charge.formula_inner(SequencePosition::default(), peptidoform_index)
-                        + loss.unwrap_or(&NeutralLoss::Gain(MolecularFormula::default())),
-                ),
-                charge: Charge::new::<crate::system::e>(charge.charge().value.try_into().unwrap()),
-                ion: annotation.clone(),
-                peptidoform_ion_index: Some(peptidoform_ion_index),
-                peptidoform_index: Some(peptidoform_index),
-                neutral_loss: loss.map(|l| vec![l.clone()]).unwrap_or_default(),
-                deviation: None,
-                confidence: None,
-                auxiliary: false,
+            .flat_map(|(term, mass)| {
+                let base = term + mass;
+                charge_carriers
+                    .range_for(charge_range, base.charge())
+                    .into_iter()
+                    .cartesian_product(std::iter::once(None).chain(neutral_losses.iter().map(Some)))
+                    .map(move |((charge, total), loss)| {
+                        let formula = &base
+                            + charge.formula_inner(SequencePosition::default(), peptidoform_index);
+                        Self {
+                            formula: Some(match loss {
+                                Some(loss) => formula + loss,
+                                None => formula,
+                            }),
+                            charge: Charge::new::<crate::system::e>(
+                                total.value.try_into().unwrap(),
+                            ),
+                            ion: annotation.clone(),
+                            peptidoform_ion_index: Some(peptidoform_ion_index),
+                            peptidoform_index: Some(peptidoform_index),
+                            neutral_loss: loss.map(|l| vec![l.clone()]).unwrap_or_default(),
+                            deviation: None,
+                            confidence: None,
+                            auxiliary: false,
+                        }
+                    })
             })
             .collect()
     }
 
-    /// Create a copy of this fragment with the given charge
+    /// Create a copy of this fragment with the given charge carriers and total charge
     /// # Panics
     /// If the charge is negative.
     #[must_use]
-    fn with_charge(&self, charge: &MolecularCharge) -> Self {
+    fn with_charge(&self, charge: &MolecularCharge, total: crate::system::isize::Charge) -> Self {
         let formula = charge
             .formula()
             .with_labels(&[AmbiguousLabel::ChargeCarrier(charge.formula())]);
-        let c = Charge::new::<crate::system::charge::e>(
-            usize::try_from(formula.charge().value).unwrap(),
-        );
+        let c = Charge::new::<crate::system::charge::e>(usize::try_from(total.value).unwrap());
         Self {
             formula: Some(self.formula.clone().unwrap_or_default() + &formula),
             charge: c,
@@ -147,10 +180,15 @@ impl Fragment {
         charge_carriers: &mut CachedCharge,
         charge_range: ChargeRange,
     ) -> impl Iterator<Item = Self> {
+        let fixed = self
+            .formula
+            .as_ref()
+            .map(MolecularFormula::charge)
+            .unwrap_or_default();
         charge_carriers
-            .range(charge_range)
+            .range_for(charge_range, fixed)
             .into_iter()
-            .map(move |c| self.with_charge(&c))
+            .map(move |(c, total)| self.with_charge(&c, total))
     }
 
     /// Create a copy of this fragment with the given neutral loss
@@ -381,6 +419,9 @@ pub enum FragmentType {
     PrecursorSideChainLoss(PeptidePosition, AminoAcid),
     /// Diagnostic ion for a given position
     Diagnostic(DiagnosticPosition),
+    /// Complementary reporter ion (eg `TMTc`), the precursor that lost the reporter ion and CO of
+    /// an isobaric label, with the name of the reporter ion
+    ComplementaryReporter(String),
     /// An internal fragment, potentially with the named bonds that resulted in this fragment
     Internal(
         Option<(BackboneNFragment, BackboneCFragment)>,
@@ -441,6 +482,7 @@ impl FragmentType {
             | Self::PrecursorSideChainLoss(n, _) => Some(n.series_number.to_string()),
             Self::B(n) | Self::Diagnostic(DiagnosticPosition::Glycan(n, _)) => Some(n.label()),
             Self::Y(bonds) => Some(bonds.iter().map(GlycanPosition::label).join("")),
+            Self::ComplementaryReporter(name) => Some(format!("[{name}]")),
             Self::Oxonium(breakages) => Some(
                 breakages
                     .iter()
@@ -494,6 +536,7 @@ impl FragmentType {
             Self::Immonium(_, aa) => Cow::Owned(format!("i{}", aa.aminoacid.char())),
             Self::PrecursorSideChainLoss(_, aa) => Cow::Owned(format!("p-s{}", aa.char())),
             Self::Precursor => Cow::Borrowed("p"),
+            Self::ComplementaryReporter(_) => Cow::Borrowed("p-r"),
             Self::Internal(fragmentation, _, _) => Cow::Owned(format!(
                 "m{}",
                 fragmentation.map_or(String::new(), |(n, c)| format!("{n}:{c}")),
@@ -527,7 +570,7 @@ impl FragmentType {
             Self::Diagnostic(_) => FragmentKind::diagnostic,
             Self::Immonium(_, _) => FragmentKind::immonium,
             Self::PrecursorSideChainLoss(_, _) => FragmentKind::precursor_side_chain_loss,
-            Self::Precursor => FragmentKind::precursor,
+            Self::Precursor | Self::ComplementaryReporter(_) => FragmentKind::precursor,
             Self::Internal(_, _, _) => FragmentKind::internal,
             Self::Unknown(_) => FragmentKind::unknown,
         }
@@ -0,0 +1,264 @@
+//! A theoretical fragment table, with a row for each position in the peptide and a column for
+//! each ion series, neutral loss, and charge, as commonly shown in teaching materials and GUIs.
+//!
+//! ```rust
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::{fragment_table::FragmentTable, system::{e, usize::Charge}, *};
+//! let peptide = Peptidoform::pro_forma("PEPTIDE", None)?.into_linear().unwrap();
+//! let table = FragmentTable::new(&peptide, Charge::new::<e>(1), &Model::cid_hcd(), MassMode::Monoisotopic);
+//! assert_eq!(table.rows.len(), 7);
+//! let mut csv = Vec::new();
+//! table.write_csv(&mut csv)?;
+//! # Ok(()) }
+//! ```
+
+use std::io::Write;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    fragment::FragmentKind,
+    peptidoform::{AtMax, Linear},
+    system::{usize::Charge, MassOverCharge},
+    AminoAcid, Fragment, MassMode, Model, NeutralLoss, Peptidoform, SequencePosition,
+};
+
+/// A theoretical fragment table for a single peptidoform. Only the primary ion series (a, b, c,
+/// d, v, w, x, y, z, and z·) are included. The table can be serialized with serde, for example to
+/// JSON, or written as CSV with [`Self::write_csv`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FragmentTable {
+    /// The columns, sorted on ion series, neutral losses, and charge
+    pub columns: Vec<FragmentTableColumn>,
+    /// The rows, one for each position in the peptide from the N terminus
+    pub rows: Vec<FragmentTableRow>,
+}
+
+/// A column in a [`FragmentTable`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FragmentTableColumn {
+    /// The ion series, eg `b` or `z·`
+    pub series: String,
+    /// The neutral losses
+    pub neutral_loss: Vec<NeutralLoss>,
+    /// The charge
+    pub charge: Charge,
+}
+
+impl FragmentTableColumn {
+    /// The label of this column, the series followed by the neutral losses and the charge if it
+    /// is not 1, eg `b`, `y-H2O1`, or `y^2`
+    pub fn label(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.series,
+            self.neutral_loss.iter().join(""),
+            if self.charge.value == 1 {
+                String::new()
+            } else {
+                format!("^{}", self.charge.value)
+            }
+        )
+    }
+}
+
+/// A row in a [`FragmentTable`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FragmentTableRow {
+    /// The amino acid at this position
+    pub amino_acid: AminoAcid,
+    /// The series number for the N terminal ion series (1 based)
+    pub n_number: usize,
+    /// The series number for the C terminal ion series (1 based)
+    pub c_number: usize,
+    /// The m/z values for each column, in the same order as [`FragmentTable::columns`]. A cell
+    /// is empty if the fragment is not generated and contains multiple values if there are
+    /// multiple options, eg for ambiguous amino acids.
+    pub mz: Vec<Vec<MassOverCharge>>,
+}
+
+impl FragmentTable {
+    /// Generate the theoretical fragments for the given peptidoform and build the table
+    pub fn new<Complexity: AtMax<Linear>>(
+        peptidoform: &Peptidoform<Complexity>,
+        max_charge: Charge,
+        model: &Model,
+        mode: MassMode,
+    ) -> Self {
+        let amino_acids = peptidoform
+            .sequence()
+            .iter()
+            .map(|s| s.aminoacid.aminoacid())
+            .collect_vec();
+        Self::from_fragments(
+            &amino_acids,
+            &peptidoform.generate_theoretical_fragments(max_charge, model),
+            mode,
+        )
+    }
+
+    /// Build the table from the given fragments for a peptidoform with the given amino acids.
+    /// Only fragments from the first peptidoform are used, fragments without a mass in the given
+    /// mode are ignored.
+    pub fn from_fragments(
+        amino_acids: &[AminoAcid],
+        fragments: &[Fragment],
+        mode: MassMode,
+    ) -> Self {
+        let cells = fragments
+            .iter()
+            .filter(|f| {
+                f.peptidoform_ion_index.map_or(true, |i| i == 0)
+                    && f.peptidoform_index.map_or(true, |i| i == 0)
+            })
+            .filter_map(|f| {
+                if !matches!(
+                    f.ion.kind(),
+                    FragmentKind::a
+                        | FragmentKind::b
+                        | FragmentKind::c
+                        | FragmentKind::d
+                        | FragmentKind::v
+                        | FragmentKind::w
+                        | FragmentKind::x
+                        | FragmentKind::y
+                        | FragmentKind::z
+                ) {
+                    return None;
+                }
+                let SequencePosition::Index(index) = f.ion.position()?.sequence_index else {
+                    return None;
+                };
+                if index >= amino_acids.len() {
+                    return None;
+                }
+                Some((
+                    FragmentTableColumn {
+                        series: f.ion.label().to_string(),
+                        neutral_loss: f.neutral_loss.clone(),
+                        charge: f.charge,
+                    },
+                    index,
+                    f.mz(mode)?,
+                ))
+            })
+            .collect_vec();
+        let columns = cells
+            .iter()
+            .map(|(column, _, _)| column.clone())
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let mut rows = amino_acids
+            .iter()
+            .enumerate()
+            .map(|(index, amino_acid)| FragmentTableRow {
+                amino_acid: *amino_acid,
+                n_number: index + 1,
+                c_number: amino_acids.len() - index,
+                mz: vec![Vec::new(); columns.len()],
+            })
+            .collect_vec();
+        for (column, index, mz) in cells {
+            let column = columns.binary_search(&column).unwrap_or_default();
+            let cell = &mut rows[index].mz[column];
+            if !cell.contains(&mz) {
+                cell.push(mz);
+            }
+        }
+        for row in &mut rows {
+            for cell in &mut row.mz {
+                cell.sort_unstable_by(|a, b| a.value.total_cmp(&b.value));
+            }
+        }
+        Self { columns, rows }
+    }
+
+    /// Write the table as CSV, with the columns `n_number`, `amino_acid`, `c_number` followed by
+    /// all fragment columns (see [`FragmentTableColumn::label`]). Multiple m/z values in a
+    /// single cell are separated by semicolons.
+    /// # Errors
+    /// If writing fails.
+    pub fn write_csv(&self, writer: impl Write) -> Result<(), CustomError> {
+        let labels = self
+            .columns
+            .iter()
+            .map(FragmentTableColumn::label)
+            .collect_vec();
+        crate::csv::write_csv(
+            writer,
+            self.rows.iter().map(|row| {
+                [
+                    ("n_number".to_string(), row.n_number.to_string()),
+                    ("amino_acid".to_string(), row.amino_acid.char().to_string()),
+                    ("c_number".to_string(), row.c_number.to_string()),
+                ]
+                .into_iter()
+                .chain(labels.iter().zip(&row.mz).map(|(label, cell)| {
+                    (
+                        label.clone(),
+                        cell.iter().map(|mz| mz.value.to_string()).join(";"),
+                    )
+                }))
+                .collect_vec()
+            }),
+        )
+        .map_err(|e| CustomError::error("Could not write fragment table", e, Context::None))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{model::PrimaryIonSeries, system::e};
+
+    #[test]
+    fn table() {
+        let peptide = Peptidoform::pro_forma("PEPTIDE", None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let model = Model::none()
+            .b(PrimaryIonSeries::default())
+            .y(PrimaryIonSeries::default());
+        let table = FragmentTable::new(
+            &peptide,
+            Charge::new::<e>(2),
+            &model,
+            MassMode::Monoisotopic,
+        );
+        assert_eq!(
+            table
+                .columns
+                .iter()
+                .map(FragmentTableColumn::label)
+                .collect_vec(),
+            ["b", "b^2", "y", "y^2"]
+        );
+        assert_eq!(table.rows.len(), 7);
+        assert_eq!(table.rows[1].amino_acid, AminoAcid::GlutamicAcid);
+        assert_eq!(table.rows[1].c_number, 6);
+        // b2 of PE
+        assert!((table.rows[1].mz[0][0].value - 227.102_67).abs() < 1e-4);
+        // y1 of E
+        assert!((table.rows[6].mz[2][0].value - 148.060_43).abs() < 1e-4);
+
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("n_number,amino_acid,c_number,b,b^2,y,y^2")
+        );
+        assert!(lines.next().unwrap().starts_with("1,P,7,"));
+
+        let json = serde_json::to_string(&table).unwrap();
+        let parsed: FragmentTable = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.columns, table.columns);
+        assert_eq!(parsed.rows[1].mz[2].len(), 1);
+    }
+}
@@ -2,7 +2,7 @@
 use crate::{
     model::*,
     modification::ModificationId,
-    system::{ratio::ppm, usize::Charge, MassOverCharge, Ratio},
+    system::{ratio::ppm, usize::Charge, Mass, MassOverCharge, Ratio},
     *,
 };
 
@@ -696,6 +696,232 @@ fn ensure_no_double_xl_labels_small_non_breaking() {
     assert_eq!(doubly_annotated.len(), 0);
 }
 
+#[test]
+fn reporter_ions() {
+    use crate::fragment::{DiagnosticPosition, FragmentType};
+
+    let peptide = Peptidoform::pro_forma("[TMT6plex]-PEPTIDEK[TMT6plex]", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let model = Model::none()
+        .reporter_ions((true, ChargeRange::ONE))
+        .complementary_reporter_ions((true, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE));
+    let fragments =
+        peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(2), &model);
+    let reporters = fragments
+        .iter()
+        .filter(|f| f.ion == FragmentType::Diagnostic(DiagnosticPosition::Reporter))
+        .map(|f| f.mz(MassMode::Monoisotopic).unwrap().value)
+        .collect_vec();
+    assert_eq!(reporters.len(), 11);
+    assert!(reporters.iter().any(|mz| (mz - 126.127_726).abs() < 1e-4));
+    assert!(reporters.iter().any(|mz| (mz - 131.138_180).abs() < 1e-4));
+    let complementary = fragments
+        .iter()
+        .filter(|f| matches!(f.ion, FragmentType::ComplementaryReporter(_)))
+        .collect_vec();
+    assert_eq!(complementary.len(), 11);
+    let tmt126 = complementary
+        .iter()
+        .find(|f| f.ion.to_string() == "p-r[tmt126]")
+        .unwrap();
+    assert_eq!(tmt126.charge.value, 1);
+    let expected = peptide.formulas()[0].monoisotopic_mass().value
+        - molecular_formula!(C 9 H 15 N 1 O 1)
+            .monoisotopic_mass()
+            .value
+        + 1.007_276;
+    assert!((tmt126.mz(MassMode::Monoisotopic).unwrap().value - expected).abs() < 1e-4);
+    assert!(Peptidoform::pro_forma("PEPTIDEK", None)
+        .unwrap()
+        .into_linear()
+        .unwrap()
+        .generate_theoretical_fragments(Charge::new::<crate::system::e>(2), &model)
+        .iter()
+        .all(|f| f.ion == FragmentType::Precursor));
+}
+
+#[test]
+fn rearrangements() {
+    use crate::fragment::FragmentType;
+
+    let peptide = Peptidoform::pro_forma("GDGAK", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let model = Model::none()
+        .b(PrimaryIonSeries::default())
+        .rearrangements(RearrangementModel::ALLOW);
+    let fragments =
+        peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(1), &model);
+    let mz = |label: &str, gain: bool| {
+        fragments
+            .iter()
+            .filter(|f| f.ion.to_string() == label && f.neutral_loss.is_empty() != gain)
+            .map(|f| f.mz(MassMode::Monoisotopic).unwrap().value)
+            .collect_vec()
+    };
+    // b+H2O for the b ion ending in an acidic residue and for b(n-1)
+    assert_eq!(
+        fragments
+            .iter()
+            .filter(|f| !f.neutral_loss.is_empty())
+            .count(),
+        2
+    );
+    assert_eq!(mz("b2", true).len(), 1);
+    assert!((mz("b4", true)[0] - 319.124_81).abs() < 1e-4);
+    // a/b pairs
+    for index in 1..=4 {
+        let (a, b) = (
+            mz(&format!("a{index}"), false),
+            mz(&format!("b{index}"), false),
+        );
+        assert_eq!(a.len(), 1);
+        assert!((b[0] - a[0] - 27.994_915).abs() < 1e-4);
+    }
+    // Internal fragments only from the non terminal residues
+    let internal = fragments
+        .iter()
+        .filter(|f| matches!(f.ion, FragmentType::Internal(..)))
+        .collect_vec();
+    assert_eq!(internal.len(), 6);
+    assert!(internal.iter().any(|f| f.ion.to_string() == "mb:y1:2"
+        && (f.mz(MassMode::Monoisotopic).unwrap().value - 173.055_68).abs() < 1e-4));
+    // Nothing is generated when disallowed
+    assert!(peptide
+        .generate_theoretical_fragments(
+            Charge::new::<crate::system::e>(1),
+            &model.rearrangements(RearrangementModel::DISALLOW)
+        )
+        .iter()
+        .all(|f| matches!(f.ion, FragmentType::b(_) | FragmentType::Precursor)));
+}
+
+#[test]
+fn mobile_proton_charges() {
+    let charge = |z| Charge::new::<crate::system::e>(z);
+    let range = |z, peptide: &str| {
+        ChargeRange::mobile_proton_for(
+            charge(z),
+            &CompoundPeptidoformIon::pro_forma(peptide, None).unwrap(),
+        )
+        .charges_iter(crate::system::isize::Charge::new::<crate::system::e>(
+            isize::try_from(z).unwrap(),
+        ))
+        .map(|c| c.value)
+        .collect_vec()
+    };
+    assert_eq!(range(1, "PEPTIDEK"), [1]);
+    assert_eq!(range(2, "PEPTIDEK"), [1]);
+    assert_eq!(range(4, "PEPTIDEK"), [1, 2]);
+    assert_eq!(range(4, "PEPHTIDEKAAR"), [1, 2, 3]);
+    assert_eq!(range(4, "PEPTIDEPEPTIDEPEPTIDE"), [1]);
+    assert_eq!(range(4, "PEPTIDE+PEPTIDEKAAKR"), [1, 2, 3]);
+
+    let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDEK", None).unwrap();
+    let model = Model::none()
+        .y(PrimaryIonSeries::default())
+        .fragment_charge_range(ChargeRange::mobile_proton_for(charge(4), &peptide));
+    let fragments = peptide.generate_theoretical_fragments(charge(4), &model);
+    assert!(fragments
+        .iter()
+        .filter(|f| f.ion != crate::fragment::FragmentType::Precursor)
+        .all(|f| f.charge.value <= 2));
+    // The same ranges are estimated by the model itself for each peptidoform ion
+    let estimated = Model::none()
+        .y(PrimaryIonSeries::default())
+        .mobile_proton_charges(true);
+    assert_eq!(
+        peptide.generate_theoretical_fragments(charge(4), &estimated),
+        fragments
+    );
+    assert_eq!(
+        Peptidoform::pro_forma("PEPTIDEK", None)
+            .unwrap()
+            .into_linear()
+            .unwrap()
+            .generate_theoretical_fragments(charge(4), &estimated),
+        fragments
+    );
+}
+
+#[test]
+fn mass_modification_resolution() {
+    let peptide = Peptidoform::pro_forma("PEPTM[+15.9949]IDE", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let model = Model::cid_hcd();
+    let resolving = model
+        .clone()
+        .mass_modification_resolution(Some(Tolerance::new_absolute(Mass::new::<
+            crate::system::dalton,
+        >(0.01))));
+    assert_eq!(
+        model.resolve_mass_modifications(&peptide).to_string(),
+        "PEPTM[+15.9949]IDE"
+    );
+    assert_eq!(
+        resolving.resolve_mass_modifications(&peptide).to_string(),
+        "PEPTM[U:Oxidation]IDE"
+    );
+    let losses = |model: &Model| {
+        peptide
+            .generate_theoretical_fragments(Charge::new::<crate::system::e>(1), model)
+            .iter()
+            .filter(|f| !f.neutral_loss.is_empty())
+            .count()
+    };
+    // The neutral loss of methionine sulfoxide is only known after resolution
+    assert!(losses(&resolving) > losses(&model));
+}
+
+#[test]
+fn neutral_loss_rules() {
+    let peptide = Peptidoform::pro_forma("GAKSG", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let losses = vec![
+        NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
+        NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
+    ];
+    let model = Model::none()
+        .b(PrimaryIonSeries::default().neutral_losses(losses.clone()))
+        .y(PrimaryIonSeries::default().neutral_losses(losses))
+        .neutral_loss_rules(NeutralLossRule::water_and_ammonia());
+    let fragments =
+        peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(1), &model);
+    let losses = |label: &str| {
+        fragments
+            .iter()
+            .filter(|f| f.ion.to_string() == label)
+            .flat_map(|f| f.neutral_loss.iter().map(ToString::to_string))
+            .sorted()
+            .collect_vec()
+    };
+    assert!(losses("b2").is_empty());
+    assert_eq!(losses("b3"), ["-H3N1"]);
+    assert_eq!(losses("b4"), ["-H2O1", "-H3N1"]);
+    assert!(losses("y1").is_empty());
+    assert_eq!(losses("y2"), ["-H2O1"]);
+    assert_eq!(losses("y3"), ["-H2O1", "-H3N1"]);
+    // Without rules all losses are generated for all fragments
+    assert_eq!(
+        peptide
+            .generate_theoretical_fragments(
+                Charge::new::<crate::system::e>(1),
+                &model.neutral_loss_rules(Vec::new())
+            )
+            .iter()
+            .filter(|f| !f.neutral_loss.is_empty())
+            .count(),
+        16
+    );
+}
+
 fn test(
     theoretical_fragments: &[(f64, &str)],
     peptide: impl Into<CompoundPeptidoformIon>,
@@ -15,6 +15,19 @@ use crate::{
 
 use crate::uom::num_traits::Zero;
 
+/// A part of a positioned glycan structure, see [`PositionedGlycanStructure::select`]. Positions
+/// are matched on their depth and branch, the series number and attachment are ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GlycanSelection<'a> {
+    /// The subtree rooted at the given position (or the whole structure if `None`) without the
+    /// subtrees rooted at any of the given breakages. The part retained by a B ion is the subtree
+    /// rooted at its position, the part retained by a Y ion is the whole structure without the
+    /// subtrees at its breakages.
+    Subtree(Option<&'a GlycanPosition>, &'a [GlycanPosition]),
+    /// Only the monosaccharide at the given position
+    SingleSugar(&'a GlycanPosition),
+}
+
 /// Rose tree representation of glycan structure
 #[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub struct PositionedGlycanStructure {
@@ -242,7 +255,75 @@ impl PositionedGlycanStructure {
         }
     }
 
-    fn position(&self, attachment: Option<(AminoAcid, usize)>) -> GlycanPosition {
+    /// The monosaccharide at this position
+    pub const fn sugar(&self) -> &MonoSaccharide {
+        &self.sugar
+    }
+
+    /// The branches from this position, sorted on decreasing mass
+    pub fn branches(&self) -> &[Self] {
+        &self.branches
+    }
+
+    /// Find the subtree rooted at the given position. Positions are matched on their depth and
+    /// branch, the series number and attachment are ignored.
+    pub fn find(&self, position: &GlycanPosition) -> Option<&Self> {
+        if self.is_at(position) {
+            Some(self)
+        } else {
+            self.branches.iter().find_map(|b| b.find(position))
+        }
+    }
+
+    /// Get all monosaccharides (as the subtrees rooted at them) in the selected part of this
+    /// structure, in depth first order. Use [`Self::position`] and [`Self::sugar`] to get the
+    /// details for each selected monosaccharide.
+    pub fn select(&self, selection: GlycanSelection<'_>) -> Vec<&Self> {
+        match selection {
+            GlycanSelection::Subtree(root, breakages) => {
+                let mut output = Vec::new();
+                if let Some(root) = root.map_or(Some(self), |r| self.find(r)) {
+                    root.select_inner(breakages, &mut output);
+                }
+                output
+            }
+            GlycanSelection::SingleSugar(position) => self.find(position).into_iter().collect(),
+        }
+    }
+
+    /// Get the composition of the selected part of this structure, see [`Self::select`]. The
+    /// result is normalised (sorted and deduplicated).
+    /// # Panics
+    /// If one monosaccharide species has occurrence outside the range of [`isize::MIN`] to [`isize::MAX`].
+    pub fn composition(&self, selection: GlycanSelection<'_>) -> Vec<(MonoSaccharide, isize)> {
+        MonoSaccharide::simplify_composition(
+            self.select(selection)
+                .into_iter()
+                .map(|s| (s.sugar.clone(), 1))
+                .collect(),
+        )
+        .expect("One monosaccharide species has a number outside of the range of isize")
+    }
+
+    /// Add this position and all its branches to the output, except for the subtrees rooted at
+    /// any of the breakages
+    fn select_inner<'a>(&'a self, breakages: &[GlycanPosition], output: &mut Vec<&'a Self>) {
+        if breakages.iter().any(|b| self.is_at(b)) {
+            return;
+        }
+        output.push(self);
+        for branch in &self.branches {
+            branch.select_inner(breakages, output);
+        }
+    }
+
+    /// Check if this is the given position, ignoring the series number and attachment
+    fn is_at(&self, position: &GlycanPosition) -> bool {
+        self.inner_depth == position.inner_depth && self.branch == position.branch
+    }
+
+    /// The position of this monosaccharide, with the given attachment
+    pub fn position(&self, attachment: Option<(AminoAcid, usize)>) -> GlycanPosition {
         GlycanPosition {
             inner_depth: self.inner_depth,
             series_number: self.outer_depth + 1,
@@ -251,3 +332,58 @@ impl PositionedGlycanStructure {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::glycan::GlycanStructure;
+
+    #[test]
+    fn select() {
+        // HexNAc at the root, with a Hex-Hex branch and a Fuc branch
+        let structure = GlycanStructure::from_str("hexnac(hex(hex),fuc)")
+            .unwrap()
+            .determine_positions();
+        let hex = &structure.branches()[0];
+        let fuc = &structure.branches()[1];
+        assert_eq!(hex.sugar().to_string(), "Hex");
+        assert_eq!(fuc.sugar().to_string(), "Fuc");
+        let hex_position = hex.position(None);
+        let fuc_position = fuc.position(Some((AminoAcid::Asparagine, 2)));
+        assert_eq!(hex_position.branch, [0]);
+        assert_eq!(
+            structure.select(GlycanSelection::Subtree(None, &[])).len(),
+            4
+        );
+        // The Y ion that lost the Fuc branch
+        let y = structure.select(GlycanSelection::Subtree(None, &[fuc_position.clone()]));
+        assert_eq!(
+            y.iter().map(|s| s.sugar().to_string()).collect_vec(),
+            ["HexNAc", "Hex", "Hex"]
+        );
+        // The B ion of the Hex branch
+        assert_eq!(
+            structure
+                .composition(GlycanSelection::Subtree(Some(&hex_position), &[]))
+                .iter()
+                .map(|(s, n)| format!("{s}{n}"))
+                .collect_vec(),
+            ["Hex2"]
+        );
+        assert_eq!(
+            structure
+                .select(GlycanSelection::SingleSugar(&fuc_position))
+                .len(),
+            1
+        );
+        assert!(structure
+            .select(GlycanSelection::Subtree(
+                Some(&hex_position),
+                &[hex_position.clone()]
+            ))
+            .is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Sample handling artifacts that can explain precursor mass differences
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identification::IdentifiedPeptide, system::Mass, AminoAcid, CompoundPeptidoformIon,
+    MolecularFormula, Tolerance, WithinTolerance,
+};
+
+/// A hypothesis of common sample handling artifacts, used to explain the difference between the
+/// experimental and theoretical precursor mass when the identification tool does not report these.
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub struct ArtifactHypothesis {
+    /// The number of asparagine or glutamine residues that are deamidated
+    pub deamidations: usize,
+    /// The N-terminal pyroglutamate formation, if any
+    pub pyro_glu: Option<PyroGlu>,
+}
+
+/// The residue an N-terminal pyroglutamate is formed from
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum PyroGlu {
+    /// From an N-terminal glutamine, loss of ammonia
+    Glutamine,
+    /// From an N-terminal glutamic acid, loss of water
+    GlutamicAcid,
+}
+
+/// An artifact hypothesis that explains the experimental precursor mass
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ArtifactMatch {
+    /// The artifacts that explain the mass difference, this is the default (empty) hypothesis if
+    /// the theoretical mass already matched
+    pub hypothesis: ArtifactHypothesis,
+    /// The signed mass error (experimental - theoretical) with the artifacts taken into account
+    pub mass_error: Mass,
+}
+
+impl ArtifactHypothesis {
+    /// Check if this hypothesis contains no artifacts
+    pub const fn is_empty(&self) -> bool {
+        self.deamidations == 0 && self.pyro_glu.is_none()
+    }
+
+    /// The total number of artifacts in this hypothesis
+    pub fn len(&self) -> usize {
+        self.deamidations + usize::from(self.pyro_glu.is_some())
+    }
+
+    /// The formula difference caused by these artifacts
+    #[allow(clippy::cast_possible_wrap)] // There are never more than i32::MAX deamidations
+    pub fn formula(&self) -> MolecularFormula {
+        let deamidation = crate::molecular_formula!(H -1 N -1 O 1);
+        let pyro_glu = match self.pyro_glu {
+            Some(PyroGlu::Glutamine) => crate::molecular_formula!(H -3 N -1),
+            Some(PyroGlu::GlutamicAcid) => crate::molecular_formula!(H -2 O -1),
+            None => MolecularFormula::default(),
+        };
+        deamidation * self.deamidations as i32 + pyro_glu
+    }
+
+    /// Find the artifact hypothesis that explains the experimental mass for the given peptide
+    /// within the given tolerance. Only unmodified asparagines and glutamines are considered for
+    /// deamidation and only an unmodified N-terminal glutamine or glutamic acid without N-terminal
+    /// modifications is considered for pyroglutamate formation. An N-terminal glutamine that forms
+    /// pyroglutamate is not also counted as a deamidation site. If multiple hypotheses match, the
+    /// one with the fewest artifacts is returned, with ties broken by the smallest mass error. If
+    /// the peptide has multiple possible formulas all of them are tried.
+    pub fn explain(
+        peptide: &CompoundPeptidoformIon,
+        experimental: Mass,
+        tolerance: Tolerance<Mass>,
+    ) -> Option<ArtifactMatch> {
+        let mut deamidation_sites = 0;
+        let mut pyro_glu_options = vec![None];
+        for peptidoform in peptide.peptidoforms() {
+            deamidation_sites += peptidoform
+                .sequence()
+                .iter()
+                .filter(|s| {
+                    s.modifications.is_empty()
+                        && matches!(
+                            s.aminoacid.aminoacid(),
+                            AminoAcid::Asparagine | AminoAcid::Glutamine
+                        )
+                })
+                .count();
+            if peptidoform.get_n_term().is_empty() {
+                if let Some(first) = peptidoform.sequence().first() {
+                    if first.modifications.is_empty() {
+                        match first.aminoacid.aminoacid() {
+                            AminoAcid::Glutamine => {
+                                pyro_glu_options.push(Some(PyroGlu::Glutamine));
+                            }
+                            AminoAcid::GlutamicAcid => {
+                                pyro_glu_options.push(Some(PyroGlu::GlutamicAcid));
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+        }
+        pyro_glu_options.dedup();
+
+        let formulas = peptide.formulas();
+        let mut best: Option<ArtifactMatch> = None;
+        for pyro_glu in pyro_glu_options {
+            // The N-terminal glutamine cannot both form pyroglutamate and be deamidated
+            let sites =
+                deamidation_sites.saturating_sub(usize::from(pyro_glu == Some(PyroGlu::Glutamine)));
+            for deamidations in 0..=sites {
+                let hypothesis = Self {
+                    deamidations,
+                    pyro_glu,
+                };
+                let difference = hypothesis.formula();
+                for formula in formulas.iter() {
+                    let theoretical = (formula + &difference).monoisotopic_mass();
+                    if !tolerance.within(&theoretical, &experimental) {
+                        continue;
+                    }
+                    let found = ArtifactMatch {
+                        hypothesis,
+                        mass_error: experimental - theoretical,
+                    };
+                    if best.as_ref().map_or(true, |b| {
+                        (found.hypothesis.len(), found.mass_error.value.abs())
+                            < (b.hypothesis.len(), b.mass_error.value.abs())
+                    }) {
+                        best = Some(found);
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+impl IdentifiedPeptide {
+    /// Find the artifact hypothesis (deamidation and/or N-terminal pyroglutamate formation) that
+    /// explains the experimental precursor mass for this peptide, see [`ArtifactHypothesis::explain`].
+    /// This returns `None` if the experimental mass or peptide is unknown, or if no hypothesis
+    /// explains the experimental mass within the tolerance. If the theoretical mass already
+    /// matches the returned hypothesis is empty.
+    pub fn explain_precursor_artifacts(&self, tolerance: Tolerance<Mass>) -> Option<ArtifactMatch> {
+        let experimental = self.experimental_mass()?;
+        let peptide = self.peptide()?.compound_peptidoform();
+        ArtifactHypothesis::explain(&peptide, experimental, tolerance)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::system::da;
+
+    #[test]
+    fn deamidation_and_pyro_glu() {
+        let peptide = CompoundPeptidoformIon::pro_forma("QANQK", None).unwrap();
+        let mass = peptide.formulas()[0].monoisotopic_mass();
+        let tolerance = Tolerance::new_ppm(10.0);
+
+        let unmodified = ArtifactHypothesis::explain(&peptide, mass, tolerance).unwrap();
+        assert!(unmodified.hypothesis.is_empty());
+
+        let deamidated =
+            ArtifactHypothesis::explain(&peptide, mass + da(2.0 * 0.984_016), tolerance).unwrap();
+        assert_eq!(deamidated.hypothesis.deamidations, 2);
+        assert_eq!(deamidated.hypothesis.pyro_glu, None);
+
+        let pyro_glu =
+            ArtifactHypothesis::explain(&peptide, mass - da(17.026_549), tolerance).unwrap();
+        assert_eq!(
+            pyro_glu.hypothesis,
+            ArtifactHypothesis {
+                deamidations: 0,
+                pyro_glu: Some(PyroGlu::Glutamine),
+            }
+        );
+
+        assert_eq!(
+            ArtifactHypothesis::explain(&peptide, mass + da(5.0), tolerance),
+            None
+        );
+
+        // The N-terminal glutamine is either deamidated or forms pyroglutamate, not both
+        let peptide = CompoundPeptidoformIon::pro_forma("QPEPTIDE", None).unwrap();
+        let mass = peptide.formulas()[0].monoisotopic_mass();
+        assert_eq!(
+            ArtifactHypothesis::explain(&peptide, mass + da(0.984_016), tolerance)
+                .unwrap()
+                .hypothesis,
+            ArtifactHypothesis {
+                deamidations: 1,
+                pyro_glu: None,
+            }
+        );
+        assert_eq!(
+            ArtifactHypothesis::explain(&peptide, mass - da(17.026_549), tolerance)
+                .unwrap()
+                .hypothesis,
+            ArtifactHypothesis {
+                deamidations: 0,
+                pyro_glu: Some(PyroGlu::Glutamine),
+            }
+        );
+        assert_eq!(
+            ArtifactHypothesis::explain(&peptide, mass - da(17.026_549 - 0.984_016), tolerance),
+            None
+        );
+
+        // A modified N-terminus cannot form pyroglutamate
+        let peptide = CompoundPeptidoformIon::pro_forma("[Acetyl]-EAK", None).unwrap();
+        let mass = peptide.formulas()[0].monoisotopic_mass();
+        assert_eq!(
+            ArtifactHypothesis::explain(&peptide, mass - da(18.010_565), tolerance),
+            None
+        );
+    }
+}
@@ -0,0 +1,640 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    modification::{ModificationId, Ontology, SimpleModificationInner},
+};
+
+use super::CVTerm;
+
+/// The value of an [`Attribute`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum AttributeValue {
+    /// An integer value
+    Integer(i64),
+    /// A floating point value
+    Float(f64),
+    /// A text value
+    Text(String),
+    /// A CV term as value
+    Term(CVTerm),
+}
+
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer(value) => write!(f, "{value}"),
+            Self::Float(value) => write!(f, "{value}"),
+            Self::Text(value) => write!(f, "{value}"),
+            Self::Term(term) => write!(f, "{}|{}", term.id, term.term),
+        }
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<usize> for AttributeValue {
+    fn from(value: usize) -> Self {
+        Self::Integer(value.try_into().unwrap_or(i64::MAX))
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<CVTerm> for AttributeValue {
+    fn from(value: CVTerm) -> Self {
+        Self::Term(value)
+    }
+}
+
+/// An attribute as used in [mzSpecLib](https://github.com/HUPO-PSI/mzSpecLib), a CV term with
+/// an optional value, unit, and group. Attributes are built with the builder methods and written
+/// in the mzSpecLib text format with [`Self::lines`] or as part of an [`AttributeSet`].
+/// ```
+/// # use rustyms::identification::{Attribute, CVTerm};
+/// let attribute = Attribute::new(CVTerm::new("MS", "MS:1000894", "retention time"))
+///     .value(12.5)
+///     .unit(CVTerm::new("UO", "UO:0000031", "minute"))
+///     .group(1);
+/// assert_eq!(
+///     attribute.lines().unwrap(),
+///     [
+///         "[1]MS:1000894|retention time=12.5",
+///         "[1]UO:0000000|unit=UO:0000031|minute"
+///     ]
+/// );
+/// ```
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Attribute {
+    /// The term that defines this attribute
+    pub term: CVTerm,
+    /// The value, if any
+    pub value: Option<AttributeValue>,
+    /// The unit of the value, if any
+    pub unit: Option<CVTerm>,
+    /// The group this attribute belongs to, if any
+    pub group: Option<usize>,
+}
+
+impl Attribute {
+    /// Create a new attribute with the given term, without a value, unit, or group.
+    pub const fn new(term: CVTerm) -> Self {
+        Self {
+            term,
+            value: None,
+            unit: None,
+            group: None,
+        }
+    }
+
+    /// Set the value.
+    #[must_use]
+    pub fn value(self, value: impl Into<AttributeValue>) -> Self {
+        Self {
+            value: Some(value.into()),
+            ..self
+        }
+    }
+
+    /// Set the unit, this is only valid for numeric values and requires a group. This can be a
+    /// known [`Unit`] or any CV term.
+    #[must_use]
+    pub fn unit(self, unit: impl Into<CVTerm>) -> Self {
+        Self {
+            unit: Some(unit.into()),
+            ..self
+        }
+    }
+
+    /// Get the value as a number, if it is numeric.
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn numeric_value(&self) -> Option<f64> {
+        match self.value {
+            Some(AttributeValue::Integer(value)) => Some(value as f64),
+            Some(AttributeValue::Float(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the unit as a known [`Unit`], if the unit is set and known.
+    pub fn known_unit(&self) -> Option<Unit> {
+        self.unit.as_ref().and_then(Unit::from_term)
+    }
+
+    /// Convert the value of this attribute to the given unit, see [`Unit::convert`].
+    /// # Errors
+    /// If the value is not numeric, if the current unit is not set or not a known [`Unit`], or if
+    /// the units cannot be converted into each other.
+    pub fn convert(self, unit: Unit, reference_mz: Option<f64>) -> Result<Self, CustomError> {
+        let invalid = |explanation: String| {
+            CustomError::error(
+                "Invalid unit conversion",
+                explanation,
+                Context::show(format!("{}|{}", self.term.id, self.term.term)),
+            )
+        };
+        let value = self
+            .numeric_value()
+            .ok_or_else(|| invalid("Only numeric values can be converted".to_string()))?;
+        let from = self
+            .known_unit()
+            .ok_or_else(|| invalid("The current unit is not set or not known".to_string()))?;
+        let value = from.convert(value, unit, reference_mz).ok_or_else(|| {
+            invalid(format!(
+                "A value in {} cannot be converted to {}{}",
+                from.name(),
+                unit.name(),
+                if from.dimension() == unit.dimension() {
+                    " without a reference m/z"
+                } else {
+                    ""
+                }
+            ))
+        })?;
+        Ok(self.value(value).unit(unit))
+    }
+
+    /// Set the group.
+    #[must_use]
+    pub fn group(self, group: usize) -> Self {
+        Self {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    /// Check that this attribute can be written: the term (and the unit and value terms) need
+    /// an accession in the form `ONTOLOGY:ID` and a name, and a unit needs a numeric value and a
+    /// group. Terms from the CVs that are included in rustyms are checked against the CV: the
+    /// modification ontologies (Unimod, PSI-MOD, XLMOD, and RESID) and the unit ontology for the
+    /// known [`Unit`]s. Terms from other CVs (eg PSI-MS) are only checked for their form.
+    /// # Errors
+    /// If any of these requirements is not met.
+    pub fn validate(&self) -> Result<(), CustomError> {
+        let invalid = |explanation: &str| {
+            CustomError::error(
+                "Invalid attribute",
+                explanation,
+                Context::show(format!("{}|{}", self.term.id, self.term.term)),
+            )
+        };
+        let terms =
+            std::iter::once(&self.term)
+                .chain(self.unit.as_ref())
+                .chain(match &self.value {
+                    Some(AttributeValue::Term(term)) => Some(term),
+                    _ => None,
+                });
+        for term in terms {
+            if !term
+                .id
+                .split_once(':')
+                .is_some_and(|(prefix, id)| !prefix.is_empty() && !id.is_empty())
+            {
+                return Err(invalid(&format!(
+                    "The accession '{}' is not in the form 'ONTOLOGY:ID'",
+                    term.id
+                )));
+            }
+            if term.term.is_empty() {
+                return Err(invalid(&format!("The term '{}' has no name", term.id)));
+            }
+            check_known_cv(term).map_err(|explanation| invalid(&explanation))?;
+        }
+        if self.unit.is_some() {
+            if !matches!(
+                self.value,
+                Some(AttributeValue::Integer(_) | AttributeValue::Float(_))
+            ) {
+                return Err(invalid("A unit can only be given for a numeric value"));
+            }
+            if self.group.is_none() {
+                return Err(invalid(
+                    "A unit can only be given for an attribute in a group",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the lines for this attribute in the mzSpecLib text format, if a unit is present this is
+    /// written as a second line in the same group.
+    /// # Errors
+    /// If this attribute is not valid, see [`Self::validate`].
+    pub fn lines(&self) -> Result<Vec<String>, CustomError> {
+        self.validate()?;
+        let group = self.group.map_or(String::new(), |g| format!("[{g}]"));
+        let mut lines = vec![format!(
+            "{group}{}|{}{}",
+            self.term.id,
+            self.term.term,
+            self.value
+                .as_ref()
+                .map_or(String::new(), |value| format!("={value}"))
+        )];
+        if let Some(unit) = &self.unit {
+            lines.push(format!("{group}UO:0000000|unit={}|{}", unit.id, unit.term));
+        }
+        Ok(lines)
+    }
+}
+
+/// Check a term against the CVs that are included in rustyms, see [`Attribute::validate`].
+/// # Errors
+/// If the accession is not found in a known CV or if the name does not match the CV.
+fn check_known_cv(term: &CVTerm) -> Result<(), String> {
+    let name = term.term.trim();
+    if let Some(unit) = Unit::from_term(term) {
+        return if unit.name().eq_ignore_ascii_case(name) {
+            Ok(())
+        } else {
+            Err(format!(
+                "The term '{}' is named '{}' in the unit ontology, not '{name}'",
+                term.id,
+                unit.name()
+            ))
+        };
+    }
+    let Some((prefix, id)) = term.id.trim().split_once(':') else {
+        return Ok(());
+    };
+    let (ontology, id) = match prefix.to_ascii_uppercase().as_str() {
+        "UNIMOD" => (Ontology::Unimod, id),
+        "MOD" => (Ontology::Psimod, id),
+        "XLMOD" => (Ontology::Xlmod, id),
+        "RESID" => (Ontology::Resid, id.trim_start_matches("AA")),
+        _ => return Ok(()),
+    };
+    let found = id
+        .parse::<usize>()
+        .ok()
+        .and_then(|id| ontology.find_id(id, None))
+        .ok_or_else(|| format!("The accession '{}' does not exist in {ontology}", term.id))?;
+    match &*found {
+        SimpleModificationInner::Database {
+            id: ModificationId { name: found, .. },
+            ..
+        }
+        | SimpleModificationInner::Linker {
+            id: ModificationId { name: found, .. },
+            ..
+        } if !found.eq_ignore_ascii_case(name) => Err(format!(
+            "The term '{}' is named '{found}' in {ontology}, not '{name}'",
+            term.id
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Common units from the [unit ontology](https://www.ebi.ac.uk/ols4/ontologies/uo) for
+/// attribute values, with conversions between related units.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    /// Second (UO:0000010)
+    Second,
+    /// Minute (UO:0000031)
+    Minute,
+    /// Hour (UO:0000032)
+    Hour,
+    /// Dalton (UO:0000221)
+    Dalton,
+    /// Parts per million (UO:0000169)
+    PartsPerMillion,
+    /// Percent (UO:0000187)
+    Percent,
+    /// Ratio, a dimensionless fraction (UO:0010006)
+    Ratio,
+}
+
+/// The quantity that a [`Unit`] measures, only units of the same dimension can be converted
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+enum Dimension {
+    Time,
+    Mass,
+    Fraction,
+}
+
+impl Unit {
+    /// All known units.
+    pub const ALL: &'static [Self] = &[
+        Self::Second,
+        Self::Minute,
+        Self::Hour,
+        Self::Dalton,
+        Self::PartsPerMillion,
+        Self::Percent,
+        Self::Ratio,
+    ];
+
+    /// The accession in the unit ontology, eg `UO:0000010`.
+    pub const fn accession(self) -> &'static str {
+        match self {
+            Self::Second => "UO:0000010",
+            Self::Minute => "UO:0000031",
+            Self::Hour => "UO:0000032",
+            Self::Dalton => "UO:0000221",
+            Self::PartsPerMillion => "UO:0000169",
+            Self::Percent => "UO:0000187",
+            Self::Ratio => "UO:0010006",
+        }
+    }
+
+    /// The name in the unit ontology, eg `second`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Second => "second",
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::Dalton => "dalton",
+            Self::PartsPerMillion => "parts per million",
+            Self::Percent => "percent",
+            Self::Ratio => "ratio",
+        }
+    }
+
+    /// Get the known unit for this CV term, based on the accession.
+    pub fn from_term(term: &CVTerm) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|unit| unit.accession() == term.id.trim())
+            .copied()
+    }
+
+    const fn dimension(self) -> Dimension {
+        match self {
+            Self::Second | Self::Minute | Self::Hour => Dimension::Time,
+            Self::Dalton | Self::PartsPerMillion => Dimension::Mass,
+            Self::Percent | Self::Ratio => Dimension::Fraction,
+        }
+    }
+
+    /// The factor to convert to the base unit of the dimension (seconds, dalton, or ratio), `None`
+    /// for ppm as that depends on the reference m/z.
+    const fn factor(self) -> Option<f64> {
+        match self {
+            Self::Second | Self::Dalton | Self::Ratio => Some(1.0),
+            Self::Minute => Some(60.0),
+            Self::Hour => Some(3600.0),
+            Self::Percent => Some(0.01),
+            Self::PartsPerMillion => None,
+        }
+    }
+
+    /// Convert a value in this unit to the given unit. Converting between ppm and dalton needs the
+    /// reference m/z. Returns `None` if the units measure a different quantity (eg time and
+    /// mass) or if the reference m/z is needed but not given.
+    /// ```
+    /// # use rustyms::identification::Unit;
+    /// assert_eq!(Unit::Minute.convert(1.5, Unit::Second, None), Some(90.0));
+    /// assert_eq!(Unit::PartsPerMillion.convert(10.0, Unit::Dalton, Some(500.0)), Some(0.005));
+    /// assert_eq!(Unit::PartsPerMillion.convert(10.0, Unit::Dalton, None), None);
+    /// assert_eq!(Unit::Minute.convert(1.5, Unit::Dalton, None), None);
+    /// ```
+    pub fn convert(self, value: f64, to: Self, reference_mz: Option<f64>) -> Option<f64> {
+        if self.dimension() != to.dimension() {
+            return None;
+        }
+        if self == to {
+            return Some(value);
+        }
+        let to_base = |unit: Self| unit.factor().or_else(|| reference_mz.map(|mz| mz / 1e6));
+        Some(value * to_base(self)? / to_base(to)?)
+    }
+}
+
+impl From<Unit> for CVTerm {
+    fn from(value: Unit) -> Self {
+        Self::new("UO", value.accession(), value.name())
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A set of attributes, that keeps track of the used groups. Attributes with a unit but without a
+/// group are automatically placed in a new group.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct AttributeSet {
+    attributes: Vec<Attribute>,
+    next_group: usize,
+}
+
+impl AttributeSet {
+    /// Create an empty set.
+    pub const fn new() -> Self {
+        Self {
+            attributes: Vec::new(),
+            next_group: 1,
+        }
+    }
+
+    /// Get all attributes.
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Get a new unused group number.
+    pub fn new_group(&mut self) -> usize {
+        let group = self.next_group.max(1);
+        self.next_group = group + 1;
+        group
+    }
+
+    /// Add an attribute.
+    /// # Errors
+    /// If the attribute is not valid, see [`Attribute::validate`].
+    pub fn add(&mut self, mut attribute: Attribute) -> Result<(), CustomError> {
+        if attribute.unit.is_some() && attribute.group.is_none() {
+            attribute.group = Some(self.new_group());
+        }
+        attribute.validate()?;
+        if let Some(group) = attribute.group {
+            self.next_group = self.next_group.max(group + 1);
+        }
+        self.attributes.push(attribute);
+        Ok(())
+    }
+
+    /// Add all attributes as a single new group, returns the group number.
+    /// # Errors
+    /// If any of the attributes is not valid, see [`Attribute::validate`], in that case none of the
+    /// attributes are added.
+    pub fn add_group(
+        &mut self,
+        attributes: impl IntoIterator<Item = Attribute>,
+    ) -> Result<usize, CustomError> {
+        let group = self.next_group.max(1);
+        let attributes = attributes
+            .into_iter()
+            .map(|attribute| {
+                let attribute = attribute.group(group);
+                attribute.validate().map(|()| attribute)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.next_group = group + 1;
+        self.attributes.extend(attributes);
+        Ok(group)
+    }
+
+    /// Get all lines in the mzSpecLib text format.
+    /// # Errors
+    /// If any attribute is not valid, see [`Attribute::validate`].
+    pub fn lines(&self) -> Result<Vec<String>, CustomError> {
+        let mut lines = Vec::new();
+        for attribute in &self.attributes {
+            lines.extend(attribute.lines()?);
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    fn term(id: &str, name: &str) -> CVTerm {
+        CVTerm::new(id.split_once(':').unwrap().0, id, name)
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(
+            Attribute::new(term("MS:1003061", "library spectrum name"))
+                .value("PEPTIDE/2")
+                .lines()
+                .unwrap(),
+            ["MS:1003061|library spectrum name=PEPTIDE/2"]
+        );
+        assert_eq!(
+            Attribute::new(term(
+                "MS:1003208",
+                "experimental precursor monoisotopic m/z"
+            ))
+            .value(AttributeValue::Term(term("MS:1000040", "m/z")))
+            .lines()
+            .unwrap(),
+            ["MS:1003208|experimental precursor monoisotopic m/z=MS:1000040|m/z"]
+        );
+        assert!(Attribute::new(term("MS:1000894", "retention time"))
+            .value(12.5)
+            .unit(term("UO:0000031", "minute"))
+            .lines()
+            .is_err());
+        assert!(Attribute::new(term("MS:1000894", "retention time"))
+            .value("late")
+            .unit(term("UO:0000031", "minute"))
+            .group(1)
+            .validate()
+            .is_err());
+        assert!(
+            Attribute::new(CVTerm::new("MS", "1000894", "retention time"))
+                .validate()
+                .is_err()
+        );
+        // Terms from the included CVs are checked against the CV
+        let modification = |id: &str, name: &str| {
+            Attribute::new(term("MS:1003275", "other attribute value"))
+                .value(AttributeValue::Term(term(id, name)))
+                .validate()
+        };
+        assert!(modification("UNIMOD:35", "Oxidation").is_ok());
+        assert!(modification("UNIMOD:35", "oxidation").is_ok());
+        assert!(modification("UNIMOD:35", "Phospho").is_err());
+        assert!(modification("UNIMOD:999999", "Oxidation").is_err());
+        assert!(Attribute::new(term("MS:1000894", "retention time"))
+            .value(1.0)
+            .unit(term("UO:0000031", "second"))
+            .group(1)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn groups() {
+        let mut set = AttributeSet::new();
+        set.add(
+            Attribute::new(term("MS:1000894", "retention time"))
+                .value(12.5)
+                .unit(term("UO:0000031", "minute")),
+        )
+        .unwrap();
+        let group = set
+            .add_group([
+                Attribute::new(term("MS:1002357", "PSM-level probability")).value(0.99),
+                Attribute::new(term("MS:1003276", "decoy")).value(0_usize),
+            ])
+            .unwrap();
+        assert_eq!(group, 2);
+        assert!(set
+            .add_group([Attribute::new(term("MS:1003276", ""))])
+            .is_err());
+        assert_eq!(set.attributes().len(), 3);
+        assert_eq!(
+            set.lines().unwrap(),
+            [
+                "[1]MS:1000894|retention time=12.5",
+                "[1]UO:0000000|unit=UO:0000031|minute",
+                "[2]MS:1002357|PSM-level probability=0.99",
+                "[2]MS:1003276|decoy=0",
+            ]
+        );
+        // The failed group does not use up a group number
+        assert_eq!(set.new_group(), 3);
+    }
+
+    #[test]
+    fn units() {
+        let rt = Attribute::new(term("MS:1000894", "retention time"))
+            .value(2_usize)
+            .unit(Unit::Minute)
+            .group(1);
+        assert_eq!(rt.known_unit(), Some(Unit::Minute));
+        let seconds = rt.clone().convert(Unit::Second, None).unwrap();
+        assert_eq!(seconds.numeric_value(), Some(120.0));
+        assert_eq!(
+            seconds.lines().unwrap()[1],
+            "[1]UO:0000000|unit=UO:0000010|second"
+        );
+        assert!(rt.clone().convert(Unit::Dalton, None).is_err());
+        let tolerance = Attribute::new(term("MS:1001412", "search tolerance plus value"))
+            .value(0.01)
+            .unit(Unit::Dalton)
+            .group(2);
+        let ppm = tolerance
+            .convert(Unit::PartsPerMillion, Some(1000.0))
+            .unwrap();
+        assert!((ppm.numeric_value().unwrap() - 10.0).abs() < 1e-9);
+        assert!(ppm.convert(Unit::Dalton, None).is_err());
+        assert!(Attribute::new(term("MS:1003276", "decoy"))
+            .value("no")
+            .convert(Unit::Second, None)
+            .is_err());
+        for unit in Unit::ALL {
+            assert_eq!(Unit::from_term(&CVTerm::from(*unit)), Some(*unit));
+        }
+    }
+}
@@ -0,0 +1,538 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Context, CustomError};
+
+use super::{Attribute, AttributeValue};
+
+/// A query over the [`Attribute`]s of (library) spectra, to select spectra without having to
+/// write custom loops over the attributes. A query can be built programmatically with the
+/// variants, or parsed from text with [`Self::parse`].
+///
+/// Keys select all attributes with the given accession or term name (case insensitive). Keys and
+/// text values that contain spaces or operators have to be quoted. A comparison holds if any of the
+/// selected attributes satisfies it. Equality compares numeric values as numbers, text values as
+/// case insensitive text, and CV term values on both their accession and name. The ordering
+/// comparisons only hold for numeric values.
+/// ```
+/// # use rustyms::identification::{Attribute, AttributeQuery, CVTerm};
+/// let attributes = [
+///     Attribute::new(CVTerm::new("MS", "MS:1000041", "charge state")).value(2_usize),
+///     Attribute::new(CVTerm::new("MS", "MS:1001469", "taxonomy: scientific name"))
+///         .value("Homo sapiens"),
+///     Attribute::new(CVTerm::new("MS", "MS:1002354", "PSM-level q-value")).value(0.001),
+/// ];
+/// let query = AttributeQuery::parse(
+///     r#"MS:1000041=2 AND "taxonomy: scientific name"="Homo sapiens" AND "PSM-level q-value"<0.01"#,
+/// )
+/// .unwrap();
+/// assert!(query.matches(&attributes));
+/// assert!(!AttributeQuery::parse(r#""charge state">=3 OR NOT MS:1001469"#)
+///     .unwrap()
+///     .matches(&attributes));
+/// ```
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum AttributeQuery {
+    /// Holds if all queries hold
+    And(Vec<Self>),
+    /// Holds if any query holds
+    Or(Vec<Self>),
+    /// Holds if the query does not hold
+    Not(Box<Self>),
+    /// Holds if there is any attribute with this key
+    Exists(String),
+    /// Holds if any attribute with this key has a value that satisfies the comparison
+    Compare(String, Comparison, AttributeValue),
+}
+
+/// A comparison in an [`AttributeQuery`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+pub enum Comparison {
+    /// `=`
+    Equal,
+    /// `!=`, holds if no attribute with this key is equal
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// All comparisons, longer symbols first so these can be parsed greedily
+    const ALL: &'static [Self] = &[
+        Self::NotEqual,
+        Self::LessOrEqual,
+        Self::GreaterOrEqual,
+        Self::Equal,
+        Self::Less,
+        Self::Greater,
+    ];
+
+    /// The symbol for this comparison
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl AttributeQuery {
+    /// Parse a query. Comparisons are written as `key=value` (or `!=`, `<`, `<=`, `>`, `>=`), a
+    /// key on its own checks if the attribute is present. These can be combined with `AND`, `OR`,
+    /// `NOT` (case insensitive), and parentheses, where `NOT` binds strongest and `OR` weakest.
+    /// Keys and values can be quoted with double quotes.
+    /// # Errors
+    /// If the query is not valid.
+    pub fn parse(query: &str) -> Result<Self, CustomError> {
+        let tokens = tokenise(query)?;
+        let mut parser = Parser {
+            query,
+            tokens: &tokens,
+            index: 0,
+        };
+        let result = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error("Expected a combination with AND or OR", token));
+        }
+        Ok(result)
+    }
+
+    /// Check if the given attributes satisfy this query.
+    pub fn matches(&self, attributes: &[Attribute]) -> bool {
+        match self {
+            Self::And(queries) => queries.iter().all(|q| q.matches(attributes)),
+            Self::Or(queries) => queries.iter().any(|q| q.matches(attributes)),
+            Self::Not(query) => !query.matches(attributes),
+            Self::Exists(key) => attributes.iter().any(|a| key_matches(key, a)),
+            Self::Compare(key, Comparison::NotEqual, value) => {
+                !Self::Compare(key.clone(), Comparison::Equal, value.clone()).matches(attributes)
+            }
+            Self::Compare(key, comparison, value) => {
+                attributes.iter().filter(|a| key_matches(key, a)).any(|a| {
+                    a.value
+                        .as_ref()
+                        .is_some_and(|v| compare(v, *comparison, value))
+                })
+            }
+        }
+    }
+
+    /// Select all items whose attributes satisfy this query.
+    pub fn filter<'a, T: 'a>(
+        &'a self,
+        items: impl IntoIterator<Item = T> + 'a,
+        attributes: impl Fn(&T) -> &[Attribute] + 'a,
+    ) -> impl Iterator<Item = T> + 'a {
+        items
+            .into_iter()
+            .filter(move |item| self.matches(attributes(item)))
+    }
+}
+
+impl std::str::FromStr for AttributeQuery {
+    type Err = CustomError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for AttributeQuery {
+    /// Shown as a query that can be parsed again
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |f: &mut std::fmt::Formatter<'_>, queries: &[Self], separator: &str| {
+            write!(f, "(")?;
+            for (index, query) in queries.iter().enumerate() {
+                if index != 0 {
+                    write!(f, " {separator} ")?;
+                }
+                write!(f, "{query}")?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            Self::And(queries) => join(f, queries, "AND"),
+            Self::Or(queries) => join(f, queries, "OR"),
+            Self::Not(query) => write!(f, "NOT {query}"),
+            Self::Exists(key) => write!(f, "{}", quote(key)),
+            Self::Compare(key, comparison, value) => write!(
+                f,
+                "{}{comparison}{}",
+                quote(key),
+                match value {
+                    AttributeValue::Integer(_) | AttributeValue::Float(_) => value.to_string(),
+                    AttributeValue::Text(text) => format!("\"{text}\""),
+                    AttributeValue::Term(term) => quote(&term.id),
+                }
+            ),
+        }
+    }
+}
+
+/// Quote the given key if needed to parse it back
+fn quote(text: &str) -> String {
+    if text.is_empty()
+        || text.contains(|c: char| c.is_whitespace() || "()\"=!<>".contains(c))
+        || ["and", "or", "not"].contains(&text.to_ascii_lowercase().as_str())
+    {
+        format!("\"{text}\"")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Check if the key selects this attribute
+fn key_matches(key: &str, attribute: &Attribute) -> bool {
+    attribute.term.id.eq_ignore_ascii_case(key) || attribute.term.term.eq_ignore_ascii_case(key)
+}
+
+/// Compare the value of an attribute (left) to the value in the query (right)
+fn compare(left: &AttributeValue, comparison: Comparison, right: &AttributeValue) -> bool {
+    let number = |value: &AttributeValue| match value {
+        #[allow(clippy::cast_precision_loss)]
+        AttributeValue::Integer(value) => Some(*value as f64),
+        AttributeValue::Float(value) => Some(*value),
+        AttributeValue::Text(text) => text.trim().parse::<f64>().ok(),
+        AttributeValue::Term(_) => None,
+    };
+    if let (Some(left), Some(right)) = (number(left), number(right)) {
+        #[allow(clippy::float_cmp)] // Stated values are compared exactly
+        return match comparison {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        };
+    }
+    let right = match right {
+        AttributeValue::Text(text) => text.clone(),
+        AttributeValue::Term(term) => term.id.clone(),
+        other => other.to_string(),
+    };
+    let equal = match left {
+        AttributeValue::Term(term) => {
+            term.id.eq_ignore_ascii_case(&right) || term.term.eq_ignore_ascii_case(&right)
+        }
+        other => other.to_string().eq_ignore_ascii_case(&right),
+    };
+    match comparison {
+        Comparison::Equal => equal,
+        Comparison::NotEqual => !equal,
+        _ => false,
+    }
+}
+
+/// A token in a query
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Comparison(Comparison),
+    Word(String),
+    Quoted(String),
+}
+
+/// Split a query into tokens, each with its character offset and length in the query
+/// # Errors
+/// If a quote is not closed or an unexpected character is found.
+fn tokenise(query: &str) -> Result<Vec<(Token, usize, usize)>, CustomError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let start = index;
+        let rest: String = chars[index..chars.len().min(index + 2)].iter().collect();
+        if chars[index].is_whitespace() {
+            index += 1;
+        } else if chars[index] == '(' {
+            tokens.push((Token::Open, start, 1));
+            index += 1;
+        } else if chars[index] == ')' {
+            tokens.push((Token::Close, start, 1));
+            index += 1;
+        } else if let Some(comparison) = Comparison::ALL
+            .iter()
+            .find(|c| rest.starts_with(c.symbol()))
+        {
+            let length = comparison.symbol().len();
+            tokens.push((Token::Comparison(*comparison), start, length));
+            index += length;
+        } else if chars[index] == '"' {
+            let Some(end) = chars[index + 1..].iter().position(|c| *c == '"') else {
+                return Err(CustomError::error(
+                    "Invalid attribute query",
+                    "The quoted text is not closed",
+                    Context::line(None, query, start, chars.len() - start),
+                ));
+            };
+            tokens.push((
+                Token::Quoted(chars[index + 1..index + 1 + end].iter().collect()),
+                start,
+                end + 2,
+            ));
+            index += end + 2;
+        } else {
+            while index < chars.len()
+                && !chars[index].is_whitespace()
+                && !"()\"=!<>".contains(chars[index])
+            {
+                index += 1;
+            }
+            if index == start {
+                return Err(CustomError::error(
+                    "Invalid attribute query",
+                    "Unexpected character",
+                    Context::line(None, query, start, 1),
+                ));
+            }
+            tokens.push((
+                Token::Word(chars[start..index].iter().collect()),
+                start,
+                index - start,
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser for queries
+struct Parser<'a> {
+    query: &'a str,
+    tokens: &'a [(Token, usize, usize)],
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a (Token, usize, usize)> {
+        self.tokens.get(self.index)
+    }
+
+    fn error(&self, explanation: &str, token: &(Token, usize, usize)) -> CustomError {
+        CustomError::error(
+            "Invalid attribute query",
+            explanation,
+            Context::line(None, self.query, token.1, token.2),
+        )
+    }
+
+    fn end_error(&self, explanation: &str) -> CustomError {
+        let length = self.query.chars().count();
+        CustomError::error(
+            "Invalid attribute query",
+            explanation,
+            Context::line(None, self.query, length, 1),
+        )
+    }
+
+    /// Check if the next token is the given keyword and consume it if so
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some((Token::Word(word), _, _)) if word.eq_ignore_ascii_case(keyword))
+        {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parse queries combined with `OR`
+    /// # Errors
+    /// If the query is not valid.
+    fn or(&mut self) -> Result<AttributeQuery, CustomError> {
+        let mut queries = vec![self.and()?];
+        while self.keyword("or") {
+            queries.push(self.and()?);
+        }
+        Ok(if queries.len() == 1 {
+            queries.remove(0)
+        } else {
+            AttributeQuery::Or(queries)
+        })
+    }
+
+    /// Parse queries combined with `AND`
+    /// # Errors
+    /// If the query is not valid.
+    fn and(&mut self) -> Result<AttributeQuery, CustomError> {
+        let mut queries = vec![self.not()?];
+        while self.keyword("and") {
+            queries.push(self.not()?);
+        }
+        Ok(if queries.len() == 1 {
+            queries.remove(0)
+        } else {
+            AttributeQuery::And(queries)
+        })
+    }
+
+    /// Parse a query optionally preceded by `NOT`
+    /// # Errors
+    /// If the query is not valid.
+    fn not(&mut self) -> Result<AttributeQuery, CustomError> {
+        if self.keyword("not") {
+            Ok(AttributeQuery::Not(Box::new(self.not()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    /// Parse a query in parentheses, a comparison, or a key
+    /// # Errors
+    /// If the query is not valid.
+    fn primary(&mut self) -> Result<AttributeQuery, CustomError> {
+        let token = self
+            .peek()
+            .ok_or_else(|| self.end_error("Expected a key or opening parenthesis"))?;
+        self.index += 1;
+        let key = match &token.0 {
+            Token::Open => {
+                let inner = self.or()?;
+                return match self.peek() {
+                    Some((Token::Close, _, _)) => {
+                        self.index += 1;
+                        Ok(inner)
+                    }
+                    Some(other) => Err(self.error("Expected a closing parenthesis", other)),
+                    None => Err(self.end_error("Expected a closing parenthesis")),
+                };
+            }
+            Token::Word(word)
+                if ["and", "or", "not"]
+                    .iter()
+                    .any(|k| word.eq_ignore_ascii_case(k)) =>
+            {
+                return Err(self.error("Expected a key, quote keys that are keywords", token));
+            }
+            Token::Word(key) | Token::Quoted(key) => key.clone(),
+            Token::Close | Token::Comparison(_) => {
+                return Err(self.error("Expected a key or opening parenthesis", token));
+            }
+        };
+        let Some((Token::Comparison(comparison), _, _)) = self.peek() else {
+            return Ok(AttributeQuery::Exists(key));
+        };
+        self.index += 1;
+        let value = match self.peek() {
+            Some((Token::Word(word), _, _)) => word
+                .parse::<i64>()
+                .map(AttributeValue::Integer)
+                .or_else(|_| word.parse::<f64>().map(AttributeValue::Float))
+                .unwrap_or_else(|_| AttributeValue::Text(word.clone())),
+            Some((Token::Quoted(text), _, _)) => AttributeValue::Text(text.clone()),
+            Some(other) => return Err(self.error("Expected a value", other)),
+            None => return Err(self.end_error("Expected a value")),
+        };
+        self.index += 1;
+        Ok(AttributeQuery::Compare(key, *comparison, value))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::CVTerm;
+
+    fn spectrum(charge: usize, organism: &str, q: f64) -> Vec<Attribute> {
+        vec![
+            Attribute::new(CVTerm::new("MS", "MS:1000041", "charge state")).value(charge),
+            Attribute::new(CVTerm::new("MS", "MS:1001469", "taxonomy: scientific name"))
+                .value(organism),
+            Attribute::new(CVTerm::new("MS", "MS:1002354", "PSM-level q-value")).value(q),
+            Attribute::new(CVTerm::new("MS", "MS:1000044", "dissociation method")).value(
+                CVTerm::new(
+                    "MS",
+                    "MS:1000422",
+                    "beam-type collision-induced dissociation",
+                ),
+            ),
+        ]
+    }
+
+    #[test]
+    fn matching() {
+        let library = [
+            spectrum(2, "Homo sapiens", 0.001),
+            spectrum(3, "Homo sapiens", 0.001),
+            spectrum(2, "Mus musculus", 0.001),
+            spectrum(2, "Homo sapiens", 0.05),
+        ];
+        let select = |query: &str| {
+            let query = AttributeQuery::parse(query).unwrap();
+            query
+                .filter(library.iter().enumerate(), |(_, a)| a)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            select(
+                r#"MS:1000041=2 AND "taxonomy: scientific name"="homo sapiens" AND "psm-level q-value"<0.01"#
+            ),
+            [0]
+        );
+        assert_eq!(select("MS:1000041>=3 or MS:1002354>0.01"), [1, 3]);
+        assert_eq!(
+            select("NOT (MS:1000041=2 AND MS:1001469!=\"Mus musculus\")"),
+            [1, 2]
+        );
+        assert_eq!(select("MS:1000044=MS:1000422"), [0, 1, 2, 3]);
+        assert_eq!(
+            select("\"dissociation method\"=\"beam-type collision-induced dissociation\""),
+            [0, 1, 2, 3]
+        );
+        assert_eq!(select("MS:1001469 AND NOT MS:1000000"), [0, 1, 2, 3]);
+        assert_eq!(select("MS:1001469<1"), Vec::<usize>::new());
+        assert_eq!(select("MS:1000041=2.0 and MS:1000041=\"2\""), [0, 2, 3]);
+    }
+
+    #[test]
+    fn parsing() {
+        for query in [
+            "a=1",
+            "NOT a AND (b<=2.5 OR c!=\"some text\")",
+            "\"charge state\">1 and \"and\"",
+        ] {
+            let parsed = AttributeQuery::parse(query).unwrap();
+            assert_eq!(
+                AttributeQuery::parse(&parsed.to_string()).unwrap(),
+                parsed,
+                "{query} -> {parsed}"
+            );
+        }
+        assert_eq!(
+            AttributeQuery::parse("a=1 or b and c").unwrap(),
+            AttributeQuery::Or(vec![
+                AttributeQuery::Compare(
+                    "a".to_string(),
+                    Comparison::Equal,
+                    AttributeValue::Integer(1)
+                ),
+                AttributeQuery::And(vec![
+                    AttributeQuery::Exists("b".to_string()),
+                    AttributeQuery::Exists("c".to_string())
+                ])
+            ])
+        );
+        for invalid in [
+            "", "a=", "=1", "a=1 b=2", "(a=1", "a=1)", "a=\"open", "and", "a AND", "a=<1",
+        ] {
+            assert!(AttributeQuery::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+}
@@ -95,6 +95,35 @@ macro_rules! format_family {
             }
             $($post_process)?
         }
+
+        impl $format {
+            /// The columns in this version of the format, all required columns and all optional
+            /// columns that are available in this version
+            pub fn columns(&self) -> Vec<&'static str> {
+                #[allow(unused_mut)]
+                let mut columns = vec![$(self.$rname,)*];
+                $(if let crate::identification::common_parser::OptionalColumn::Optional(column)
+                    | crate::identification::common_parser::OptionalColumn::Required(column) = self.$oname {
+                    columns.push(column);
+                })*
+                columns
+            }
+        }
+
+        impl $data {
+            /// All versions of this format that can be read
+            pub fn supported_versions() -> Vec<$version> {
+                $versions.iter().map(|format| format.version.clone()).collect()
+            }
+
+            /// All versions of this format that can be read, with the columns for each version
+            pub fn schemas() -> Vec<($version, Vec<&'static str>)> {
+                $versions
+                    .iter()
+                    .map(|format| (format.version.clone(), format.columns()))
+                    .collect()
+            }
+        }
     };
 }
 
@@ -0,0 +1,110 @@
+#![allow(clippy::missing_panics_doc)]
+//! Run all example files in `test_files` as described in its `manifest.tsv`. Additional files can
+//! be tested by setting the `RUSTYMS_IDENTIFICATION_CORPUS` environment variable to a directory
+//! with the files and a `manifest.tsv` in the same format, this allows regression testing of
+//! format changes with files that cannot be bundled with the crate.
+//!
+//! The manifest is a tab separated file with a header and the following columns: `file` (relative
+//! to the manifest), `format` (see [`IdentifiedPeptideFileFormat`]), `version`, `count` (the
+//! number of peptides), `first_id`, and `first_peptide` (the ProForma of the first peptide or
+//! empty if it has no peptide).
+
+use std::{path::Path, str::FromStr};
+
+use crate::identification::IdentifiedPeptideFileFormat;
+
+/// The environment variable to set to an additional corpus directory
+const CORPUS_VARIABLE: &str = "RUSTYMS_IDENTIFICATION_CORPUS";
+
+#[test]
+fn bundled_corpus() {
+    let directory = Path::new("src/identification/test_files");
+    let listed = run_corpus(directory);
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let name = entry.unwrap().file_name().to_string_lossy().to_string();
+        assert!(
+            name == "manifest.tsv" || listed.contains(&name),
+            "The test file '{name}' is not listed in the manifest"
+        );
+    }
+}
+
+#[test]
+fn external_corpus() {
+    if let Ok(directory) = std::env::var(CORPUS_VARIABLE) {
+        run_corpus(Path::new(&directory));
+    }
+}
+
+#[test]
+fn formats() {
+    for format in IdentifiedPeptideFileFormat::ALL {
+        assert_eq!(
+            IdentifiedPeptideFileFormat::from_str(&format.to_string().to_ascii_lowercase()),
+            Ok(*format)
+        );
+        assert!(!format.versions().is_empty(), "{format} has no versions");
+        assert!(
+            !format.extensions().is_empty(),
+            "{format} has no extensions"
+        );
+    }
+    assert!(IdentifiedPeptideFileFormat::from_str("Not a format").is_err());
+    let schemas = IdentifiedPeptideFileFormat::Sage.schemas();
+    assert_eq!(schemas.len(), 1);
+    assert!(schemas[0].1.contains(&"peptide"));
+}
+
+/// Test all files in the manifest in the given directory, returns the names of all tested files
+fn run_corpus(directory: &Path) -> Vec<String> {
+    let manifest = std::fs::read_to_string(directory.join("manifest.tsv")).unwrap_or_else(|e| {
+        panic!(
+            "Could not read the manifest in '{}': {e}",
+            directory.display()
+        )
+    });
+    let mut files = Vec::new();
+    for (index, line) in manifest.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<_> = line.split('\t').collect();
+        assert_eq!(
+            fields.len(),
+            6,
+            "Manifest line {} does not have 6 columns",
+            index + 1
+        );
+        let [file, format, version, count, first_id, first_peptide] = fields[..] else {
+            unreachable!()
+        };
+        let format = IdentifiedPeptideFileFormat::from_str(format).unwrap();
+        let peptides: Vec<_> = format
+            .parse_file(directory.join(file), None)
+            .unwrap_or_else(|e| panic!("Could not open '{file}': {e}"))
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| panic!("Could not read '{file}': {e}"));
+        assert_eq!(
+            peptides.len(),
+            count.parse::<usize>().unwrap(),
+            "Incorrect number of peptides in '{file}'"
+        );
+        for peptide in &peptides {
+            assert_eq!(
+                peptide.format_version(),
+                version,
+                "Incorrect version in '{file}'"
+            );
+        }
+        if let Some(first) = peptides.first() {
+            assert_eq!(first.id(), first_id, "Incorrect first id in '{file}'");
+            assert_eq!(
+                first.peptide().map(|p| p.to_string()).unwrap_or_default(),
+                first_peptide,
+                "Incorrect first peptide in '{file}'"
+            );
+        }
+        files.push(file.to_string());
+    }
+    files
+}
@@ -77,13 +77,17 @@ format_family!(
 );
 
 impl From<DeepNovoFamilyData> for IdentifiedPeptide {
-    fn from(value: DeepNovoFamilyData) -> Self {
+    fn from(mut value: DeepNovoFamilyData) -> Self {
+        let local_confidence: Option<Vec<f64>> = value
+            .local_confidence
+            .as_ref()
+            .map(|lc| lc.iter().map(|v| 2.0 / (1.0 + (-v).exp())).collect());
+        if let (Some(peptide), Some(local_confidence)) = (&mut value.peptide, &local_confidence) {
+            peptide.set_local_confidence(local_confidence);
+        }
         Self {
             score: value.score.map(|score| (2.0 / (1.0 + (-score).exp()))),
-            local_confidence: value
-                .local_confidence
-                .as_ref()
-                .map(|lc| lc.iter().map(|v| 2.0 / (1.0 + (-v).exp())).collect()),
+            local_confidence,
             metadata: MetaData::DeepNovoFamily(value),
         }
     }
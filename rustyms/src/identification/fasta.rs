@@ -7,13 +7,7 @@ use crate::{
 };
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::{
-    io::{BufRead, BufReader},
-    num::ParseIntError,
-    ops::Range,
-    path::Path,
-    str::FromStr,
-};
+use std::{io::BufRead, num::ParseIntError, ops::Range, path::Path, str::FromStr};
 
 /// A single parsed line of a fasta file
 #[allow(missing_docs)]
@@ -444,14 +438,7 @@ impl FastaData {
     /// A custom error when it is not a valid fasta file
     pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Self>, CustomError> {
         let path = path.as_ref();
-        let file = std::fs::File::open(path).map_err(|_| {
-            CustomError::error(
-                "Failed reading fasta file",
-                "Error occurred while opening the file",
-                Context::show(path.to_string_lossy()),
-            )
-        })?;
-        let reader = BufReader::new(file);
+        let reader = crate::compression::open_file(path)?;
         Self::parse_reader(reader, Some(path))
     }
     /// Parse a single fasta file from a reader
@@ -690,7 +677,7 @@ impl From<FastaData> for IdentifiedPeptide {
 #[allow(clippy::missing_panics_doc)]
 fn empty_lines() {
     let file = ">A\naaa\n\naaa";
-    let fasta = FastaData::parse_reader(BufReader::new(file.as_bytes()), None).unwrap();
+    let fasta = FastaData::parse_reader(std::io::BufReader::new(file.as_bytes()), None).unwrap();
     assert_eq!(fasta.len(), 1);
     assert_eq!(
         fasta[0].peptide,
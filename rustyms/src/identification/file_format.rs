@@ -0,0 +1,259 @@
+use std::{
+    io::{BufReader, Read},
+    path::Path,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    error::{Context, CustomError},
+    ontologies::CustomDatabase,
+    DeepNovoFamilyData, FastaData, IdentifiedPeptide, IdentifiedPeptideIter,
+    IdentifiedPeptideSource, InstaNovoData, MSFraggerData, MZTabData, MaxQuantData, NovoBData,
+    NovorData, OpairData, PLGSData, PLinkData, PeaksData, PepNetData, PowerNovoData, SageData,
+    SpectrumSequenceListData,
+};
+
+/// All file formats for identified peptides that can be read. This can be used to enumerate the
+/// supported formats with their versions and columns, and to read a file with a known format
+/// (instead of detecting it with [`open_identified_peptides_file`](super::open_identified_peptides_file)).
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum IdentifiedPeptideFileFormat {
+    /// DeepNovo and related tools (eg PointNovo), see [`DeepNovoFamilyData`]
+    DeepNovoFamily,
+    /// Fasta, see [`FastaData`]
+    Fasta,
+    /// InstaNovo, see [`InstaNovoData`]
+    InstaNovo,
+    /// MaxQuant, see [`MaxQuantData`]
+    MaxQuant,
+    /// MSFragger, see [`MSFraggerData`]
+    MSFragger,
+    /// mzTab, see [`MZTabData`]
+    MZTab,
+    /// NovoB, see [`NovoBData`]
+    NovoB,
+    /// Novor, see [`NovorData`]
+    Novor,
+    /// OPair, see [`OpairData`]
+    Opair,
+    /// PEAKS, see [`PeaksData`]
+    Peaks,
+    /// PepNet, see [`PepNetData`]
+    PepNet,
+    /// PLGS, see [`PLGSData`]
+    PLGS,
+    /// pLink, see [`PLinkData`]
+    PLink,
+    /// PowerNovo, see [`PowerNovoData`]
+    PowerNovo,
+    /// Sage, see [`SageData`]
+    Sage,
+    /// Spectrum sequence list, see [`SpectrumSequenceListData`]
+    SpectrumSequenceList,
+}
+
+impl IdentifiedPeptideFileFormat {
+    /// All formats
+    pub const ALL: &'static [Self] = &[
+        Self::DeepNovoFamily,
+        Self::Fasta,
+        Self::InstaNovo,
+        Self::MaxQuant,
+        Self::MSFragger,
+        Self::MZTab,
+        Self::NovoB,
+        Self::Novor,
+        Self::Opair,
+        Self::Peaks,
+        Self::PepNet,
+        Self::PLGS,
+        Self::PLink,
+        Self::PowerNovo,
+        Self::Sage,
+        Self::SpectrumSequenceList,
+    ];
+
+    /// The name of the format, the same as [`IdentifiedPeptide::format_name`]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::DeepNovoFamily => "DeepNovo Family",
+            Self::Fasta => "Fasta",
+            Self::InstaNovo => "InstaNovo",
+            Self::MaxQuant => "MaxQuant",
+            Self::MSFragger => "MSFragger",
+            Self::MZTab => "mzTab",
+            Self::NovoB => "NovoB",
+            Self::Novor => "Novor",
+            Self::Opair => "OPair",
+            Self::Peaks => "PEAKS",
+            Self::PepNet => "PepNet",
+            Self::PLGS => "ProteinLynx Global Server",
+            Self::PLink => "pLink",
+            Self::PowerNovo => "PowerNovo",
+            Self::Sage => "Sage",
+            Self::SpectrumSequenceList => "SpectrumSequenceList",
+        }
+    }
+
+    /// The file extensions that are recognised for this format by
+    /// [`open_identified_peptides_file`](super::open_identified_peptides_file), any of these can
+    /// additionally be gzipped.
+    pub const fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::DeepNovoFamily => &["deepnovo_denovo"],
+            Self::Fasta => &["fasta"],
+            Self::InstaNovo
+            | Self::Novor
+            | Self::Peaks
+            | Self::PLGS
+            | Self::PLink
+            | Self::PowerNovo => &["csv"],
+            Self::MaxQuant | Self::NovoB => &["txt"],
+            Self::MSFragger | Self::PepNet | Self::Sage => &["tsv"],
+            Self::MZTab => &["mztab"],
+            Self::Opair => &["psmtsv"],
+            Self::SpectrumSequenceList => &["ssl"],
+        }
+    }
+
+    /// All supported versions of this format, as given by [`IdentifiedPeptide::format_version`]
+    pub fn versions(self) -> Vec<String> {
+        self.schemas()
+            .into_iter()
+            .map(|(version, _)| version)
+            .collect()
+    }
+
+    /// All supported versions of this format, with the columns that are used for each version.
+    /// Fasta and mzTab are not column based formats so have no columns.
+    pub fn schemas(self) -> Vec<(String, Vec<&'static str>)> {
+        fn schemas<V: ToString>(
+            schemas: Vec<(V, Vec<&'static str>)>,
+        ) -> Vec<(String, Vec<&'static str>)> {
+            schemas
+                .into_iter()
+                .map(|(version, columns)| (version.to_string(), columns))
+                .collect()
+        }
+        match self {
+            Self::DeepNovoFamily => schemas(DeepNovoFamilyData::schemas()),
+            Self::Fasta => vec![("Fasta".to_string(), Vec::new())],
+            Self::InstaNovo => schemas(InstaNovoData::schemas()),
+            Self::MaxQuant => schemas(MaxQuantData::schemas()),
+            Self::MSFragger => schemas(MSFraggerData::schemas()),
+            Self::MZTab => vec![("mzTab 1.0".to_string(), Vec::new())],
+            Self::NovoB => schemas(NovoBData::schemas()),
+            Self::Novor => schemas(NovorData::schemas()),
+            Self::Opair => schemas(OpairData::schemas()),
+            Self::Peaks => schemas(PeaksData::schemas()),
+            Self::PepNet => schemas(PepNetData::schemas()),
+            Self::PLGS => schemas(PLGSData::schemas()),
+            Self::PLink => schemas(PLinkData::schemas()),
+            Self::PowerNovo => schemas(PowerNovoData::schemas()),
+            Self::Sage => schemas(SageData::schemas()),
+            Self::SpectrumSequenceList => schemas(SpectrumSequenceListData::schemas()),
+        }
+    }
+
+    /// Read identified peptides in this format from the given reader. The version of the format
+    /// is detected automatically.
+    /// # Errors
+    /// If the reader could not be read as this format.
+    pub fn parse_reader<'a>(
+        self,
+        reader: impl Read + 'a,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>, CustomError>
+    {
+        /// # Errors
+        /// If the reader could not be read as this format.
+        fn boxed<'a, T>(
+            reader: impl Read + 'a,
+            custom_database: Option<&'a CustomDatabase>,
+        ) -> Result<
+            Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>,
+            CustomError,
+        >
+        where
+            T: IdentifiedPeptideSource + Into<IdentifiedPeptide> + 'a,
+            T::Format: 'static,
+        {
+            T::parse_reader(reader, custom_database).map(IdentifiedPeptideIter::into_box)
+        }
+        match self {
+            Self::DeepNovoFamily => boxed::<DeepNovoFamilyData>(reader, custom_database),
+            Self::Fasta => FastaData::parse_reader(BufReader::new(reader), None).map(|peptides| {
+                Box::new(peptides.into_iter().map(|p| Ok(p.into())))
+                    as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
+            }),
+            Self::InstaNovo => boxed::<InstaNovoData>(reader, custom_database),
+            Self::MaxQuant => boxed::<MaxQuantData>(reader, custom_database),
+            Self::MSFragger => boxed::<MSFraggerData>(reader, custom_database),
+            Self::MZTab => Ok(Box::new(
+                MZTabData::parse_reader(BufReader::new(reader), custom_database)
+                    .map(|p| p.map(Into::into)),
+            )),
+            Self::NovoB => boxed::<NovoBData>(reader, custom_database),
+            Self::Novor => boxed::<NovorData>(reader, custom_database),
+            Self::Opair => boxed::<OpairData>(reader, custom_database),
+            Self::Peaks => boxed::<PeaksData>(reader, custom_database),
+            Self::PepNet => boxed::<PepNetData>(reader, custom_database),
+            Self::PLGS => boxed::<PLGSData>(reader, custom_database),
+            Self::PLink => boxed::<PLinkData>(reader, custom_database),
+            Self::PowerNovo => boxed::<PowerNovoData>(reader, custom_database),
+            Self::Sage => boxed::<SageData>(reader, custom_database),
+            Self::SpectrumSequenceList => {
+                boxed::<SpectrumSequenceListData>(reader, custom_database)
+            }
+        }
+    }
+
+    /// Read identified peptides in this format from the given file, gzipped files are
+    /// uncompressed automatically. The version of the format is detected automatically.
+    /// # Errors
+    /// If the file could not be opened or could not be read as this format.
+    pub fn parse_file<'a>(
+        self,
+        path: impl AsRef<Path>,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>, CustomError>
+    {
+        self.parse_reader(crate::compression::open_file(path)?, custom_database)
+    }
+}
+
+impl std::fmt::Display for IdentifiedPeptideFileFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for IdentifiedPeptideFileFormat {
+    type Err = CustomError;
+
+    /// Parse the name of a format, case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Self::ALL
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                CustomError::error(
+                    "Unknown identified peptide format",
+                    format!(
+                        "The format should be one of: {}",
+                        Self::ALL
+                            .iter()
+                            .map(|f| f.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    Context::show(s),
+                )
+            })
+    }
+}
@@ -0,0 +1,236 @@
+use std::{io::Read, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compression::open_file,
+    csv::{parse_csv_raw_with_comments, CsvLine},
+    error::{Context, CustomError},
+    identification::{
+        common_parser::{HasLocation, Location},
+        IdentifiedPeptide, MetaData, ScoreDirection, SpectrumId,
+    },
+    ontologies::CustomDatabase,
+    system::usize::Charge,
+    Peptidoform, SemiAmbiguous, SloppyParsingParameters,
+};
+
+static NUMBER_ERROR: (&str, &str) = (
+    "Invalid generic line",
+    "This column is not a number but it is required to be a number in this format",
+);
+
+/// A user defined column mapping to read peptides from any CSV or TSV file, for tools that do
+/// not have a dedicated format. The mapping can be (de)serialised with serde to store it in a
+/// configuration file, for example as JSON (only the peptide column is required):
+/// ```json
+/// {
+///     "name": "My tool",
+///     "separator": ",",
+///     "peptide": "Sequence",
+///     "charge": "z",
+///     "scan": "Scan",
+///     "score": "Score",
+///     "score_scale": 100.0,
+///     "score_direction": "HigherIsBetter",
+///     "raw_file": "File",
+///     "comment": "##"
+/// }
+/// ```
+/// Column names are matched case insensitively. The peptides are read with
+/// [`Peptidoform::sloppy_pro_forma`] so most common peptide notations are supported.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GenericFormat {
+    /// The name of this format, stored in every peptide as its version
+    #[serde(default = "GenericFormat::default_name")]
+    pub name: String,
+    /// The column separator
+    #[serde(default = "GenericFormat::default_separator")]
+    pub separator: char,
+    /// The column containing the peptide sequence
+    pub peptide: String,
+    /// The column containing the precursor charge
+    #[serde(default)]
+    pub charge: Option<String>,
+    /// The column containing the scan, either a scan index or a native id
+    #[serde(default)]
+    pub scan: Option<String>,
+    /// The column containing the score
+    #[serde(default)]
+    pub score: Option<String>,
+    /// The maximal score, used to scale the score to -1..=1 for [`IdentifiedPeptide::score`]
+    /// (negated if a lower score is better). If this is not set the score is only stored in the
+    /// metadata.
+    #[serde(default)]
+    pub score_scale: Option<f64>,
+    /// If a higher or lower score is better
+    #[serde(default)]
+    pub score_direction: ScoreDirection,
+    /// The column containing the raw file
+    #[serde(default)]
+    pub raw_file: Option<String>,
+    /// Skip lines before the header that start with this prefix, use `##` to read files with a
+    /// provenance as written by [`write_peptide_report`](super::write_peptide_report)
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// The data from a file read with a [`GenericFormat`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GenericData {
+    /// The name of the [`GenericFormat`] that was used to read the data
+    pub format: String,
+    /// The line index (0 based) in the original file
+    pub line: usize,
+    /// The peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The precursor charge
+    pub z: Option<Charge>,
+    /// The scan
+    pub scan: Option<SpectrumId>,
+    /// The score, as found in the file
+    pub score: Option<f64>,
+    /// If a higher or lower score is better
+    pub score_direction: ScoreDirection,
+    /// The raw file
+    pub raw_file: Option<PathBuf>,
+}
+
+impl GenericFormat {
+    fn default_name() -> String {
+        "Generic".to_string()
+    }
+
+    const fn default_separator() -> char {
+        '\t'
+    }
+
+    /// Create a new mapping with only the peptide column set, the separator is a tab
+    pub fn new(peptide: impl Into<String>) -> Self {
+        Self {
+            name: Self::default_name(),
+            separator: Self::default_separator(),
+            peptide: peptide.into(),
+            charge: None,
+            scan: None,
+            score: None,
+            score_scale: None,
+            score_direction: ScoreDirection::HigherIsBetter,
+            raw_file: None,
+            comment: None,
+        }
+    }
+
+    /// The separator as a byte
+    /// # Errors
+    /// If the separator is not a single byte character.
+    fn separator(&self) -> Result<u8, CustomError> {
+        u8::try_from(self.separator)
+            .ok()
+            .filter(u8::is_ascii)
+            .ok_or_else(|| {
+                CustomError::error(
+                    "Invalid generic format",
+                    "The separator has to be an ASCII character",
+                    Context::show(self.separator),
+                )
+            })
+    }
+
+    /// Read all peptides from the given file, gzipped files are uncompressed automatically.
+    /// # Errors
+    /// If the file could not be opened or the header line could not be read.
+    pub fn parse_file<'a>(
+        &'a self,
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<impl Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a, CustomError>
+    {
+        let lines = parse_csv_raw_with_comments(
+            open_file(path)?,
+            self.separator()?,
+            None,
+            self.comment.as_deref(),
+        )?;
+        Ok(lines.map(move |line| {
+            self.parse_line(&line?, custom_database)
+                .map(|data| self.identified_peptide(data))
+        }))
+    }
+
+    /// Read all peptides from the given reader.
+    /// # Errors
+    /// If the header line could not be read.
+    pub fn parse_reader<'a>(
+        &'a self,
+        reader: impl Read + 'a,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<impl Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a, CustomError>
+    {
+        let lines =
+            parse_csv_raw_with_comments(reader, self.separator()?, None, self.comment.as_deref())?;
+        Ok(lines.map(move |line| {
+            self.parse_line(&line?, custom_database)
+                .map(|data| self.identified_peptide(data))
+        }))
+    }
+
+    /// Parse a single line with this column mapping.
+    /// # Errors
+    /// If any of the mapped columns is missing or has an invalid value.
+    pub fn parse_line(
+        &self,
+        line: &CsvLine,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<GenericData, CustomError> {
+        let column = |name: &Option<String>| -> Result<Option<Location<'_>>, CustomError> {
+            name.as_ref()
+                .map(|name| line.column(&name.to_lowercase()))
+                .transpose()
+                .map(|l| l.and_then(Location::or_empty))
+        };
+        let peptide = line.column(&self.peptide.to_lowercase())?;
+        Ok(GenericData {
+            format: self.name.clone(),
+            line: line.line_index(),
+            peptide: Peptidoform::sloppy_pro_forma(
+                peptide.full_line(),
+                peptide.location.clone(),
+                custom_database,
+                &SloppyParsingParameters::default(),
+            )?,
+            z: column(&self.charge)?
+                .map(|l| {
+                    l.trim_end_matches(".0")
+                        .parse::<usize>(NUMBER_ERROR)
+                        .map(Charge::new::<crate::system::e>)
+                })
+                .transpose()?,
+            scan: column(&self.scan)?.map(|l| {
+                l.as_str()
+                    .parse::<usize>()
+                    .map_or_else(|_| SpectrumId::Native(l.get_string()), SpectrumId::Index)
+            }),
+            score: column(&self.score)?
+                .map(|l| l.parse::<f64>(NUMBER_ERROR))
+                .transpose()?,
+            score_direction: self.score_direction,
+            raw_file: column(&self.raw_file)?.map(|l| PathBuf::from(l.get_string())),
+        })
+    }
+
+    /// Create an identified peptide, scaling the score with [`Self::score_scale`]
+    fn identified_peptide(&self, data: GenericData) -> IdentifiedPeptide {
+        IdentifiedPeptide {
+            score: data.score.and_then(|score| {
+                self.score_scale.map(|scale| {
+                    self.score_direction
+                        .directed(score / scale)
+                        .clamp(-1.0, 1.0)
+                })
+            }),
+            local_confidence: None,
+            metadata: MetaData::Generic(data),
+        }
+    }
+}
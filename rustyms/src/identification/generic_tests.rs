@@ -0,0 +1,68 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::BufReader;
+
+use crate::identification::{GenericFormat, SpectrumId, SpectrumIds};
+
+#[test]
+fn generic() {
+    let format: GenericFormat = serde_json::from_str(
+        r#"{"name": "Tool v1", "separator": ",", "peptide": "Sequence", "charge": "z", "scan": "Scan", "score": "Score", "score_scale": 100.0, "raw_file": "File"}"#,
+    )
+    .unwrap();
+    let peptides = format
+        .parse_reader(BufReader::new(DATA.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(peptides.len(), 3);
+    assert_eq!(peptides[0].format_name(), "Generic");
+    assert_eq!(peptides[0].format_version(), "Tool v1");
+    assert_eq!(peptides[0].id(), "12");
+    assert_eq!(peptides[0].peptide().unwrap().to_string(), "PEPTIDE");
+    assert_eq!(peptides[0].charge().map(|c| c.value), Some(2));
+    assert_eq!(peptides[0].score, Some(0.85));
+    assert_eq!(
+        peptides[0].scans(),
+        SpectrumIds::FileKnown(vec![("run1.raw".into(), vec![SpectrumId::Index(12)])])
+    );
+    assert_eq!(
+        peptides[1].peptide().unwrap().to_string(),
+        "PEPM[U:Oxidation]TIDEK"
+    );
+    assert_eq!(peptides[1].charge(), None);
+    assert_eq!(peptides[1].score, None);
+    assert_eq!(
+        peptides[2].scans(),
+        SpectrumIds::FileKnown(vec![(
+            "run2.raw".into(),
+            vec![SpectrumId::Native("controllerType=0 scan=7".to_string())]
+        )])
+    );
+}
+
+#[test]
+fn generic_minimal() {
+    let format: GenericFormat = serde_json::from_str(r#"{"peptide": "peptide"}"#).unwrap();
+    assert_eq!(format, GenericFormat::new("peptide"));
+    let peptides = format
+        .parse_reader(
+            BufReader::new("Peptide\tOther\nAAA\t1\nCCC\t2".as_bytes()),
+            None,
+        )
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(peptides.len(), 2);
+    assert_eq!(peptides[1].id(), "2");
+    assert_eq!(peptides[1].scans(), SpectrumIds::None);
+    assert!(GenericFormat::new("Sequence")
+        .parse_reader(BufReader::new("Peptide\tOther\nAAA\t1".as_bytes()), None)
+        .unwrap()
+        .all(|p| p.is_err()));
+}
+
+const DATA: &str = r"Sequence,z,Scan,Score,File
+PEPTIDE,2,12,85,run1.raw
+PEPM(ox)TIDEK,,13,,run1.raw
+AAAK,3,controllerType=0 scan=7,150,run2.raw
+";
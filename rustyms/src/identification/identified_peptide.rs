@@ -13,16 +13,16 @@ use crate::{
     formula::MultiChemical,
     identification::{
         deepnovofamily::DeepNovoFamilyData, fasta::FastaData, fasta::FastaIdentifier,
-        instanovo::InstaNovoData, novob::NovoBData, novor::NovorData, opair::OpairData,
-        peaks::PeaksData, pepnet::PepNetData, plink::PLinkData, powernovo::PowerNovoData,
-        system::MassOverCharge, MSFraggerData, MZTabData, MaxQuantData, PLGSData, SageData,
-        SpectrumSequenceListData,
+        generic::GenericData, instanovo::InstaNovoData, novob::NovoBData, novor::NovorData,
+        opair::OpairData, peaks::PeaksData, pepnet::PepNetData, plink::PLinkData,
+        powernovo::PowerNovoData, system::MassOverCharge, MSFraggerData, MZTabData, MaxQuantData,
+        PLGSData, SageData, SpectrumSequenceListData,
     },
     ontologies::CustomDatabase,
     peptidoform::{SemiAmbiguous, SimpleLinear},
     system::usize::Charge,
     system::{OrderedTime, Time},
-    Peptidoform, PeptidoformIon,
+    Peptidoform, PeptidoformIon, RawSpectrum,
 };
 
 use super::CompoundPeptidoformIon;
@@ -46,6 +46,8 @@ pub enum MetaData {
     DeepNovoFamily(DeepNovoFamilyData),
     /// Fasta metadata
     Fasta(FastaData),
+    /// Metadata for a file read with a user defined column mapping
+    Generic(GenericData),
     /// MaxQuant metadata
     MaxQuant(MaxQuantData),
     /// InstaNovo metadata
@@ -174,6 +176,7 @@ impl IdentifiedPeptide {
             | MetaData::Opair(OpairData { peptide, .. })
             | MetaData::PepNet(PepNetData { peptide, .. })
             | MetaData::PowerNovo(PowerNovoData { peptide, .. })
+            | MetaData::Generic(GenericData { peptide, .. })
             | MetaData::Sage(SageData { peptide, .. }) => {
                 Some(ReturnedPeptide::LinearSemiAmbiguous(peptide))
             }
@@ -226,6 +229,7 @@ impl IdentifiedPeptide {
             MetaData::SpectrumSequenceList(_) => "SpectrumSequenceList",
             MetaData::DeepNovoFamily(_) => "DeepNovo Family",
             MetaData::Fasta(_) => "Fasta",
+            MetaData::Generic(_) => "Generic",
             MetaData::InstaNovo(_) => "InstaNovo",
             MetaData::MaxQuant(_) => "MaxQuant",
             MetaData::MSFragger(_) => "MSFragger",
@@ -250,6 +254,7 @@ impl IdentifiedPeptide {
             }
             MetaData::DeepNovoFamily(DeepNovoFamilyData { version, .. }) => version.to_string(),
             MetaData::Fasta(_) => "Fasta".to_string(),
+            MetaData::Generic(GenericData { format, .. }) => format.clone(),
             MetaData::InstaNovo(InstaNovoData { version, .. }) => version.to_string(),
             MetaData::MaxQuant(MaxQuantData { version, .. }) => version.to_string(),
             MetaData::MSFragger(MSFraggerData { version, .. }) => version.to_string(),
@@ -298,6 +303,9 @@ impl IdentifiedPeptide {
             MetaData::PowerNovo(PowerNovoData { scan, .. }) => {
                 scan.as_ref().map_or("-".to_string(), ToString::to_string)
             }
+            MetaData::Generic(GenericData { scan, line, .. }) => scan
+                .as_ref()
+                .map_or_else(|| line.to_string(), ToString::to_string),
             MetaData::PepNet(_) => "-".to_string(),
             MetaData::PLGS(PLGSData {
                 peptide_component_id,
@@ -349,6 +357,7 @@ impl IdentifiedPeptide {
             | MetaData::InstaNovo(InstaNovoData { z, .. })
             | MetaData::MZTab(MZTabData { z, .. }) => Some(*z),
             MetaData::Peaks(PeaksData { z, .. })
+            | MetaData::Generic(GenericData { z, .. })
             | MetaData::DeepNovoFamily(DeepNovoFamilyData { z, .. }) => *z,
             MetaData::SpectrumSequenceList(SpectrumSequenceListData { z, .. }) => {
                 (z.value >= 0).then_some(Charge::new::<crate::system::charge::e>(z.value as usize))
@@ -383,6 +392,7 @@ impl IdentifiedPeptide {
             MetaData::DeepNovoFamily(_)
             | MetaData::InstaNovo(_)
             | MetaData::Fasta(_)
+            | MetaData::Generic(_)
             | MetaData::NovoB(_)
             | MetaData::PowerNovo(_)
             | MetaData::PepNet(_)
@@ -492,10 +502,50 @@ impl IdentifiedPeptide {
                 OrderedTime::from(*precursor_lift_off_rt)
                     ..=OrderedTime::from(*precursor_touch_down_rt),
             )]),
+            MetaData::Generic(GenericData { raw_file, scan, .. }) => {
+                match (raw_file.clone(), scan.clone()) {
+                    (Some(raw_file), scan) => {
+                        SpectrumIds::FileKnown(vec![(raw_file, scan.into_iter().collect())])
+                    }
+                    (None, Some(scan)) => SpectrumIds::FileNotKnown(vec![scan]),
+                    (None, None) => SpectrumIds::None,
+                }
+            }
             MetaData::Fasta(_) | MetaData::PepNet(_) => SpectrumIds::None,
         }
     }
 
+    /// Find the spectra for this identified peptide (see [`Self::scans`]) in the given spectra, for
+    /// example as read from an MGF file. If a spectrum is known to come from another raw file than
+    /// the raw file of this peptide it is not selected. See [`SpectrumId::matches`] for how the
+    /// spectra are matched.
+    pub fn find_spectra<'a>(&self, spectra: &'a [RawSpectrum]) -> Vec<&'a RawSpectrum> {
+        let ids: Vec<(Option<PathBuf>, SpectrumId)> = match self.scans() {
+            SpectrumIds::None => return Vec::new(),
+            SpectrumIds::FileNotKnown(ids) => ids.into_iter().map(|id| (None, id)).collect(),
+            SpectrumIds::FileKnown(files) => files
+                .into_iter()
+                .flat_map(|(file, ids)| ids.into_iter().map(move |id| (Some(file.clone()), id)))
+                .collect(),
+        };
+        spectra
+            .iter()
+            .enumerate()
+            .filter(|(index, spectrum)| {
+                ids.iter().any(|(file, id)| {
+                    file.as_ref().zip(spectrum.raw_file.as_ref()).map_or(
+                        true,
+                        |(file, raw_file)| {
+                            file_stem(&file.to_string_lossy())
+                                .eq_ignore_ascii_case(file_stem(raw_file))
+                        },
+                    ) && id.matches(*index, spectrum)
+                })
+            })
+            .map(|(_, spectrum)| spectrum)
+            .collect()
+    }
+
     /// Get the mz as experimentally determined
     pub fn experimental_mz(&self) -> Option<MassOverCharge> {
         match &self.metadata {
@@ -519,6 +569,7 @@ impl IdentifiedPeptide {
             }
             MetaData::DeepNovoFamily(_)
             | MetaData::Fasta(_)
+            | MetaData::Generic(_)
             | MetaData::SpectrumSequenceList(_)
             | MetaData::PowerNovo(_)
             | MetaData::PepNet(_) => None,
@@ -548,6 +599,7 @@ impl IdentifiedPeptide {
                 mz.and_then(|mz| z.map(|z| (mz, z)).map(|(mz, z)| mz * z.to_float()))
             }
             MetaData::Fasta(_)
+            | MetaData::Generic(_)
             | MetaData::PowerNovo(_)
             | MetaData::SpectrumSequenceList(_)
             | MetaData::PepNet(_) => None,
@@ -607,6 +659,7 @@ impl IdentifiedPeptide {
             | MetaData::PLink(_)
             | MetaData::Novor(_)
             | MetaData::Fasta(_)
+            | MetaData::Generic(_)
             | MetaData::DeepNovoFamily(_)
             | MetaData::InstaNovo(_)
             | MetaData::PowerNovo(_)
@@ -629,6 +682,7 @@ impl IdentifiedPeptide {
             | MetaData::NovoB(_)
             | MetaData::Opair(_)
             | MetaData::Fasta(_)
+            | MetaData::Generic(_)
             | MetaData::PowerNovo(_)
             | MetaData::DeepNovoFamily(_)
             | MetaData::SpectrumSequenceList(_)
@@ -667,6 +721,7 @@ impl IdentifiedPeptide {
             | MetaData::PLink(_)
             | MetaData::NovoB(_)
             | MetaData::Fasta(_)
+            | MetaData::Generic(_)
             | MetaData::PowerNovo(_)
             | MetaData::SpectrumSequenceList(_)
             | MetaData::PepNet(_) => None,
@@ -695,6 +750,12 @@ pub enum SpectrumIds {
     FileKnown(Vec<(PathBuf, Vec<SpectrumId>)>),
 }
 
+/// The name of a file without directories and extension, with both `/` and `\\` as separators
+fn file_stem(path: &str) -> &str {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
 /// A spectrum identifier
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum SpectrumId {
@@ -740,6 +801,32 @@ impl SpectrumId {
             Self::Index(_) | Self::RetentionTime(_) => None,
         }
     }
+
+    /// Check if this identifier refers to the given spectrum, which is at the given index in its
+    /// file. An index is matched to the scan number of the spectrum if known and otherwise to the
+    /// index in the file. A native ID is matched to the title, or to the scan number or index if
+    /// it contains a `scan=N` or `index=N` part. A retention time range is matched to the
+    /// retention time of the spectrum.
+    pub fn matches(&self, index: usize, spectrum: &RawSpectrum) -> bool {
+        match self {
+            Self::Index(i) => spectrum.raw_scan_number.unwrap_or(index) == *i,
+            Self::Native(native) => {
+                native.trim() == spectrum.title.trim()
+                    || native
+                        .split_whitespace()
+                        .any(|part| match part.split_once('=') {
+                            Some(("scan", n)) => n
+                                .parse::<usize>()
+                                .is_ok_and(|n| spectrum.raw_scan_number == Some(n)),
+                            Some(("index", n)) => n.parse::<usize>().is_ok_and(|n| n == index),
+                            _ => false,
+                        })
+            }
+            Self::RetentionTime(range) => spectrum
+                .rt
+                .is_some_and(|rt| range.contains(&OrderedTime::from(rt))),
+        }
+    }
 }
 
 /// The required methods for any source of identified peptides
@@ -59,16 +59,16 @@ format_family!(
 );
 
 impl From<InstaNovoData> for IdentifiedPeptide {
-    fn from(value: InstaNovoData) -> Self {
+    fn from(mut value: InstaNovoData) -> Self {
+        let local_confidence: Vec<f64> = value
+            .local_confidence
+            .iter()
+            .map(|v| 2.0 / (1.0 + 1.25_f64.powf(-v)))
+            .collect();
+        value.peptide.set_local_confidence(&local_confidence);
         Self {
             score: Some(2.0 / (1.0 + 1.01_f64.powf(-value.score))),
-            local_confidence: Some(
-                value
-                    .local_confidence
-                    .iter()
-                    .map(|v| 2.0 / (1.0 + 1.25_f64.powf(-v)))
-                    .collect(),
-            ),
+            local_confidence: Some(local_confidence),
             metadata: MetaData::InstaNovo(value),
         }
     }
@@ -3,9 +3,14 @@
 #[macro_use]
 mod common_parser;
 
+mod artifacts;
+mod attribute;
+mod attribute_query;
 mod deepnovofamily;
 mod fasta;
+mod file_format;
 mod general;
+mod generic;
 mod identified_peptide;
 mod instanovo;
 mod maxquant;
@@ -16,16 +21,24 @@ mod novor;
 mod opair;
 mod peaks;
 mod pepnet;
+mod peptide_report;
 mod plgs;
 mod plink;
 mod powernovo;
 mod sage;
+mod score;
 mod ssl;
+mod variants;
 
 use crate::*;
+pub use artifacts::*;
+pub use attribute::*;
+pub use attribute_query::*;
 pub use deepnovofamily::*;
 pub use fasta::*;
+pub use file_format::*;
 pub use general::*;
+pub use generic::*;
 pub use identified_peptide::*;
 pub use instanovo::*;
 pub use maxquant::*;
@@ -36,15 +49,22 @@ pub use novor::*;
 pub use opair::*;
 pub use peaks::*;
 pub use pepnet::*;
+pub use peptide_report::*;
 pub use plgs::*;
 pub use plink::*;
 pub use powernovo::*;
 pub use sage::*;
+pub use score::*;
 pub use ssl::*;
+pub use variants::*;
 
+#[cfg(test)]
+mod corpus_tests;
 #[cfg(test)]
 mod deepnovofamily_tests;
 #[cfg(test)]
+mod generic_tests;
+#[cfg(test)]
 mod instanovo_tests;
 #[cfg(test)]
 mod maxquant_tests;
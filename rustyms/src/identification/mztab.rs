@@ -1,20 +1,14 @@
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    ops::Range,
-    str::FromStr,
-};
+use std::{collections::HashMap, io::BufRead, ops::Range, str::FromStr};
 
-use flate2::bufread::GzDecoder;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    compression::open_file,
     error::{Context, CustomError},
-    helper_functions::{check_extension, explain_number_error},
+    helper_functions::explain_number_error,
     identification::{IdentifiedPeptide, MetaData, SpectrumId, SpectrumIds},
-    modification::SimpleModification,
+    modification::{Ontology, SimpleModification},
     ontologies::CustomDatabase,
     system::{usize::Charge, MassOverCharge, Time},
     AminoAcid, PeptideModificationSearch, Peptidoform, ReturnModification, SemiAmbiguous,
@@ -79,26 +73,10 @@ impl MZTabData {
         path: impl AsRef<std::path::Path>,
         custom_database: Option<&CustomDatabase>,
     ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
-        let file = File::open(path.as_ref()).map_err(|e| {
-            CustomError::error(
-                "Could not open file",
-                e,
-                crate::error::Context::Show {
-                    line: path.as_ref().to_string_lossy().to_string(),
-                },
-            )
-        })?;
-        if check_extension(path, "gz") {
-            Ok(Box::new(Self::parse_reader(
-                BufReader::new(GzDecoder::new(BufReader::new(file))),
-                custom_database,
-            )))
-        } else {
-            Ok(Box::new(Self::parse_reader(
-                BufReader::new(file),
-                custom_database,
-            )))
-        }
+        Ok(Box::new(Self::parse_reader(
+            open_file(path)?,
+            custom_database,
+        )))
     }
 
     /// Parse a mzTab file directly from a buffered reader
@@ -798,6 +776,121 @@ impl std::str::FromStr for CVTerm {
     }
 }
 
+impl std::fmt::Display for CVTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}, {}, {}, {}]",
+            self.ontology, self.id, self.term, self.comment
+        )
+    }
+}
+
+impl CVTerm {
+    /// Create a new CV term without a comment. The term is not checked, use [`Self::checked`] to
+    /// create a term that is guaranteed to exist in one of the bundled ontologies.
+    pub fn new(
+        ontology: impl Into<String>,
+        id: impl Into<String>,
+        term: impl Into<String>,
+    ) -> Self {
+        Self {
+            ontology: ontology.into(),
+            id: id.into(),
+            term: term.into(),
+            comment: String::new(),
+        }
+    }
+
+    /// Create a new CV term for a modification from one of the bundled modification ontologies
+    /// (Unimod, PSI-MOD, GNOme, XLMOD, and RESID) or the custom database. The ontology is given
+    /// by its accession prefix (eg `UNIMOD`) and the id can be given with or without this prefix
+    /// (eg `UNIMOD:35` or `35`), any leading zeros are kept (eg `MOD:00046`). The name has to be
+    /// the name of the modification with this id, ignoring case.
+    /// # Errors
+    /// If the ontology is not one of the bundled modification ontologies, if the id does not exist
+    /// in the ontology, or if the name does not match the name of the modification with this id.
+    pub fn checked(
+        ontology: &str,
+        id: &str,
+        term: &str,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let ontology = ontology.trim().to_ascii_uppercase();
+        let id = id.trim();
+        let id = id.split_once(':').map_or(id, |(_, id)| id);
+        let term = Self {
+            id: format!("{ontology}:{id}"),
+            ontology,
+            term: term.trim().to_string(),
+            comment: String::new(),
+        };
+        term.modification(custom_database)?;
+        Ok(term)
+    }
+
+    /// Get the modification this term refers to, if it is a term from one of the bundled
+    /// modification ontologies (or the custom database), see [`Self::checked`].
+    /// # Errors
+    /// If the ontology is not one of the bundled modification ontologies, if the id does not exist
+    /// in the ontology, or if the name does not match the name of the modification with this id.
+    pub fn modification(
+        &self,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<SimpleModification, CustomError> {
+        let ontology = [
+            Ontology::Unimod,
+            Ontology::Psimod,
+            Ontology::Gnome,
+            Ontology::Xlmod,
+            Ontology::Resid,
+            Ontology::Custom,
+        ]
+        .into_iter()
+        .find(|o| o.name().eq_ignore_ascii_case(self.ontology.trim()))
+        .ok_or_else(|| {
+            CustomError::error(
+                "Invalid CV term",
+                "The ontology is not one of the bundled modification ontologies (UNIMOD, MOD, GNO, XLMOD, RESID, or CUSTOM)",
+                Context::show(self.to_string()),
+            )
+        })?;
+        let id = self.id.trim();
+        let id = id.split_once(':').map_or(id, |(_, id)| id);
+        let modification = if ontology == Ontology::Gnome {
+            ontology.find_name(id, custom_database)
+        } else {
+            id.parse::<usize>()
+                .ok()
+                .and_then(|id| ontology.find_id(id, custom_database))
+        }
+        .ok_or_else(|| {
+            CustomError::error(
+                "Invalid CV term",
+                format!("The id does not exist in {ontology}"),
+                Context::show(self.to_string()),
+            )
+        })?;
+        let name = match &*modification {
+            SimpleModificationInner::Database { id, .. }
+            | SimpleModificationInner::Gno { id, .. }
+            | SimpleModificationInner::Linker { id, .. } => id.name.as_str(),
+            _ => "",
+        };
+        if name.eq_ignore_ascii_case(self.term.trim()) {
+            Ok(modification)
+        } else {
+            Err(CustomError::error(
+                "Invalid CV term",
+                format!(
+                    "The name does not match the name for this id in {ontology}, which is '{name}'"
+                ),
+                Context::show(self.to_string()),
+            ))
+        }
+    }
+}
+
 /// The reliability of a PSM
 #[allow(missing_docs)]
 #[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
@@ -3,9 +3,31 @@ use std::io::{BufRead, BufReader};
 
 use crate::{
     error::CustomError,
-    identification::{test_identified_peptide, IdentifiedPeptide, MZTabData},
+    identification::{test_identified_peptide, CVTerm, IdentifiedPeptide, MZTabData},
 };
 
+#[test]
+fn cv_terms() {
+    let term = CVTerm::checked("unimod", "UNIMOD:35", "oxidation", None).unwrap();
+    assert_eq!(term.to_string(), "[UNIMOD, UNIMOD:35, oxidation, ]");
+    assert_eq!(term.to_string().parse::<CVTerm>().unwrap(), term);
+    assert_eq!(
+        CVTerm::checked("UNIMOD", "1", "Acetyl", None).unwrap().id,
+        "UNIMOD:1"
+    );
+    assert!(CVTerm::checked("UNIMOD", "35", "Acetyl", None).is_err());
+    assert!(CVTerm::checked("UNIMOD", "UNIMOD:0", "Oxidation", None).is_err());
+    assert!(CVTerm::checked("UNIMOD", "00", "Oxidation", None).is_err());
+    let term = CVTerm::checked("MOD", "MOD:00046", "O-phospho-L-serine", None).unwrap();
+    assert_eq!(term.id, "MOD:00046");
+    assert_eq!(term.to_string().parse::<CVTerm>().unwrap(), term);
+    assert!(term.modification(None).is_ok());
+    assert!(CVTerm::checked("MS", "MS:1001083", "ms-ms search", None).is_err());
+    assert!(CVTerm::new("MS", "MS:1001083", "ms-ms search")
+        .modification(None)
+        .is_err());
+}
+
 #[test]
 fn pride_exp_excerpt_ac_1643() {
     assert_eq!(
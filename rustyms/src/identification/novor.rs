@@ -59,13 +59,17 @@ format_family!(
 );
 
 impl From<NovorData> for IdentifiedPeptide {
-    fn from(value: NovorData) -> Self {
+    fn from(mut value: NovorData) -> Self {
+        let local_confidence: Option<Vec<f64>> = value
+            .local_confidence
+            .as_ref()
+            .map(|lc| lc.iter().map(|v| *v / 100.0).collect());
+        if let Some(local_confidence) = &local_confidence {
+            value.peptide.set_local_confidence(local_confidence);
+        }
         Self {
             score: Some((value.score / 100.0).clamp(-1.0, 1.0)),
-            local_confidence: value
-                .local_confidence
-                .as_ref()
-                .map(|lc| lc.iter().map(|v| *v / 100.0).collect()),
+            local_confidence,
             metadata: MetaData::Novor(value),
         }
     }
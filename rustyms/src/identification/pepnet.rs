@@ -44,10 +44,12 @@ format_family!(
 );
 
 impl From<PepNetData> for IdentifiedPeptide {
-    fn from(value: PepNetData) -> Self {
+    fn from(mut value: PepNetData) -> Self {
+        let local_confidence = value.local_confidence.clone();
+        value.peptide.set_local_confidence(&local_confidence);
         Self {
             score: Some(value.score),
-            local_confidence: Some(value.local_confidence.clone()),
+            local_confidence: Some(local_confidence),
             metadata: MetaData::PepNet(value),
         }
     }
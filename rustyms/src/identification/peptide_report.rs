@@ -0,0 +1,519 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::Write,
+    path::PathBuf,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    identification::{IdentifiedPeptide, ReturnedPeptide, SpectrumId, SpectrumIds},
+    modification::{Ontology, SimpleModificationInner},
+    peptidoform::SimpleLinear,
+    provenance::Provenance,
+    system::{usize::Charge, MassOverCharge, Time},
+    Chemical, CompoundPeptidoformIon, Modification, Peptidoform,
+};
+
+/// All identified peptides (PSMs) for a single peptidoform in a single run, see [`peptide_report`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeptideReportEntry<'a> {
+    /// The peptidoform, in ProForma notation
+    pub peptidoform: String,
+    /// The run (raw file), if known
+    pub run: Option<PathBuf>,
+    /// The best PSM, the one with the highest [`IdentifiedPeptide::score`]
+    pub best: &'a IdentifiedPeptide,
+    /// All PSMs for this peptidoform in this run, including the best PSM
+    pub psms: Vec<&'a IdentifiedPeptide>,
+}
+
+impl PeptideReportEntry<'_> {
+    /// The average score of all PSMs with a score
+    pub fn mean_score(&self) -> Option<f64> {
+        let scores = self.psms.iter().filter_map(|p| p.score).collect_vec();
+        (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64)
+    }
+
+    /// All distinct charges of the PSMs, sorted
+    pub fn charges(&self) -> Vec<Charge> {
+        self.psms
+            .iter()
+            .filter_map(|p| p.charge())
+            .sorted_by_key(|c| c.value)
+            .dedup()
+            .collect()
+    }
+
+    /// The lowest and highest retention time of the PSMs
+    pub fn retention_time_range(&self) -> Option<(Time, Time)> {
+        self.psms
+            .iter()
+            .filter_map(|p| p.retention_time())
+            .minmax_by(|a, b| a.value.total_cmp(&b.value))
+            .into_option()
+    }
+}
+
+/// Aggregate the given identified peptides (PSMs) into a peptide level report. All peptides with
+/// the same peptidoform from the same run (the first raw file of [`IdentifiedPeptide::scans`]) are
+/// grouped and the PSM with the highest [`IdentifiedPeptide::score`] is selected as the best PSM.
+/// Peptides without a peptide (eg Fasta without a sequence) are ignored. The entries are sorted
+/// by the first occurrence of the peptidoform and run in the given peptides.
+pub fn peptide_report<'a>(
+    peptides: impl IntoIterator<Item = &'a IdentifiedPeptide>,
+) -> Vec<PeptideReportEntry<'a>> {
+    let mut entries: Vec<PeptideReportEntry<'a>> = Vec::new();
+    // The index of the entry for each peptidoform and run, the peptidoforms are compared on their
+    // structure and not their text
+    let mut lookup: HashMap<(CompoundPeptidoformIon, Option<PathBuf>), usize> = HashMap::new();
+    for peptide in peptides {
+        let Some(peptidoform) = peptide
+            .peptide()
+            .map(|p| p.compound_peptidoform().into_owned())
+        else {
+            continue;
+        };
+        let run = match peptide.scans() {
+            SpectrumIds::FileKnown(files) => files.into_iter().next().map(|(file, _)| file),
+            _ => None,
+        };
+        match lookup.entry((peptidoform, run)) {
+            Entry::Occupied(index) => {
+                let entry = &mut entries[*index.get()];
+                if peptide.score.unwrap_or(f64::NEG_INFINITY)
+                    > entry.best.score.unwrap_or(f64::NEG_INFINITY)
+                {
+                    entry.best = peptide;
+                }
+                entry.psms.push(peptide);
+            }
+            Entry::Vacant(vacant) => {
+                entries.push(PeptideReportEntry {
+                    peptidoform: vacant.key().0.to_string(),
+                    run: vacant.key().1.clone(),
+                    best: peptide,
+                    psms: vec![peptide],
+                });
+                vacant.insert(entries.len() - 1);
+            }
+        }
+    }
+    entries
+}
+
+/// The file formats for [`write_peptide_report`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PeptideReportFormat {
+    /// A CSV file with one line per peptidoform per run
+    Csv,
+    /// An mzTab 1.0 summary file with only the metadata and the peptide (PEP) section. Only
+    /// linear peptides can be written in this format.
+    MzTab,
+}
+
+/// Write a peptide level report, see [`peptide_report`] to create the report. If a provenance is
+/// given it is written as comment lines for CSV and as custom metadata for mzTab.
+/// # Errors
+/// If writing fails, or if a peptide that is not a linear peptide is written as mzTab.
+pub fn write_peptide_report(
+    report: &[PeptideReportEntry<'_>],
+    format: PeptideReportFormat,
+    provenance: Option<&Provenance>,
+    writer: impl Write,
+) -> Result<(), CustomError> {
+    match format {
+        PeptideReportFormat::Csv => write_csv(report, provenance, writer),
+        PeptideReportFormat::MzTab => write_mztab(report, provenance, writer),
+    }
+}
+
+/// Convert an IO error into a custom error
+fn io_error(error: std::io::Error) -> CustomError {
+    CustomError::error("Could not write peptide report", error, Context::None)
+}
+
+/// Write the report as CSV
+/// # Errors
+/// If writing fails.
+fn write_csv(
+    report: &[PeptideReportEntry<'_>],
+    provenance: Option<&Provenance>,
+    mut writer: impl Write,
+) -> Result<(), CustomError> {
+    if let Some(provenance) = provenance {
+        provenance
+            .write_csv_comments(&mut writer)
+            .map_err(io_error)?;
+    }
+    let optional = |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+    crate::csv::write_csv(
+        writer,
+        report.iter().map(|entry| {
+            let rt_range = entry.retention_time_range();
+            vec![
+                ("peptidoform".to_string(), entry.peptidoform.clone()),
+                (
+                    "run".to_string(),
+                    entry
+                        .run
+                        .as_ref()
+                        .map_or(String::new(), |r| r.to_string_lossy().to_string()),
+                ),
+                ("psms".to_string(), entry.psms.len().to_string()),
+                (
+                    "best_format".to_string(),
+                    entry.best.format_name().to_string(),
+                ),
+                ("best_id".to_string(), entry.best.id()),
+                ("best_score".to_string(), optional(entry.best.score)),
+                ("mean_score".to_string(), optional(entry.mean_score())),
+                (
+                    "charges".to_string(),
+                    entry.charges().iter().map(|c| c.value).join(";"),
+                ),
+                (
+                    "best_rt".to_string(),
+                    optional(
+                        entry
+                            .best
+                            .retention_time()
+                            .map(|t| t.get::<crate::system::time::s>()),
+                    ),
+                ),
+                (
+                    "rt_min".to_string(),
+                    optional(rt_range.map(|r| r.0.get::<crate::system::time::s>())),
+                ),
+                (
+                    "rt_max".to_string(),
+                    optional(rt_range.map(|r| r.1.get::<crate::system::time::s>())),
+                ),
+                (
+                    "best_mz".to_string(),
+                    optional(entry.best.experimental_mz().map(|mz| mz.value)),
+                ),
+            ]
+        }),
+    )
+    .map_err(io_error)
+}
+
+/// The columns in the mzTab peptide section
+const MZTAB_COLUMNS: &[&str] = &[
+    "sequence",
+    "accession",
+    "unique",
+    "database",
+    "database_version",
+    "search_engine",
+    "best_search_engine_score[1]",
+    "modifications",
+    "retention_time",
+    "retention_time_window",
+    "charge",
+    "mass_to_charge",
+    "spectra_ref",
+    "opt_global_proforma",
+    "opt_global_psm_count",
+];
+
+/// Write the report as an mzTab summary file
+/// # Errors
+/// If writing fails or if a peptide is not linear.
+fn write_mztab(
+    report: &[PeptideReportEntry<'_>],
+    provenance: Option<&Provenance>,
+    mut writer: impl Write,
+) -> Result<(), CustomError> {
+    let runs: Vec<Option<&PathBuf>> = report.iter().map(|e| e.run.as_ref()).unique().collect();
+    writeln!(writer, "MTD\tmzTab-version\t1.0.0").map_err(io_error)?;
+    writeln!(writer, "MTD\tmzTab-mode\tSummary").map_err(io_error)?;
+    writeln!(writer, "MTD\tmzTab-type\tIdentification").map_err(io_error)?;
+    writeln!(writer, "MTD\tdescription\tPeptide level report").map_err(io_error)?;
+    for (index, run) in runs.iter().enumerate() {
+        writeln!(
+            writer,
+            "MTD\tms_run[{}]-location\t{}",
+            index + 1,
+            run.map_or("null".to_string(), |r| format!("file://{}", r.display()))
+        )
+        .map_err(io_error)?;
+    }
+    writeln!(
+        writer,
+        "MTD\tpeptide_search_engine_score[1]\t[MS, MS:1001153, search engine specific score, ]"
+    )
+    .map_err(io_error)?;
+    writeln!(
+        writer,
+        "MTD\tfixed_mod[1]\t[MS, MS:1002453, No fixed modifications searched, ]"
+    )
+    .map_err(io_error)?;
+    writeln!(
+        writer,
+        "MTD\tvariable_mod[1]\t[MS, MS:1002454, No variable modifications searched, ]"
+    )
+    .map_err(io_error)?;
+    if let Some(provenance) = provenance {
+        provenance
+            .write_mztab_metadata(&mut writer, 1)
+            .map_err(io_error)?;
+    }
+    writeln!(writer).map_err(io_error)?;
+    writeln!(writer, "PEH\t{}", MZTAB_COLUMNS.join("\t")).map_err(io_error)?;
+    for entry in report {
+        let Some(peptide) = entry.best.peptide().and_then(ReturnedPeptide::peptide) else {
+            return Err(CustomError::error(
+                "Unsupported peptide",
+                "Only linear peptides can be written in the mzTab peptide section",
+                Context::show(&entry.peptidoform),
+            ));
+        };
+        let run = runs
+            .iter()
+            .position(|r| *r == entry.run.as_ref())
+            .unwrap_or(0)
+            + 1;
+        let null = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+        writeln!(
+            writer,
+            "PEP\t{}\tnull\tnull\tnull\tnull\t[, , {}, ]\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            peptide
+                .sequence()
+                .iter()
+                .map(|e| e.aminoacid.char())
+                .join(""),
+            entry.best.format_name(),
+            null(entry.best.score.map(|s| s.to_string())),
+            mztab_modifications(&peptide),
+            null(
+                entry
+                    .best
+                    .retention_time()
+                    .map(|t| t.get::<crate::system::time::s>().to_string())
+            ),
+            null(entry.retention_time_range().map(|(min, max)| format!(
+                "{}|{}",
+                min.get::<crate::system::time::s>(),
+                max.get::<crate::system::time::s>()
+            ))),
+            null(entry.best.charge().map(|c| c.value.to_string())),
+            null(
+                entry
+                    .best
+                    .experimental_mz()
+                    .map(|mz: MassOverCharge| mz.value.to_string())
+            ),
+            null(mztab_spectra_ref(entry.best, run)),
+            entry.peptidoform,
+            entry.psms.len(),
+        )
+        .map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// The modifications in mzTab notation, with positions 0 for the N-terminus and length + 1 for
+/// the C-terminus. Unimod and PSI-MOD modifications are written with their accession, all other
+/// modifications as `CHEMMOD` with their monoisotopic mass.
+fn mztab_modifications(peptide: &Peptidoform<SimpleLinear>) -> String {
+    let modification = |position: usize, modification: &Modification| {
+        let simple = modification.simple()?;
+        Some(match simple.as_ref() {
+            SimpleModificationInner::Database { id, .. } if id.ontology == Ontology::Unimod => {
+                format!("{position}-UNIMOD:{}", id.id?)
+            }
+            SimpleModificationInner::Database { id, .. } if id.ontology == Ontology::Psimod => {
+                format!("{position}-MOD:{:05}", id.id?)
+            }
+            other => format!(
+                "{position}-CHEMMOD:{:+}",
+                other.formula().monoisotopic_mass().value
+            ),
+        })
+    };
+    let modifications = peptide
+        .get_n_term()
+        .iter()
+        .filter_map(|m| modification(0, m))
+        .chain(peptide.sequence().iter().enumerate().flat_map(|(i, e)| {
+            e.modifications
+                .iter()
+                .filter_map(move |m| modification(i + 1, m))
+        }))
+        .chain(
+            peptide
+                .get_c_term()
+                .iter()
+                .filter_map(|m| modification(peptide.len() + 1, m)),
+        )
+        .join(",");
+    if modifications.is_empty() {
+        "null".to_string()
+    } else {
+        modifications
+    }
+}
+
+/// The spectra reference in mzTab notation
+fn mztab_spectra_ref(peptide: &IdentifiedPeptide, run: usize) -> Option<String> {
+    let spectra = match peptide.scans() {
+        SpectrumIds::FileKnown(files) => files.into_iter().next()?.1,
+        SpectrumIds::FileNotKnown(spectra) => spectra,
+        SpectrumIds::None => return None,
+    };
+    let spectra = spectra
+        .iter()
+        .filter_map(|s| match s {
+            SpectrumId::Index(i) => Some(format!("ms_run[{run}]:index={i}")),
+            SpectrumId::Native(n) => Some(format!("ms_run[{run}]:{n}")),
+            SpectrumId::RetentionTime(_) => None,
+        })
+        .join("|");
+    (!spectra.is_empty()).then_some(spectra)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::GenericFormat;
+    use std::io::BufReader;
+
+    fn peptides() -> Vec<IdentifiedPeptide> {
+        let mut format = GenericFormat::new("peptide");
+        format.score = Some("score".to_string());
+        format.score_scale = Some(1.0);
+        format.charge = Some("z".to_string());
+        format.scan = Some("scan".to_string());
+        format.raw_file = Some("file".to_string());
+        format
+            .parse_reader(
+                BufReader::new(
+                    "peptide\tscore\tz\tscan\tfile\nPEPM[Oxidation]K\t0.5\t2\t1\ta.raw\nPEPM[Oxidation]K\t0.9\t3\t2\ta.raw\nPEPMK\t0.7\t2\t3\ta.raw\nPEPM[Oxidation]K\t0.1\t2\t4\tb.raw"
+                        .as_bytes(),
+                ),
+                None,
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn aggregate() {
+        let peptides = peptides();
+        let report = peptide_report(&peptides);
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[0].psms.len(), 2);
+        assert_eq!(report[0].best.id(), "2");
+        assert_eq!(report[0].mean_score(), Some(0.7));
+        assert_eq!(
+            report[0].charges().iter().map(|c| c.value).collect_vec(),
+            [2, 3]
+        );
+        assert_eq!(report[1].peptidoform, "PEPMK");
+        assert_eq!(report[2].run, Some(PathBuf::from("b.raw")));
+    }
+
+    #[test]
+    fn aggregate_canonical() {
+        // Differently written but identical peptidoforms are grouped
+        let mut format = GenericFormat::new("peptide");
+        format.raw_file = Some("file".to_string());
+        let peptides = format
+            .parse_reader(
+                BufReader::new(
+                    "peptide\tfile\nPEPM[Oxidation]K\ta.raw\nPEPM[U:Oxidation]K\ta.raw\nPEPM[+15.9949]K\ta.raw"
+                        .as_bytes(),
+                ),
+                None,
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let report = peptide_report(&peptides);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].psms.len(), 2);
+        assert_eq!(report[0].peptidoform, "PEPM[U:Oxidation]K");
+    }
+
+    #[test]
+    fn write() {
+        let peptides = peptides();
+        let report = peptide_report(&peptides);
+        let mut csv = Vec::new();
+        write_peptide_report(&report, PeptideReportFormat::Csv, None, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect_vec();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("peptidoform,run,psms,best_format,best_id,best_score"));
+        assert!(lines[1].starts_with("PEPM[U:Oxidation]K,a.raw,2,Generic,2,0.9"));
+
+        let mut mztab = Vec::new();
+        write_peptide_report(&report, PeptideReportFormat::MzTab, None, &mut mztab).unwrap();
+        let mztab = String::from_utf8(mztab).unwrap();
+        assert!(mztab.contains("MTD\tms_run[2]-location\tfile://b.raw"));
+        let pep = mztab
+            .lines()
+            .filter(|l| l.starts_with("PEP"))
+            .map(|l| l.split('\t').collect_vec())
+            .collect_vec();
+        assert_eq!(pep.len(), 3);
+        assert_eq!(pep[0][1], "PEPMK");
+        assert_eq!(pep[0][8], "4-UNIMOD:35");
+        assert_eq!(pep[0][11], "3");
+        assert_eq!(pep[0][13], "ms_run[1]:index=2");
+        assert_eq!(pep[1][8], "null");
+        assert_eq!(pep[2][13], "ms_run[2]:index=4");
+        assert_eq!(pep[0][15], "2");
+    }
+
+    #[test]
+    fn provenance() {
+        let peptides = peptides();
+        let report = peptide_report(&peptides);
+        let provenance = Provenance::new()
+            .software("tool 1.0")
+            .parameter("fdr", 0.01);
+        let mut csv = Vec::new();
+        write_peptide_report(
+            &report,
+            PeptideReportFormat::Csv,
+            Some(&provenance),
+            &mut csv,
+        )
+        .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("## software: tool 1.0\n"));
+        assert!(csv.contains("## parameter[fdr]: 0.01\n"));
+        assert!(crate::csv::parse_csv_raw(csv.as_bytes(), b',', None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .is_err());
+        let lines = crate::csv::parse_csv_raw_with_comments(csv.as_bytes(), b',', None, Some("##"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0].index_column("peptidoform").unwrap().0,
+            "PEPM[U:Oxidation]K"
+        );
+
+        let mut mztab = Vec::new();
+        write_peptide_report(
+            &report,
+            PeptideReportFormat::MzTab,
+            Some(&provenance),
+            &mut mztab,
+        )
+        .unwrap();
+        let mztab = String::from_utf8(mztab).unwrap();
+        assert!(mztab.contains("MTD\tcustom[1]\t[, , software, tool 1.0]\n"));
+        assert!(mztab.contains("MTD\tcustom[3]\t[, , parameter[fdr], 0.01]\n"));
+    }
+}
@@ -62,10 +62,12 @@ format_family!(
 static IDENTIFER_REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
 
 impl From<PowerNovoData> for IdentifiedPeptide {
-    fn from(value: PowerNovoData) -> Self {
+    fn from(mut value: PowerNovoData) -> Self {
+        let local_confidence = value.local_confidence.clone();
+        value.peptide.set_local_confidence(&local_confidence);
         Self {
             score: Some(value.score),
-            local_confidence: Some(value.local_confidence.clone()),
+            local_confidence: Some(local_confidence),
             metadata: MetaData::PowerNovo(value),
         }
     }
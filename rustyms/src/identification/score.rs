@@ -0,0 +1,390 @@
+use std::{borrow::Cow, collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::identification::{
+    DeepNovoFamilyData, GenericData, IdentifiedPeptide, IdentifiedPeptideFileFormat, InstaNovoData,
+    MSFraggerData, MZTabData, MaxQuantData, MetaData, NovoBData, NovorData, OpairData, PLGSData,
+    PLinkData, PeaksData, PepNetData, PowerNovoData, SageData, SpectrumIds,
+    SpectrumSequenceListData,
+};
+
+/// If a higher or lower score indicates a better identification
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum ScoreDirection {
+    /// A higher score is better, eg a probability or a hyperscore
+    #[default]
+    HigherIsBetter,
+    /// A lower score is better, eg an e-value or a q-value
+    LowerIsBetter,
+}
+
+impl ScoreDirection {
+    /// Guess the direction from the name of a score, e-values, p-values, q-values, and
+    /// posterior error probabilities (PEP) are lower is better, anything else is assumed to be
+    /// higher is better. The name is matched on whole words, so a 'peptide score' is higher is
+    /// better while a 'PSM PEP' is lower is better.
+    pub fn from_name(name: &str) -> Self {
+        /// Words that indicate a lower is better score, the values also match as suffix of a
+        /// word (eg `SpecEValue`)
+        const LOWER_IS_BETTER: &[&str] = &[
+            "evalue",
+            "expect",
+            "expectation",
+            "pvalue",
+            "qvalue",
+            "pep",
+            "posteriorerror",
+        ];
+        let matches = |word: &str| {
+            LOWER_IS_BETTER
+                .iter()
+                .any(|n| word == *n || (n.ends_with("value") && word.ends_with(n)))
+        };
+        // Words are split on anything but letters, digits, and hyphens, hyphens are removed so
+        // 'q-value' is 'qvalue'
+        let words = name
+            .to_ascii_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.replace('-', ""))
+            .collect::<Vec<_>>();
+        // Also match two consecutive words to handle 'q value' and 'posterior error probability'
+        if words.iter().any(|w| matches(w)) || words.windows(2).any(|w| matches(&w.concat())) {
+            Self::LowerIsBetter
+        } else {
+            Self::HigherIsBetter
+        }
+    }
+
+    /// Turn a score with this direction into a score where higher is better
+    pub fn directed(self, score: f64) -> f64 {
+        match self {
+            Self::HigherIsBetter => score,
+            Self::LowerIsBetter => -score,
+        }
+    }
+}
+
+/// The type of the original score of an identified peptide, see [`IdentifiedPeptide::original_score`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScoreType {
+    /// The name of the score as used by the program
+    pub name: Cow<'static, str>,
+    /// The direction of the score
+    pub direction: ScoreDirection,
+}
+
+impl ScoreType {
+    /// Create a new score type
+    pub const fn new(name: &'static str, direction: ScoreDirection) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            direction,
+        }
+    }
+
+    /// Create a new score type with the direction guessed from the name, see [`ScoreDirection::from_name`]
+    pub fn from_name(name: impl Into<Cow<'static, str>>) -> Self {
+        let name = name.into();
+        let direction = ScoreDirection::from_name(&name);
+        Self { name, direction }
+    }
+}
+
+impl std::fmt::Display for ScoreType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl IdentifiedPeptideFileFormat {
+    /// The type of the main score of this format, this is the score returned by
+    /// [`IdentifiedPeptide::original_score`]. Formats where the score type depends on the file
+    /// (mzTab and SSL) and formats without a score (Fasta) return `None`. For PEAKS this is the
+    /// de novo score, if this is not available the ALC or -10lgP is used instead.
+    pub const fn score_type(self) -> Option<ScoreType> {
+        use ScoreDirection::{HigherIsBetter, LowerIsBetter};
+        match self {
+            Self::DeepNovoFamily | Self::InstaNovo => {
+                Some(ScoreType::new("log probability", HigherIsBetter))
+            }
+            Self::MaxQuant => Some(ScoreType::new("Andromeda score", HigherIsBetter)),
+            Self::MSFragger => Some(ScoreType::new("hyperscore", HigherIsBetter)),
+            Self::NovoB => Some(ScoreType::new("probability", HigherIsBetter)),
+            Self::Novor | Self::Opair | Self::PepNet | Self::PowerNovo => {
+                Some(ScoreType::new("score", HigherIsBetter))
+            }
+            Self::Peaks => Some(ScoreType::new("de novo score", HigherIsBetter)),
+            Self::PLGS => Some(ScoreType::new("peptide score", HigherIsBetter)),
+            Self::PLink => Some(ScoreType::new("score", LowerIsBetter)),
+            Self::Sage => Some(ScoreType::new("discriminant score", HigherIsBetter)),
+            Self::Fasta | Self::MZTab | Self::SpectrumSequenceList => None,
+        }
+    }
+}
+
+impl IdentifiedPeptide {
+    /// Get the main score as reported by the program, without any scaling, with the type of
+    /// the score. In contrast to [`Self::score`] this score is not comparable between
+    /// programs, use [`normalise_scores`] to make these scores comparable.
+    pub fn original_score(&self) -> Option<(f64, ScoreType)> {
+        let fixed = |score: f64, format: IdentifiedPeptideFileFormat| {
+            format.score_type().map(|t| (score, t))
+        };
+        match &self.metadata {
+            MetaData::DeepNovoFamily(DeepNovoFamilyData { score, .. }) => {
+                score.and_then(|s| fixed(s, IdentifiedPeptideFileFormat::DeepNovoFamily))
+            }
+            MetaData::Fasta(_) => None,
+            MetaData::Generic(GenericData {
+                format,
+                score,
+                score_direction,
+                ..
+            }) => score.map(|s| {
+                (
+                    s,
+                    ScoreType {
+                        name: Cow::Owned(format.clone()),
+                        direction: *score_direction,
+                    },
+                )
+            }),
+            MetaData::InstaNovo(InstaNovoData { score, .. }) => {
+                fixed(*score, IdentifiedPeptideFileFormat::InstaNovo)
+            }
+            MetaData::MaxQuant(MaxQuantData { score, .. }) => (!score.is_nan())
+                .then(|| fixed(*score, IdentifiedPeptideFileFormat::MaxQuant))
+                .flatten(),
+            MetaData::MSFragger(MSFraggerData { hyperscore, .. }) => {
+                // The hyperscore is stored divided by 100
+                fixed(hyperscore * 100.0, IdentifiedPeptideFileFormat::MSFragger)
+            }
+            MetaData::MZTab(MZTabData { search_engine, .. }) => {
+                search_engine.iter().find_map(|(_, score, score_type)| {
+                    score.map(|s| (s, ScoreType::from_name(score_type.term.clone())))
+                })
+            }
+            MetaData::NovoB(NovoBData {
+                score_forward,
+                score_reverse,
+                ..
+            }) => fixed(
+                score_forward.max(*score_reverse),
+                IdentifiedPeptideFileFormat::NovoB,
+            ),
+            MetaData::Novor(NovorData { score, .. }) => {
+                fixed(*score, IdentifiedPeptideFileFormat::Novor)
+            }
+            MetaData::Opair(OpairData { score, .. }) => {
+                fixed(*score, IdentifiedPeptideFileFormat::Opair)
+            }
+            MetaData::Peaks(PeaksData {
+                de_novo_score,
+                alc,
+                logp,
+                ..
+            }) => de_novo_score
+                .and_then(|s| fixed(s, IdentifiedPeptideFileFormat::Peaks))
+                .or_else(|| alc.map(|s| (s, ScoreType::new("ALC", ScoreDirection::HigherIsBetter))))
+                .or_else(|| {
+                    logp.map(|s| (s, ScoreType::new("-10lgP", ScoreDirection::HigherIsBetter)))
+                }),
+            MetaData::PepNet(PepNetData { score, .. }) => {
+                fixed(*score, IdentifiedPeptideFileFormat::PepNet)
+            }
+            MetaData::PLGS(PLGSData { peptide_score, .. }) => {
+                fixed(*peptide_score, IdentifiedPeptideFileFormat::PLGS)
+            }
+            MetaData::PLink(PLinkData { score, .. }) => {
+                fixed(*score, IdentifiedPeptideFileFormat::PLink)
+            }
+            MetaData::PowerNovo(PowerNovoData { score, .. }) => {
+                fixed(*score, IdentifiedPeptideFileFormat::PowerNovo)
+            }
+            MetaData::Sage(SageData {
+                sage_discriminant_score,
+                ..
+            }) => fixed(*sage_discriminant_score, IdentifiedPeptideFileFormat::Sage),
+            MetaData::SpectrumSequenceList(SpectrumSequenceListData {
+                score, score_type, ..
+            }) => score.map(|s| {
+                (
+                    s,
+                    score_type.as_ref().map_or_else(
+                        || ScoreType::new("score", ScoreDirection::HigherIsBetter),
+                        |t| ScoreType::from_name(t.clone()),
+                    ),
+                )
+            }),
+        }
+    }
+}
+
+/// The normalisation method for [`normalise_scores`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScoreNormalisation {
+    /// The rank of the score scaled to 0..=1, with 1 for the best score. Tied scores get the
+    /// average of their ranks.
+    Rank,
+    /// The number of standard deviations from the mean score (z-score)
+    ZScore,
+}
+
+/// A group of comparable scores: the format name, the score type, and the run
+type ScoreGroup = (&'static str, ScoreType, Option<PathBuf>);
+
+/// Normalise the original scores (see [`IdentifiedPeptide::original_score`]) of the given
+/// peptides so that these can be compared between programs. The scores are normalised within
+/// groups of peptides from the same program, with the same score type, and from the same run
+/// (the first raw file of [`IdentifiedPeptide::scans`]). For all normalised scores a higher
+/// score is better. The returned scores are in the same order as the peptides, peptides without
+/// a score get `None`.
+pub fn normalise_scores(
+    peptides: &[IdentifiedPeptide],
+    normalisation: ScoreNormalisation,
+) -> Vec<Option<f64>> {
+    let mut groups: HashMap<ScoreGroup, Vec<(usize, f64)>> = HashMap::new();
+    for (index, peptide) in peptides.iter().enumerate() {
+        if let Some((score, score_type)) = peptide.original_score() {
+            if score.is_nan() {
+                continue;
+            }
+            let run = match peptide.scans() {
+                SpectrumIds::FileKnown(files) => files.into_iter().next().map(|(file, _)| file),
+                _ => None,
+            };
+            let score = score_type.direction.directed(score);
+            groups
+                .entry((peptide.format_name(), score_type, run))
+                .or_default()
+                .push((index, score));
+        }
+    }
+
+    let mut result = vec![None; peptides.len()];
+    for mut group in groups.into_values() {
+        match normalisation {
+            ScoreNormalisation::Rank => {
+                group.sort_unstable_by(|a, b| a.1.total_cmp(&b.1));
+                let max = group.len().saturating_sub(1) as f64;
+                let mut start = 0;
+                while start < group.len() {
+                    let end = group[start..]
+                        .iter()
+                        .position(|s| s.1.total_cmp(&group[start].1).is_ne())
+                        .map_or(group.len(), |p| start + p);
+                    let rank = (start + end - 1) as f64 / 2.0;
+                    for (index, _) in &group[start..end] {
+                        result[*index] = Some(if max == 0.0 { 1.0 } else { rank / max });
+                    }
+                    start = end;
+                }
+            }
+            ScoreNormalisation::ZScore => {
+                let n = group.len() as f64;
+                let mean = group.iter().map(|s| s.1).sum::<f64>() / n;
+                let sd = (group.iter().map(|s| (s.1 - mean).powi(2)).sum::<f64>() / n).sqrt();
+                for (index, score) in group {
+                    result[index] = Some(if sd == 0.0 { 0.0 } else { (score - mean) / sd });
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::GenericFormat;
+    use std::io::BufReader;
+
+    #[test]
+    fn direction() {
+        assert_eq!(
+            ScoreDirection::from_name("PERCOLATOR QVALUE"),
+            ScoreDirection::LowerIsBetter
+        );
+        assert_eq!(
+            ScoreDirection::from_name("MS-GF:SpecEValue"),
+            ScoreDirection::LowerIsBetter
+        );
+        assert_eq!(
+            ScoreDirection::from_name("hyperscore"),
+            ScoreDirection::HigherIsBetter
+        );
+        for name in [
+            "PEP",
+            "Percolator:PEP",
+            "posterior_error_probability",
+            "Posterior Error Probability",
+            "q-value",
+            "Comet:e value",
+            "X!Tandem:expect",
+        ] {
+            assert_eq!(
+                ScoreDirection::from_name(name),
+                ScoreDirection::LowerIsBetter,
+                "{name}"
+            );
+        }
+        for name in ["peptide score", "PeptideProphet probability", "prepscore"] {
+            assert_eq!(
+                ScoreDirection::from_name(name),
+                ScoreDirection::HigherIsBetter,
+                "{name}"
+            );
+        }
+        assert_eq!(
+            IdentifiedPeptideFileFormat::PLink
+                .score_type()
+                .map(|t| t.direction),
+            Some(ScoreDirection::LowerIsBetter)
+        );
+    }
+
+    fn peptides(data: &str, direction: ScoreDirection) -> Vec<IdentifiedPeptide> {
+        let mut format = GenericFormat::new("peptide");
+        format.score = Some("score".to_string());
+        format.raw_file = Some("file".to_string());
+        format.score_direction = direction;
+        format
+            .parse_reader(BufReader::new(data.as_bytes()), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn rank() {
+        let peptides = peptides(
+            "peptide\tscore\tfile\nAAA\t0.01\ta\nCCC\t0.5\ta\nDDD\t0.01\ta\nEEE\t0.001\ta\nFFF\t10\tb\nGGG\t\tb",
+            ScoreDirection::LowerIsBetter,
+        );
+        assert_eq!(
+            peptides[0].original_score().map(|s| s.1.direction),
+            Some(ScoreDirection::LowerIsBetter)
+        );
+        assert_eq!(
+            normalise_scores(&peptides, ScoreNormalisation::Rank),
+            vec![Some(0.5), Some(0.0), Some(0.5), Some(1.0), Some(1.0), None]
+        );
+    }
+
+    #[test]
+    fn z_score() {
+        let peptides = peptides(
+            "peptide\tscore\tfile\nAAA\t1\ta\nCCC\t3\ta\nDDD\t5\tb",
+            ScoreDirection::HigherIsBetter,
+        );
+        assert_eq!(
+            normalise_scores(&peptides, ScoreNormalisation::ZScore),
+            vec![Some(-1.0), Some(1.0), Some(0.0)]
+        );
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    io::{BufRead, Read},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    identification::FastaData,
+    transformation::{PointMutation, SequenceTransformation, TransformedPeptidoform},
+    Protease,
+};
+
+/// A single amino acid variant (SAAV) in a protein, used to generate variant peptides for
+/// proteogenomic searches, see [`FastaData::digest_with_variants`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ProteinVariant {
+    /// The accession of the protein, this is matched to the accession in the fasta header
+    pub accession: String,
+    /// The amino acid change, with the position in the protein
+    pub mutation: PointMutation,
+    /// The identifier of the variant, if known, eg a `dbSNP` rs number or a `UniProt` VAR id
+    pub identifier: Option<String>,
+}
+
+impl ProteinVariant {
+    /// Create a new variant
+    pub fn new(accession: impl Into<String>, mutation: PointMutation) -> Self {
+        Self {
+            accession: accession.into(),
+            mutation,
+            identifier: None,
+        }
+    }
+
+    /// All mutations for the protein with the given accession as a single transformation
+    pub fn mutations(variants: &[Self], accession: &str) -> SequenceTransformation {
+        SequenceTransformation::Mutations(
+            variants
+                .iter()
+                .filter(|v| v.accession == accession)
+                .map(|v| v.mutation)
+                .collect(),
+        )
+    }
+
+    /// Parse a simple variant list, see [`Self::parse_reader`].
+    /// # Errors
+    /// If the file could not be opened or is not a valid variant list.
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Self>, CustomError> {
+        crate::csv::parse_csv(path, b'\t', None)?
+            .map(|line| Self::from_line(&line?))
+            .collect()
+    }
+
+    /// Parse a simple variant list. This is a tab separated file (or any other separator if
+    /// indicated with a `sep=` first line) with a header line that contains at least the columns
+    /// `accession` and `variant` and optionally the column `identifier`. The variants are written
+    /// as point mutations with 1 based positions in the protein, eg `K9R` or `p.Lys9Arg`.
+    /// # Errors
+    /// If the file is not a valid variant list.
+    pub fn parse_reader(reader: impl Read) -> Result<Vec<Self>, CustomError> {
+        crate::csv::parse_csv_raw(reader, b'\t', None)?
+            .map(|line| Self::from_line(&line?))
+            .collect()
+    }
+
+    /// Parse a single line of a variant list
+    /// # Errors
+    /// If the needed columns are missing or the variant is not a valid point mutation.
+    fn from_line(line: &crate::csv::CsvLine) -> Result<Self, CustomError> {
+        let (variant, range) = line.index_column("variant")?;
+        Ok(Self {
+            accession: line.index_column("accession")?.0.trim().to_string(),
+            mutation: variant
+                .parse()
+                .map_err(|e: CustomError| e.with_context(line.range_context(range.clone())))?,
+            identifier: line
+                .index_column("identifier")
+                .ok()
+                .map(|(id, _)| id.trim())
+                .filter(|id| !id.is_empty() && *id != "-")
+                .map(ToString::to_string),
+        })
+    }
+
+    /// Parse the `UniProt` human variant list (`humsavar.txt`), see [`Self::parse_uniprot_reader`].
+    /// # Errors
+    /// If the file could not be opened or is not a valid `UniProt` variant list.
+    pub fn parse_uniprot_file(path: impl AsRef<Path>) -> Result<Vec<Self>, CustomError> {
+        let path = path.as_ref();
+        Self::parse_uniprot_reader(crate::compression::open_file(path)?, Some(path))
+    }
+
+    /// Parse the `UniProt` human variant list (`humsavar.txt`). All lines between the line of
+    /// underscores below the column headers and the closing line of dashes are read as
+    /// variants, with the Swiss-Prot accession, the `FTId` as identifier, and the amino acid change
+    /// in HGVS notation (eg `p.His52Arg`).
+    /// # Errors
+    /// If the file is not a valid `UniProt` variant list.
+    pub fn parse_uniprot_reader(
+        reader: impl BufRead,
+        path: Option<&Path>,
+    ) -> Result<Vec<Self>, CustomError> {
+        let mut variants = Vec::new();
+        let mut in_body = false;
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|_| read_error(line_index, path))?;
+            if !in_body {
+                in_body = line.starts_with("___");
+                continue;
+            }
+            if line.starts_with('-') {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let [_gene, accession, identifier, change, ..] = columns[..] else {
+                return Err(CustomError::error(
+                    "Invalid UniProt variant line",
+                    "A variant line should contain at least the gene name, accession, FTId, and amino acid change",
+                    Context::full_line(line_index, &line),
+                ));
+            };
+            variants.push(Self {
+                accession: accession.to_string(),
+                mutation: change.parse().map_err(|e: CustomError| {
+                    e.with_context(Context::full_line(line_index, &line))
+                })?,
+                identifier: Some(identifier.to_string()),
+            });
+        }
+        Ok(variants)
+    }
+
+    /// Parse a VCF file annotated with `snpEff`, see [`Self::parse_vcf_reader`].
+    /// # Errors
+    /// If the file could not be opened or is not a valid VCF file.
+    pub fn parse_vcf_file(path: impl AsRef<Path>) -> Result<Vec<Self>, CustomError> {
+        let path = path.as_ref();
+        Self::parse_vcf_reader(crate::compression::open_file(path)?, Some(path))
+    }
+
+    /// Parse a VCF file with functional annotations in the `ANN` info field (as written by
+    /// `snpEff`). Only the missense variants are used, with the feature id (transcript) as
+    /// accession and the HGVS protein change as mutation. The accessions have to match the
+    /// protein database, so these have to be from the same annotation (for example both Ensembl).
+    /// The `ID` column is used as identifier if it is not empty.
+    /// # Errors
+    /// If the file is not a valid VCF file, or if a missense annotation does not contain a valid
+    /// protein change.
+    pub fn parse_vcf_reader(
+        reader: impl BufRead,
+        path: Option<&Path>,
+    ) -> Result<Vec<Self>, CustomError> {
+        let mut variants = Vec::new();
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|_| read_error(line_index, path))?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 8 {
+                return Err(CustomError::error(
+                    "Invalid VCF line",
+                    "A VCF line should contain at least 8 tab separated columns",
+                    Context::full_line(line_index, &line),
+                ));
+            }
+            let identifier = Some(columns[2])
+                .filter(|id| !id.is_empty() && *id != ".")
+                .map(ToString::to_string);
+            let annotations = columns[7]
+                .split(';')
+                .filter_map(|info| info.strip_prefix("ANN="))
+                .flat_map(|ann| ann.split(','));
+            for annotation in annotations {
+                let fields: Vec<&str> = annotation.split('|').collect();
+                if fields.len() < 11 || !fields[1].split('&').any(|kind| kind == "missense_variant")
+                {
+                    continue;
+                }
+                let variant = Self {
+                    accession: fields[6].to_string(),
+                    mutation: fields[10].parse().map_err(|e: CustomError| {
+                        e.with_context(Context::full_line(line_index, &line))
+                    })?,
+                    identifier: identifier.clone(),
+                };
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+        Ok(variants)
+    }
+}
+
+fn read_error(line_index: usize, path: Option<&Path>) -> CustomError {
+    CustomError::error(
+        "Failed reading variant file",
+        format!("Error occurred while reading line {}", line_index + 1),
+        path.map_or(Context::None, |p| Context::show(p.to_string_lossy())),
+    )
+}
+
+impl FastaData {
+    /// Generate the variant proteins for all given variants of this protein. Variants for other
+    /// proteins and variants where the original amino acid does not match the sequence are
+    /// ignored. Every variant protein contains a single mutation.
+    pub fn variant_proteins(&self, variants: &[ProteinVariant]) -> Vec<TransformedPeptidoform> {
+        ProteinVariant::mutations(variants, self.identifier().accession())
+            .apply(self.peptide())
+            .into_iter()
+            .map(|(peptidoform, applied)| TransformedPeptidoform {
+                peptidoform,
+                transformations: vec![applied],
+            })
+            .collect()
+    }
+
+    /// Digest this protein and all variant proteins (see [`Self::variant_proteins`]). All
+    /// peptides from the canonical protein are returned without transformations, followed by all
+    /// peptides that cover a variant, with the applied mutation as provenance. Variant peptides
+    /// that are identical to a canonical peptide are not returned. Because the variant proteins
+    /// are digested, variants that remove or create a cleavage site result in the correct
+    /// peptides.
+    pub fn digest_with_variants(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+        variants: &[ProteinVariant],
+    ) -> Vec<TransformedPeptidoform> {
+        let canonical = self.peptide().digest(protease, max_missed_cleavages);
+        let mut result: Vec<TransformedPeptidoform> = Vec::new();
+        for variant in self.variant_proteins(variants) {
+            let Some(crate::transformation::AppliedTransformation::Mutation(mutation)) =
+                variant.transformations.first()
+            else {
+                continue;
+            };
+            for peptide in variant.peptidoform.digest(protease, max_missed_cleavages) {
+                if peptide
+                    .get_protein_position()
+                    .is_some_and(|p| p.range().contains(&mutation.index))
+                    && !canonical.contains(&peptide)
+                    && !result.iter().any(|r| r.peptidoform == peptide)
+                {
+                    result.push(TransformedPeptidoform {
+                        peptidoform: peptide,
+                        transformations: variant.transformations.clone(),
+                    });
+                }
+            }
+        }
+        canonical
+            .into_iter()
+            .map(|peptidoform| TransformedPeptidoform {
+                peptidoform,
+                transformations: Vec::new(),
+            })
+            .chain(result)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{transformation::AppliedTransformation, AminoAcid};
+
+    #[test]
+    fn parse_list() {
+        let variants = ProteinVariant::parse_reader(
+            "accession\tvariant\tidentifier\nP1\tK9R\trs123\nP2\tp.Glu3Gln\t\n".as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].accession, "P1");
+        assert_eq!(variants[0].mutation.to_string(), "K9R");
+        assert_eq!(variants[0].identifier.as_deref(), Some("rs123"));
+        assert_eq!(
+            variants[1].mutation,
+            PointMutation {
+                index: 2,
+                from: AminoAcid::GlutamicAcid,
+                to: AminoAcid::Glutamine
+            }
+        );
+        assert_eq!(variants[1].identifier, None);
+        assert!(ProteinVariant::parse_reader("accession\tvariant\nP1\tK9\n".as_bytes()).is_err());
+        assert!(ProteinVariant::parse_reader("accession\tchange\nP1\tK9R\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn parse_uniprot() {
+        let file = "Description: Human polymorphisms and disease mutations\n\
+            Main        Swiss-Prot  AC         FTId        AA change      Variant       dbSNP         Disease name\n\
+            gene name   \n\
+            ______________________________________________________________________________________\n\
+            A1BG       P04217     VAR_018369  p.His52Arg     LB/B      rs893184    -\n\
+            A1BG       P04217     VAR_018370  p.His395Arg    LB/B      rs2241788   -\n\
+            \n\
+            -----------------------------------------------------------------------\n\
+            Copyrighted by the UniProt Consortium\n";
+        let variants = ProteinVariant::parse_uniprot_reader(file.as_bytes(), None).unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].accession, "P04217");
+        assert_eq!(variants[0].mutation.to_string(), "H52R");
+        assert_eq!(variants[1].identifier.as_deref(), Some("VAR_018370"));
+    }
+
+    #[test]
+    fn parse_vcf() {
+        let file = "##fileformat=VCFv4.2\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+            1\t100\trs1\tA\tG\t.\tPASS\tDP=10;ANN=G|missense_variant|MODERATE|GENE|G1|transcript|T1|protein_coding|1/2|c.26A>G|p.Lys9Arg|26/100|26/90|9/30||,G|missense_variant|MODERATE|GENE|G1|transcript|T2|protein_coding|1/2|c.26A>G|p.Lys9Arg|26/100|26/90|9/30||\n\
+            1\t200\t.\tC\tT\t.\tPASS\tANN=T|synonymous_variant|LOW|GENE|G1|transcript|T1|protein_coding|1/2|c.30C>T|p.Leu10Leu|30/100|30/90|10/30||\n";
+        let variants = ProteinVariant::parse_vcf_reader(file.as_bytes(), None).unwrap();
+        assert_eq!(
+            variants
+                .iter()
+                .map(|v| (v.accession.as_str(), v.mutation.to_string()))
+                .collect::<Vec<_>>(),
+            [("T1", "K9R".to_string()), ("T2", "K9R".to_string())]
+        );
+        assert_eq!(variants[0].identifier.as_deref(), Some("rs1"));
+    }
+
+    #[test]
+    fn variant_peptides() {
+        let fasta = FastaData::parse_reader(
+            ">sp|P1|TEST\nMPEPTIDEKAMPEKTTK\n>sp|P2|OTHER\nPEPK\n".as_bytes(),
+            None,
+        )
+        .unwrap();
+        let variants = vec![
+            ProteinVariant::new("P1", "E3Q".parse().unwrap()),
+            // Removes a cleavage site
+            ProteinVariant::new("P1", "K9Q".parse().unwrap()),
+            // Does not match the sequence
+            ProteinVariant::new("P1", "A3Q".parse().unwrap()),
+            ProteinVariant::new("P2", "E2Q".parse().unwrap()),
+        ];
+        let trypsin: Protease = "trypsin".parse().unwrap();
+        let peptides = fasta[0].digest_with_variants(&trypsin, 0, &variants);
+        let show = |p: &TransformedPeptidoform| {
+            (
+                p.peptidoform.to_string(),
+                p.transformations
+                    .iter()
+                    .map(|t| match t {
+                        AppliedTransformation::Mutation(m) => m.to_string(),
+                        _ => String::new(),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            peptides.iter().map(show).collect::<Vec<_>>(),
+            [
+                ("MPEPTIDEK".to_string(), vec![]),
+                ("AMPEK".to_string(), vec![]),
+                ("TTK".to_string(), vec![]),
+                ("MPQPTIDEK".to_string(), vec!["E3Q".to_string()]),
+                ("MPEPTIDEQAMPEK".to_string(), vec!["K9Q".to_string()]),
+            ]
+        );
+        assert_eq!(
+            peptides[4]
+                .peptidoform
+                .get_protein_position()
+                .unwrap()
+                .range(),
+            0..14
+        );
+    }
+}
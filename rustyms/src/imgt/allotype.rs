@@ -0,0 +1,310 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::{
+    peptidoform::{AtMax, Linear},
+    Peptidoform,
+};
+
+use super::{ChainType, Species};
+
+/// An allotype, a variant of a constant gene that is inherited and so differs between
+/// individuals, for example G1m17 or Km3 in humans. Allotypes are defined by one or more amino
+/// acid differences, see [`AllotypeMarker`]. See [`allotypes`] for all known allotypes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct Allotype {
+    /// The name, eg `G1m17`
+    pub name: &'static str,
+    /// The older letter based name, eg `G1m(z)`
+    pub alternative_name: Option<&'static str>,
+    /// The species
+    pub species: Species,
+    /// The chain
+    pub chain: ChainType,
+    /// The name of the constant gene, eg `IGHG1`
+    pub gene: &'static str,
+    /// The amino acid differences that define this allotype
+    pub markers: &'static [AllotypeMarker],
+}
+
+/// A stretch of amino acids that defines (part of) an allotype. This is stored with some of the
+/// surrounding sequence, so that peptides can be placed on the marker.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct AllotypeMarker {
+    /// The position of the first amino acid of the core in EU numbering (heavy chain) or Kabat
+    /// numbering (light chain)
+    pub position: usize,
+    /// The sequence around the marker
+    pub context: &'static str,
+    /// The amino acids in the context that define the allotype
+    pub core: Range<usize>,
+}
+
+impl AllotypeMarker {
+    /// The amino acids that define the allotype, eg `K` for G1m17
+    pub fn residues(&self) -> &'static str {
+        &self.context[self.core.clone()]
+    }
+
+    /// Check if a peptide covers this marker and if so if it agrees with it. A peptide covers the
+    /// marker if it can be placed on the context with at least [`MIN_ALLOTYPE_OVERLAP`] amino
+    /// acids overlapping, all core amino acids overlapping, and all overlapping amino acids
+    /// outside of the core identical. Then the peptide supports the
+    /// marker (`Some(true)`) if the core is identical as well and contradicts it (`Some(false)`)
+    /// otherwise. I and L are seen as identical.
+    pub fn covered_by<Complexity: AtMax<Linear>>(
+        &self,
+        peptide: &Peptidoform<Complexity>,
+    ) -> Option<bool> {
+        let peptide: Vec<char> = peptide
+            .sequence()
+            .iter()
+            .map(|element| canonical(element.aminoacid.aminoacid().char()))
+            .collect();
+        let context: Vec<char> = self.context.chars().map(canonical).collect();
+        if peptide.is_empty() {
+            return None;
+        }
+        let mut result = None;
+        // At `shift` the last amino acid of the peptide is placed on context position `shift`
+        let last = peptide.len() - 1;
+        for shift in 0..context.len() + last {
+            let start = shift.saturating_sub(last);
+            let end = (shift + 1).min(context.len());
+            if end - start < MIN_ALLOTYPE_OVERLAP || start > self.core.start || end < self.core.end
+            {
+                continue;
+            }
+            let mut core_identical = true;
+            let mut flanks_identical = true;
+            for c in start..end {
+                let identical = context[c] == peptide[c + last - shift];
+                if self.core.contains(&c) {
+                    core_identical &= identical;
+                } else {
+                    flanks_identical &= identical;
+                }
+            }
+            if flanks_identical {
+                result = Some(result.unwrap_or(false) || core_identical);
+            }
+        }
+        result
+    }
+}
+
+/// The minimal number of amino acids a peptide has to overlap with the context of an
+/// [`AllotypeMarker`] to be considered covering the marker.
+pub const MIN_ALLOTYPE_OVERLAP: usize = 5;
+
+/// See I and J as L
+const fn canonical(aminoacid: char) -> char {
+    match aminoacid {
+        'I' | 'J' => 'L',
+        other => other,
+    }
+}
+
+/// The evidence for a single allotype in a set of peptides, see [`allotype_support`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct AllotypeSupport {
+    /// The allotype
+    pub allotype: &'static Allotype,
+    /// For each marker of the allotype the indices of the peptides that support it
+    pub supporting: Vec<Vec<usize>>,
+    /// For each marker of the allotype the indices of the peptides that contradict it
+    pub contradicting: Vec<Vec<usize>>,
+}
+
+impl AllotypeSupport {
+    /// Check if all markers are supported by at least one peptide and none are contradicted.
+    pub fn is_supported(&self) -> bool {
+        self.supporting.iter().all(|s| !s.is_empty())
+            && self.contradicting.iter().all(Vec::is_empty)
+    }
+
+    /// Check if any marker is contradicted by at least one peptide.
+    pub fn is_contradicted(&self) -> bool {
+        self.contradicting.iter().any(|s| !s.is_empty())
+    }
+}
+
+/// Test which allotypes are supported by the given identified peptides. For every known allotype
+/// (see [`allotypes`]) this returns which peptides support and contradict each of its markers.
+/// Note that a heterozygous individual can have support for two allotypes at the same position,
+/// which then also contradict each other.
+pub fn allotype_support<'a, Complexity: AtMax<Linear> + 'a>(
+    peptides: impl IntoIterator<Item = &'a Peptidoform<Complexity>>,
+) -> Vec<AllotypeSupport> {
+    let peptides: Vec<_> = peptides.into_iter().collect();
+    allotypes()
+        .iter()
+        .map(|allotype| {
+            let mut supporting = vec![Vec::new(); allotype.markers.len()];
+            let mut contradicting = vec![Vec::new(); allotype.markers.len()];
+            for (marker_index, marker) in allotype.markers.iter().enumerate() {
+                for (peptide_index, peptide) in peptides.iter().enumerate() {
+                    match marker.covered_by(peptide) {
+                        Some(true) => supporting[marker_index].push(peptide_index),
+                        Some(false) => contradicting[marker_index].push(peptide_index),
+                        None => (),
+                    }
+                }
+            }
+            AllotypeSupport {
+                allotype,
+                supporting,
+                contradicting,
+            }
+        })
+        .collect()
+}
+
+/// All known allotypes.
+pub const fn allotypes() -> &'static [Allotype] {
+    ALLOTYPES
+}
+
+/// Create an allotype marker
+const fn marker(position: usize, context: &'static str, core: Range<usize>) -> AllotypeMarker {
+    AllotypeMarker {
+        position,
+        context,
+        core,
+    }
+}
+
+const ALLOTYPES: &[Allotype] = &[
+    Allotype {
+        name: "G1m17",
+        alternative_name: Some("G1m(z)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::Heavy,
+        gene: "IGHG1",
+        markers: &[marker(214, "PSNTKVDKKVEPKSCDK", 8..9)],
+    },
+    Allotype {
+        name: "G1m3",
+        alternative_name: Some("G1m(f)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::Heavy,
+        gene: "IGHG1",
+        markers: &[marker(214, "PSNTKVDKRVEPKSCDK", 8..9)],
+    },
+    Allotype {
+        name: "G1m1",
+        alternative_name: Some("G1m(a)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::Heavy,
+        gene: "IGHG1",
+        markers: &[marker(356, "YTLPPSRDELTKNQVSL", 7..10)],
+    },
+    Allotype {
+        name: "nG1m1",
+        alternative_name: None,
+        species: Species::HomoSapiens,
+        chain: ChainType::Heavy,
+        gene: "IGHG1",
+        markers: &[marker(356, "YTLPPSREEMTKNQVSL", 7..10)],
+    },
+    Allotype {
+        name: "G1m2",
+        alternative_name: Some("G1m(x)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::Heavy,
+        gene: "IGHG1",
+        markers: &[marker(431, "SCSVMHEGLHNHYTQK", 7..8)],
+    },
+    Allotype {
+        name: "Km1",
+        alternative_name: Some("Inv(1)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::LightKappa,
+        gene: "IGKC",
+        markers: &[
+            marker(153, "KVQWKVDNVLQSGNSQE", 8..9),
+            marker(191, "EKHKVYACELTHQGLSS", 9..10),
+        ],
+    },
+    Allotype {
+        name: "Km1,2",
+        alternative_name: Some("Inv(1,2)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::LightKappa,
+        gene: "IGKC",
+        markers: &[
+            marker(153, "KVQWKVDNALQSGNSQE", 8..9),
+            marker(191, "EKHKVYACELTHQGLSS", 9..10),
+        ],
+    },
+    Allotype {
+        name: "Km3",
+        alternative_name: Some("Inv(3)"),
+        species: Species::HomoSapiens,
+        chain: ChainType::LightKappa,
+        gene: "IGKC",
+        markers: &[
+            marker(153, "KVQWKVDNALQSGNSQE", 8..9),
+            marker(191, "EKHKVYACEVTHQGLSS", 9..10),
+        ],
+    },
+];
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    fn peptide(sequence: &str) -> Peptidoform<Linear> {
+        Peptidoform::pro_forma(sequence, None)
+            .unwrap()
+            .into_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn markers() {
+        let g1m3 = &allotypes()[1].markers[0];
+        assert_eq!(g1m3.residues(), "R");
+        assert_eq!(g1m3.covered_by(&peptide("VDKRVEPK")), Some(true));
+        assert_eq!(g1m3.covered_by(&peptide("VDKKVEPK")), Some(false));
+        assert_eq!(g1m3.covered_by(&peptide("SCDKTHTCPPCPAPELLGG")), None);
+        assert_eq!(g1m3.covered_by(&peptide("NTKVDKR")), Some(true));
+        assert_eq!(
+            g1m3.covered_by(&peptide("RVEPKSCDKTHTCPPCPAPELLGGPSVFLFPPKPK")),
+            Some(true)
+        );
+        assert_eq!(g1m3.covered_by(&peptide("DKR")), None);
+        let g1m1 = &allotypes()[2].markers[0];
+        assert_eq!(g1m1.residues(), "DEL");
+        assert_eq!(g1m1.covered_by(&peptide("EPQVYTLPPSRDEITK")), Some(true));
+        assert_eq!(g1m1.covered_by(&peptide("EPQVYTLPPSREEMTK")), Some(false));
+        assert_eq!(g1m1.covered_by(&peptide("NQVSLTCLVK")), None);
+    }
+
+    #[test]
+    fn support() {
+        let peptides = [
+            peptide("VDKKVEPK"),
+            peptide("EPQVYTLPPSREEMTK"),
+            peptide("VDNALQSGNSQESVTEQDSK"),
+            peptide("VYACEVTHQGLSSPVTK"),
+            peptide("WQQGNVFSCSVMHEALHNHYTQK"),
+        ];
+        let support = allotype_support(&peptides);
+        let supported: Vec<_> = support
+            .iter()
+            .filter(|s| s.is_supported())
+            .map(|s| s.allotype.name)
+            .collect();
+        assert_eq!(supported, vec!["G1m17", "nG1m1", "Km3"]);
+        let contradicted: Vec<_> = support
+            .iter()
+            .filter(|s| s.is_contradicted())
+            .map(|s| s.allotype.name)
+            .collect();
+        assert_eq!(contradicted, vec!["G1m3", "G1m1", "G1m2", "Km1", "Km1,2"]);
+        assert_eq!(support[7].supporting, vec![vec![2], vec![3]]);
+    }
+}
@@ -0,0 +1,358 @@
+use std::io::BufRead;
+
+use crate::{
+    csv::parse_csv_raw,
+    error::{Context, CustomError},
+    peptidoform::{Annotation, Region},
+    AminoAcid, CheckedAminoAcid, Peptidoform, UnAmbiguous,
+};
+
+use super::{AnnotatedSequence, Gene, GeneType, Germline, Germlines, Species};
+
+impl Germlines {
+    /// Parse a germline FASTA file, for example a germline set from [OGRDB](https://ogrdb.airr-community.org/),
+    /// into a germline database that can be used instead of or in addition to the IMGT data with
+    /// [`Selection::germlines_in`](super::Selection::germlines_in) and [`Self::find`].
+    ///
+    /// The header has to start with the allele name in IMGT notation, eg `>IGHV1-2*02`. IMGT
+    /// reference directory headers (`>accession|IGHV1-2*02|Homo sapiens|...`) are supported as well.
+    /// The sequences can be nucleotides or amino acids, see [`Self::add_custom_allele`] for how
+    /// these are handled.
+    /// # Errors
+    /// If the file could not be read, if a name is not a valid IMGT allele name, if a sequence is
+    /// invalid, or if an allele occurs more than once.
+    pub fn from_fasta(reader: impl BufRead, species: Species) -> Result<Self, CustomError> {
+        let mut germlines = Self::new(species);
+        let mut last: Option<(usize, String, String)> = None;
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| {
+                CustomError::error("Could not read germline FASTA file", e, Context::none())
+            })?;
+            if let Some(header) = line.strip_prefix('>') {
+                if let Some((index, header, sequence)) = last.take() {
+                    germlines.add_custom_allele(
+                        fasta_name(&header),
+                        &sequence,
+                        &Context::full_line(index, format!(">{header}")),
+                    )?;
+                }
+                last = Some((line_index, header.to_string(), String::new()));
+            } else if let Some((_, _, sequence)) = &mut last {
+                sequence.push_str(line.trim());
+            } else if !line.trim().is_empty() {
+                return Err(CustomError::error(
+                    "Invalid germline FASTA file",
+                    "A FASTA file should start with a header line ('>')",
+                    Context::full_line(line_index, line),
+                ));
+            }
+        }
+        if let Some((index, header, sequence)) = last {
+            germlines.add_custom_allele(
+                fasta_name(&header),
+                &sequence,
+                &Context::full_line(index, format!(">{header}")),
+            )?;
+        }
+        Ok(germlines)
+    }
+
+    /// Parse a tab separated germline file in the AIRR format into a germline database, see
+    /// [`Self::from_fasta`]. The allele name is taken from the column `label` or `sequence_id`
+    /// and the sequence from the column `sequence_alignment` (IMGT gapped), `coding_sequence`,
+    /// or `sequence`, whichever is present first.
+    /// # Errors
+    /// If the file could not be read, if the columns are missing, if a name is not a valid IMGT
+    /// allele name, if a sequence is invalid, or if an allele occurs more than once.
+    pub fn from_airr_tsv(
+        reader: impl std::io::Read,
+        species: Species,
+    ) -> Result<Self, CustomError> {
+        let mut germlines = Self::new(species);
+        for line in parse_csv_raw(reader, b'\t', None)? {
+            let line = line?;
+            let name = line
+                .index_column("label")
+                .or_else(|_| line.index_column("sequence_id"))?
+                .0;
+            let sequence = line
+                .index_column("sequence_alignment")
+                .or_else(|_| line.index_column("coding_sequence"))
+                .or_else(|_| line.index_column("sequence"))?
+                .0;
+            germlines.add_custom_allele(name, sequence, &line.full_context())?;
+        }
+        Ok(germlines)
+    }
+
+    /// Add a single allele to this database. The name has to be an IMGT allele name, eg
+    /// `IGHV1-2*02`. The sequence can be given as nucleotides or as amino acids, a sequence that
+    /// only contains `ACGTUN` is seen as nucleotides. Nucleotide sequences are translated in the
+    /// first reading frame without stop codons, for J genes the reading frame with the conserved
+    /// `WGxG` or `FGxG` motif is preferred.
+    ///
+    /// Gaps (`.` or `-`) in the sequence are interpreted as IMGT gaps, for nucleotide sequences
+    /// a gap has to span a full codon. For gapped V genes the framework and CDR regions and the
+    /// conserved cysteines and tryptophan are annotated based on the IMGT unique numbering, for
+    /// all other sequences the regions are unknown.
+    /// # Errors
+    /// If the name is not a valid IMGT allele name, if the sequence is invalid, or if the allele
+    /// is already present.
+    pub fn add_custom_allele(
+        &mut self,
+        name: &str,
+        sequence: &str,
+        context: &Context,
+    ) -> Result<(), CustomError> {
+        let (gene, allele) = Gene::from_imgt_name_with_allele(name)
+            .map_err(|e| CustomError::error("Invalid germline name", e, context.clone()))?;
+        if self
+            .find(self.species, gene.clone(), Some(allele))
+            .is_some()
+        {
+            return Err(CustomError::error(
+                "Duplicate germline",
+                format!("The allele {name} is defined more than once"),
+                context.clone(),
+            ));
+        }
+        let error =
+            |long: &str| CustomError::error("Invalid germline sequence", long, context.clone());
+        let sequence = sequence.trim();
+        let gapped = sequence.contains(['.', '-']);
+        let residues = if sequence
+            .chars()
+            .all(|c| "ACGTUN.-".contains(c.to_ascii_uppercase()))
+        {
+            translate(sequence, gapped, gene.kind == GeneType::J).map_err(|e| error(&e))?
+        } else {
+            sequence
+                .chars()
+                .scan(0_u16, |position, c| {
+                    *position += 1;
+                    Some(if matches!(c, '.' | '-') {
+                        None
+                    } else {
+                        Some(
+                            AminoAcid::try_from(c)
+                                .map(|aa| (*position, aa))
+                                .map_err(|()| {
+                                    error(&format!("The character '{c}' is not a valid amino acid"))
+                                }),
+                        )
+                    })
+                })
+                .flatten()
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if residues.is_empty() {
+            return Err(error("The sequence is empty"));
+        }
+        let peptide = residues
+            .iter()
+            .map(|(_, aa)| CheckedAminoAcid::new(*aa).into_unambiguous())
+            .collect::<Option<Peptidoform<UnAmbiguous>>>()
+            .ok_or_else(|| error("The sequence contains ambiguous amino acids"))?;
+        let sequence = if gapped && gene.kind == GeneType::V {
+            imgt_annotated(peptide, &residues)
+        } else {
+            AnnotatedSequence::new(peptide, vec![(Region::None, residues.len())], Vec::new())
+        };
+        self.insert(Germline {
+            name: gene,
+            alleles: vec![(allele, sequence)],
+        });
+        Ok(())
+    }
+}
+
+/// Get the allele name from a FASTA header, handling IMGT reference directory headers
+fn fasta_name(header: &str) -> &str {
+    let name = header.split_ascii_whitespace().next().unwrap_or_default();
+    if name.contains('|') {
+        header.split('|').nth(1).unwrap_or_default().trim()
+    } else {
+        name
+    }
+}
+
+/// Translate a nucleotide sequence, returns the amino acids with their (1 based) codon index.
+/// Gapped sequences are translated in the first reading frame, otherwise the first reading frame
+/// without stop codons is used, or for J genes the first with the `WGxG`/`FGxG` motif if any.
+/// # Errors
+/// If no reading frame can be translated without stop codons or invalid codons.
+fn translate(sequence: &str, gapped: bool, j: bool) -> Result<Vec<(u16, AminoAcid)>, String> {
+    let sequence = sequence.to_ascii_lowercase().replace('u', "t");
+    let frames = if gapped {
+        0..1
+    } else {
+        0..3.min(sequence.len())
+    };
+    let mut translated = Vec::new();
+    let mut last_error = String::from("The sequence is too short");
+    for frame in frames {
+        match translate_frame(&sequence[frame..]) {
+            Ok(residues) => translated.push(residues),
+            Err(e) => last_error = e,
+        }
+    }
+    let motif = |residues: &&Vec<(u16, AminoAcid)>| {
+        residues.windows(4).any(|w| {
+            matches!(w[0].1, AminoAcid::Tryptophan | AminoAcid::Phenylalanine)
+                && w[1].1 == AminoAcid::Glycine
+                && w[3].1 == AminoAcid::Glycine
+        })
+    };
+    let selected = if j {
+        translated.iter().find(motif).or_else(|| translated.first())
+    } else {
+        translated.first()
+    };
+    selected.cloned().ok_or(last_error)
+}
+
+/// Translate a nucleotide sequence in the first reading frame, see [`translate`]
+/// # Errors
+/// If there is a stop codon or an invalid codon.
+fn translate_frame(sequence: &str) -> Result<Vec<(u16, AminoAcid)>, String> {
+    let codons = sequence.as_bytes().chunks_exact(3);
+    let mut residues = Vec::with_capacity(codons.len());
+    for (index, codon) in codons.enumerate() {
+        let position = u16::try_from(index + 1).unwrap_or(u16::MAX);
+        let codon = std::str::from_utf8(codon).unwrap_or_default();
+        if codon.chars().all(|c| matches!(c, '.' | '-')) {
+            continue;
+        }
+        match AminoAcid::from_dna(codon) {
+            Ok(Some(aa)) => residues.push((position, aa)),
+            Ok(None) => return Err(format!("Stop codon at codon {position}")),
+            Err(_) => return Err(format!("The codon '{codon}' is not a valid codon")),
+        }
+    }
+    Ok(residues)
+}
+
+/// Annotate the regions and conserved residues for a V gene from the IMGT position of every residue
+fn imgt_annotated(
+    peptide: Peptidoform<UnAmbiguous>,
+    residues: &[(u16, AminoAcid)],
+) -> AnnotatedSequence {
+    let mut regions: Vec<(Region, usize)> = Vec::new();
+    let mut annotations = Vec::new();
+    for (index, (position, _)) in residues.iter().enumerate() {
+        let region = match position {
+            1..=26 => Region::Framework(1),
+            27..=38 => Region::ComplementarityDeterminingRegion(1),
+            39..=55 => Region::Framework(2),
+            56..=65 => Region::ComplementarityDeterminingRegion(2),
+            66..=104 => Region::Framework(3),
+            _ => Region::ComplementarityDeterminingRegion(3),
+        };
+        match regions.last_mut() {
+            Some((last, length)) if *last == region => *length += 1,
+            _ => regions.push((region, 1)),
+        }
+        if matches!(position, 23 | 41 | 104) {
+            annotations.push((Annotation::Conserved, index));
+        }
+    }
+    AnnotatedSequence::new(peptide, regions, annotations)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        imgt::{ChainType, Selection},
+        peptidoform::AnnotatedPeptide,
+    };
+
+    const FASTA: &str = ">IGHV1-2*02
+caggtgcagctggtgcagtctggggct...gaggtgaagaagcctggggcctcagtgaaggtctcctgcaaggcttctggatacaccttc............accggctactatatgcactgggtgcgacaggcccctggacaagggcttgagtggatgggatggatcaaccctaac......agtggtggcacaaactatgcacagaagtttcag...ggcagggtcaccatgaccagggacacgtccatcagcacagcctacatggagctgagcaggctgagatctgacgacacggccgtgtattactgtgcgagaga
+>X62106|IGHJ4*02|Homo sapiens|F|J-REGION
+ACTACTTTGACTACTGGGGCCAGGGAACCCTGGTCACCGTCTCCTCAG
+";
+
+    #[test]
+    fn fasta() {
+        let germlines = Germlines::from_fasta(FASTA.as_bytes(), Species::HomoSapiens).unwrap();
+        let v = germlines
+            .find(
+                Species::HomoSapiens,
+                Gene::from_imgt_name("IGHV1-2").unwrap(),
+                Some(2),
+            )
+            .unwrap();
+        assert_eq!(
+            v.sequence.to_string(),
+            "QVQLVQSGAEVKKPGASVKVSCKASGYTFTGYYMHWVRQAPGQGLEWMGWINPNSGGTNYAQKFQGRVTMTRDTSISTAYMELSRLRSDDTAVYYCAR"
+        );
+        assert_eq!(
+            v.regions(),
+            [
+                (Region::Framework(1), 25),
+                (Region::ComplementarityDeterminingRegion(1), 8),
+                (Region::Framework(2), 17),
+                (Region::ComplementarityDeterminingRegion(2), 8),
+                (Region::Framework(3), 38),
+                (Region::ComplementarityDeterminingRegion(3), 2),
+            ]
+        );
+        assert_eq!(
+            v.annotations(),
+            [
+                (Annotation::Conserved, 21),
+                (Annotation::Conserved, 35),
+                (Annotation::Conserved, 95)
+            ]
+        );
+        let j = germlines
+            .find(
+                Species::HomoSapiens,
+                Gene::from_imgt_name("IGHJ4").unwrap(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(j.sequence.to_string(), "YFDYWGQGTLVTVSS");
+
+        let custom = [germlines];
+        let selected: Vec<_> =
+            Selection::<std::hash::RandomState, std::hash::RandomState>::default()
+                .chain(HashSet::from([ChainType::Heavy]))
+                .germlines_in(crate::imgt::imgt_germlines().chain(&custom))
+                .filter(|a| a.gene.to_string() == "IGHJ4")
+                .map(|a| a.species)
+                .collect();
+        assert!(selected.len() > 1);
+        assert!(selected.contains(&Species::HomoSapiens));
+    }
+
+    #[test]
+    fn errors() {
+        let parse = |text: &str| Germlines::from_fasta(text.as_bytes(), Species::HomoSapiens);
+        assert!(parse(">IGHJ4*02\nACTACTTTGACTAC\n>IGHJ4*02\nYFDYWGQGTLVTVSS").is_err());
+        assert!(parse(">NOTAGENE\nYFDYWGQGTLVTVSS").is_err());
+        assert!(parse(">IGHJ4*02\nYFDYWGQGTLVTV+SS").is_err());
+        assert!(parse(">IGHJ4*02\nYFDYWGQGTLVTVSBS").is_err());
+        assert!(parse("YFDYWGQGTLVTVSS").is_err());
+    }
+
+    #[test]
+    fn airr_tsv() {
+        let germlines = Germlines::from_airr_tsv(
+            "sequence_id\tsequence\nIGHJ4*02\tYFDYWGQGTLVTVSS\nIGHJ6*01\tYYYYYGMDVWGQGTTVTVSS\n"
+                .as_bytes(),
+            Species::HomoSapiens,
+        )
+        .unwrap();
+        let selected: Vec<_> =
+            Selection::<std::hash::RandomState, std::hash::RandomState>::default()
+                .germlines_in([&germlines])
+                .map(|a| a.name())
+                .collect();
+        assert_eq!(selected, ["IGHJ4*02", "IGHJ6*01"]);
+    }
+}
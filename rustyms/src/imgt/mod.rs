@@ -1,6 +1,8 @@
 //! This crate handles parsing the [IMGT LIGM-DB database](https://www.imgt.org/) into structures compatible with rustyms.
 //! It additionally stores all regions and annotations. There are two main ways of selecting germline(s), specified by name
 //! [`get_germline`](crate::imgt::get_germline) or by building a query over the data [`Selection`](crate::imgt::Selection).
+//! Additional germlines, for example from OGRDB, can be loaded at runtime with [`Germlines::from_fasta`](crate::imgt::Germlines::from_fasta)
+//! and queried with [`Selection::germlines_in`](crate::imgt::Selection::germlines_in).
 //!
 //! <details><summary>Data present per species</summary>
 //!
@@ -18,17 +20,22 @@
 //! assert_eq!(first.name(), "IGHV1-2*01");
 //! ```
 
+mod allotype;
+mod custom;
 mod fancy;
 #[rustfmt::skip]
 mod germlines;
+mod numbering;
 mod select;
 mod shared;
 
+pub use allotype::*;
 pub use fancy::*;
 #[cfg(feature = "rayon")]
 use germlines::par_germlines;
 use germlines::{all_germlines, germlines};
 
+pub use numbering::*;
 pub use select::*;
 #[allow(unused_imports)]
 pub use shared::*;
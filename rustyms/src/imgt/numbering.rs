@@ -0,0 +1,317 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+use crate::peptidoform::{AnnotatedPeptide, Region};
+
+use super::Allele;
+
+/// A position in the [IMGT unique numbering](https://www.imgt.org/IMGTScientificChart/Numbering/IMGTIGVLsuperfamily.html)
+/// for V domains, eg `32` or `111.1`. The framework and CDR regions each have a fixed range of
+/// positions: FR1 1-26, CDR1 27-38, FR2 39-55, CDR2 56-65, FR3 66-104, CDR3 105-117, and FR4
+/// 118-128.
+///
+/// Shorter CDRs have gaps at the centre of the loop (the extra residue of an odd length is placed
+/// in the first half), longer CDRs get insertions at the centre, eg `111.1, 111.2, 112.2, 112.1`
+/// for CDR3. Shorter frameworks have gaps on the known gap positions (10 for FR1, 73, 81, and 82
+/// for FR3), if more residues are missing the framework is assumed to be truncated at the outer
+/// edge of the domain. Longer frameworks get insertions at their last position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ImgtPosition {
+    /// The position number
+    pub number: u16,
+    /// The insertion number, 0 if this is not an insertion
+    pub insertion: u16,
+}
+
+impl ImgtPosition {
+    /// A position that is not an insertion
+    pub const fn new(number: u16) -> Self {
+        Self {
+            number,
+            insertion: 0,
+        }
+    }
+
+    /// An insertion on the given position
+    pub const fn insertion(number: u16, insertion: u16) -> Self {
+        Self { number, insertion }
+    }
+}
+
+impl Display for ImgtPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.insertion == 0 {
+            write!(f, "{}", self.number)
+        } else {
+            write!(f, "{}.{}", self.number, self.insertion)
+        }
+    }
+}
+
+/// Where a region is located in a sequence, to determine how partial regions are numbered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Location {
+    First,
+    Middle,
+    Last,
+}
+
+/// Number a sequence with the given regions (as given by [`AnnotatedPeptide::regions`]) in IMGT
+/// unique numbering, see [`ImgtPosition`]. This returns one position for every residue, residues
+/// outside of the V domain regions get `None`. A CDR3 at the start of the sequence is numbered
+/// from its end (the J part of CDR3) and a CDR3 at the end of the sequence is numbered from its
+/// start (the V part of CDR3). If the whole sequence is a single CDR3 (a D gene) it cannot be
+/// numbered on its own.
+pub fn imgt_numbering(regions: &[(Region, usize)]) -> Vec<Option<ImgtPosition>> {
+    let mut numbering = Vec::with_capacity(regions.iter().map(|(_, l)| l).sum());
+    for (index, (region, length)) in regions.iter().enumerate() {
+        let location = if regions.len() == 1 {
+            None
+        } else if index == 0 {
+            Some(Location::First)
+        } else if index == regions.len() - 1 {
+            Some(Location::Last)
+        } else {
+            Some(Location::Middle)
+        };
+        match location.and_then(|location| number_region(region, *length, location)) {
+            Some(positions) => numbering.extend(positions.into_iter().map(Some)),
+            None => numbering.extend(std::iter::repeat(None).take(*length)),
+        }
+    }
+    numbering
+}
+
+/// Number a single region, returns `None` if this region is not part of the V domain
+fn number_region(region: &Region, length: usize, location: Location) -> Option<Vec<ImgtPosition>> {
+    let (range, gaps): (RangeInclusive<u16>, &[u16]) = match region {
+        Region::Framework(1) => (1..=26, &[10]),
+        Region::ComplementarityDeterminingRegion(1) => (27..=38, &[]),
+        Region::Framework(2) => (39..=55, &[]),
+        Region::ComplementarityDeterminingRegion(2) => (56..=65, &[]),
+        Region::Framework(3) => (66..=104, &[73, 81, 82]),
+        Region::ComplementarityDeterminingRegion(3) => (105..=117, &[]),
+        Region::Framework(4) => (118..=128, &[]),
+        _ => return None,
+    };
+    let positions: Vec<u16> = range.collect();
+    let standard = positions.len();
+    Some(match region {
+        Region::ComplementarityDeterminingRegion(3)
+            if location == Location::First && length <= standard =>
+        {
+            positions[standard - length..]
+                .iter()
+                .map(|n| ImgtPosition::new(*n))
+                .collect()
+        }
+        Region::ComplementarityDeterminingRegion(3)
+            if location == Location::Last && length <= standard =>
+        {
+            positions[..length]
+                .iter()
+                .map(|n| ImgtPosition::new(*n))
+                .collect()
+        }
+        Region::ComplementarityDeterminingRegion(_) => number_loop(&positions, length),
+        _ => number_framework(&positions, gaps, length, location),
+    })
+}
+
+/// Number a CDR, with gaps or insertions at the centre of the loop
+fn number_loop(positions: &[u16], length: usize) -> Vec<ImgtPosition> {
+    let half = positions.len().div_ceil(2);
+    if length <= positions.len() {
+        let first = length.div_ceil(2);
+        positions[..first]
+            .iter()
+            .chain(&positions[positions.len() - (length - first)..])
+            .map(|n| ImgtPosition::new(*n))
+            .collect()
+    } else {
+        let extra = length - positions.len();
+        let (before, after) = (positions[half - 1], positions[half]);
+        positions[..half]
+            .iter()
+            .map(|n| ImgtPosition::new(*n))
+            .chain((1..=extra.div_ceil(2)).map(|i| ImgtPosition::insertion(before, i as u16)))
+            .chain(
+                (1..=extra / 2)
+                    .rev()
+                    .map(|i| ImgtPosition::insertion(after, i as u16)),
+            )
+            .chain(positions[half..].iter().map(|n| ImgtPosition::new(*n)))
+            .collect()
+    }
+}
+
+/// Number a framework, with gaps on the known positions
+fn number_framework(
+    positions: &[u16],
+    gaps: &[u16],
+    length: usize,
+    location: Location,
+) -> Vec<ImgtPosition> {
+    let standard = positions.len();
+    if length >= standard {
+        positions
+            .iter()
+            .map(|n| ImgtPosition::new(*n))
+            .chain(
+                (1..=length - standard)
+                    .map(|i| ImgtPosition::insertion(*positions.last().unwrap_or(&0), i as u16)),
+            )
+            .collect()
+    } else if standard - length <= gaps.len() {
+        let gaps = &gaps[..standard - length];
+        positions
+            .iter()
+            .filter(|n| !gaps.contains(n))
+            .map(|n| ImgtPosition::new(*n))
+            .collect()
+    } else if location == Location::First {
+        positions[standard - length..]
+            .iter()
+            .map(|n| ImgtPosition::new(*n))
+            .collect()
+    } else {
+        positions[..length]
+            .iter()
+            .map(|n| ImgtPosition::new(*n))
+            .collect()
+    }
+}
+
+impl Allele<'_> {
+    /// Get the IMGT unique numbering for all residues in this allele, see [`imgt_numbering`].
+    pub fn imgt_numbering(&self) -> Vec<Option<ImgtPosition>> {
+        imgt_numbering(self.regions())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::imgt::{get_germline, Gene, Species};
+
+    fn numbering(name: &str) -> Vec<String> {
+        let (gene, allele) = Gene::from_imgt_name_with_allele(name).unwrap();
+        get_germline(Species::HomoSapiens, gene, Some(allele))
+            .unwrap()
+            .imgt_numbering()
+            .iter()
+            .map(|p| p.map_or("-".to_string(), |p| p.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn germlines() {
+        let heavy = numbering("IGHV3-23*01");
+        assert_eq!(heavy.len(), 98);
+        assert_eq!(heavy[8..11], ["9", "11", "12"]);
+        assert_eq!(
+            heavy[25..33],
+            ["27", "28", "29", "30", "35", "36", "37", "38"]
+        );
+        assert_eq!(heavy[33], "39");
+        assert_eq!(
+            heavy[50..58],
+            ["56", "57", "58", "59", "62", "63", "64", "65"]
+        );
+        assert_eq!(heavy[58..64], ["66", "67", "68", "69", "70", "71"]);
+        assert_eq!(heavy[64], "72");
+        assert_eq!(heavy[65], "74");
+        assert_eq!(heavy[94..], ["103", "104", "105", "106"]);
+
+        let kappa = numbering("IGKV1-5*01");
+        assert_eq!(kappa[..26].last().unwrap(), "26");
+        assert_eq!(kappa[26..32], ["27", "28", "29", "36", "37", "38"]);
+        assert_eq!(kappa[49..52], ["56", "57", "65"]);
+
+        let j = numbering("IGHJ4*01");
+        assert_eq!(
+            j,
+            [
+                "113", "114", "115", "116", "117", "118", "119", "120", "121", "122", "123", "124",
+                "125", "126", "127", "128"
+            ]
+        );
+        assert_eq!(numbering("IGKJ1*01").last().unwrap(), "127");
+    }
+
+    #[test]
+    fn loops() {
+        let cdr3 = |length| {
+            number_loop(&(105..=117).collect::<Vec<_>>(), length)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(cdr3(13).len(), 13);
+        assert_eq!(
+            cdr3(12),
+            ["105", "106", "107", "108", "109", "110", "112", "113", "114", "115", "116", "117"]
+        );
+        assert_eq!(cdr3(5), ["105", "106", "107", "116", "117"]);
+        assert_eq!(cdr3(16)[6..11], ["111", "111.1", "111.2", "112.1", "112"]);
+        assert_eq!(
+            imgt_numbering(&[(Region::ComplementarityDeterminingRegion(3), 4)]),
+            vec![None; 4]
+        );
+    }
+
+    #[cfg(feature = "align")]
+    #[test]
+    fn consecutive() {
+        use crate::{
+            align::{consecutive_align, AlignScoring, AlignType},
+            imgt::{AlleleSelection, ChainType, GeneType},
+            peptidoform::SimpleLinear,
+            Peptidoform,
+        };
+        use std::collections::HashSet;
+
+        let query = Peptidoform::pro_forma(
+            "EVQLLESGGGLVQPGGSLRLSCAASGFTFSSYAMSWVRQAPGKGLEWVSAISGSGGSTYYADSVKGRFTISRDNSKNTLYLQMNSLRAEDTAVYYCAKDRGYSSGWYFDYWGQGTLVTVSS",
+            None,
+        )
+        .unwrap()
+        .into_simple_linear()
+        .unwrap();
+        let alignment = consecutive_align::<4, SimpleLinear>(
+            &query,
+            &[
+                (GeneType::V, AlignType::GLOBAL_LEFT),
+                (GeneType::J, AlignType::GLOBAL_RIGHT),
+            ],
+            Some(HashSet::<_, std::hash::RandomState>::from([
+                Species::HomoSapiens,
+            ])),
+            Some(HashSet::<_, std::hash::RandomState>::from([
+                ChainType::Heavy,
+            ])),
+            AlleleSelection::First,
+            AlignScoring::default(),
+            1,
+        );
+        let regions = alignment.numbered_regions();
+        assert_eq!(regions.len(), 7);
+        let (cdr3, region, positions) = &regions[5];
+        assert_eq!(*region, Region::ComplementarityDeterminingRegion(3));
+        assert_eq!(cdr3.to_string(), "AKDRGYSSGWYFDY");
+        assert_eq!(
+            positions
+                .iter()
+                .map(|p| p.unwrap().to_string())
+                .collect::<Vec<_>>(),
+            [
+                "105", "106", "107", "108", "109", "110", "111", "111.1", "112", "113", "114",
+                "115", "116", "117"
+            ]
+        );
+        assert_eq!(regions[0].0.to_string(), "EVQLLESGGGLVQPGGSLRLSCAAS");
+        assert_eq!(regions[6].2.last(), Some(&Some(ImgtPosition::new(128))));
+    }
+}
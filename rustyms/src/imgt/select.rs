@@ -17,6 +17,17 @@ pub fn get_germline(
     super::germlines(species).and_then(|g| g.find(species, gene, allele))
 }
 
+/// All embedded IMGT germline databases, to combine with custom germlines in [`Selection::germlines_in`].
+pub fn imgt_germlines<'a>() -> impl Iterator<Item = &'a Germlines> {
+    super::all_germlines().map(|g| g as &'a Germlines)
+}
+
+#[cfg(feature = "rayon")]
+/// All embedded IMGT germline databases in parallel fashion, see [`imgt_germlines`]. Only available with feature `rayon`.
+pub fn par_imgt_germlines<'a>() -> impl ParallelIterator<Item = &'a Germlines> {
+    super::par_germlines().map(|g| g as &'a Germlines)
+}
+
 /// The selection rules for iterating over a selection of germlines.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Selection<S1: std::hash::BuildHasher, S2: std::hash::BuildHasher> {
@@ -72,7 +83,17 @@ impl<
 {
     /// Get the selected alleles
     pub fn germlines(self) -> impl Iterator<Item = Allele<'static>> {
-        super::all_germlines()
+        self.germlines_in(super::all_germlines())
+    }
+
+    /// Get the selected alleles from the given germline databases, for example custom germlines
+    /// loaded with [`Germlines::from_fasta`], optionally combined with [`imgt_germlines`].
+    pub fn germlines_in<'a>(
+        self,
+        databases: impl IntoIterator<Item = &'a Germlines>,
+    ) -> impl Iterator<Item = Allele<'a>> {
+        databases
+            .into_iter()
             .filter(move |g| {
                 self.species
                     .as_ref()
@@ -97,9 +118,22 @@ impl<
     }
 
     #[cfg(feature = "rayon")]
-    /// Get the selected alleles in parallel fashion, only available if you enable the feature "rayon" (on by default)
+    /// Get the selected alleles in parallel fashion, only available if you enable the feature "rayon" (on by default).
+    /// Collecting the iterator (for example with `collect::<Vec<_>>()`) gives the alleles in the same order as [`Self::germlines`],
+    /// but note that methods like `for_each` do not process the alleles in order.
     pub fn par_germlines(self) -> impl ParallelIterator<Item = Allele<'static>> {
-        super::par_germlines()
+        self.par_germlines_in(super::par_germlines())
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Get the selected alleles from the given germline databases in parallel fashion, see
+    /// [`Self::germlines_in`] and [`Self::par_germlines`]. Only available with feature `rayon`.
+    pub fn par_germlines_in<'a>(
+        self,
+        databases: impl IntoParallelIterator<Item = &'a Germlines>,
+    ) -> impl ParallelIterator<Item = Allele<'a>> {
+        databases
+            .into_par_iter()
             .filter(move |g| {
                 self.species
                     .as_ref()
@@ -124,6 +158,35 @@ impl<
             })
             .map(Into::into)
     }
+
+    /// Write the selected alleles as a FASTA file, so that an external search engine can use the
+    /// exact same germlines. Every allele gets a header like `>IGHV1-2*01 OS=Homo sapiens
+    /// GN=IGHV1-2` followed by the sequence in lines of 60 amino acids. Note that the identifier
+    /// (the allele name) is only unique if a single species is selected.
+    /// # Errors
+    /// If writing to the writer failed.
+    pub fn write_fasta(self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        for allele in self.germlines() {
+            writeln!(
+                writer,
+                ">{} OS={} GN={}",
+                allele.name(),
+                allele.species.scientific_name(),
+                allele.gene
+            )?;
+            let sequence: Vec<u8> = allele
+                .sequence
+                .sequence()
+                .iter()
+                .map(|element| element.aminoacid.aminoacid().char() as u8)
+                .collect();
+            for line in sequence.chunks(60) {
+                writer.write_all(line)?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 fn contains_gene(s: &HashSet<GeneType>, gene: GeneType) -> bool {
@@ -287,6 +350,45 @@ mod tests {
         assert_eq!(first.name(), "IGHGP*01");
     }
 
+    #[cfg(feature = "identification")]
+    #[test]
+    fn fasta() {
+        let selection = Selection::default()
+            .species([Species::HomoSapiens])
+            .chain([ChainType::Heavy])
+            .gene([GeneType::J]);
+        let mut fasta = Vec::new();
+        selection.clone().write_fasta(&mut fasta).unwrap();
+        let parsed =
+            crate::identification::FastaData::parse_reader(fasta.as_slice(), None).unwrap();
+        let germlines: Vec<_> = selection.germlines().collect();
+        assert_eq!(parsed.len(), germlines.len());
+        for (fasta, germline) in parsed.iter().zip(&germlines) {
+            assert_eq!(fasta.identifier().accession(), germline.name());
+            assert_eq!(
+                fasta.tags().collect::<Vec<_>>(),
+                [
+                    ("OS", "Homo sapiens"),
+                    ("GN", germline.gene.to_string().as_str())
+                ]
+            );
+            assert_eq!(fasta.peptide().to_string(), germline.sequence.to_string());
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_order() {
+        use rayon::iter::ParallelIterator;
+        let selection = Selection::default()
+            .species([Species::HomoSapiens])
+            .chain([ChainType::Heavy]);
+        let sequential: Vec<_> = selection.clone().germlines().map(|a| a.name()).collect();
+        let parallel: Vec<_> = selection.par_germlines().map(|a| a.name()).collect();
+        assert!(!sequential.is_empty());
+        assert_eq!(sequential, parallel);
+    }
+
     #[test]
     fn gene_selections() {
         let constant = HashSet::from([GeneType::C(None)]);
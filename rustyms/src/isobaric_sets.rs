@@ -8,7 +8,8 @@ use crate::{
     peptidoform::SimpleLinear,
     placement_rule::{PlacementRule, Position},
     system::{fraction, Mass, Ratio},
-    AminoAcid, Chemical, Peptidoform, SemiAmbiguous, SequenceElement, SequencePosition, Tolerance,
+    AminoAcid, Chemical, IsotopeLabeling, Peptidoform, SemiAmbiguous, SequenceElement,
+    SequencePosition, Tolerance,
 };
 
 /// A list of building blocks for a sequence defined by its sequence elements and its mass.
@@ -18,12 +19,14 @@ pub type TerminalBuildingBlocks = Vec<(SequenceElement<SemiAmbiguous>, SimpleMod
 /// Get the possible building blocks for sequences based on the given modifications.
 /// Useful for any automated sequence generation, like isobaric set generation or de novo sequencing.
 /// The result is for each location (N term, center, C term) the list of all possible building blocks with its mass, sorted on mass.
+/// The masses of the building blocks have the given isotope labeling applied.
 /// # Panics
 /// Panics if any of the modifications does not have a defined mass.
 pub fn building_blocks(
     amino_acids: &[AminoAcid],
     fixed: &[(SimpleModification, Option<PlacementRule>)],
     variable: &[(SimpleModification, Option<PlacementRule>)],
+    labeling: IsotopeLabeling,
 ) -> (
     TerminalBuildingBlocks,
     BuildingBlocks,
@@ -78,6 +81,7 @@ pub fn building_blocks(
         position: &impl Fn(&PlacementRule) -> Vec<AminoAcid>,
         fixed: &[(SimpleModification, Option<PlacementRule>)],
         variable: &[(SimpleModification, Option<PlacementRule>)],
+        labeling: IsotopeLabeling,
     ) -> TerminalBuildingBlocks {
         let mut options = fixed
             .iter()
@@ -131,7 +135,7 @@ pub fn building_blocks(
                 )
                 .0
                 .iter()
-                .map(|f| f.monoisotopic_mass() + m.formula().monoisotopic_mass())
+                .map(|f| labeling.apply(f + m.formula()).monoisotopic_mass())
                 .map(|mass| (a.clone(), mc.clone(), mass))
                 .collect_vec()
             })
@@ -201,7 +205,7 @@ pub fn building_blocks(
                 s.formulas_all(&[], &[], &mut Vec::new(), false, position, 0)
                     .0
                     .iter()
-                    .map(|f| (s.clone(), f.monoisotopic_mass()))
+                    .map(|f| (s.clone(), labeling.apply(f.clone()).monoisotopic_mass()))
                     .collect_vec()
             })
             .collect();
@@ -211,16 +215,28 @@ pub fn building_blocks(
 
     // Create the building blocks
     (
-        generate_terminal(&|rule| n_term_options(amino_acids, rule), fixed, variable),
+        generate_terminal(
+            &|rule| n_term_options(amino_acids, rule),
+            fixed,
+            variable,
+            labeling,
+        ),
         generate(SequencePosition::Index(0)),
-        generate_terminal(&|rule| c_term_options(amino_acids, rule), fixed, variable),
+        generate_terminal(
+            &|rule| c_term_options(amino_acids, rule),
+            fixed,
+            variable,
+            labeling,
+        ),
     )
 }
 
 /// Find the isobaric sets for the given mass with the given modifications and ppm error.
 /// The modifications are placed on any location they are allowed based on the given placement
 /// rules, so using any modifications which provide those is advised. If the provided [`LinearPeptide`]
-/// has multiple formulas, it uses the formula with the lowest monoisotopic mass.
+/// has multiple formulas, it uses the formula with the lowest monoisotopic mass. The isotope
+/// labeling is applied to all building blocks and the base, so the mass should be the mass of the
+/// fully labeled peptide.
 /// # Panics
 /// Panics if any of the modifications does not have a defined mass. Or if the weight of the
 /// base selection is already in the tolerance of the given mass.
@@ -231,6 +247,7 @@ pub fn find_isobaric_sets(
     fixed: &[(SimpleModification, Option<PlacementRule>)],
     variable: &[(SimpleModification, Option<PlacementRule>)],
     base: Option<&Peptidoform<SimpleLinear>>,
+    labeling: IsotopeLabeling,
 ) -> IsobaricSetIterator {
     let bounds = tolerance.bounds(mass);
     let base_mass = base
@@ -238,12 +255,12 @@ pub fn find_isobaric_sets(
             b.formulas()
                 .mass_bounds()
                 .into_option()
-                .map(|(f, _)| f.monoisotopic_mass())
+                .map(|(f, _)| labeling.apply(f.clone()).monoisotopic_mass())
         })
         .unwrap_or_default();
     let bounds = (bounds.0 - base_mass, bounds.1 - base_mass);
     assert!(bounds.0.value > 0.0, "Cannot have a base selection that has a weight within the tolerance of the intended final mass for isobaric search.");
-    let (n_term, center, c_term) = building_blocks(amino_acids, fixed, variable);
+    let (n_term, center, c_term) = building_blocks(amino_acids, fixed, variable, labeling);
 
     IsobaricSetIterator::new(n_term, c_term, center, bounds, base)
 }
@@ -515,6 +532,7 @@ mod tests {
             &[],
             &[],
             None,
+            IsotopeLabeling::None,
         )
         .collect();
         assert_eq!(
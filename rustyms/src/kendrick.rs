@@ -0,0 +1,140 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{system::Mass, MassMode, MolecularFormula};
+
+/// A Kendrick mass scale, which rescales masses so that the base unit (for example CH2 or `HexNAc`)
+/// has an integer mass. Molecules that only differ in the number of base units then have the same
+/// Kendrick mass defect, which makes it possible to detect homologous series.
+/// ```rust
+/// # use rustyms::{*, system::da};
+/// let scale = KendrickScale::new(molecular_formula!(C 1 H 2), MassMode::Monoisotopic);
+/// let a = molecular_formula!(C 10 H 22).monoisotopic_mass();
+/// let b = molecular_formula!(C 12 H 26).monoisotopic_mass();
+/// assert!((scale.mass_defect(a) - scale.mass_defect(b)).abs() < 1e-6);
+/// assert_eq!(scale.series(&[a, da(150.0), b], 0.001), vec![vec![0, 2]]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KendrickScale {
+    /// The base unit
+    base: MolecularFormula,
+    /// The mass mode used for the base unit
+    mode: MassMode,
+    /// The factor to convert masses to Kendrick masses (nominal mass / mass of the base)
+    factor: f64,
+}
+
+impl KendrickScale {
+    /// Create a new Kendrick scale based on the given base unit. Masses given to this scale should
+    /// be in the same mass mode.
+    pub fn new(base: MolecularFormula, mode: MassMode) -> Self {
+        let mass = base.mass(mode).value;
+        let factor = if mass == 0.0 {
+            1.0
+        } else {
+            mass.round() / mass
+        };
+        Self { base, mode, factor }
+    }
+
+    /// The classic Kendrick scale based on CH2
+    pub fn methylene() -> Self {
+        Self::new(molecular_formula!(C 1 H 2), MassMode::Monoisotopic)
+    }
+
+    /// The base unit of this scale
+    pub const fn base(&self) -> &MolecularFormula {
+        &self.base
+    }
+
+    /// The mass mode of this scale
+    pub const fn mode(&self) -> MassMode {
+        self.mode
+    }
+
+    /// The Kendrick mass for the given mass
+    pub fn mass(&self, mass: Mass) -> f64 {
+        mass.value * self.factor
+    }
+
+    /// The Kendrick mass defect for the given mass, defined as the nominal Kendrick mass minus the
+    /// Kendrick mass
+    pub fn mass_defect(&self, mass: Mass) -> f64 {
+        let kendrick = self.mass(mass);
+        kendrick.round() - kendrick
+    }
+
+    /// Find all series of masses that differ by (multiples of) the base unit, meaning that these
+    /// have the same Kendrick mass defect within the given tolerance. It returns the indices of
+    /// all masses in each series of at least two masses, each series sorted on mass.
+    pub fn series(&self, masses: &[Mass], tolerance: f64) -> Vec<Vec<usize>> {
+        let defects = masses.iter().map(|m| self.mass_defect(*m)).collect_vec();
+        let sorted = (0..masses.len())
+            .sorted_by(|a, b| defects[*a].total_cmp(&defects[*b]))
+            .collect_vec();
+        let mut series = Vec::new();
+        let mut current: Vec<usize> = Vec::new();
+        for index in sorted {
+            if current
+                .last()
+                .is_some_and(|last| defects[index] - defects[*last] > tolerance)
+            {
+                series.push(std::mem::take(&mut current));
+            }
+            current.push(index);
+        }
+        series.push(current);
+        series
+            .into_iter()
+            .filter(|s| s.len() > 1)
+            .map(|s| {
+                s.into_iter()
+                    .sorted_by(|a, b| masses[*a].value.total_cmp(&masses[*b].value))
+                    .collect()
+            })
+            .sorted_by(|a: &Vec<usize>, b: &Vec<usize>| {
+                masses[a[0]].value.total_cmp(&masses[b[0]].value)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        glycan::{BaseSugar, GlycanSubstituent, MonoSaccharide},
+        system::da,
+        Chemical,
+    };
+
+    #[test]
+    fn methylene() {
+        let scale = KendrickScale::methylene();
+        assert!((scale.mass(da(14.01565)) - 14.0).abs() < 1e-5);
+        // Alkanes and fatty acids
+        let masses = (10..15)
+            .map(|n| {
+                (molecular_formula!(C 1 H 2) * n + molecular_formula!(H 2)).monoisotopic_mass()
+            })
+            .chain((8..11).map(|n| {
+                (molecular_formula!(C 1 H 2) * n + molecular_formula!(O 2)).monoisotopic_mass()
+            }))
+            .collect_vec();
+        let series = scale.series(&masses, 0.001);
+        assert_eq!(series, vec![vec![0, 1, 2, 3, 4], vec![5, 6, 7]]);
+    }
+
+    #[test]
+    fn hexnac() {
+        let hexnac =
+            MonoSaccharide::new(BaseSugar::Hexose(None), &[GlycanSubstituent::NAcetyl]).formula();
+        let scale = KendrickScale::new(hexnac.clone(), MassMode::Monoisotopic);
+        assert_eq!(scale.base(), &hexnac);
+        let peptide = molecular_formula!(C 30 H 50 N 8 O 12).monoisotopic_mass();
+        let glycopeptide = peptide + hexnac.monoisotopic_mass() * 3.0;
+        assert!((scale.mass_defect(peptide) - scale.mass_defect(glycopeptide)).abs() < 1e-6);
+        assert!((scale.mass(glycopeptide) - scale.mass(peptide) - 3.0 * 203.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,176 @@
+use std::num::NonZeroU16;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Element, MolecularFormula};
+
+/// A preset for full stable isotope labeling, as used for metabolically labeled organisms. This
+/// is equivalent to the ProForma global isotope modifications (for example `<15N>`), but can be
+/// used where peptidoforms cannot carry global isotope modifications, like the building blocks
+/// for [`crate::find_isobaric_sets`] and the mass calculations in alignments.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum IsotopeLabeling {
+    /// No labeling, all elements have their natural isotopic composition
+    #[default]
+    None,
+    /// All nitrogen is 15N
+    N15,
+    /// All carbon is 13C
+    C13,
+    /// All carbon is 13C and all nitrogen is 15N
+    C13N15,
+}
+
+impl IsotopeLabeling {
+    /// All labeling presets
+    pub const ALL: &'static [Self] = &[Self::None, Self::N15, Self::C13, Self::C13N15];
+
+    /// The global isotope modifications for this labeling, these can be used with
+    /// [`crate::Peptidoform::global`]
+    #[allow(clippy::missing_panics_doc)] // Cannot panic
+    pub fn global_isotope_modifications(self) -> Vec<(Element, Option<NonZeroU16>)> {
+        let n15 = (Element::N, Some(NonZeroU16::new(15).unwrap()));
+        let c13 = (Element::C, Some(NonZeroU16::new(13).unwrap()));
+        match self {
+            Self::None => Vec::new(),
+            Self::N15 => vec![n15],
+            Self::C13 => vec![c13],
+            Self::C13N15 => vec![c13, n15],
+        }
+    }
+
+    /// Apply this labeling to the given formula
+    #[allow(clippy::missing_panics_doc)] // Cannot panic, all presets are valid isotopes
+    #[must_use]
+    pub fn apply(self, formula: MolecularFormula) -> MolecularFormula {
+        if self == Self::None {
+            formula
+        } else {
+            formula
+                .with_global_isotope_modifications(&self.global_isotope_modifications())
+                .unwrap()
+        }
+    }
+}
+
+impl std::fmt::Display for IsotopeLabeling {
+    /// Shown as the ProForma global isotope modifications
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => Ok(()),
+            Self::N15 => write!(f, "<15N>"),
+            Self::C13 => write!(f, "<13C>"),
+            Self::C13N15 => write!(f, "<13C><15N>"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        align::{align, AlignScoring, AlignType},
+        find_isobaric_sets,
+        peptidoform::SimpleLinear,
+        AminoAcid, MultiChemical, Peptidoform, Tolerance,
+    };
+
+    #[test]
+    fn matches_pro_forma() {
+        for labeling in IsotopeLabeling::ALL {
+            let peptide = Peptidoform::pro_forma(&format!("{labeling}PEPTIDEK"), None)
+                .unwrap()
+                .into_linear()
+                .unwrap();
+            let plain = Peptidoform::pro_forma("PEPTIDEK", None)
+                .unwrap()
+                .into_linear()
+                .unwrap();
+            assert_eq!(
+                peptide.formulas(),
+                plain
+                    .formulas()
+                    .iter()
+                    .map(|f| labeling.apply(f.clone()))
+                    .collect(),
+                "{labeling:?}"
+            );
+        }
+        let glycine = AminoAcid::Glycine.formulas()[0].clone();
+        let shift = IsotopeLabeling::N15
+            .apply(glycine.clone())
+            .monoisotopic_mass()
+            - glycine.monoisotopic_mass();
+        let n15 = Element::N.mass(NonZeroU16::new(15)).unwrap() - Element::N.mass(None).unwrap();
+        assert!((shift - n15).value.abs() < 1e-9);
+    }
+
+    #[test]
+    fn isobaric_sets() {
+        let labeled = IsotopeLabeling::N15.apply(
+            Peptidoform::pro_forma("AG", None)
+                .unwrap()
+                .into_unambiguous()
+                .unwrap()
+                .bare_formula(),
+        );
+        let sets: Vec<Peptidoform<SimpleLinear>> = find_isobaric_sets(
+            labeled.monoisotopic_mass(),
+            Tolerance::new_ppm(10.0),
+            AminoAcid::UNIQUE_MASS_AMINO_ACIDS,
+            &[],
+            &[],
+            None,
+            IsotopeLabeling::N15,
+        )
+        .collect();
+        assert_eq!(
+            sets.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["GA", "Q"]
+        );
+        assert_eq!(
+            find_isobaric_sets(
+                labeled.monoisotopic_mass(),
+                Tolerance::new_ppm(10.0),
+                AminoAcid::UNIQUE_MASS_AMINO_ACIDS,
+                &[],
+                &[],
+                None,
+                IsotopeLabeling::None,
+            )
+            .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        // K and Q with a mass modification are isobaric, but K contains one more carbon
+        let a = Peptidoform::pro_forma("AKA", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("AQ[+0.036385]A", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let short = |labeling| {
+            align::<4, SimpleLinear, SimpleLinear>(
+                &a,
+                &b,
+                AlignScoring {
+                    labeling,
+                    ..AlignScoring::default()
+                },
+                AlignType::GLOBAL,
+            )
+            .short()
+        };
+        assert_eq!(short(IsotopeLabeling::None), "1=1i1=");
+        assert_eq!(short(IsotopeLabeling::N15), "1=1i1=");
+        assert_eq!(short(IsotopeLabeling::C13), "1=1X1=");
+    }
+}
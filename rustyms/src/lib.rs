@@ -23,6 +23,10 @@ pub mod identification;
 /// Only available with feature `imgt`.
 pub mod imgt;
 
+#[cfg(feature = "identification")]
+/// Only available with feature `identification`.
+pub mod search;
+
 #[cfg(test)]
 mod fragmentation_tests;
 #[macro_use]
@@ -36,15 +40,24 @@ pub mod csv;
 
 pub mod aminoacid_properties;
 mod aminoacids;
+pub mod annotation_cache;
+pub mod antibody;
+pub mod capabilities;
 mod checked_aminoacid;
+pub mod checkpoint;
+#[path = "shared/compression.rs"]
+pub mod compression;
 mod element;
 pub mod error;
 pub mod fragment;
+pub mod fragment_table;
 pub mod glycan;
 mod isobaric_sets;
 #[cfg(feature = "isotopes")]
 /// Only available with feature `isotopes`.
 mod isotopes;
+mod kendrick;
+mod labeling;
 mod mass_mode;
 pub mod model;
 pub mod modification;
@@ -57,6 +70,7 @@ pub mod ontologies;
 pub mod peptidoform;
 pub mod placement_rule;
 mod protease;
+pub mod provenance;
 #[cfg(feature = "rand")]
 /// Only available with features `rand`.
 mod rand;
@@ -67,10 +81,14 @@ mod sequence_position;
 pub mod spectrum;
 pub mod system;
 mod tolerance;
+pub mod transformation;
+mod uncertain_mass;
 
 pub use crate::element::*;
 pub use crate::formula::*;
 pub use crate::isobaric_sets::{building_blocks, find_isobaric_sets};
+pub use crate::kendrick::KendrickScale;
+pub use crate::labeling::IsotopeLabeling;
 pub use crate::mass_mode::MassMode;
 pub use crate::model::Model;
 pub use crate::modification::{CrossLinkName, Modification};
@@ -83,6 +101,7 @@ pub use crate::sequence_element::SequenceElement;
 pub use crate::sequence_position::*;
 pub use crate::spectrum::{AnnotatableSpectrum, AnnotatedSpectrum, RawSpectrum};
 pub use crate::tolerance::*;
+pub use crate::uncertain_mass::UncertainMass;
 pub use aminoacids::AminoAcid;
 pub use checked_aminoacid::CheckedAminoAcid;
 pub use fragment::Fragment;
@@ -1,13 +1,16 @@
 //! Handle model instantiation.
 
-use std::ops::RangeInclusive;
+use std::{borrow::Cow, ops::RangeInclusive};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     fragment::PeptidePosition,
-    system::{e, f64::MassOverCharge, isize::Charge, mz},
-    NeutralLoss, Tolerance,
+    modification::{Ontology, SimpleModificationInner},
+    peptidoform::PeptideModificationSearch,
+    spectrum::{Contaminant, NoiseThreshold},
+    system::{e, f64::MassOverCharge, isize::Charge, mz, Mass},
+    AminoAcid, CompoundPeptidoformIon, Modification, NeutralLoss, Peptidoform, Tolerance,
 };
 
 /// Control what charges are allowed for an ion series. Defined as an inclusive range.
@@ -54,6 +57,74 @@ impl ChargeRange {
         start: ChargePoint::Absolute(1),
         end: ChargePoint::Relative(0),
     };
+    /// Range from 1 to one below the precursor
+    pub const ONE_TO_PRECURSOR_MINUS_ONE: Self = Self {
+        start: ChargePoint::Absolute(1),
+        end: ChargePoint::Relative(-1),
+    };
+
+    /// Estimate the fragment charges from the precursor charge and the peptide, following the
+    /// mobile proton model. Each basic residue (R, K, or H) and the N terminus can sequester a
+    /// proton and at least one proton is retained on the complementary fragment, additionally
+    /// fragments are assumed to carry at most one charge per five residues. This gives the range
+    /// from 1 to the minimum of the precursor charge minus one, the number of basic residues plus
+    /// one, and the length divided by five (rounded up), with singly charged fragments always
+    /// allowed.
+    pub fn mobile_proton(
+        precursor: crate::system::usize::Charge,
+        length: usize,
+        basic_residues: usize,
+    ) -> Self {
+        let end = precursor
+            .value
+            .saturating_sub(1)
+            .min(basic_residues + 1)
+            .min(length.div_ceil(5))
+            .max(1);
+        Self {
+            start: ChargePoint::Absolute(1),
+            end: ChargePoint::Absolute(isize::try_from(end).unwrap_or(isize::MAX)),
+        }
+    }
+
+    /// Estimate the fragment charges for the given peptidoform, see [`Self::mobile_proton`]. For
+    /// cross-linked peptidoforms the lengths and basic residues of all peptides are summed, for
+    /// chimeric peptidoforms the widest range of all peptidoform ions is used.
+    pub fn mobile_proton_for(
+        precursor: crate::system::usize::Charge,
+        peptidoform: &CompoundPeptidoformIon,
+    ) -> Self {
+        peptidoform
+            .peptidoform_ions()
+            .iter()
+            .map(|ion| Self::mobile_proton_for_peptides(precursor, ion.peptidoforms()))
+            .max_by_key(|range| range.end)
+            .unwrap_or(Self::ONE)
+    }
+
+    /// Estimate the fragment charges for the given (cross-linked) peptides, the lengths and basic
+    /// residues of all peptides are summed, see [`Self::mobile_proton`].
+    fn mobile_proton_for_peptides<Complexity>(
+        precursor: crate::system::usize::Charge,
+        peptides: &[Peptidoform<Complexity>],
+    ) -> Self {
+        let (length, basic) = peptides.iter().fold((0, 0), |acc, p| {
+            (
+                acc.0 + p.len(),
+                acc.1
+                    + p.sequence()
+                        .iter()
+                        .filter(|s| {
+                            matches!(
+                                s.aminoacid.aminoacid(),
+                                AminoAcid::Arginine | AminoAcid::Lysine | AminoAcid::Histidine
+                            )
+                        })
+                        .count(),
+            )
+        });
+        Self::mobile_proton(precursor, length, basic)
+    }
 }
 
 /// A reference point for charge range definition.
@@ -107,14 +178,110 @@ pub struct Model {
     pub modification_specific_neutral_losses: bool,
     /// If the diagnostic ions specific for modifications should be generated with the allowed charge range
     pub modification_specific_diagnostic_ions: (bool, ChargeRange),
+    /// If the reporter ions of isobaric labels (TMT and iTRAQ) should be generated with the allowed charge range
+    pub reporter_ions: (bool, ChargeRange),
+    /// If the complementary reporter ions (eg `TMTc`) of isobaric labels should be generated with the allowed charge range
+    pub complementary_reporter_ions: (bool, ChargeRange),
     /// Glycan fragmentation
     pub glycan: GlycanModel,
+    /// Gas-phase rearrangement fragments
+    pub rearrangements: RearrangementModel,
     /// Allow any MS cleavable cross-link to be cleaved
     pub allow_cross_link_cleavage: bool,
     /// The matching tolerance
     pub tolerance: Tolerance<MassOverCharge>,
     /// The range in which fragments fall, can be used to limit the theoretical fragments to a known window
     pub mz_range: RangeInclusive<MassOverCharge>,
+    /// A dynamic intensity threshold, peaks below the local noise level are not annotated
+    #[serde(default)]
+    pub noise_threshold: Option<NoiseThreshold>,
+    /// Residue context aware rules for the neutral losses of the primary ion series (a, b, c, d,
+    /// v, w, x, y, and z), if empty all neutral losses are allowed for all fragments
+    #[serde(default)]
+    pub neutral_loss_rules: Vec<NeutralLossRule>,
+    /// Resolve mass only modifications to the closest named modification from Unimod within
+    /// this tolerance before generating fragments, so that the diagnostic ions and neutral
+    /// losses of the named modification are used, see [`Model::resolve_mass_modifications`]
+    #[serde(default)]
+    pub mass_modification_resolution: Option<Tolerance<Mass>>,
+    /// Known background ions, matching peaks are labelled as contaminant and are not annotated
+    /// nor counted in the scores, see [`Contaminant::common`]
+    #[serde(default)]
+    pub contaminants: Vec<Contaminant>,
+    /// Take fixed charges (for example from quaternary amine tags or other intrinsically charged
+    /// modifications) into account when placing charges on fragments. If set the charge ranges
+    /// are interpreted as the total charge of the fragment (fixed charges plus charge carriers),
+    /// so fragments containing a fixed charge need fewer charge carriers and always carry at
+    /// least their fixed charge. Relative charge ranges are relative to the total precursor
+    /// charge.
+    #[serde(default)]
+    pub fixed_charges: bool,
+    /// Estimate the charge range of the primary ion series (a, b, c, d, v, w, x, y, and z) for
+    /// each peptidoform ion from the precursor charge and its sequence, see
+    /// [`ChargeRange::mobile_proton`]. If set this overrules the charge ranges of these series.
+    #[serde(default)]
+    pub mobile_proton_charges: bool,
+}
+
+/// A rule restricting a neutral loss to fragments that contain at least one of the given amino
+/// acids, see [`Model::neutral_loss_rules`]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct NeutralLossRule {
+    /// The neutral loss this rule applies to
+    pub loss: NeutralLoss,
+    /// The amino acids that allow this loss
+    pub amino_acids: Vec<AminoAcid>,
+}
+
+impl NeutralLossRule {
+    /// Create a new rule
+    pub const fn new(loss: NeutralLoss, amino_acids: Vec<AminoAcid>) -> Self {
+        Self { loss, amino_acids }
+    }
+
+    /// The common residue specific rules: water loss only from fragments with S, T, E, or D and
+    /// ammonia loss only from fragments with R, K, N, or Q
+    pub fn water_and_ammonia() -> Vec<Self> {
+        vec![
+            Self::new(
+                NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
+                vec![
+                    AminoAcid::Serine,
+                    AminoAcid::Threonine,
+                    AminoAcid::GlutamicAcid,
+                    AminoAcid::AsparticAcid,
+                ],
+            ),
+            Self::new(
+                NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
+                vec![
+                    AminoAcid::Arginine,
+                    AminoAcid::Lysine,
+                    AminoAcid::Asparagine,
+                    AminoAcid::Glutamine,
+                ],
+            ),
+        ]
+    }
+
+    /// Filter the given neutral losses for a fragment with the given amino acids. A loss is
+    /// retained if there is no rule for it or if any rule for it allows it.
+    pub fn filter<'a>(
+        rules: &[Self],
+        losses: &'a [NeutralLoss],
+        residues: &[AminoAcid],
+    ) -> Cow<'a, [NeutralLoss]> {
+        let allowed = |loss: &NeutralLoss| {
+            let mut applicable = rules.iter().filter(|rule| rule.loss == *loss).peekable();
+            applicable.peek().is_none()
+                || applicable.any(|rule| residues.iter().any(|aa| rule.amino_acids.contains(aa)))
+        };
+        if losses.iter().all(allowed) {
+            Cow::Borrowed(losses)
+        } else {
+            Cow::Owned(losses.iter().filter(|l| allowed(l)).cloned().collect())
+        }
+    }
 }
 
 /// The settings for any primary ion series
@@ -237,29 +404,99 @@ impl GlycanModel {
     };
 }
 
-/// A struct to handle all possible fragments that could be generated on a single location
+/// The settings for fragments formed by gas-phase rearrangements. These are well documented
+/// artefact ions that are not formed by a direct cleavage of the backbone, but that can explain a
+/// sizeable part of the intensity in CID/HCD spectra.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct RearrangementModel {
+    /// b ions with an additional water (`b+H2O`), for the b ion that only lacks the C terminal
+    /// residue (b<sub>n-1</sub>) and for b ions ending in an acidic residue (D/E). The C terminal
+    /// carboxyl group (or the acidic side chain) donates its oxygen to the oxazolone ring formed
+    /// by the b ion, see Thorne, Ballard & Gaskell, J. Am. Soc. Mass Spectrom. 1990, 1(3), 249-257.
+    pub water_gain: bool,
+    /// a ions formed by the loss of CO from b ions, which results in a/b pairs (a peak 28 Da below
+    /// every b ion) even when the a series is not fragmented directly, see Harrison, Mass Spectrom.
+    /// Rev. 2009, 28(4), 640-654. This does not generate a ions on locations where the a series is
+    /// already generated.
+    pub a_from_b: bool,
+    /// The lengths of b type internal fragments (and their a type counterparts with a loss of CO)
+    /// formed by the cyclization and reopening of b ions (macrocyclic b ions), see Harrison, Young,
+    /// Bythell & Paizs, J. Am. Chem. Soc. 2006, 128(32), 10364-10365. These only contain residues
+    /// that are not at the termini of the peptide. A range of `0..=0` disables these fragments.
+    pub internal_lengths: RangeInclusive<usize>,
+    /// The allowed charges for the internal fragments
+    pub internal_charge_range: ChargeRange,
+}
+
+impl RearrangementModel {
+    /// Set the generation of `b+H2O` ions, see [`Self::water_gain`]
+    #[must_use]
+    pub const fn water_gain(self, water_gain: bool) -> Self {
+        Self { water_gain, ..self }
+    }
+    /// Set the generation of a ions from b ions, see [`Self::a_from_b`]
+    #[must_use]
+    pub const fn a_from_b(self, a_from_b: bool) -> Self {
+        Self { a_from_b, ..self }
+    }
+    /// Set the lengths of internal fragments, see [`Self::internal_lengths`]
+    #[must_use]
+    pub const fn internal_lengths(self, internal_lengths: RangeInclusive<usize>) -> Self {
+        Self {
+            internal_lengths,
+            ..self
+        }
+    }
+    /// Replace the charge range for internal fragments
+    #[must_use]
+    pub const fn internal_charge_range(self, internal_charge_range: ChargeRange) -> Self {
+        Self {
+            internal_charge_range,
+            ..self
+        }
+    }
+    /// Default set for models that allow rearrangement fragments
+    pub const ALLOW: Self = Self {
+        water_gain: true,
+        a_from_b: true,
+        internal_lengths: 2..=4,
+        internal_charge_range: ChargeRange::ONE,
+    };
+    /// Default set for models that disallow rearrangement fragments
+    pub const DISALLOW: Self = Self {
+        water_gain: false,
+        a_from_b: false,
+        internal_lengths: 0..=0,
+        internal_charge_range: ChargeRange::ONE,
+    };
+}
+
+/// A struct to handle all possible fragments that could be generated on a single location. The
+/// neutral losses of the ion series are a [`Cow`] (up to version 0.9.0 a slice) as they are owned
+/// when restricted by the [neutral loss rules](Model::neutral_loss_rules), see
+/// [`Model::ions_in_context`].
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 #[non_exhaustive]
 pub struct PossibleIons<'a> {
     /// a series ions
-    pub a: (bool, &'a [NeutralLoss], ChargeRange),
+    pub a: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// b series ions
-    pub b: (bool, &'a [NeutralLoss], ChargeRange),
+    pub b: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// c series ions
-    pub c: (bool, &'a [NeutralLoss], ChargeRange),
+    pub c: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// d series ions (side chain fragmentation from a)
-    pub d: (bool, &'a [NeutralLoss], ChargeRange),
+    pub d: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// v series ions (full side chain broken off)
-    pub v: (bool, &'a [NeutralLoss], ChargeRange),
+    pub v: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// w series ions (side chain fragmentation from z)
-    pub w: (bool, &'a [NeutralLoss], ChargeRange),
+    pub w: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// x series ions
-    pub x: (bool, &'a [NeutralLoss], ChargeRange),
+    pub x: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// y series ions
-    pub y: (bool, &'a [NeutralLoss], ChargeRange),
+    pub y: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// z series ions
-    pub z: (bool, &'a [NeutralLoss], ChargeRange),
+    pub z: (bool, Cow<'a, [NeutralLoss]>, ChargeRange),
     /// precursor ions
     pub precursor: (&'a [NeutralLoss], ChargeRange),
     /// immonium
@@ -335,6 +572,14 @@ impl Model {
     pub fn glycan(self, glycan: GlycanModel) -> Self {
         Self { glycan, ..self }
     }
+    /// Set the gas-phase rearrangement fragments
+    #[must_use]
+    pub fn rearrangements(self, rearrangements: RearrangementModel) -> Self {
+        Self {
+            rearrangements,
+            ..self
+        }
+    }
     /// Overwrite the precursor neutral losses
     #[must_use]
     pub fn precursor(self, neutral_loss: Vec<NeutralLoss>, charges: ChargeRange) -> Self {
@@ -372,6 +617,38 @@ impl Model {
             ..self
         }
     }
+    /// Set reporter ions
+    #[must_use]
+    pub fn reporter_ions(self, state: (bool, ChargeRange)) -> Self {
+        Self {
+            reporter_ions: state,
+            ..self
+        }
+    }
+    /// Set complementary reporter ions
+    #[must_use]
+    pub fn complementary_reporter_ions(self, state: (bool, ChargeRange)) -> Self {
+        Self {
+            complementary_reporter_ions: state,
+            ..self
+        }
+    }
+    /// Set the charge range of all primary ion series (a, b, c, d, v, w, x, y, and z)
+    #[must_use]
+    pub fn fragment_charge_range(self, charge_range: ChargeRange) -> Self {
+        Self {
+            a: self.a.charge_range(charge_range),
+            b: self.b.charge_range(charge_range),
+            c: self.c.charge_range(charge_range),
+            d: self.d.charge_range(charge_range),
+            v: self.v.charge_range(charge_range),
+            w: self.w.charge_range(charge_range),
+            x: self.x.charge_range(charge_range),
+            y: self.y.charge_range(charge_range),
+            z: self.z.charge_range(charge_range),
+            ..self
+        }
+    }
     /// Set the tolerance
     #[must_use]
     pub fn allow_cross_link_cleavage(self, state: bool) -> Self {
@@ -393,56 +670,157 @@ impl Model {
     pub fn mz_range(self, mz_range: RangeInclusive<MassOverCharge>) -> Self {
         Self { mz_range, ..self }
     }
+    /// Set the residue context aware neutral loss rules
+    #[must_use]
+    pub fn neutral_loss_rules(self, neutral_loss_rules: Vec<NeutralLossRule>) -> Self {
+        Self {
+            neutral_loss_rules,
+            ..self
+        }
+    }
+    /// Set the tolerance for resolving mass only modifications to named modifications, `None`
+    /// (the default) turns this off
+    #[must_use]
+    pub fn mass_modification_resolution(
+        self,
+        mass_modification_resolution: Option<Tolerance<Mass>>,
+    ) -> Self {
+        Self {
+            mass_modification_resolution,
+            ..self
+        }
+    }
+    /// Set the known background ions
+    #[must_use]
+    pub fn contaminants(self, contaminants: Vec<Contaminant>) -> Self {
+        Self {
+            contaminants,
+            ..self
+        }
+    }
+    /// Set if fixed charges are taken into account when placing charges on fragments
+    #[must_use]
+    pub fn fixed_charges(self, fixed_charges: bool) -> Self {
+        Self {
+            fixed_charges,
+            ..self
+        }
+    }
+    /// Set if the charge ranges of the primary ion series are estimated for each peptidoform ion,
+    /// see [`Self::mobile_proton_charges`](Model#structfield.mobile_proton_charges)
+    #[must_use]
+    pub fn mobile_proton_charges(self, mobile_proton_charges: bool) -> Self {
+        Self {
+            mobile_proton_charges,
+            ..self
+        }
+    }
+    /// Get the model to generate the fragments of the given (cross-linked) peptides with, if
+    /// [`Self::mobile_proton_charges`](Model#structfield.mobile_proton_charges) is set this
+    /// uses the estimated charge ranges for these peptides.
+    pub(crate) fn for_peptides<Complexity>(
+        &self,
+        precursor: crate::system::usize::Charge,
+        peptides: &[Peptidoform<Complexity>],
+    ) -> Cow<'_, Self> {
+        if self.mobile_proton_charges {
+            Cow::Owned(
+                self.clone()
+                    .fragment_charge_range(ChargeRange::mobile_proton_for_peptides(
+                        precursor, peptides,
+                    )),
+            )
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+    /// Set the dynamic noise threshold
+    #[must_use]
+    pub fn noise_threshold(self, noise_threshold: Option<NoiseThreshold>) -> Self {
+        Self {
+            noise_threshold,
+            ..self
+        }
+    }
 }
 
 impl Model {
+    /// Resolve all mass only modifications in the given peptidoform to the closest named
+    /// modification from Unimod if [`Model::mass_modification_resolution`] is set.
+    /// Mass modifications without a single closest match within the tolerance are kept as is.
+    pub fn resolve_mass_modifications<'a, Complexity>(
+        &self,
+        peptidoform: &'a Peptidoform<Complexity>,
+    ) -> Cow<'a, Peptidoform<Complexity>> {
+        let Some(tolerance) = self.mass_modification_resolution else {
+            return Cow::Borrowed(peptidoform);
+        };
+        let is_mass = |m: &Modification| matches!(m, Modification::Simple(simple) if matches!(**simple, SimpleModificationInner::Mass(_)));
+        let has_mass = peptidoform.get_n_term().iter().any(is_mass)
+            || peptidoform.get_c_term().iter().any(is_mass)
+            || peptidoform
+                .sequence()
+                .iter()
+                .any(|s| s.modifications.iter().any(is_mass));
+        if has_mass {
+            Cow::Owned(
+                PeptideModificationSearch::in_ontologies(vec![Ontology::Unimod], None)
+                    .tolerance(tolerance)
+                    .force_closest(true)
+                    .search(peptidoform.clone()),
+            )
+        } else {
+            Cow::Borrowed(peptidoform)
+        }
+    }
+
     /// Give all possible ions for the given N position
     pub fn ions(&self, position: PeptidePosition) -> PossibleIons {
         let c_position = position.flip_terminal();
         PossibleIons {
             a: (
                 self.a.location.possible(position),
-                self.a.neutral_losses.as_slice(),
+                Cow::Borrowed(self.a.neutral_losses.as_slice()),
                 self.a.charge_range,
             ),
             b: (
                 self.b.location.possible(position),
-                self.b.neutral_losses.as_slice(),
+                Cow::Borrowed(self.b.neutral_losses.as_slice()),
                 self.b.charge_range,
             ),
             c: (
                 self.c.location.possible(position),
-                self.c.neutral_losses.as_slice(),
+                Cow::Borrowed(self.c.neutral_losses.as_slice()),
                 self.c.charge_range,
             ),
             d: (
                 self.d.location.possible(position),
-                self.d.neutral_losses.as_slice(),
+                Cow::Borrowed(self.d.neutral_losses.as_slice()),
                 self.d.charge_range,
             ),
             v: (
                 self.v.location.possible(c_position),
-                self.v.neutral_losses.as_slice(),
+                Cow::Borrowed(self.v.neutral_losses.as_slice()),
                 self.v.charge_range,
             ),
             w: (
                 self.w.location.possible(c_position),
-                self.w.neutral_losses.as_slice(),
+                Cow::Borrowed(self.w.neutral_losses.as_slice()),
                 self.w.charge_range,
             ),
             x: (
                 self.x.location.possible(c_position),
-                self.x.neutral_losses.as_slice(),
+                Cow::Borrowed(self.x.neutral_losses.as_slice()),
                 self.x.charge_range,
             ),
             y: (
                 self.y.location.possible(c_position),
-                self.y.neutral_losses.as_slice(),
+                Cow::Borrowed(self.y.neutral_losses.as_slice()),
                 self.y.charge_range,
             ),
             z: (
                 self.z.location.possible(c_position),
-                self.z.neutral_losses.as_slice(),
+                Cow::Borrowed(self.z.neutral_losses.as_slice()),
                 self.z.charge_range,
             ),
             precursor: (self.precursor.0.as_slice(), self.precursor.1),
@@ -450,6 +828,37 @@ impl Model {
         }
     }
 
+    /// Give all possible ions for the given N position, with the neutral losses restricted by
+    /// the [neutral loss rules](Model::neutral_loss_rules) given the amino acids in the N
+    /// terminal fragments (a, b, c, and d) and in the C terminal fragments (v, w, x, y, and z).
+    pub fn ions_in_context(
+        &self,
+        position: PeptidePosition,
+        n_terminal: &[AminoAcid],
+        c_terminal: &[AminoAcid],
+    ) -> PossibleIons<'_> {
+        let mut ions = self.ions(position);
+        if !self.neutral_loss_rules.is_empty() {
+            let rules = &self.neutral_loss_rules;
+            for (series, residues) in [
+                (&mut ions.a, n_terminal),
+                (&mut ions.b, n_terminal),
+                (&mut ions.c, n_terminal),
+                (&mut ions.d, n_terminal),
+                (&mut ions.v, c_terminal),
+                (&mut ions.w, c_terminal),
+                (&mut ions.x, c_terminal),
+                (&mut ions.y, c_terminal),
+                (&mut ions.z, c_terminal),
+            ] {
+                if let Cow::Borrowed(losses) = series.1 {
+                    series.1 = NeutralLossRule::filter(rules, losses, residues);
+                }
+            }
+        }
+        ions
+    }
+
     /// Generate all possible fragments
     pub fn all() -> Self {
         Self {
@@ -479,11 +888,20 @@ impl Model {
             m: true,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (true, ChargeRange::ONE),
+            complementary_reporter_ions: (true, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::ALLOW
                 .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
+            rearrangements: RearrangementModel::ALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -504,10 +922,19 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: false,
             modification_specific_diagnostic_ions: (false, ChargeRange::ONE),
+            reporter_ions: (false, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::DISALLOW,
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: false,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -536,11 +963,20 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (true, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::ALLOW
                 .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -573,11 +1009,20 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (false, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::ALLOW
                 .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -609,11 +1054,20 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (false, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::ALLOW
                 .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -643,10 +1097,19 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (true, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::DISALLOW,
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -682,10 +1145,19 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (false, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::DISALLOW,
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 
@@ -730,10 +1202,19 @@ impl Model {
             m: false,
             modification_specific_neutral_losses: true,
             modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
+            reporter_ions: (false, ChargeRange::ONE),
+            complementary_reporter_ions: (false, ChargeRange::ONE_TO_PRECURSOR_MINUS_ONE),
             glycan: GlycanModel::DISALLOW,
+            rearrangements: RearrangementModel::DISALLOW,
             allow_cross_link_cleavage: true,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            noise_threshold: None,
+            neutral_loss_rules: Vec::new(),
+            mass_modification_resolution: None,
+            contaminants: Vec::new(),
+            fixed_charges: false,
+            mobile_proton_charges: false,
         }
     }
 }
@@ -14,8 +14,8 @@ use std::{
 use crate::{
     glycan::{GlycanStructure, MonoSaccharide},
     molecular_charge::CachedCharge,
-    peptidoform::Linked,
-    placement_rule::{PlacementRule, Position},
+    peptidoform::{Linked, ProteinPosition},
+    placement_rule::{PlacementMismatch, PlacementRule, Position},
     system::OrderedMass,
     AmbiguousLabel, AminoAcid, Chemical, DiagnosticIon, Fragment, Model, MolecularFormula, Multi,
     NeutralLoss, Peptidoform, SequenceElement, SequencePosition,
@@ -23,6 +23,9 @@ use crate::{
 
 include!("shared/modification.rs");
 
+mod fragmentation;
+pub use fragmentation::*;
+
 impl ModificationId {
     /// Get the accession number name for the ontology
     pub fn url(&self) -> Option<String> {
@@ -178,6 +181,17 @@ impl SimpleModificationInner {
         &self,
         seq: &SequenceElement<T>,
         position: SequencePosition,
+    ) -> RulePossible {
+        self.is_possible_in_protein(seq, position, None)
+    }
+
+    /// Check to see if this modification can be placed on the specified element, taking the
+    /// location of the peptidoform in its protein into account for protein terminal rules if known
+    pub fn is_possible_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
     ) -> RulePossible {
         match self {
             Self::Database { specificities, .. } if specificities.is_empty() => {
@@ -189,7 +203,8 @@ impl SimpleModificationInner {
                     .iter()
                     .enumerate()
                     .filter_map(|(index, (rules, _, _))| {
-                        PlacementRule::any_possible(rules, seq, position).then_some(index)
+                        PlacementRule::any_possible_in_protein(rules, seq, position, protein)
+                            .then_some(index)
                     })
                     .collect();
                 if matching.is_empty() {
@@ -206,15 +221,22 @@ impl SimpleModificationInner {
                 .enumerate()
                 .map(|(index, spec)| match spec {
                     LinkerSpecificity::Symmetric(rules, _, _) => {
-                        if PlacementRule::any_possible(rules, seq, position) {
+                        if PlacementRule::any_possible_in_protein(rules, seq, position, protein) {
                             RulePossible::Symmetric(BTreeSet::from([index]))
                         } else {
                             RulePossible::No
                         }
                     }
                     LinkerSpecificity::Asymmetric((rules_left, rules_right), _, _) => {
-                        let left = PlacementRule::any_possible(rules_left, seq, position);
-                        let right = PlacementRule::any_possible(rules_right, seq, position);
+                        let left = PlacementRule::any_possible_in_protein(
+                            rules_left, seq, position, protein,
+                        );
+                        let right = PlacementRule::any_possible_in_protein(
+                            rules_right,
+                            seq,
+                            position,
+                            protein,
+                        );
                         if left && right {
                             RulePossible::Symmetric(BTreeSet::from([index]))
                         } else if left {
@@ -231,6 +253,61 @@ impl SimpleModificationInner {
         }
     }
 
+    /// Validate if this modification can be placed on the specified element according to its
+    /// placement rules. If it can be placed this returns the first rule that allows the
+    /// placement, or `None` if this modification has no placement rules (in which case it is
+    /// allowed anywhere). If it cannot be placed this returns every rule with the reason that
+    /// rule does not allow the placement.
+    /// # Errors
+    /// If none of the placement rules allow this modification at this location.
+    pub fn validate_placement<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+    ) -> Result<Option<PlacementRule>, Vec<(PlacementRule, PlacementMismatch)>> {
+        self.validate_placement_in_protein(seq, position, None)
+    }
+
+    /// Validate if this modification can be placed on the specified element, taking the location
+    /// of the peptidoform in its protein into account for protein terminal rules if known. See
+    /// [`Self::validate_placement`].
+    /// # Errors
+    /// If none of the placement rules allow this modification at this location.
+    pub fn validate_placement_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
+    ) -> Result<Option<PlacementRule>, Vec<(PlacementRule, PlacementMismatch)>> {
+        let rules: Vec<&PlacementRule> = match self {
+            Self::Database { specificities, .. } => specificities
+                .iter()
+                .flat_map(|(rules, _, _)| rules)
+                .collect(),
+            Self::Linker { specificities, .. } => specificities
+                .iter()
+                .flat_map(|spec| match spec {
+                    LinkerSpecificity::Symmetric(rules, _, _) => rules.iter().collect_vec(),
+                    LinkerSpecificity::Asymmetric((rules_left, rules_right), _, _) => {
+                        rules_left.iter().chain(rules_right).collect_vec()
+                    }
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        let mut mismatches = Vec::with_capacity(rules.len());
+        for rule in rules {
+            match rule.mismatch_in_protein(seq, position, protein) {
+                None => return Ok(Some(rule.clone())),
+                Some(reason) => mismatches.push((rule.clone(), reason)),
+            }
+        }
+        Err(mismatches)
+    }
+
     /// Check to see if this modification can be placed on the specified element
     pub fn is_possible_aa(&self, aa: AminoAcid, position: Position) -> RulePossible {
         match self {
@@ -630,10 +707,21 @@ impl Modification {
         &self,
         seq: &SequenceElement<T>,
         position: SequencePosition,
+    ) -> RulePossible {
+        self.is_possible_in_protein(seq, position, None)
+    }
+
+    /// Check to see if this modification can be placed on the specified element, taking the
+    /// location of the peptidoform in its protein into account for protein terminal rules if known
+    pub fn is_possible_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
     ) -> RulePossible {
         self.simple().map_or(
             RulePossible::Symmetric(std::collections::BTreeSet::new()),
-            |s| s.is_possible(seq, position),
+            |s| s.is_possible_in_protein(seq, position, protein),
         )
     }
 
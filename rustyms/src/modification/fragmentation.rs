@@ -0,0 +1,236 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Ontology, SimpleModificationInner};
+use crate::{
+    peptidoform::ProteinPosition,
+    placement_rule::{PlacementRule, Position},
+    AminoAcid, DiagnosticIon, NeutralLoss, SequenceElement, SequencePosition,
+};
+
+/// The characteristic fragmentation of a modification with a defined structure as described in
+/// literature, for modifications where the ontology does not (fully) define these. These are
+/// applied during fragment generation on top of the neutral losses and diagnostic ions defined in
+/// the ontology, see [`SimpleModificationInner::fragmentation`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ModificationFragmentation {
+    /// The ontology of the modification
+    pub ontology: Ontology,
+    /// The id of the modification in the ontology
+    pub id: usize,
+    /// The locations where this fragmentation applies, if empty it applies at all locations
+    pub rules: Vec<PlacementRule>,
+    /// The neutral losses from peptide fragments containing this modification
+    pub neutral_losses: Vec<NeutralLoss>,
+    /// The diagnostic ions
+    pub diagnostic_ions: Vec<DiagnosticIon>,
+    /// The literature describing this fragmentation, or how it was derived
+    pub reference: String,
+}
+
+static LITERATURE_FRAGMENTATION_CELL: OnceLock<Vec<ModificationFragmentation>> = OnceLock::new();
+
+/// All characteristic modification fragmentations from literature that are known to this crate
+pub fn literature_fragmentation() -> &'static [ModificationFragmentation] {
+    LITERATURE_FRAGMENTATION_CELL.get_or_init(|| {
+        let on = |amino_acids: &[AminoAcid]| {
+            vec![PlacementRule::AminoAcid(
+                amino_acids.to_vec(),
+                Position::Anywhere,
+            )]
+        };
+        vec![
+            ModificationFragmentation {
+                ontology: Ontology::Unimod,
+                id: 1,
+                rules: on(&[AminoAcid::Lysine]),
+                neutral_losses: Vec::new(),
+                diagnostic_ions: vec![
+                    // Immonium ion of acetyllysine (m/z 143.118) and its ammonia loss (m/z 126.091)
+                    DiagnosticIon(molecular_formula!(C 7 H 14 N 2 O 1)),
+                    DiagnosticIon(molecular_formula!(C 7 H 11 N 1 O 1)),
+                ],
+                reference: "Trelle and Jensen (2008) Anal. Chem. 80, 3422–3430".to_string(),
+            },
+            ModificationFragmentation {
+                ontology: Ontology::Unimod,
+                id: 21,
+                rules: on(&[AminoAcid::Tyrosine]),
+                neutral_losses: Vec::new(),
+                diagnostic_ions: vec![
+                    // Immonium ion of phosphotyrosine (m/z 216.042)
+                    DiagnosticIon(molecular_formula!(C 8 H 10 N 1 O 4 P 1)),
+                ],
+                reference: "Steen et al. (2001) Anal. Chem. 73, 1440–1448".to_string(),
+            },
+            ModificationFragmentation {
+                ontology: Ontology::Unimod,
+                id: 121,
+                rules: on(&[AminoAcid::Lysine]),
+                neutral_losses: Vec::new(),
+                diagnostic_ions: vec![
+                    // Immonium ion of the ubiquitin GG remnant on lysine (m/z 215.150)
+                    DiagnosticIon(molecular_formula!(C 9 H 18 N 4 O 2)),
+                ],
+                reference: "Derived from the structure of the GG modified lysine".to_string(),
+            },
+            ModificationFragmentation {
+                ontology: Ontology::Unimod,
+                id: 213,
+                rules: Vec::new(),
+                neutral_losses: vec![NeutralLoss::Loss(
+                    molecular_formula!(C 15 H 21 N 5 O 13 P 2),
+                )],
+                diagnostic_ions: vec![
+                    // Adenine (m/z 136.062)
+                    DiagnosticIon(molecular_formula!(C 5 H 5 N 5)),
+                    // Adenosine - H2O (m/z 250.094)
+                    DiagnosticIon(molecular_formula!(C 10 H 11 N 5 O 3)),
+                    // AMP (m/z 348.070)
+                    DiagnosticIon(molecular_formula!(C 10 H 14 N 5 O 7 P 1)),
+                    // ADP (m/z 428.037)
+                    DiagnosticIon(molecular_formula!(C 10 H 15 N 5 O 10 P 2)),
+                    // ADP-ribose - H2O (m/z 542.068)
+                    DiagnosticIon(molecular_formula!(C 15 H 21 N 5 O 13 P 2)),
+                ],
+                reference: "Hengel and Goodlett (2012) Int. J. Mass Spectrom. 312, 114–121"
+                    .to_string(),
+            },
+        ]
+    })
+}
+
+impl SimpleModificationInner {
+    /// All characteristic fragmentations from literature for this modification, see
+    /// [`literature_fragmentation`]
+    pub fn literature_fragmentation(
+        &self,
+    ) -> impl Iterator<Item = &'static ModificationFragmentation> + '_ {
+        let id = match self {
+            Self::Database { id, .. } => id.id.map(|i| (id.ontology, i)),
+            _ => None,
+        };
+        literature_fragmentation()
+            .iter()
+            .filter(move |f| id == Some((f.ontology, f.id)))
+    }
+
+    /// All neutral losses and diagnostic ions for this modification at the given location, as
+    /// defined in the ontology extended with the characteristic fragmentation from literature
+    /// (see [`Self::literature_fragmentation`]).
+    pub fn fragmentation<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
+    ) -> (Vec<NeutralLoss>, Vec<DiagnosticIon>) {
+        let mut neutral_losses = Vec::new();
+        let mut diagnostic_ions = Vec::new();
+        if let Self::Database { specificities, .. } = self {
+            for (rules, losses, ions) in specificities {
+                if PlacementRule::any_possible_in_protein(rules, seq, position, protein) {
+                    neutral_losses.extend_from_slice(losses);
+                    diagnostic_ions.extend_from_slice(ions);
+                }
+            }
+        }
+        for fragmentation in self.literature_fragmentation() {
+            if fragmentation.rules.is_empty()
+                || PlacementRule::any_possible_in_protein(
+                    &fragmentation.rules,
+                    seq,
+                    position,
+                    protein,
+                )
+            {
+                for loss in &fragmentation.neutral_losses {
+                    if !neutral_losses.contains(loss) {
+                        neutral_losses.push(loss.clone());
+                    }
+                }
+                for ion in &fragmentation.diagnostic_ions {
+                    if !diagnostic_ions.contains(ion) {
+                        diagnostic_ions.push(ion.clone());
+                    }
+                }
+            }
+        }
+        (neutral_losses, diagnostic_ions)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        fragment::FragmentType,
+        peptidoform::SemiAmbiguous,
+        system::{e, usize::Charge},
+        MassMode, Model, MolecularFormula, Peptidoform,
+    };
+
+    #[test]
+    fn marker_masses() {
+        let mz = |formula: &MolecularFormula| {
+            (formula.clone() + molecular_formula!(H 1 Electron -1))
+                .monoisotopic_mass()
+                .value
+        };
+        let expected = [
+            (1, vec![143.117_89, 126.091_34]),
+            (21, vec![216.042_06]),
+            (121, vec![215.150_25]),
+            (
+                213,
+                vec![136.061_77, 250.093_52, 348.070_42, 428.036_75, 542.068_35],
+            ),
+        ];
+        for (id, masses) in expected {
+            let fragmentation = literature_fragmentation()
+                .iter()
+                .find(|f| f.id == id)
+                .unwrap();
+            for (ion, mass) in fragmentation.diagnostic_ions.iter().zip(masses) {
+                assert!((mz(&ion.0) - mass).abs() < 1e-3, "{id}: {}", mz(&ion.0));
+            }
+        }
+    }
+
+    #[test]
+    fn applied() {
+        let acetyl = Ontology::Unimod.find_id(1, None).unwrap();
+        let lysine = SequenceElement::<SemiAmbiguous>::new(AminoAcid::Lysine.into(), None);
+        let serine = SequenceElement::<SemiAmbiguous>::new(AminoAcid::Serine.into(), None);
+        assert_eq!(acetyl.literature_fragmentation().count(), 1);
+        assert_eq!(
+            acetyl
+                .fragmentation(&lysine, SequencePosition::Index(0), None)
+                .1
+                .len(),
+            2
+        );
+        assert!(acetyl
+            .fragmentation(&serine, SequencePosition::Index(0), None)
+            .1
+            .is_empty());
+
+        let peptide = Peptidoform::pro_forma("PEPT[ADP-Ribosyl]IDEK", None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &Model::all());
+        let diagnostic = fragments
+            .iter()
+            .filter(|f| matches!(f.ion, FragmentType::Diagnostic(_)))
+            .filter_map(|f| f.mz(MassMode::Monoisotopic))
+            .collect::<Vec<_>>();
+        assert!(diagnostic
+            .iter()
+            .any(|mz| (mz.value - 136.061_77).abs() < 1e-3));
+        assert!(diagnostic
+            .iter()
+            .any(|mz| (mz.value - 542.068_35).abs() < 1e-3));
+    }
+}
@@ -11,6 +11,8 @@ pub struct CachedCharge {
     charge: MolecularCharge,
     options: HashMap<Charge, Vec<MolecularCharge>>,
     number: Charge,
+    /// The fixed charge of the full peptidoform, if set charges are placed fixed charge aware
+    fixed: Option<Charge>,
 }
 
 impl CachedCharge {
@@ -33,6 +35,58 @@ impl CachedCharge {
         }
         options
     }
+
+    /// Place charges fixed charge aware, with the given fixed charge of the full peptidoform
+    #[must_use]
+    pub fn with_fixed_charge(self, fixed: Charge) -> Self {
+        Self {
+            fixed: Some(fixed),
+            ..self
+        }
+    }
+
+    /// Get all options for a fragment with the given fixed charge, together with the total
+    /// charge of the fragment. If this is not fixed charge aware the fixed charge is ignored and
+    /// the total charge is the charge of the charge carriers. Otherwise the range is used for
+    /// the total charge, and a fragment with a positive fixed charge is always given at least
+    /// its fixed charge.
+    pub fn range_for(
+        &mut self,
+        range: ChargeRange,
+        fragment_fixed: Charge,
+    ) -> Vec<(MolecularCharge, Charge)> {
+        let Some(fixed) = self.fixed else {
+            return self
+                .range(range)
+                .into_iter()
+                .map(|c| {
+                    let charge = c.charge();
+                    (c, charge)
+                })
+                .collect();
+        };
+        let totals = range.charges_iter(self.charge() + fixed);
+        if fragment_fixed.value > 0
+            && totals
+                .clone()
+                .next_back()
+                .map_or(true, |t| t < fragment_fixed)
+        {
+            return vec![(MolecularCharge::new(&[]), fragment_fixed)];
+        }
+        let mut options = Vec::new();
+        for total in totals {
+            let carriers = total - fragment_fixed;
+            match carriers.value.cmp(&0) {
+                Ordering::Less => (),
+                Ordering::Equal => options.push((MolecularCharge::new(&[]), total)),
+                Ordering::Greater => {
+                    options.extend(self.options(carriers).iter().map(|c| (c.clone(), total)));
+                }
+            }
+        }
+        options
+    }
 }
 
 impl From<MolecularCharge> for CachedCharge {
@@ -42,6 +96,7 @@ impl From<MolecularCharge> for CachedCharge {
             charge: value,
             options: HashMap::new(),
             number: n,
+            fixed: None,
         }
     }
 }
@@ -52,6 +107,7 @@ impl From<&MolecularCharge> for CachedCharge {
             charge: value.clone(),
             options: HashMap::new(),
             number: value.charge(),
+            fixed: None,
         }
     }
 }
@@ -229,9 +285,14 @@ impl std::fmt::Display for MolecularCharge {
 #[cfg(test)]
 #[allow(clippy::missing_panics_doc)]
 mod tests {
-    use crate::Chemical;
+    use crate::{
+        fragment::FragmentType,
+        model::ChargeRange,
+        system::{e, isize::Charge, usize},
+        Chemical, Model, Peptidoform,
+    };
 
-    use super::MolecularCharge;
+    use super::{CachedCharge, MolecularCharge};
 
     #[test]
     fn simple_charge_options() {
@@ -240,4 +301,68 @@ mod tests {
         assert_eq!(options.len(), 1);
         assert_eq!(options[0].formula(), molecular_formula!(H 1 Electron -1));
     }
+
+    #[test]
+    fn fixed_charge_options() {
+        let mut cached = CachedCharge::from(MolecularCharge::proton(2));
+        // Not fixed charge aware: the fixed charge is ignored
+        let options = cached.range_for(ChargeRange::ONE_TO_PRECURSOR, Charge::new::<e>(1));
+        assert_eq!(
+            options.iter().map(|(_, t)| t.value).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        // Fixed charge aware: the precursor is 3+, a fragment with the fixed charge needs 0 to 2
+        // protons, one without needs 1 to 3
+        let mut cached = cached.with_fixed_charge(Charge::new::<e>(1));
+        let options = cached.range_for(ChargeRange::ONE_TO_PRECURSOR, Charge::new::<e>(1));
+        assert_eq!(
+            options
+                .iter()
+                .map(|(c, t)| (c.charge().value, t.value))
+                .collect::<Vec<_>>(),
+            vec![(0, 1), (1, 2), (2, 3)]
+        );
+        let options = cached.range_for(ChargeRange::ONE_TO_PRECURSOR, Charge::new::<e>(0));
+        assert_eq!(
+            options.iter().map(|(_, t)| t.value).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        // A fragment always carries at least its fixed charge
+        let options = cached.range_for(ChargeRange::ONE, Charge::new::<e>(2));
+        assert_eq!(
+            options
+                .iter()
+                .map(|(c, t)| (c.charge().value, t.value))
+                .collect::<Vec<_>>(),
+            vec![(0, 2)]
+        );
+    }
+
+    #[test]
+    fn fixed_charge_fragments() {
+        let peptide = Peptidoform::pro_forma("[Formula:C5H12N1O1:z+1]-AGPEPTIDEK/2", None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let fragments = peptide.generate_theoretical_fragments(
+            usize::Charge::new::<e>(2),
+            &Model::cid_hcd().fixed_charges(true),
+        );
+        for fragment in &fragments {
+            let formula = fragment.formula.as_ref().unwrap();
+            assert_eq!(
+                formula.charge().value,
+                fragment.charge.value as isize,
+                "{fragment:?}"
+            );
+        }
+        // b ions contain the tag and can be detected without any additional proton
+        assert!(fragments
+            .iter()
+            .any(|f| matches!(f.ion, FragmentType::b(_)) && f.charge.value == 1));
+        // y ions do not contain the tag and need protons, up to the full precursor charge
+        assert!(fragments
+            .iter()
+            .any(|f| matches!(f.ion, FragmentType::y(_)) && f.charge.value == 3));
+    }
 }
@@ -652,6 +652,30 @@ fn parse_confidence(
     }
 }
 
+/// Get the names and formulas of the reporter ions for the isobaric label with the given Unimod
+/// id (TMT, `TMT2plex`, `TMT6plex`/`TMT10plex`/`TMT11plex`, `iTRAQ4plex`, and `iTRAQ8plex`).
+pub fn isobaric_reporter_ions(
+    unimod_id: usize,
+) -> impl Iterator<Item = &'static (&'static str, MolecularFormula)> {
+    let names: &[&str] = match unimod_id {
+        739 => &["tmt126"],
+        738 => &["tmt126", "tmt127c"],
+        737 => &[
+            "tmt126", "tmt127n", "tmt127c", "tmt128n", "tmt128c", "tmt129n", "tmt129c", "tmt130n",
+            "tmt130c", "tmt131n", "tmt131c",
+        ],
+        214 => &["itraq114", "itraq115", "itraq116", "itraq117"],
+        730 => &[
+            "itraq113", "itraq114", "itraq115", "itraq116", "itraq117", "itraq118", "itraq119",
+            "itraq121",
+        ],
+        _ => &[],
+    };
+    mz_paf_named_molecules()
+        .iter()
+        .filter(move |(name, _)| names.contains(name))
+}
+
 fn mz_paf_named_molecules() -> &'static Vec<(&'static str, MolecularFormula)> {
     MZPAF_NAMED_MOLECULES_CELL.get_or_init(|| {
         vec![
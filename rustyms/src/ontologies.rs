@@ -103,6 +103,23 @@ impl Ontology {
         None
     }
 
+    /// A hash (64 bit FNV-1a) of the built in database for this ontology, this identifies the
+    /// exact version of the database that was included at compile time. Returns `None` for
+    /// [`Self::Custom`].
+    pub fn database_hash(self) -> Option<u64> {
+        let data = match self {
+            Self::Gnome => GNOME_DATA,
+            Self::Psimod => PSIMOD_DATA,
+            Self::Unimod => UNIMOD_DATA,
+            Self::Resid => RESID_DATA,
+            Self::Xlmod => XLMOD_DATA,
+            Self::Custom => return None,
+        };
+        Some(data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        }))
+    }
+
     /// Find the given id in this ontology
     pub fn find_id(
         self,
@@ -122,34 +139,37 @@ impl Ontology {
 /// # Panics
 /// Panics when the modifications are not correctly provided at compile time, always report a panic if it occurs here.
 fn unimod_ontology() -> &'static OntologyModificationList {
-    UNIMOD_CELL
-        .get_or_init(|| bincode::deserialize(include_bytes!("databases/unimod.dat")).unwrap())
+    UNIMOD_CELL.get_or_init(|| bincode::deserialize(UNIMOD_DATA).unwrap())
 }
 /// Get the PSI-MOD ontology
 /// # Panics
 /// Panics when the modifications are not correctly provided at compile time, always report a panic if it occurs here.
 fn psimod_ontology() -> &'static OntologyModificationList {
-    PSIMOD_CELL
-        .get_or_init(|| bincode::deserialize(include_bytes!("databases/psimod.dat")).unwrap())
+    PSIMOD_CELL.get_or_init(|| bincode::deserialize(PSIMOD_DATA).unwrap())
 }
 /// Get the Gnome ontology
 /// # Panics
 /// Panics when the modifications are not correctly provided at compile time, always report a panic if it occurs here.
 fn gnome_ontology() -> &'static OntologyModificationList {
-    GNOME_CELL.get_or_init(|| bincode::deserialize(include_bytes!("databases/gnome.dat")).unwrap())
+    GNOME_CELL.get_or_init(|| bincode::deserialize(GNOME_DATA).unwrap())
 }
 /// Get the Resid ontology
 /// # Panics
 /// Panics when the modifications are not correctly provided at compile time, always report a panic if it occurs here.
 fn resid_ontology() -> &'static OntologyModificationList {
-    RESID_CELL.get_or_init(|| bincode::deserialize(include_bytes!("databases/resid.dat")).unwrap())
+    RESID_CELL.get_or_init(|| bincode::deserialize(RESID_DATA).unwrap())
 }
 /// Get the Xlmod ontology
 /// # Panics
 /// Panics when the modifications are not correctly provided at compile time, always report a panic if it occurs here.
 fn xlmod_ontology() -> &'static OntologyModificationList {
-    XLMOD_CELL.get_or_init(|| bincode::deserialize(include_bytes!("databases/xlmod.dat")).unwrap())
+    XLMOD_CELL.get_or_init(|| bincode::deserialize(XLMOD_DATA).unwrap())
 }
+const UNIMOD_DATA: &[u8] = include_bytes!("databases/unimod.dat");
+const PSIMOD_DATA: &[u8] = include_bytes!("databases/psimod.dat");
+const GNOME_DATA: &[u8] = include_bytes!("databases/gnome.dat");
+const RESID_DATA: &[u8] = include_bytes!("databases/resid.dat");
+const XLMOD_DATA: &[u8] = include_bytes!("databases/xlmod.dat");
 static UNIMOD_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
 static PSIMOD_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
 static GNOME_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
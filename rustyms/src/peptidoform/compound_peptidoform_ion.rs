@@ -4,8 +4,8 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    peptidoform::Linked, system::usize::Charge, Fragment, Model, MolecularFormula, Multi,
-    Peptidoform, PeptidoformIon,
+    peptidoform::Linked, spectrum::DecoyStrategy, system::usize::Charge, Fragment, Model,
+    MolecularFormula, Multi, Peptidoform, PeptidoformIon,
 };
 
 /// A single full ProForma entry. This entry can contain multiple sets of cross-linked peptides.
@@ -34,6 +34,12 @@ impl CompoundPeptidoformIon {
         self.0.iter().flat_map(|p| p.formulas().to_vec()).collect()
     }
 
+    /// Get all possible formula differences between this and the other compound peptidoform
+    /// (self - other)
+    pub fn formula_difference(&self, other: &Self) -> Multi<MolecularFormula> {
+        (&self.formulas() * &-other.formulas()).unique()
+    }
+
     /// Assume there is exactly one peptidoform in this compound peptidoform.
     #[doc(alias = "assume_linear")]
     pub fn singular(mut self) -> Option<PeptidoformIon> {
@@ -72,6 +78,40 @@ impl CompoundPeptidoformIon {
         base
     }
 
+    /// Generate decoy fragments for this compound peptidoform, following the given strategy. The
+    /// peptidoform (ion) indices of the decoy fragments are identical to the targets they are based
+    /// on. See [`crate::AnnotatableSpectrum::annotate_with_decoys`] to use these to estimate the
+    /// rate of random matches of an annotation.
+    pub fn generate_decoy_fragments(
+        &self,
+        max_charge: Charge,
+        model: &Model,
+        strategy: DecoyStrategy,
+    ) -> Vec<Fragment> {
+        match strategy {
+            DecoyStrategy::MassShift(shift) => self
+                .generate_theoretical_fragments(max_charge, model)
+                .into_iter()
+                .map(|fragment| fragment.with_mz_shift(shift))
+                .collect(),
+            DecoyStrategy::Reversed => Self(
+                self.0
+                    .iter()
+                    .map(|peptidoform_ion| {
+                        PeptidoformIon(
+                            peptidoform_ion
+                                .peptidoforms()
+                                .iter()
+                                .map(Peptidoform::reverse)
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            )
+            .generate_theoretical_fragments(max_charge, model),
+        }
+    }
+
     /// Display this compound peptidoform.
     /// `specification_compliant` Displays this compound peptidoform either normalised to the
     /// internal representation (with false) or as fully spec compliant ProForma (no glycan
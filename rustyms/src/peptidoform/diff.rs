@@ -0,0 +1,338 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AminoAcid, Modification, MolecularFormula, Multi, MultiChemical, Peptidoform, SequencePosition,
+};
+
+use super::{AtMax, Linear};
+
+/// A semantic comparison of two peptidoforms, see [`Peptidoform::diff`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PeptidoformDiff {
+    /// All changes going from the first to the second peptidoform
+    pub changes: Vec<PeptidoformChange>,
+    /// If both peptidoforms have the same unmodified amino acid sequence
+    pub same_backbone: bool,
+    /// If both peptidoforms have at least one possible molecular formula in common, so they can
+    /// not be distinguished based on the precursor mass
+    pub isobaric: bool,
+}
+
+impl PeptidoformDiff {
+    /// Check if the peptidoforms are identical, apart from labile and global modifications
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// A single change between two peptidoforms, see [`PeptidoformDiff`]. All positions are given
+/// for the peptidoform they refer to, so the first peptidoform for removals and the second for
+/// additions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PeptidoformChange {
+    /// A single amino acid is substituted by another amino acid, only used when both peptidoforms
+    /// have the same length
+    Substitution {
+        /// The sequence index
+        index: usize,
+        /// The amino acid in the first peptidoform
+        from: AminoAcid,
+        /// The amino acid in the second peptidoform
+        to: AminoAcid,
+        /// If the amino acids have the same molecular formula, eg I and L
+        isobaric: bool,
+    },
+    /// A stretch of amino acids is replaced by a stretch of a different length, this covers the
+    /// region between the longest shared prefix and suffix of both sequences
+    Replacement {
+        /// The sequence indices in the first peptidoform
+        from: Range<usize>,
+        /// The sequence indices in the second peptidoform
+        to: Range<usize>,
+        /// If both stretches have the same molecular formula, eg GG and N
+        isobaric: bool,
+    },
+    /// A modification is present in both peptidoforms but at a different position
+    ModificationMoved {
+        /// The modification
+        modification: Modification,
+        /// The position in the first peptidoform
+        from: SequencePosition,
+        /// The position in the second peptidoform
+        to: SequencePosition,
+    },
+    /// A modification is only present in the first peptidoform
+    ModificationRemoved {
+        /// The modification
+        modification: Modification,
+        /// The position in the first peptidoform
+        position: SequencePosition,
+    },
+    /// A modification is only present in the second peptidoform
+    ModificationAdded {
+        /// The modification
+        modification: Modification,
+        /// The position in the second peptidoform
+        position: SequencePosition,
+    },
+}
+
+/// A position that is comparable between two peptidoforms with a different length
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Anchor {
+    NTerm,
+    CTerm,
+    /// An index in the shared prefix, or any index if both have the same length
+    FromN(usize),
+    /// An index in the shared suffix, counted from the C terminus
+    FromC(usize),
+    /// An index in the replaced region, this never matches
+    Replaced,
+}
+
+impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
+    /// Compare this peptidoform to another peptidoform. The amino acid sequences are compared
+    /// position by position if they have the same length, otherwise the region between the
+    /// longest shared prefix and suffix is reported as a single replacement. Modifications are
+    /// matched on their position (relative to the nearest terminus for sequences of different
+    /// lengths), modifications that are not matched but occur in both peptidoforms are reported as
+    /// moved. Labile and global modifications and charge carriers are not compared.
+    pub fn diff<OtherComplexity: AtMax<Linear>>(
+        &self,
+        other: &Peptidoform<OtherComplexity>,
+    ) -> PeptidoformDiff {
+        let a: Vec<AminoAcid> = self
+            .sequence()
+            .iter()
+            .map(|s| s.aminoacid.aminoacid())
+            .collect();
+        let b: Vec<AminoAcid> = other
+            .sequence()
+            .iter()
+            .map(|s| s.aminoacid.aminoacid())
+            .collect();
+        let mut changes = Vec::new();
+
+        let same_length = a.len() == b.len();
+        let prefix = a.iter().zip(&b).take_while(|(a, b)| a == b).count();
+        let suffix = a[prefix..]
+            .iter()
+            .rev()
+            .zip(b[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        if same_length {
+            changes.extend(
+                a.iter()
+                    .zip(&b)
+                    .enumerate()
+                    .filter(|(_, (a, b))| a != b)
+                    .map(|(index, (from, to))| PeptidoformChange::Substitution {
+                        index,
+                        from: *from,
+                        to: *to,
+                        isobaric: isobaric(
+                            &residues_formulas(&[*from]),
+                            &residues_formulas(&[*to]),
+                        ),
+                    }),
+            );
+        } else {
+            let from = prefix..a.len() - suffix;
+            let to = prefix..b.len() - suffix;
+            changes.push(PeptidoformChange::Replacement {
+                isobaric: isobaric(
+                    &residues_formulas(&a[from.clone()]),
+                    &residues_formulas(&b[to.clone()]),
+                ),
+                from,
+                to,
+            });
+        }
+
+        let anchor = |position: SequencePosition, length: usize| match position {
+            SequencePosition::NTerm => Anchor::NTerm,
+            SequencePosition::CTerm => Anchor::CTerm,
+            SequencePosition::Index(i) if same_length || i < prefix => Anchor::FromN(i),
+            SequencePosition::Index(i) if i >= length - suffix => Anchor::FromC(length - i),
+            SequencePosition::Index(_) => Anchor::Replaced,
+        };
+        let mut removed = modifications(self)
+            .map(|(p, m)| (p, anchor(p, a.len()), m))
+            .collect::<Vec<_>>();
+        let mut added = Vec::new();
+        for (position, modification) in modifications(other) {
+            let other_anchor = anchor(position, b.len());
+            if let Some(index) = removed.iter().position(|(_, anchor, m)| {
+                *anchor == other_anchor && other_anchor != Anchor::Replaced && *m == modification
+            }) {
+                removed.remove(index);
+            } else {
+                added.push((position, modification));
+            }
+        }
+        for (from, _, modification) in removed {
+            if let Some(index) = added.iter().position(|(_, m)| *m == modification) {
+                let (to, _) = added.remove(index);
+                changes.push(PeptidoformChange::ModificationMoved {
+                    modification: modification.clone(),
+                    from,
+                    to,
+                });
+            } else {
+                changes.push(PeptidoformChange::ModificationRemoved {
+                    modification: modification.clone(),
+                    position: from,
+                });
+            }
+        }
+        changes.extend(added.into_iter().map(|(position, modification)| {
+            PeptidoformChange::ModificationAdded {
+                modification: modification.clone(),
+                position,
+            }
+        }));
+
+        PeptidoformDiff {
+            changes,
+            same_backbone: a == b,
+            isobaric: isobaric(&self.formulas(), &other.formulas()),
+        }
+    }
+}
+
+/// All modifications with their position, including the terminal modifications
+fn modifications<Complexity>(
+    peptidoform: &Peptidoform<Complexity>,
+) -> impl Iterator<Item = (SequencePosition, &Modification)> {
+    peptidoform
+        .get_n_term()
+        .iter()
+        .map(|m| (SequencePosition::NTerm, m))
+        .chain(
+            peptidoform
+                .sequence()
+                .iter()
+                .enumerate()
+                .flat_map(|(index, element)| {
+                    element
+                        .modifications
+                        .iter()
+                        .map(move |m| (SequencePosition::Index(index), m))
+                }),
+        )
+        .chain(
+            peptidoform
+                .get_c_term()
+                .iter()
+                .map(|m| (SequencePosition::CTerm, m)),
+        )
+}
+
+/// All possible formulas for the given stretch of amino acids
+fn residues_formulas(residues: &[AminoAcid]) -> Multi<MolecularFormula> {
+    residues.iter().fold(Multi::default(), |acc, aa| {
+        acc * aa.formulas_inner(SequencePosition::Index(0), 0)
+    })
+}
+
+/// If the two sets of formulas share at least one formula, ignoring the ambiguous labels
+fn isobaric(a: &Multi<MolecularFormula>, b: &Multi<MolecularFormula>) -> bool {
+    a.iter().any(|f| {
+        b.iter()
+            .any(|g| f.elements() == g.elements() && f.additional_mass() == g.additional_mass())
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    fn diff(a: &str, b: &str) -> PeptidoformDiff {
+        let a = Peptidoform::pro_forma(a, None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        let b = Peptidoform::pro_forma(b, None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        a.diff(&b)
+    }
+
+    #[test]
+    fn identical() {
+        let result = diff(
+            "[Acetyl]-PEM[Oxidation]PTIDE",
+            "[Acetyl]-PEM[Oxidation]PTIDE",
+        );
+        assert!(result.is_empty());
+        assert!(result.same_backbone);
+        assert!(result.isobaric);
+    }
+
+    #[test]
+    fn moved_modification() {
+        let result = diff("PEM[Oxidation]PTIDEM", "PEMPTIDEM[Oxidation]");
+        assert!(result.same_backbone);
+        assert!(result.isobaric);
+        assert!(matches!(
+            result.changes.as_slice(),
+            [PeptidoformChange::ModificationMoved {
+                from: SequencePosition::Index(2),
+                to: SequencePosition::Index(8),
+                ..
+            }]
+        ));
+        let result = diff("PEPTIDE", "[Acetyl]-PEPTIDE");
+        assert!(!result.isobaric);
+        assert!(matches!(
+            result.changes.as_slice(),
+            [PeptidoformChange::ModificationAdded {
+                position: SequencePosition::NTerm,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn substitutions() {
+        let result = diff("PEPTIDE", "PEPTLDQ");
+        assert!(!result.same_backbone);
+        assert!(!result.isobaric);
+        assert_eq!(
+            result.changes,
+            [
+                PeptidoformChange::Substitution {
+                    index: 4,
+                    from: AminoAcid::Isoleucine,
+                    to: AminoAcid::Leucine,
+                    isobaric: true,
+                },
+                PeptidoformChange::Substitution {
+                    index: 6,
+                    from: AminoAcid::GlutamicAcid,
+                    to: AminoAcid::Glutamine,
+                    isobaric: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn replacement() {
+        let result = diff("PEPNTIDEK[Oxidation]", "PEPGGTIDEK[Oxidation]");
+        assert!(result.isobaric);
+        assert_eq!(
+            result.changes,
+            [PeptidoformChange::Replacement {
+                from: 3..4,
+                to: 3..5,
+                isobaric: true,
+            }]
+        );
+    }
+}
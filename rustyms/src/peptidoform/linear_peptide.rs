@@ -2,19 +2,23 @@
 
 use crate::{
     checked_aminoacid::CheckedAminoAcid,
-    fragment::{DiagnosticPosition, Fragment, FragmentType, PeptidePosition},
+    error::{Context, CustomError},
+    fragment::{
+        BackboneCFragment, BackboneNFragment, DiagnosticPosition, Fragment, FragmentType,
+        PeptidePosition,
+    },
     glycan::MonoSaccharide,
     helper_functions::{peptide_range_contains, RangeExtension},
     modification::{
-        CrossLinkName, GnoComposition, LinkerSpecificity, Modification, SimpleModification,
-        SimpleModificationInner,
+        CrossLinkName, GnoComposition, LinkerSpecificity, Modification, Ontology,
+        SimpleModification, SimpleModificationInner,
     },
     molecular_charge::{CachedCharge, MolecularCharge},
     peptidoform::*,
-    placement_rule::PlacementRule,
+    placement_rule::{PlacementMismatch, PlacementRule},
     system::usize::Charge,
-    AmbiguousLabel, DiagnosticIon, Element, Model, MolecularFormula, Multi, MultiChemical,
-    NeutralLoss, Protease, SequenceElement, SequencePosition,
+    AmbiguousLabel, AminoAcid, DiagnosticIon, Element, Model, MolecularFormula, Multi,
+    MultiChemical, NeutralLoss, Protease, SequenceElement, SequencePosition,
 };
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
@@ -24,7 +28,7 @@ use std::{
     fmt::{Display, Write},
     marker::PhantomData,
     num::NonZeroU16,
-    ops::{Index, IndexMut, RangeBounds},
+    ops::{Index, IndexMut, Range, RangeBounds, RangeInclusive},
     slice::SliceIndex,
 };
 
@@ -84,7 +88,7 @@ use std::{
 /// PEPTIDE/3[1Zn+2,1H+1]
 /// ```
 ///
-#[derive(PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Peptidoform<Complexity> {
     /// Global isotope modifications, saved as the element and the species that
     /// all occurrence of that element will consist of. For example (N, 15) will
@@ -103,6 +107,18 @@ pub struct Peptidoform<Complexity> {
     modifications_of_unknown_position: Vec<AmbiguousEntry>,
     /// The adduct ions, if specified
     charge_carriers: Option<MolecularCharge>,
+    /// The location in the protein this peptide originates from, if known. This is not taken into
+    /// account when comparing peptides and is not serialized, to keep the serialized form
+    /// compatible with the stored germlines.
+    #[serde(skip)]
+    protein_position: Option<ProteinPosition>,
+    /// The fixed modifications that were placed as a ProForma global modification
+    /// (`<[Carbamidomethyl]@C>`) with the rule used to place them. The modifications themselves
+    /// are placed in the sequence as normal, this is only used to display them as global
+    /// modification again. Like the protein position this is not taken into account when
+    /// comparing peptides and is not serialized.
+    #[serde(skip)]
+    fixed: Vec<(SimpleModification, PlacementRule)>,
     /// The marker indicating which level of complexity this peptide (potentially) uses
     marker: PhantomData<Complexity>,
 }
@@ -130,6 +146,8 @@ impl<Complexity> Default for Peptidoform<Complexity> {
             sequence: Vec::new(),
             modifications_of_unknown_position: Vec::new(),
             charge_carriers: None,
+            protein_position: None,
+            fixed: Vec::new(),
             marker: PhantomData,
         }
     }
@@ -145,6 +163,8 @@ impl<Complexity> Clone for Peptidoform<Complexity> {
             sequence: self.sequence.clone(),
             modifications_of_unknown_position: self.modifications_of_unknown_position.clone(),
             charge_carriers: self.charge_carriers.clone(),
+            protein_position: self.protein_position,
+            fixed: self.fixed.clone(),
             marker: PhantomData,
         }
     }
@@ -178,6 +198,44 @@ impl<Complexity> std::hash::Hash for Peptidoform<Complexity> {
 
 impl<Complexity> Eq for Peptidoform<Complexity> {}
 
+impl<Complexity: PartialOrd> PartialOrd for Peptidoform<Complexity> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.compared_fields().partial_cmp(&other.compared_fields())
+    }
+}
+
+impl<Complexity: Ord> Ord for Peptidoform<Complexity> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.compared_fields().cmp(&other.compared_fields())
+    }
+}
+
+impl<Complexity> Peptidoform<Complexity> {
+    /// All fields that are taken into account when comparing peptides
+    #[allow(clippy::type_complexity)]
+    fn compared_fields(
+        &self,
+    ) -> (
+        &[(Element, Option<NonZeroU16>)],
+        &[SimpleModification],
+        &[Modification],
+        &[Modification],
+        &[SequenceElement<Complexity>],
+        &[AmbiguousEntry],
+        Option<&MolecularCharge>,
+    ) {
+        (
+            &self.global,
+            &self.labile,
+            &self.n_term,
+            &self.c_term,
+            &self.sequence,
+            &self.modifications_of_unknown_position,
+            self.charge_carriers.as_ref(),
+        )
+    }
+}
+
 /// Implement the complexity checks to reduce the complexity of a peptide in a controlled fashion.
 impl<Complexity> Peptidoform<Complexity> {
     /// Check if this peptide does not use any of the features reserved for [`Linked`].
@@ -304,6 +362,8 @@ impl<Complexity> Peptidoform<Complexity> {
                 .collect(),
             modifications_of_unknown_position: self.modifications_of_unknown_position,
             charge_carriers: self.charge_carriers,
+            protein_position: self.protein_position,
+            fixed: self.fixed,
             marker: PhantomData,
         }
     }
@@ -360,6 +420,17 @@ impl<Complexity> Peptidoform<Complexity> {
         self.c_term = term;
     }
 
+    /// Get the location in the protein this peptide originates from, if known. This is set for
+    /// peptides resulting from a digestion.
+    pub const fn get_protein_position(&self) -> Option<&ProteinPosition> {
+        self.protein_position.as_ref()
+    }
+
+    /// Set the location in the protein this peptide originates from
+    pub fn set_protein_position(&mut self, position: Option<ProteinPosition>) {
+        self.protein_position = position;
+    }
+
     /// Get the number of amino acids making up this peptide
     pub fn len(&self) -> usize {
         self.sequence.len()
@@ -405,6 +476,188 @@ impl<Complexity> Peptidoform<Complexity> {
         }
     }
 
+    /// Set the confidence for every residue, for example the local confidence from a de novo
+    /// tool. If the number of given confidences does not match the length of this peptidoform
+    /// nothing is changed and `false` is returned.
+    pub fn set_local_confidence(&mut self, confidence: &[f64]) -> bool {
+        if confidence.len() != self.len() {
+            return false;
+        }
+        for (seq, confidence) in self.sequence.iter_mut().zip(confidence) {
+            seq.confidence = Some(OrderedFloat(*confidence));
+        }
+        true
+    }
+
+    /// Get the confidence for every residue, only if all residues have a confidence set
+    pub fn local_confidence(&self) -> Option<Vec<f64>> {
+        self.sequence
+            .iter()
+            .map(|seq| seq.confidence.map(|c| c.0))
+            .collect()
+    }
+
+    /// Validate if the given modification can be placed at the given position in this peptidoform
+    /// according to its placement rules, see [`SimpleModificationInner::validate_placement`]. The
+    /// N and C terminal positions are checked on the first and last amino acid respectively.
+    /// # Errors
+    /// If none of the placement rules allow this modification at this position, with for every
+    /// rule the reason it does not allow the placement.
+    /// # Panics
+    /// If the position is outside of this peptidoform, or if the peptidoform is empty.
+    pub fn validate_placement(
+        &self,
+        position: SequencePosition,
+        modification: &SimpleModification,
+    ) -> Result<Option<PlacementRule>, Vec<(PlacementRule, PlacementMismatch)>> {
+        modification.validate_placement_in_protein(
+            &self[position],
+            position,
+            self.protein_position.as_ref(),
+        )
+    }
+
+    /// Apply a fixed modification to this peptidoform after the fact, for peptidoforms from
+    /// formats that do not encode fixed modifications. This is equivalent to the ProForma global
+    /// modification `<[modification]@rule>`, so it places the modification on every location that
+    /// matches the given rule, or all locations allowed by the placement rules of the
+    /// modification if no rule is given. Locations that already have this modification are
+    /// skipped, so applying the same fixed modification twice does not place it twice. The
+    /// modification is kept as a global modification, so if all locations selected by the rule
+    /// carry it, displaying this peptidoform as ProForma gives `<[modification]@rule>` instead
+    /// of the modification on every location. It returns all locations where the modification
+    /// was placed.
+    /// # Errors
+    /// If the given rule selects a location where the placement rules of the modification do not
+    /// allow it, see [`Self::validate_placement`], or if no rule is given and the modification
+    /// has no placement rules. In both cases this peptidoform is left unchanged.
+    pub fn apply_fixed_modification(
+        &mut self,
+        modification: &SimpleModification,
+        rule: Option<&PlacementRule>,
+    ) -> Result<Vec<SequencePosition>, CustomError> {
+        if rule.is_none() && modification.placement_rules().is_empty() {
+            return Err(CustomError::error(
+                "Invalid fixed modification",
+                format!(
+                    "The modification {modification} has no placement rules, \
+                     so a rule has to be given to apply it as a fixed modification"
+                ),
+                Context::none(),
+            ));
+        }
+        let mut positions = Vec::new();
+        let protein = self.protein_position.as_ref();
+        if let (Some(first), Some(last)) = (self.sequence.first(), self.sequence.last()) {
+            let candidates = std::iter::once((SequencePosition::NTerm, first))
+                .chain(
+                    self.sequence
+                        .iter()
+                        .enumerate()
+                        .map(|(index, seq)| (SequencePosition::Index(index), seq)),
+                )
+                .chain(std::iter::once((SequencePosition::CTerm, last)));
+            for (position, seq) in candidates {
+                let selected = rule.map_or_else(
+                    || {
+                        modification
+                            .is_possible_in_protein(seq, position, protein)
+                            .any_possible()
+                    },
+                    |rule| rule.is_possible_in_protein(seq, position, protein),
+                );
+                // Terminal positions are only considered if the rule does not already select the side chain
+                let side_chain_selected = match position {
+                    SequencePosition::NTerm => rule.map_or_else(
+                        || {
+                            modification
+                                .is_possible_in_protein(seq, SequencePosition::Index(0), protein)
+                                .any_possible()
+                        },
+                        |rule| {
+                            rule.is_possible_in_protein(seq, SequencePosition::Index(0), protein)
+                        },
+                    ),
+                    SequencePosition::CTerm => rule.map_or_else(
+                        || {
+                            modification
+                                .is_possible_in_protein(
+                                    seq,
+                                    SequencePosition::Index(self.len() - 1),
+                                    protein,
+                                )
+                                .any_possible()
+                        },
+                        |rule| {
+                            rule.is_possible_in_protein(
+                                seq,
+                                SequencePosition::Index(self.len() - 1),
+                                protein,
+                            )
+                        },
+                    ),
+                    SequencePosition::Index(_) => false,
+                };
+                if !selected || side_chain_selected {
+                    continue;
+                }
+                if let Err(reasons) =
+                    modification.validate_placement_in_protein(seq, position, protein)
+                {
+                    return Err(CustomError::error(
+                        "Modification incorrectly placed",
+                        format!(
+                            "The fixed modification {modification} cannot be placed on {}: {}",
+                            match position {
+                                SequencePosition::NTerm => "the N-terminus".to_string(),
+                                SequencePosition::CTerm => "the C-terminus".to_string(),
+                                SequencePosition::Index(index) =>
+                                    format!("the side chain of {} at index {index}", seq.aminoacid),
+                            },
+                            reasons.iter().map(|(_, reason)| reason).join(", ")
+                        ),
+                        Context::none(),
+                    ));
+                }
+                let present = match position {
+                    SequencePosition::NTerm => &self.n_term[..],
+                    SequencePosition::CTerm => &self.c_term[..],
+                    SequencePosition::Index(index) => &self.sequence[index].modifications[..],
+                }
+                .iter()
+                .any(|m| matches!(m, Modification::Simple(m) if m == modification));
+                if !present {
+                    positions.push(position);
+                }
+            }
+        }
+        for position in &positions {
+            self.add_simple_modification(*position, modification.clone());
+        }
+        let rules = rule.map_or_else(
+            || match &**modification {
+                SimpleModificationInner::Database { specificities, .. } => specificities
+                    .iter()
+                    .flat_map(|(rules, _, _)| rules.iter().cloned())
+                    .collect_vec(),
+                _ => Vec::new(),
+            },
+            |rule| vec![rule.clone()],
+        );
+        for rule in rules {
+            self.add_fixed(modification.clone(), rule);
+        }
+        Ok(positions)
+    }
+
+    /// Keep track of a fixed modification that is placed as global modification.
+    pub(super) fn add_fixed(&mut self, modification: SimpleModification, rule: PlacementRule) {
+        let fixed = (modification, rule);
+        if !self.fixed.contains(&fixed) {
+            self.fixed.push(fixed);
+        }
+    }
+
     /// Set the charge carriers, use [`Self::charge_carriers`] unless absolutely necessary.
     pub(super) fn set_charge_carriers(&mut self, charge_carriers: Option<MolecularCharge>) {
         self.charge_carriers = charge_carriers;
@@ -482,24 +735,16 @@ impl<Complexity> Peptidoform<Complexity> {
                     .filter_map(|modification| match modification {
                         Modification::Simple(modification)
                         | Modification::Ambiguous { modification, .. } => match &**modification {
-                            SimpleModificationInner::Database { specificities, .. } => Some(
-                                specificities
-                                    .iter()
-                                    .filter_map(move |(rules, rule_losses, _)| {
-                                        if PlacementRule::any_possible(
-                                            rules,
-                                            aa,
-                                            pos.sequence_index,
-                                        ) {
-                                            Some(rule_losses)
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                    .flatten()
-                                    .map(move |loss| {
-                                        (loss.clone(), peptidoform_index, pos.sequence_index)
-                                    })
+                            SimpleModificationInner::Database { .. } => Some(
+                                modification
+                                    .fragmentation(
+                                        aa,
+                                        pos.sequence_index,
+                                        self.protein_position.as_ref(),
+                                    )
+                                    .0
+                                    .into_iter()
+                                    .map(move |loss| (loss, peptidoform_index, pos.sequence_index))
                                     .collect_vec(),
                             ),
                             _ => None, // TODO: potentially hydrolysed cross-linkers could also have neutral losses
@@ -579,6 +824,26 @@ impl<Complexity> Peptidoform<Complexity> {
             .collect()
     }
 
+    /// Find the reporter ions for all isobaric labels on this peptide
+    fn reporter_ions(&self) -> Vec<&'static (&'static str, MolecularFormula)> {
+        self.n_term
+            .iter()
+            .chain(self.c_term.iter())
+            .chain(self.sequence.iter().flat_map(|s| s.modifications.iter()))
+            .filter_map(|m| match m.simple().map(AsRef::as_ref) {
+                Some(SimpleModificationInner::Database { id, .. })
+                    if id.ontology == Ontology::Unimod =>
+                {
+                    id.id
+                }
+                _ => None,
+            })
+            .unique()
+            .flat_map(crate::mzpaf::isobaric_reporter_ions)
+            .unique_by(|(name, _)| *name)
+            .collect()
+    }
+
     /// Iterate over a range in the peptide and keep track of the position
     pub(super) fn iter(
         &self,
@@ -740,6 +1005,100 @@ impl<Complexity> Peptidoform<Complexity> {
         (formulas * all_ambiguous_options, seen)
     }
 
+    /// Generate the gas-phase rearrangement fragments as set by [`Model::rearrangements`]. The
+    /// `b+H2O` and a/b pair fragments are derived from the given b fragments (without neutral
+    /// losses), the internal fragments are generated from the sequence. Internal fragments that
+    /// contain a cross-link are not generated.
+    fn rearrangement_fragments(
+        &self,
+        fragments: &[Fragment],
+        model: &Model,
+        peptidoform_ion_index: usize,
+        peptidoform_index: usize,
+        all_peptides: &[Peptidoform<Linked>],
+        charge_carriers: &mut CachedCharge,
+    ) -> Vec<Fragment> {
+        let settings = &model.rearrangements;
+        let mut output = Vec::new();
+        if settings.water_gain || settings.a_from_b {
+            let water = NeutralLoss::Gain(molecular_formula!(H 2 O 1));
+            for fragment in fragments.iter().filter(|f| f.neutral_loss.is_empty()) {
+                let FragmentType::b(position) = fragment.ion else {
+                    continue;
+                };
+                let SequencePosition::Index(index) = position.sequence_index else {
+                    continue;
+                };
+                if settings.water_gain
+                    && (position.series_number + 1 == self.len()
+                        || matches!(
+                            self.sequence[index].aminoacid.aminoacid(),
+                            AminoAcid::AsparticAcid | AminoAcid::GlutamicAcid
+                        ))
+                {
+                    output.push(fragment.with_neutral_loss(&water));
+                }
+                if settings.a_from_b && !model.a.location.possible(position) {
+                    output.push(Fragment {
+                        formula: fragment
+                            .formula
+                            .as_ref()
+                            .map(|f| f - &molecular_formula!(C 1 O 1)),
+                        ion: FragmentType::a(position),
+                        ..fragment.clone()
+                    });
+                }
+            }
+        }
+
+        let min = (*settings.internal_lengths.start()).max(2);
+        let max = *settings.internal_lengths.end();
+        if max >= min && self.len() > min + 1 {
+            for start in 1..self.len() - min {
+                for end in start + min - 1..=(start + max - 1).min(self.len() - 2) {
+                    let (formulas, seen) = self.all_masses(
+                        start..=end,
+                        start..=end,
+                        &Multi::default(),
+                        false,
+                        all_peptides,
+                        &[peptidoform_index],
+                        &mut Vec::new(),
+                        model.allow_cross_link_cleavage,
+                        peptidoform_index,
+                    );
+                    if !seen.is_empty() {
+                        continue;
+                    }
+                    let positions = (
+                        PeptidePosition::n(SequencePosition::Index(start), self.len()),
+                        PeptidePosition::n(SequencePosition::Index(end), self.len()),
+                    );
+                    for (n, formulas) in [
+                        (BackboneNFragment::b, formulas.clone()),
+                        (BackboneNFragment::a, formulas - molecular_formula!(C 1 O 1)),
+                    ] {
+                        output.extend(Fragment::generate_all(
+                            &formulas,
+                            peptidoform_ion_index,
+                            peptidoform_index,
+                            &FragmentType::Internal(
+                                Some((n, BackboneCFragment::y)),
+                                positions.0,
+                                positions.1,
+                            ),
+                            &Multi::default(),
+                            &[],
+                            charge_carriers,
+                            settings.internal_charge_range,
+                        ));
+                    }
+                }
+            }
+        }
+        output
+    }
+
     /// Generate the theoretical fragments for this peptide, with the given maximal charge of the fragments, and the given model.
     /// With the global isotope modifications applied.
     /// # Panics
@@ -761,8 +1120,29 @@ impl<Complexity> Peptidoform<Complexity> {
             .as_ref()
             .unwrap_or(&default_charge)
             .into();
+        if model.fixed_charges {
+            let fixed = self
+                .formulas_inner(
+                    peptidoform_index,
+                    all_peptides,
+                    &[],
+                    &mut Vec::new(),
+                    model.allow_cross_link_cleavage,
+                )
+                .0
+                .iter()
+                .map(MolecularFormula::charge)
+                .max()
+                .unwrap_or_default();
+            charge_carriers = charge_carriers.with_fixed_charge(fixed);
+        }
 
         let mut output = Vec::with_capacity(20 * self.sequence.len() + 75); // Empirically derived required size of the buffer (Derived from Hecklib)
+        let residues: Vec<AminoAcid> = self
+            .sequence
+            .iter()
+            .map(|s| s.aminoacid.aminoacid())
+            .collect();
         for sequence_index in 0..self.sequence.len() {
             let position = PeptidePosition::n(SequencePosition::Index(sequence_index), self.len());
             let mut cross_links = Vec::new();
@@ -819,6 +1199,11 @@ impl<Complexity> Peptidoform<Complexity> {
                     (acc.0 * f, acc.1.union(&s).cloned().collect())
                 });
 
+            let ions = model.ions_in_context(
+                position,
+                &residues[..=sequence_index],
+                &residues[sequence_index..],
+            );
             output.append(
                 &mut self.sequence[sequence_index]
                     .aminoacid
@@ -830,7 +1215,7 @@ impl<Complexity> Peptidoform<Complexity> {
                         &mut charge_carriers,
                         SequencePosition::Index(sequence_index),
                         self.sequence.len(),
-                        &model.ions(position),
+                        &ions,
                         peptidoform_ion_index,
                         peptidoform_index,
                         (
@@ -882,6 +1267,15 @@ impl<Complexity> Peptidoform<Complexity> {
                 );
             }
         }
+        output.extend(self.rearrangement_fragments(
+            &output,
+            model,
+            peptidoform_ion_index,
+            peptidoform_index,
+            all_peptides,
+            &mut charge_carriers,
+        ));
+
         for fragment in &mut output {
             fragment.formula = fragment.formula.as_ref().map(|f| {
                 f.with_global_isotope_modifications(&self.global)
@@ -968,6 +1362,40 @@ impl<Complexity> Peptidoform<Complexity> {
             }
         }
 
+        if model.reporter_ions.0 || model.complementary_reporter_ions.0 {
+            // Add the reporter ions and complementary reporter ions of isobaric labels
+            for (name, reporter) in self.reporter_ions() {
+                if model.reporter_ions.0 {
+                    output.extend(
+                        Fragment {
+                            formula: Some(reporter.clone()),
+                            charge: Charge::default(),
+                            ion: FragmentType::Diagnostic(DiagnosticPosition::Reporter),
+                            peptidoform_ion_index: Some(peptidoform_ion_index),
+                            peptidoform_index: Some(peptidoform_index),
+                            neutral_loss: Vec::new(),
+                            deviation: None,
+                            confidence: None,
+                            auxiliary: false,
+                        }
+                        .with_charge_range(&mut charge_carriers, model.reporter_ions.1),
+                    );
+                }
+                if model.complementary_reporter_ions.0 {
+                    output.extend(Fragment::generate_all(
+                        &(&full_precursor - &(reporter + &molecular_formula!(C 1 O 1))),
+                        peptidoform_ion_index,
+                        peptidoform_index,
+                        &FragmentType::ComplementaryReporter((*name).to_string()),
+                        &Multi::default(),
+                        &[],
+                        &mut charge_carriers,
+                        model.complementary_reporter_ions.1,
+                    ));
+                }
+            }
+        }
+
         // Add labile glycan fragments
         for modification in &self.labile {
             match &**modification {
@@ -1157,6 +1585,36 @@ impl<Complexity> Peptidoform<Complexity> {
         f: &mut impl Write,
         show_global_mods: bool,
         specification_compliant: bool,
+    ) -> std::fmt::Result {
+        self.display_inner(f, show_global_mods, false, specification_compliant)
+    }
+
+    /// Display this peptide as ProForma including the local confidence of the residues (see
+    /// [`Self::set_local_confidence`]) as INFO tags (`A[INFO:confidence=0.9]`), which are read
+    /// back when parsing.
+    /// # Errors
+    /// If the formatter supplied errors.
+    /// # Panics
+    /// If there is an ambiguous modification without a definition, this indicates an error in rustyms.
+    pub fn display_with_confidence(
+        &self,
+        f: &mut impl Write,
+        specification_compliant: bool,
+    ) -> std::fmt::Result {
+        self.display_inner(f, true, true, specification_compliant)
+    }
+
+    /// Display this peptide, see [`Self::display`] and [`Self::display_with_confidence`].
+    /// # Errors
+    /// If the formatter supplied errors.
+    /// # Panics
+    /// If there is an ambiguous modification without a definition, this indicates an error in rustyms.
+    fn display_inner(
+        &self,
+        f: &mut impl Write,
+        show_global_mods: bool,
+        show_confidence: bool,
+        specification_compliant: bool,
     ) -> std::fmt::Result {
         if show_global_mods {
             for (element, isotope) in &self.global {
@@ -1168,6 +1626,16 @@ impl<Complexity> Peptidoform<Complexity> {
                 )?;
             }
         }
+        let (fixed, mut hidden) = if show_global_mods {
+            self.displayed_fixed_modifications()
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        for (modification, targets) in fixed {
+            write!(f, "<[")?;
+            modification.display(f, specification_compliant)?;
+            write!(f, "]@{}>", targets.join(","))?;
+        }
         for labile in &self.labile {
             write!(f, "{{{labile}}}")?;
         }
@@ -1260,6 +1728,9 @@ impl<Complexity> Peptidoform<Complexity> {
         }
         let mut any_n = false;
         for m in self.get_n_term() {
+            if take_hidden(&mut hidden, SequencePosition::NTerm, m) {
+                continue;
+            }
             let mut display_ambiguous = false;
 
             if let Modification::Ambiguous { id, .. } = m {
@@ -1282,12 +1753,19 @@ impl<Complexity> Peptidoform<Complexity> {
         }
         let mut last_ambiguous = None;
         for (index, position) in self.sequence.iter().enumerate() {
+            let hidden_here = hidden
+                .iter()
+                .filter(|(p, _)| *p == SequencePosition::Index(index))
+                .map(|(_, m)| *m)
+                .collect_vec();
             placed_ambiguous.extend(position.display(
                 f,
                 &placed_ambiguous,
                 &preferred_ambiguous_position,
                 index,
                 last_ambiguous,
+                &hidden_here,
+                show_confidence,
                 specification_compliant,
             )?);
             last_ambiguous = position.ambiguous;
@@ -1297,6 +1775,9 @@ impl<Complexity> Peptidoform<Complexity> {
         }
         let mut first = true;
         for m in self.get_c_term() {
+            if take_hidden(&mut hidden, SequencePosition::CTerm, m) {
+                continue;
+            }
             let mut display_ambiguous = false;
             if let Modification::Ambiguous { id, .. } = m {
                 display_ambiguous = !placed_ambiguous.contains(id);
@@ -1316,6 +1797,67 @@ impl<Complexity> Peptidoform<Complexity> {
         Ok(())
     }
 
+    /// The fixed modifications that can be displayed as ProForma global modification with their
+    /// targets, and all modifications that are not displayed on their location because of that.
+    /// A fixed modification is only displayed as global modification if all locations selected by
+    /// its rule carry the modification, so that parsing the ProForma again places the
+    /// modification on exactly the same locations.
+    fn displayed_fixed_modifications(
+        &self,
+    ) -> (
+        Vec<(&SimpleModification, Vec<String>)>,
+        HiddenModifications<'_>,
+    ) {
+        let mut global: Vec<(&SimpleModification, Vec<String>)> = Vec::new();
+        let mut hidden: HiddenModifications<'_> = Vec::new();
+        if self.sequence.is_empty() {
+            return (global, hidden);
+        }
+        for (modification, rule) in &self.fixed {
+            let Some(targets) = rule.pro_forma_targets() else {
+                continue;
+            };
+            let positions = self
+                .iter(..)
+                .filter(|(position, seq)| rule.is_possible(seq, position.sequence_index))
+                .map(|(position, _)| position.sequence_index)
+                .collect_vec();
+            let all_present = positions.iter().all(|position| {
+                let present = match position {
+                    SequencePosition::NTerm => &self.n_term[..],
+                    SequencePosition::CTerm => &self.c_term[..],
+                    SequencePosition::Index(index) => &self.sequence[*index].modifications[..],
+                }
+                .iter()
+                .filter(|m| matches!(m, Modification::Simple(m) if m == modification))
+                .count();
+                let already_hidden = hidden
+                    .iter()
+                    .filter(|(p, m)| p == position && *m == modification)
+                    .count();
+                present > already_hidden
+            });
+            if !all_present {
+                continue;
+            }
+            hidden.extend(
+                positions
+                    .into_iter()
+                    .map(|position| (position, modification)),
+            );
+            if let Some((_, existing)) = global.iter_mut().find(|(m, _)| *m == modification) {
+                for target in targets {
+                    if !existing.contains(&target) {
+                        existing.push(target);
+                    }
+                }
+            } else {
+                global.push((modification, targets));
+            }
+        }
+        (global, hidden)
+    }
+
     /// Get the reverse of this peptide
     #[must_use]
     pub fn reverse(&self) -> Self {
@@ -1336,6 +1878,7 @@ impl<Complexity> Peptidoform<Complexity> {
                     ..m
                 })
                 .collect(),
+            protein_position: self.protein_position.map(ProteinPosition::reverse),
             ..self.clone()
         }
     }
@@ -1383,6 +1926,9 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
     /// Get a region of this peptide as a new peptide (with all terminal/global/ambiguous modifications).
     #[must_use]
     pub fn sub_peptide(&self, index: impl RangeBounds<usize>) -> Self {
+        let sequence =
+            self.sequence[(index.start_bound().cloned(), index.end_bound().cloned())].to_vec();
+        let start = index.start_index();
         Self {
             n_term: if index.contains(&0) {
                 self.n_term.clone()
@@ -1394,25 +1940,122 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
             } else {
                 Vec::new()
             },
-            sequence: self.sequence[(index.start_bound().cloned(), index.end_bound().cloned())]
-                .to_vec(),
+            protein_position: self
+                .protein_position
+                .map(|position| position.sub_range(start..start + sequence.len())),
+            sequence,
             ..self.clone()
         }
     }
 
     /// Digest this sequence with the given protease and the given maximal number of missed cleavages.
+    /// With `max_missed_cleavages = 0` this gives only the fully cleaved peptides, every extra
+    /// allowed missed cleavage adds the peptides that span one more cut site. Empty peptides are
+    /// never returned.
     pub fn digest(&self, protease: &Protease, max_missed_cleavages: usize) -> Vec<Self> {
+        self.digest_ranges(protease, max_missed_cleavages, false)
+            .into_iter()
+            .map(|range| self.protein_sub_peptide(range))
+            .collect()
+    }
+
+    /// Semi specifically digest this sequence with the given protease and the given maximal
+    /// number of missed cleavages. This gives all peptides where at least one of the termini is a
+    /// cut site of the protease (or a terminus of this sequence), with a length within the given
+    /// range. The number of missed cleavages is counted from the specific terminus.
+    pub fn semi_digest(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+        length: RangeInclusive<usize>,
+    ) -> Vec<Self> {
+        self.digest_ranges(protease, max_missed_cleavages, true)
+            .into_iter()
+            .filter(|range| length.contains(&range.len()))
+            .map(|range| self.protein_sub_peptide(range))
+            .collect()
+    }
+
+    /// Digest this sequence for the discovery of neo N termini (N terminomics). This gives all
+    /// peptides with a C terminus that is a cut site of the protease (or the C terminus of this
+    /// sequence) and any N terminus, with a length within the given range. The number of missed
+    /// cleavages is counted from the C terminus. See [`ProteinPosition::n_terminus`] to classify
+    /// the N termini of the resulting peptides.
+    pub fn neo_n_terminal_digest(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+        length: RangeInclusive<usize>,
+    ) -> Vec<Self> {
+        self.digest_ranges(protease, max_missed_cleavages, true)
+            .into_iter()
+            .filter(|range| {
+                length.contains(&range.len())
+                    && (range.end == self.len() || protease.cuts_at(&self.sequence, range.end))
+            })
+            .map(|range| self.protein_sub_peptide(range))
+            .collect()
+    }
+
+    /// Get for every residue if it is covered by at least one of the peptides resulting from a
+    /// (semi specific) digestion with the given protease, with the given maximal number of missed
+    /// cleavages and peptide length. This gives the theoretical sequence coverage that can be
+    /// reached with this protease.
+    pub fn digest_coverage(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+        length: RangeInclusive<usize>,
+        semi_specific: bool,
+    ) -> Vec<bool> {
+        let mut coverage = vec![false; self.len()];
+        for range in self.digest_ranges(protease, max_missed_cleavages, semi_specific) {
+            if length.contains(&range.len()) {
+                coverage[range].fill(true);
+            }
+        }
+        coverage
+    }
+
+    /// Get a region of this protein as a new peptide, with the location in the protein set if
+    /// this is not a region of a protein already
+    fn protein_sub_peptide(&self, range: Range<usize>) -> Self {
+        let mut peptide = self.sub_peptide(range.clone());
+        if self.protein_position.is_none() {
+            peptide.protein_position = Some(ProteinPosition::new(range, self.len()));
+        }
+        peptide
+    }
+
+    /// Get all unique (non empty) ranges that result from a (semi specific) digestion
+    fn digest_ranges(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+        semi_specific: bool,
+    ) -> Vec<Range<usize>> {
         let mut sites = vec![0];
         sites.extend_from_slice(&protease.match_locations(&self.sequence));
         sites.push(self.len());
+        sites.dedup();
 
         let mut result = Vec::new();
-
         for (index, start) in sites.iter().enumerate() {
-            for end in sites.iter().skip(index).take(max_missed_cleavages + 1) {
-                result.push(self.sub_peptide((*start)..*end));
+            for end in sites.iter().skip(index + 1).take(max_missed_cleavages + 1) {
+                result.push(*start..*end);
             }
         }
+        if semi_specific {
+            for (index, site) in sites.iter().enumerate() {
+                // The last allowed site in each direction, the sequence is cut anywhere before it
+                let next = sites[(index + max_missed_cleavages + 1).min(sites.len() - 1)];
+                let previous = sites[index.saturating_sub(max_missed_cleavages + 1)];
+                result.extend((site + 1..next).map(|end| *site..end));
+                result.extend((previous + 1..*site).map(|start| start..*site));
+            }
+            result.sort_unstable_by_key(|range| (range.start, range.end));
+            result.dedup();
+        }
         result
     }
 
@@ -1442,7 +2085,24 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
         max_charge: Charge,
         model: &Model,
     ) -> Vec<Fragment> {
-        self.generate_theoretical_fragments_inner(max_charge, model, 0, 0, &[])
+        let peptide = model.resolve_mass_modifications(self);
+        peptide.generate_theoretical_fragments_inner(
+            max_charge,
+            &model.for_peptides(max_charge, std::slice::from_ref(peptide.as_ref())),
+            0,
+            0,
+            &[],
+        )
+    }
+
+    /// Gives all possible formula differences between this peptide and the other peptide
+    /// (self - other), for example to find the chemical explanation of a mass shift between two
+    /// peptidoforms. (Any B/Z will result in multiple possible formulas.)
+    pub fn formula_difference<OtherComplexity: AtMax<Linear>>(
+        &self,
+        other: &Peptidoform<OtherComplexity>,
+    ) -> Multi<MolecularFormula> {
+        (&self.formulas() * &-other.formulas()).unique()
     }
 
     /// Gives the formulas for the whole peptide. With the global isotope modifications applied. (Any B/Z will result in multiple possible formulas.)
@@ -1570,13 +2230,21 @@ impl<Complexity: AtLeast<SimpleLinear>> Peptidoform<Complexity> {
             .iter(range)
             .filter(|(position, seq)| {
                 modification
-                    .is_possible(seq, position.sequence_index)
+                    .is_possible_in_protein(
+                        seq,
+                        position.sequence_index,
+                        self.protein_position.as_ref(),
+                    )
                     .any_possible()
                     && (settings.position.is_none()
                         || settings.position.as_ref().is_some_and(|rules| {
-                            rules
-                                .iter()
-                                .any(|rule| rule.is_possible(seq, position.sequence_index))
+                            rules.iter().any(|rule| {
+                                rule.is_possible_in_protein(
+                                    seq,
+                                    position.sequence_index,
+                                    self.protein_position.as_ref(),
+                                )
+                            })
                         }))
                     && (settings.colocalise_placed_modifications
                         || self[position.sequence_index]
@@ -1714,6 +2382,8 @@ impl<OwnComplexity: AtMax<SemiAmbiguous>> Peptidoform<OwnComplexity> {
                     .collect(),
                 modifications_of_unknown_position: Vec::new(),
                 charge_carriers: self.charge_carriers,
+                protein_position: None,
+                fixed: self.fixed.into_iter().chain(other.fixed).unique().collect(),
                 marker: PhantomData,
             })
         } else {
@@ -1742,6 +2412,8 @@ where
             sequence: value.into_iter().map(std::convert::Into::into).collect(),
             modifications_of_unknown_position: Vec::new(),
             charge_carriers: None,
+            protein_position: None,
+            fixed: Vec::new(),
             marker: PhantomData,
         }
     }
@@ -1819,3 +2491,24 @@ into!(UnAmbiguous => Linear);
 into!(SemiAmbiguous => SimpleLinear);
 into!(UnAmbiguous => SimpleLinear);
 into!(UnAmbiguous => SemiAmbiguous);
+
+/// Modifications that are not displayed on their location because they are displayed as global
+/// modification
+type HiddenModifications<'a> = Vec<(SequencePosition, &'a SimpleModification)>;
+
+/// Remove the given modification from the hidden modifications if it is hidden on the given
+/// location, returns true if it was hidden.
+fn take_hidden(
+    hidden: &mut HiddenModifications<'_>,
+    position: SequencePosition,
+    modification: &Modification,
+) -> bool {
+    let Modification::Simple(modification) = modification else {
+        return false;
+    };
+    hidden
+        .iter()
+        .position(|(p, m)| *p == position && *m == modification)
+        .map(|index| hidden.remove(index))
+        .is_some()
+}
@@ -3,12 +3,15 @@
 mod annotated;
 mod complexity;
 mod compound_peptidoform_ion;
+mod diff;
 mod find_modifications;
 mod linear_peptide;
 mod parse;
 mod parse_modification;
 mod parse_sloppy;
 mod peptidoform_ion;
+mod protein_position;
+mod spans;
 #[cfg(test)]
 mod tests;
 mod validate;
@@ -16,8 +19,11 @@ mod validate;
 pub use annotated::*;
 pub use complexity::*;
 pub use compound_peptidoform_ion::*;
+pub use diff::*;
 pub use find_modifications::*;
 pub use linear_peptide::*;
 pub use parse_modification::*;
 pub use parse_sloppy::SloppyParsingParameters;
 pub use peptidoform_ion::*;
+pub use protein_position::*;
+pub use spans::*;
@@ -17,7 +17,9 @@ use crate::{
     PeptidoformIon, SequenceElement, SequencePosition,
 };
 
-use super::{GlobalModification, Linear, ReturnModification, SemiAmbiguous};
+use super::{
+    GlobalModification, Linear, ProFormaSpanKind, ReturnModification, SemiAmbiguous, SpanRecorder,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 enum End {
@@ -96,23 +98,87 @@ impl CompoundPeptidoformIon {
     ///
     /// # Errors
     /// It fails when the string is not a valid ProForma string.
-    #[allow(clippy::too_many_lines)]
     pub fn pro_forma(
         value: &str,
         custom_database: Option<&CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let mut errors = Vec::new();
+        let result = Self::pro_forma_inner(
+            value,
+            custom_database,
+            &mut errors,
+            &mut SpanRecorder::default(),
+        );
+        // The first error found is the error that stopped the parsing in strict mode
+        errors.into_iter().next().map_or(result, Err)
+    }
+
+    /// Parse a compound peptidoform in the [ProForma specification](https://github.com/HUPO-PSI/ProForma)
+    /// in an error tolerant mode, intended for editors that want to show all problems at once.
+    /// Instead of stopping at the first error the parser recovers from most errors and continues,
+    /// for example by skipping an invalid amino acid or an invalid modification. This returns a
+    /// best-effort parsed peptidoform (if any could be made) and all errors that were found, the
+    /// context of each error highlights the problematic range in the given string. If no errors
+    /// are returned the peptidoform is identical to the one given by [`Self::pro_forma`].
+    ///
+    /// Some errors cannot be recovered from, such as unclosed brackets, invalid global
+    /// modifications, invalid charge states, and invalid cross-links. In that case no peptidoform
+    /// is returned, but all errors found up to that point are.
+    pub fn pro_forma_tolerant(
+        value: &str,
+        custom_database: Option<&CustomDatabase>,
+    ) -> (Option<Self>, Vec<CustomError>) {
+        let mut errors = Vec::new();
+        match Self::pro_forma_inner(
+            value,
+            custom_database,
+            &mut errors,
+            &mut SpanRecorder::default(),
+        ) {
+            Ok(peptidoform) => (Some(peptidoform), errors),
+            Err(error) => {
+                errors.push(error);
+                (None, errors)
+            }
+        }
+    }
+
+    /// Parse a compound peptidoform, pushing all recoverable errors into `errors` and continuing.
+    /// The spans of all elements are recorded in `spans`.
+    /// # Errors
+    /// If an error was found that could not be recovered from.
+    pub(super) fn pro_forma_inner(
+        value: &str,
+        custom_database: Option<&CustomDatabase>,
+        errors: &mut Vec<CustomError>,
+        spans: &mut SpanRecorder,
     ) -> Result<Self, CustomError> {
         let mut peptidoforms = Vec::new();
         // Global modification(s)
-        let (mut start, global_modifications) = global_modifications(value, 0, custom_database)?;
-        let (peptidoform, tail) =
-            Self::parse_peptidoform(value, start, &global_modifications, custom_database)?;
+        let (mut start, global_modifications) =
+            global_modifications(value, 0, custom_database, spans)?;
+        let (peptidoform, tail) = Self::parse_peptidoform(
+            value,
+            start,
+            &global_modifications,
+            custom_database,
+            errors,
+            spans,
+        )?;
         start = tail;
         peptidoforms.push(peptidoform);
 
         // Parse any following chimeric species
         while start < value.len() {
-            let (peptidoform, tail) =
-                Self::parse_peptidoform(value, start, &global_modifications, custom_database)?;
+            spans.peptidoform_ion_index = peptidoforms.len();
+            let (peptidoform, tail) = Self::parse_peptidoform(
+                value,
+                start,
+                &global_modifications,
+                custom_database,
+                errors,
+                spans,
+            )?;
             peptidoforms.push(peptidoform);
             start = tail;
         }
@@ -129,12 +195,15 @@ impl CompoundPeptidoformIon {
     }
 
     /// # Errors
-    /// It returns an error if the line is not a supported ProForma line.
+    /// It returns an error if the line is not a supported ProForma line and the error could not
+    /// be recovered from, all recoverable errors are pushed into `errors`.
     fn parse_peptidoform(
         line: &str,
         mut index: usize,
         global_modifications: &[GlobalModification],
         custom_database: Option<&CustomDatabase>,
+        errors: &mut Vec<CustomError>,
+        spans: &mut SpanRecorder,
     ) -> Result<(PeptidoformIon, usize), CustomError> {
         let mut peptides = Vec::new();
         let mut ending = End::CrossLink;
@@ -144,13 +213,20 @@ impl CompoundPeptidoformIon {
 
         // Parse any following cross-linked species
         while index < line.len() && ending == End::CrossLink {
-            let mut result =
-                Self::parse_linear_peptide(line, index, custom_database, &mut cross_link_lookup)?;
+            spans.peptidoform_index = peptides.len();
+            let mut result = Self::parse_linear_peptide(
+                line,
+                index,
+                custom_database,
+                &mut cross_link_lookup,
+                errors,
+                spans,
+            )?;
             if !result
                 .peptide
                 .apply_global_modifications(global_modifications)
             {
-                return Err(CustomError::error(
+                errors.push(CustomError::error(
                     "Invalid global isotope modification",
                     "There is an invalid global isotope modification",
                     Context::full_line(0, line),
@@ -191,13 +267,16 @@ impl CompoundPeptidoformIon {
     }
 
     /// # Errors
-    /// It returns an error if the line is not a supported ProForma line.
+    /// It returns an error if the line is not a supported ProForma line and the error could not
+    /// be recovered from, all recoverable errors are pushed into `errors`.
     #[allow(clippy::missing_panics_doc)] // Can not panic
     fn parse_linear_peptide(
         line: &str,
         mut index: usize,
         custom_database: Option<&CustomDatabase>,
         cross_link_lookup: &mut CrossLinkLookup,
+        errors: &mut Vec<CustomError>,
+        spans: &mut SpanRecorder,
     ) -> Result<LinearPeptideResult, CustomError> {
         if line.trim().is_empty() {
             return Err(CustomError::error(
@@ -211,6 +290,7 @@ impl CompoundPeptidoformIon {
         let mut c_term = false;
         let mut ambiguous_aa_counter = std::num::NonZeroU32::MIN;
         let mut ambiguous_aa = None;
+        let mut ambiguous_aa_start = None; // Text and sequence index of the open ambiguous amino acid set
         let mut ambiguous_lookup = Vec::new();
         let mut cross_link_found_positions: Vec<(usize, SequencePosition)> = Vec::new();
         let mut ambiguous_found_positions: Vec<(
@@ -224,26 +304,32 @@ impl CompoundPeptidoformIon {
         let mut ending = End::Empty;
 
         // Unknown position mods
-        if let Some(result) =
+        if let Some((buf, result)) =
             global_unknown_position_mods(chars, index, line, custom_database, &mut ambiguous_lookup)
         {
-            let (buf, mods) = result.map_err(|errors| {
-                CustomError::error(
-                    "Some unknown position modifications are invalid",
-                    "See the underlying errors for more details.",
-                    Context::Show {
-                        line: line.to_string(),
-                    },
-                )
-                .with_underlying_errors(errors)
-            })?;
+            for span in &result.0 {
+                spans.push(ProFormaSpanKind::UnknownPositionModification, span.clone());
+            }
+            let result = result.1;
             index = buf;
-
-            unknown_position_modifications = mods;
+            match result {
+                Ok(mods) => unknown_position_modifications = mods,
+                Err(underlying) => errors.push(
+                    CustomError::error(
+                        "Some unknown position modifications are invalid",
+                        "See the underlying errors for more details.",
+                        Context::Show {
+                            line: line.to_string(),
+                        },
+                    )
+                    .with_underlying_errors(underlying),
+                ),
+            }
         }
 
         // Labile modification(s)
-        let (mut index, labile) = labile_modifications(line, index, custom_database)?;
+        let (mut index, labile) =
+            labile_modifications(line, index, custom_database, errors, spans)?;
         peptide = peptide.labile(labile);
 
         // N term modification
@@ -253,6 +339,10 @@ impl CompoundPeptidoformIon {
                     "No valid closing delimiter, an N terminal modification should be closed by ']-'",
                     Context::line(None, line, index, 1),
                 ))?;
+            spans.push(
+                ProFormaSpanKind::Modification(SequencePosition::NTerm),
+                index..end_index,
+            );
             if let Some(m) = SimpleModificationInner::try_from(
                 line,
                 index + 1..end_index - 1,
@@ -260,22 +350,28 @@ impl CompoundPeptidoformIon {
                 cross_link_lookup,
                 custom_database,
             )
-            .map(|m| match m.0 {
-                ReturnModification::Defined(simple) => Some(simple),
-                ReturnModification::CrossLinkReferenced(id) => {
-                    cross_link_found_positions.push((id, SequencePosition::NTerm));
+            .map_or_else(
+                |error| {
+                    errors.push(error);
                     None
-                }
-                ReturnModification::Ambiguous(id, localisation_score, preferred) => {
-                    ambiguous_found_positions.push((
-                        SequencePosition::NTerm,
-                        preferred,
-                        id,
-                        localisation_score,
-                    ));
-                    None
-                }
-            })? {
+                },
+                |m| match m.0 {
+                    ReturnModification::Defined(simple) => Some(simple),
+                    ReturnModification::CrossLinkReferenced(id) => {
+                        cross_link_found_positions.push((id, SequencePosition::NTerm));
+                        None
+                    }
+                    ReturnModification::Ambiguous(id, localisation_score, preferred) => {
+                        ambiguous_found_positions.push((
+                            SequencePosition::NTerm,
+                            preferred,
+                            id,
+                            localisation_score,
+                        ));
+                        None
+                    }
+                },
+            ) {
                 peptide.add_simple_n_term(m);
             }
             index = end_index + 1;
@@ -287,20 +383,25 @@ impl CompoundPeptidoformIon {
             match (c_term, chars[index]) {
                 (false, b'(') if chars.get(index + 1) == Some(&b'?') => {
                     if braces_start.is_some() {
-                        return Err(CustomError::error(
+                        errors.push(CustomError::error(
                             "Invalid ambiguous amino acid set",
                             "Ambiguous amino acid sets cannot be nested within ranged ambiguous modifications",
                             Context::line(None, line, index, 1),
                         ));
+                        index += 2;
+                        continue;
                     }
                     if ambiguous_aa.is_some() {
-                        return Err(CustomError::error(
+                        errors.push(CustomError::error(
                             "Invalid ambiguous amino acid set",
                             "Ambiguous amino acid sets cannot be nested within ambiguous amino acid sets",
                             Context::line(None, line, index, 1),
                         ));
+                        index += 2;
+                        continue;
                     }
                     ambiguous_aa = Some(ambiguous_aa_counter);
+                    ambiguous_aa_start = Some((index, peptide.len()));
                     ambiguous_aa_counter = ambiguous_aa_counter.checked_add(1).ok_or_else(|| CustomError::error(
                         "Invalid ambiguous amino acid set",
                         format!("There are too many ambiguous amino acid sets, there can only be {} in one linear peptide", std::num::NonZeroU32::MAX),
@@ -310,22 +411,34 @@ impl CompoundPeptidoformIon {
                 }
                 (false, b')') if ambiguous_aa.is_some() => {
                     ambiguous_aa = None;
+                    if let Some((start, start_index)) = ambiguous_aa_start.take() {
+                        spans.push(
+                            ProFormaSpanKind::AmbiguousAminoAcids(
+                                start_index..=peptide.len().saturating_sub(1),
+                            ),
+                            start..index + 1,
+                        );
+                    }
                     index += 1;
                 }
                 (false, b'(') => {
                     if braces_start.is_some() {
-                        return Err(CustomError::error(
+                        errors.push(CustomError::error(
                             "Invalid ranged ambiguous modification",
                             "Ranged ambiguous modifications cannot be nested within ranged ambiguous modifications",
                             Context::line(None, line, index, 1),
                         ));
+                        index += 1;
+                        continue;
                     }
                     if ambiguous_aa.is_some() {
-                        return Err(CustomError::error(
+                        errors.push(CustomError::error(
                             "Invalid ranged ambiguous modification",
                             "Ranged ambiguous modifications cannot be nested within ambiguous amino acid sets",
                             Context::line(None, line, index, 1),
                         ));
+                        index += 1;
+                        continue;
                     }
                     braces_start = Some(peptide.len());
                     index += 1;
@@ -335,37 +448,52 @@ impl CompoundPeptidoformIon {
                     braces_start = None;
                     index += 1;
                     while chars.get(index) == Some(&b'[') {
-                        let end_index = end_of_enclosure(line, index+1, b'[', b']').ok_or_else(||CustomError::error(
-                            "Invalid ranged ambiguous modification",
-                            "No valid closing delimiter",
-                            Context::line(None, line, index, 1),
-                        ))?;
+                        let end_index =
+                            end_of_enclosure(line, index + 1, b'[', b']').ok_or_else(|| {
+                                CustomError::error(
+                                    "Invalid ranged ambiguous modification",
+                                    "No valid closing delimiter",
+                                    Context::line(None, line, index, 1),
+                                )
+                            })?;
+                        spans.push(
+                            ProFormaSpanKind::RangedModification(
+                                start..=peptide.len().saturating_sub(1),
+                            ),
+                            index..end_index + 1,
+                        );
                         let modification = SimpleModificationInner::try_from(
                             line, index + 1..end_index,
                             &mut ambiguous_lookup, cross_link_lookup, custom_database,
-                        )?.0.defined().ok_or_else(|| CustomError::error(
+                        ).and_then(|m| m.0.defined().ok_or_else(|| CustomError::error(
                             "Invalid ranged ambiguous modification",
                             "A ranged ambiguous modification has to be fully defined, so no ambiguous modification is allowed",
                             Context::line(None, line, index, 1),
-                        ))?;
+                        )));
                         index = end_index + 1;
-                        ranged_unknown_position_modifications.push((
-                            start,
-                            peptide.len().saturating_sub(1),
-                            modification,
-                        ));
+                        match modification {
+                            Ok(modification) => ranged_unknown_position_modifications.push((
+                                start,
+                                peptide.len().saturating_sub(1),
+                                modification,
+                            )),
+                            Err(error) => errors.push(error),
+                        }
                     }
                 }
                 (false, b'/') => {
                     // Chimeric peptide
-                    if chars.get(index+1) == Some(&b'/') {
+                    if chars.get(index + 1) == Some(&b'/') {
+                        spans.push(ProFormaSpanKind::CrossLinkSeparator, index..index + 2);
                         index += 2; // Potentially this can be followed by another peptide
                         ending = End::CrossLink;
                     } else {
                         let (buf, charge_carriers) = parse_charge_state(line, index)?;
+                        spans.push(ProFormaSpanKind::ChargeState, index..buf);
                         index = buf;
                         peptide = peptide.charge_carriers(Some(charge_carriers));
                         if index < chars.len() && chars[index] == b'+' {
+                            spans.push(ProFormaSpanKind::ChimericSeparator, index..index + 1);
                             index += 1; // Potentially this can be followed by another peptide
                             ending = End::Chimeric;
                         }
@@ -373,45 +501,88 @@ impl CompoundPeptidoformIon {
                     break;
                 }
                 (is_c_term, b'[') => {
-                    let end_index = end_of_enclosure(line, index+1, b'[', b']').ok_or_else(||CustomError::error(
-                        "Invalid modification",
-                        "No valid closing delimiter",
-                        Context::line(None, line, index, 1),
-                    ))?;
-                    let (modification,_) = SimpleModificationInner::try_from(
-                        line, index + 1..end_index,
-                        &mut ambiguous_lookup, cross_link_lookup, custom_database,
-                    )?;
-                    let start_index = index +1;
+                    let end_index =
+                        end_of_enclosure(line, index + 1, b'[', b']').ok_or_else(|| {
+                            CustomError::error(
+                                "Invalid modification",
+                                "No valid closing delimiter",
+                                Context::line(None, line, index, 1),
+                            )
+                        })?;
+                    spans.push(
+                        ProFormaSpanKind::Modification(if is_c_term {
+                            SequencePosition::CTerm
+                        } else {
+                            SequencePosition::Index(peptide.len().saturating_sub(1))
+                        }),
+                        index..end_index + 1,
+                    );
+                    // The local confidence as written by the Display implementation of SequenceElement
+                    if let (false, Some(aa), Some(confidence)) = (
+                        is_c_term,
+                        peptide.sequence_mut().last_mut(),
+                        local_confidence(&line[index + 1..end_index]),
+                    ) {
+                        aa.confidence = Some(OrderedFloat(confidence));
+                        index = end_index + 1;
+                        continue;
+                    }
+                    let modification = SimpleModificationInner::try_from(
+                        line,
+                        index + 1..end_index,
+                        &mut ambiguous_lookup,
+                        cross_link_lookup,
+                        custom_database,
+                    );
+                    let start_index = index + 1;
                     index = end_index + 1;
+                    let modification = match modification {
+                        Ok((modification, _)) => Some(modification),
+                        Err(error) => {
+                            errors.push(error);
+                            None
+                        }
+                    };
                     if is_c_term {
-                        if let Some(m) =
-                            match modification {
-                                ReturnModification::Defined(simple) => Ok(Some(simple)),
-                                ReturnModification::CrossLinkReferenced(id) =>
-                                    {cross_link_found_positions.push((id, SequencePosition::CTerm)); Ok(None)},
-                                ReturnModification::Ambiguous(id, localisation_score, preferred) => {
-                                    ambiguous_found_positions.push((
-                                        SequencePosition::CTerm,
-                                        preferred,
-                                        id,
-                                        localisation_score,
-                                    ));
-                                    Ok(None)
-                                }
-                            }? {
+                        if let Some(m) = match modification {
+                            Some(ReturnModification::Defined(simple)) => Some(simple),
+                            Some(ReturnModification::CrossLinkReferenced(id)) => {
+                                cross_link_found_positions.push((id, SequencePosition::CTerm));
+                                None
+                            }
+                            Some(ReturnModification::Ambiguous(
+                                id,
+                                localisation_score,
+                                preferred,
+                            )) => {
+                                ambiguous_found_positions.push((
+                                    SequencePosition::CTerm,
+                                    preferred,
+                                    id,
+                                    localisation_score,
+                                ));
+                                None
+                            }
+                            None => None,
+                        } {
                             peptide.add_simple_c_term(m);
                         }
 
-                        if index + 1 < chars.len() && chars[index] == b'/' && chars[index+1] != b'/' {
+                        if index + 1 < chars.len()
+                            && chars[index] == b'/'
+                            && chars[index + 1] != b'/'
+                        {
                             let (buf, charge_carriers) = parse_charge_state(line, index)?;
+                            spans.push(ProFormaSpanKind::ChargeState, index..buf);
                             index = buf;
                             peptide = peptide.charge_carriers(Some(charge_carriers));
                         }
                         if index < chars.len() && chars[index] == b'+' {
+                            spans.push(ProFormaSpanKind::ChimericSeparator, index..index + 1);
                             index += 1; // If a peptide in a chimeric definition contains a C terminal modification
                             ending = End::Chimeric;
-                        } else if index + 1 < chars.len() && chars[index..=index+1] == *b"//" {
+                        } else if index + 1 < chars.len() && chars[index..=index + 1] == *b"//" {
+                            spans.push(ProFormaSpanKind::CrossLinkSeparator, index..index + 2);
                             index += 2; // If a peptide in a cross-linked definition contains a C terminal modification
                             ending = End::CrossLink;
                         }
@@ -419,22 +590,37 @@ impl CompoundPeptidoformIon {
                         break;
                     }
 
-                    if let Some((sequence_index, aa)) = peptide.sequence_mut().iter_mut().enumerate().next_back() {
+                    if let Some((sequence_index, aa)) =
+                        peptide.sequence_mut().iter_mut().enumerate().next_back()
+                    {
                         match modification {
-                            ReturnModification::Defined(m) => aa.modifications.push(Modification::Simple(m)),
-                            ReturnModification::Ambiguous(id, localisation_score, preferred) =>
-                                ambiguous_found_positions.push((SequencePosition::Index(sequence_index), preferred, id, localisation_score)),
-                            ReturnModification::CrossLinkReferenced(id) =>
-                                cross_link_found_positions.push((id, SequencePosition::Index(sequence_index))),
+                            Some(ReturnModification::Defined(m)) => {
+                                aa.modifications.push(Modification::Simple(m));
+                            }
+                            Some(ReturnModification::Ambiguous(
+                                id,
+                                localisation_score,
+                                preferred,
+                            )) => ambiguous_found_positions.push((
+                                SequencePosition::Index(sequence_index),
+                                preferred,
+                                id,
+                                localisation_score,
+                            )),
+                            Some(ReturnModification::CrossLinkReferenced(id)) => {
+                                cross_link_found_positions
+                                    .push((id, SequencePosition::Index(sequence_index)));
+                            }
+                            None => (),
                         }
                     } else {
-                        return Err(
+                        errors.push(
                             CustomError::error(
                                 "Invalid modification",
                                 "A modification cannot be placed before any amino acid, did you want to use an N terminal modification ('[mod]-AA..')? or did you want a modification of unknown position ('[mod]?AA..')?",
                                 Context::line(None, line, start_index, index - start_index - 1),
                             )
-                        )
+                        );
                     }
                 }
                 (false, b'-') => {
@@ -443,48 +629,56 @@ impl CompoundPeptidoformIon {
                 }
                 (false, b'+') => {
                     // Chimeric spectrum stop for now, remove the plus
+                    spans.push(ProFormaSpanKind::ChimericSeparator, index..index + 1);
                     index += 1;
                     ending = End::Chimeric;
                     break;
                 }
                 (false, ch) => {
-                    peptide.sequence_mut().push(SequenceElement::new(
-                        CheckedAminoAcid::<SemiAmbiguous>::try_from(ch).map_err(|()| CustomError::error(
+                    if let Ok(aa) = CheckedAminoAcid::<SemiAmbiguous>::try_from(ch) {
+                        spans.push(ProFormaSpanKind::AminoAcid(peptide.len()), index..index + 1);
+                        peptide
+                            .sequence_mut()
+                            .push(SequenceElement::new(aa.into(), ambiguous_aa));
+                        index += 1;
+                    } else {
+                        errors.push(CustomError::error(
                             "Invalid amino acid",
                             "This character is not a valid amino acid",
                             Context::line(None, line, index, 1),
-                        ))?.into(),
-                        ambiguous_aa,
-                    ));
-                    index += 1;
+                        ));
+                        // Skip the full character, which could be multiple bytes
+                        index += line[index..].chars().next().map_or(1, char::len_utf8);
+                    }
                 }
                 (true, _) => {
-                    return Err(
+                    errors.push(
                         CustomError::error(
                             "Parsing error",
                             "A singular hyphen cannot exist ('-'), if this is part of a c-terminus follow the format 'AA-[modification]'",
                             Context::line(None, line, index, 1),
                         )
-                    )
+                    );
+                    c_term = false; // Continue parsing the rest as if the hyphen was not there
                 }
             }
         }
         if c_term {
-            return Err(CustomError::error(
+            errors.push(CustomError::error(
                 "Invalid peptide",
                 "A single hyphen cannot end the definition, if a C terminal modification is intended use 'SEQ-[MOD]'",
                 Context::line(None, line, line.len().saturating_sub(2), 1),
             ));
         }
         if let Some(pos) = braces_start {
-            return Err(CustomError::error(
+            errors.push(CustomError::error(
                 "Invalid peptide",
                 format!("Unclosed brace at amino acid position {pos}"),
                 Context::full_line(0, line),
             ));
         }
         if ambiguous_aa.is_some() {
-            return Err(CustomError::error(
+            errors.push(CustomError::error(
                 "Invalid peptide",
                 "Unclosed ambiguous amino acid group",
                 Context::full_line(0, line),
@@ -508,14 +702,23 @@ impl CompoundPeptidoformIon {
                 .map(|(index, _, _, score)| (*index, *score))
                 .collect_vec();
             let preferred = ambiguous.iter().find_map(|p| p.1.then_some(p.0));
-            if !peptide.add_ambiguous_modification(ambiguous_lookup[id].modification.clone().ok_or_else(||
-                CustomError::error(
+            let Some(modification) = ambiguous_lookup[id].modification.clone() else {
+                errors.push(CustomError::error(
                     "Invalid ambiguous modification",
                     format!("Ambiguous modification {} did not have a definition for the actual modification", ambiguous_lookup[id].name),
                     Context::full_line(0, line),
-                )
-                )?, Some(ambiguous_lookup[id].name.clone()), &positions, preferred, None,  true) {
-                return Err(CustomError::error(
+                ));
+                continue;
+            };
+            if !peptide.add_ambiguous_modification(
+                modification,
+                Some(ambiguous_lookup[id].name.clone()),
+                &positions,
+                preferred,
+                None,
+                true,
+            ) {
+                errors.push(CustomError::error(
                     "Modification of unknown position cannot be placed", 
                     format!("There is no position where this ambiguous modification {} can be placed based on the placement rules in the database.", ambiguous_lookup[id].name),
                     Context::full_line(0, line),
@@ -523,13 +726,19 @@ impl CompoundPeptidoformIon {
             }
         }
 
-        peptide.apply_unknown_position_modification(
-            &unknown_position_modifications,
-            &ambiguous_lookup,
-        )?;
-        peptide
-            .apply_ranged_unknown_position_modification(&ranged_unknown_position_modifications)?;
-        peptide.enforce_modification_rules()?;
+        if let Err(error) = peptide
+            .apply_unknown_position_modification(&unknown_position_modifications, &ambiguous_lookup)
+        {
+            errors.push(error);
+        }
+        if let Err(error) = peptide
+            .apply_ranged_unknown_position_modification(&ranged_unknown_position_modifications)
+        {
+            errors.push(error);
+        }
+        if let Err(error) = peptide.enforce_modification_rules() {
+            errors.push(error);
+        }
 
         Ok(LinearPeptideResult {
             peptide,
@@ -540,13 +749,14 @@ impl CompoundPeptidoformIon {
     }
 }
 
-/// Parse global modifications
+/// Parse global modifications, the span of each global modification is recorded in `spans`.
 /// # Errors
 /// If the global modifications are not defined to the specification
 pub(super) fn global_modifications(
     line: &str,
     mut index: usize,
     custom_database: Option<&CustomDatabase>,
+    spans: &mut SpanRecorder,
 ) -> Result<(usize, Vec<GlobalModification>), CustomError> {
     let chars = line.as_bytes();
     let mut global_modifications = Vec::new();
@@ -638,6 +848,7 @@ pub(super) fn global_modifications(
             global_modifications.push(GlobalModification::Isotope(el, num));
         }
 
+        spans.push(ProFormaSpanKind::GlobalModification, index..end_index + 1);
         index = end_index + 1;
     }
     Ok((index, global_modifications))
@@ -698,10 +909,16 @@ pub fn parse_placement_rules(
     Ok(result)
 }
 
-/// If the text is recognised as a unknown mods list it is Some(..), if it has errors during parsing Some(Err(..))
-/// The returned happy path contains the mods and the index from where to continue parsing.
-/// # Errors
-/// Give all errors when the text cannot be read as mods of unknown position.
+/// The text spans of the modifications of unknown position and the parsed modifications (as indices
+/// into the ambiguous lookup), or all errors found while parsing them
+type UnknownPositionModifications = (
+    Vec<std::ops::Range<usize>>,
+    Result<Vec<usize>, Vec<CustomError>>,
+);
+
+/// If the text is recognised as a unknown mods list it is Some(..), if it has errors during parsing Some((index, Err(..)))
+/// The returned value contains the index from where to continue parsing and the mods, or all
+/// errors when the text cannot be read as mods of unknown position.
 /// # Panics
 /// Breaks if the text is not valid UTF-8
 pub(super) fn global_unknown_position_mods(
@@ -710,9 +927,10 @@ pub(super) fn global_unknown_position_mods(
     line: &str,
     custom_database: Option<&CustomDatabase>,
     ambiguous_lookup: &mut AmbiguousLookup,
-) -> Option<Result<(usize, Vec<usize>), Vec<CustomError>>> {
+) -> Option<(usize, UnknownPositionModifications)> {
     let mut index = start;
     let mut modifications = Vec::new();
+    let mut spans = Vec::new();
     let mut errs = Vec::new();
     let mut cross_link_lookup = Vec::new();
 
@@ -720,6 +938,7 @@ pub(super) fn global_unknown_position_mods(
     while chars.get(index) == Some(&b'[') {
         let start_index = index;
         index = next_char(chars, index + 1, b']')? + 1;
+        spans.push(start_index..index);
         let id = match SimpleModificationInner::try_from(
             std::str::from_utf8(chars).unwrap(),
             start_index + 1..index - 1,
@@ -756,6 +975,9 @@ pub(super) fn global_unknown_position_mods(
         let number = if chars.get(index) == Some(&b'^') {
             if let Some((len, num)) = next_num(chars, index + 1, false) {
                 index += len + 1;
+                if let Some(span) = spans.last_mut() {
+                    span.end = index; // Include the copy number in the span
+                }
                 if num < 0 {
                     errs.push(
                         CustomError::error("Invalid unknown position modification", "A modification of unknown position with multiple copies cannot have more a negative number of copies", Context::line(None, std::str::from_utf8(chars).unwrap(), index, 1)));
@@ -788,11 +1010,17 @@ pub(super) fn global_unknown_position_mods(
         }
     }
     if chars.get(index) == Some(&b'?') {
-        Some(if errs.is_empty() {
-            Ok((index + 1, modifications))
-        } else {
-            Err(errs)
-        })
+        Some((
+            index + 1,
+            (
+                spans,
+                if errs.is_empty() {
+                    Ok(modifications)
+                } else {
+                    Err(errs)
+                },
+            ),
+        ))
     } else {
         ambiguous_lookup.clear(); // Any ambiguous N terminal modification was incorrectly already added to the lookup
         None
@@ -800,12 +1028,16 @@ pub(super) fn global_unknown_position_mods(
 }
 
 /// Parse labile modifications `{mod}{mod2}`. These are assumed to fall off from the peptide in the MS.
+/// Any invalid modification is skipped and its error pushed into `errors`, the span of each
+/// modification is recorded in `spans`.
 /// # Errors
-/// If the mods are not followed by a closing brace. Or if the mods are ambiguous.
+/// If the mods are not followed by a closing brace.
 fn labile_modifications(
     line: &str,
     mut index: usize,
     custom_database: Option<&CustomDatabase>,
+    errors: &mut Vec<CustomError>,
+    spans: &mut SpanRecorder,
 ) -> Result<(usize, Vec<SimpleModification>), CustomError> {
     let chars = line.as_bytes();
     let mut labile = Vec::new();
@@ -817,30 +1049,41 @@ fn labile_modifications(
                 Context::line(None, line, index, 1),
             )
         })?;
+        spans.push(ProFormaSpanKind::LabileModification, index..end_index + 1);
 
-        labile.push(
-            SimpleModificationInner::try_from(
-                line,
-                index + 1..end_index,
-                &mut Vec::new(),
-                &mut Vec::new(),
-                custom_database,
-            )
-            .and_then(|m| {
-                m.0.defined().ok_or_else(|| {
-                    CustomError::error(
-                        "Invalid labile modification",
-                        "A labile modification cannot be ambiguous or a cross-linker",
-                        Context::line(None, line, index + 1, end_index - 1 - index),
-                    )
-                })
-            })?,
-        );
+        match SimpleModificationInner::try_from(
+            line,
+            index + 1..end_index,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            custom_database,
+        )
+        .and_then(|m| {
+            m.0.defined().ok_or_else(|| {
+                CustomError::error(
+                    "Invalid labile modification",
+                    "A labile modification cannot be ambiguous or a cross-linker",
+                    Context::line(None, line, index + 1, end_index - 1 - index),
+                )
+            })
+        }) {
+            Ok(modification) => labile.push(modification),
+            Err(error) => errors.push(error),
+        }
         index = end_index + 1;
     }
     Ok((index, labile))
 }
 
+/// Parse a local confidence INFO tag (`INFO:confidence=0.9`) as written for a [`SequenceElement`].
+fn local_confidence(text: &str) -> Option<f64> {
+    let (head, tail) = text.split_once('=')?;
+    head.trim()
+        .eq_ignore_ascii_case("info:confidence")
+        .then(|| tail.trim().parse::<f64>().ok())
+        .flatten()
+}
+
 /// Parse a charge state `/2` or more complex ones like `/2[+2Na+]`.
 /// Assumes the text starts with `/`.
 /// # Errors
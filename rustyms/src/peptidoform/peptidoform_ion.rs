@@ -64,14 +64,23 @@ impl PeptidoformIon {
         model: &Model,
         peptidoform_ion_index: usize,
     ) -> Vec<Fragment> {
+        let resolved: Option<Vec<_>> = model.mass_modification_resolution.is_some().then(|| {
+            self.peptidoforms()
+                .iter()
+                .map(|p| model.resolve_mass_modifications(p).into_owned())
+                .collect()
+        });
+        let peptidoforms = resolved.as_deref().unwrap_or(&self.0);
+        let model = model.for_peptides(max_charge, peptidoforms);
+        let model = model.as_ref();
         let mut base = Vec::new();
-        for (index, peptide) in self.peptidoforms().iter().enumerate() {
+        for (index, peptide) in peptidoforms.iter().enumerate() {
             base.extend(peptide.generate_theoretical_fragments_inner(
                 max_charge,
                 model,
                 peptidoform_ion_index,
                 index,
-                &self.0,
+                peptidoforms,
             ));
         }
         base
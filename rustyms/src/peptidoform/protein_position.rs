@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AminoAcid, Protease, SequenceElement};
+
+/// The location of a peptidoform in the protein it originates from. This is set on the
+/// peptidoforms resulting from a digestion (see [`crate::Peptidoform::digest`]) and is used to
+/// only allow modifications with protein terminal placement rules (see
+/// [`crate::placement_rule::Position::ProteinNTerm`]) on peptidoforms that are located at that
+/// protein terminus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ProteinPosition {
+    /// The sequence index in the protein of the first amino acid
+    pub start: usize,
+    /// The sequence index in the protein after the last amino acid (exclusive)
+    pub end: usize,
+    /// The length of the protein
+    pub protein_length: usize,
+}
+
+impl ProteinPosition {
+    /// Create a new protein position for the given range in a protein of the given length
+    pub const fn new(range: Range<usize>, protein_length: usize) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+            protein_length,
+        }
+    }
+
+    /// The sequence indices in the protein
+    pub const fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Check if the peptidoform starts at the protein N terminus
+    pub const fn is_protein_n_term(&self) -> bool {
+        self.start == 0
+    }
+
+    /// Check if the peptidoform ends at the protein C terminus
+    pub const fn is_protein_c_term(&self) -> bool {
+        self.end == self.protein_length
+    }
+
+    /// Determine the origin of the N terminus of this peptidoform, given the full protein
+    /// sequence and the protease used for digestion
+    pub fn n_terminus<T>(
+        &self,
+        protein: &[SequenceElement<T>],
+        protease: &Protease,
+    ) -> PeptideTerminus {
+        if self.start == 0 {
+            PeptideTerminus::Protein
+        } else if self.start == 1
+            && protein
+                .first()
+                .is_some_and(|s| s.aminoacid.aminoacid() == AminoAcid::Methionine)
+        {
+            PeptideTerminus::InitiatorMethionineRemoved
+        } else if protease.cuts_at(protein, self.start) {
+            PeptideTerminus::Specific
+        } else {
+            PeptideTerminus::NonSpecific
+        }
+    }
+
+    /// Determine the origin of the C terminus of this peptidoform, given the full protein
+    /// sequence and the protease used for digestion
+    pub fn c_terminus<T>(
+        &self,
+        protein: &[SequenceElement<T>],
+        protease: &Protease,
+    ) -> PeptideTerminus {
+        if self.end == self.protein_length {
+            PeptideTerminus::Protein
+        } else if protease.cuts_at(protein, self.end) {
+            PeptideTerminus::Specific
+        } else {
+            PeptideTerminus::NonSpecific
+        }
+    }
+
+    /// The position of a region of this peptidoform, with the range given in indices in the
+    /// peptidoform
+    #[must_use]
+    pub(crate) const fn sub_range(self, range: Range<usize>) -> Self {
+        Self {
+            start: self.start + range.start,
+            end: self.start + range.end,
+            protein_length: self.protein_length,
+        }
+    }
+
+    /// The position of this peptidoform in the reversed protein
+    #[must_use]
+    pub(crate) const fn reverse(self) -> Self {
+        Self {
+            start: self.protein_length - self.end,
+            end: self.protein_length - self.start,
+            protein_length: self.protein_length,
+        }
+    }
+}
+
+/// The origin of a terminus of a peptidoform, see [`ProteinPosition::n_terminus`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PeptideTerminus {
+    /// The terminus of the protein
+    Protein,
+    /// The protein N terminus after removal of the initiator methionine
+    InitiatorMethionineRemoved,
+    /// A cut site of the protease
+    Specific,
+    /// Any other location, for example a neo N terminus from endogenous proteolysis
+    NonSpecific,
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        modification::Ontology,
+        placement_rule::{PlacementMismatch, Position},
+        Peptidoform, Protease, SequencePosition,
+    };
+
+    #[test]
+    fn digest_provenance() {
+        let trypsin: Protease = "trypsin".parse().unwrap();
+        let protein = Peptidoform::pro_forma("MPEPTIDEKMAPEK", None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        let peptides = protein.digest(&trypsin, 0);
+        assert_eq!(
+            peptides[0].get_protein_position(),
+            Some(&ProteinPosition::new(0..9, 14))
+        );
+        assert_eq!(
+            peptides[1].get_protein_position(),
+            Some(&ProteinPosition::new(9..14, 14))
+        );
+        assert!(peptides[1]
+            .get_protein_position()
+            .unwrap()
+            .is_protein_c_term());
+        assert_eq!(
+            peptides[1].sub_peptide(1..3).get_protein_position(),
+            Some(&ProteinPosition::new(10..12, 14))
+        );
+        assert_eq!(
+            peptides[0].reverse().get_protein_position(),
+            Some(&ProteinPosition::new(5..14, 14))
+        );
+        let sequence = protein.sequence();
+        assert_eq!(
+            peptides[1]
+                .get_protein_position()
+                .unwrap()
+                .n_terminus(sequence, &trypsin),
+            PeptideTerminus::Specific
+        );
+        assert_eq!(
+            ProteinPosition::new(1..9, 14).n_terminus(sequence, &trypsin),
+            PeptideTerminus::InitiatorMethionineRemoved
+        );
+        assert_eq!(
+            ProteinPosition::new(3..9, 14).n_terminus(sequence, &trypsin),
+            PeptideTerminus::NonSpecific
+        );
+        assert_eq!(
+            ProteinPosition::new(3..9, 14).c_terminus(sequence, &trypsin),
+            PeptideTerminus::Specific
+        );
+        assert_eq!(
+            ProteinPosition::new(3..8, 14).c_terminus(sequence, &trypsin),
+            PeptideTerminus::NonSpecific
+        );
+        assert_eq!(
+            peptides[1]
+                .get_protein_position()
+                .unwrap()
+                .c_terminus(sequence, &trypsin),
+            PeptideTerminus::Protein
+        );
+        // The provenance is not taken into account when comparing peptides
+        assert_eq!(
+            peptides[1],
+            Peptidoform::pro_forma("MAPEK", None)
+                .unwrap()
+                .into_semi_ambiguous()
+                .unwrap()
+        );
+
+        let met_loss = Ontology::Unimod.find_name("Met-loss", None).unwrap();
+        assert!(peptides[0]
+            .validate_placement(SequencePosition::NTerm, &met_loss)
+            .is_ok());
+        let reasons = peptides[1]
+            .validate_placement(SequencePosition::NTerm, &met_loss)
+            .unwrap_err();
+        assert!(
+            reasons
+                .iter()
+                .all(|(_, reason)| *reason
+                    == PlacementMismatch::ProteinPosition(Position::ProteinNTerm)),
+            "{reasons:?}"
+        );
+        // Without a known protein position the protein terminal rules are allowed
+        let mut unknown = peptides[1].clone();
+        unknown.set_protein_position(None);
+        assert!(unknown
+            .validate_placement(SequencePosition::NTerm, &met_loss)
+            .is_ok());
+    }
+}
@@ -0,0 +1,313 @@
+use std::ops::{Range, RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    ontologies::CustomDatabase,
+    CompoundPeptidoformIon, SequencePosition,
+};
+
+/// The source spans of all elements of a ProForma definition, this can be used by editors to map
+/// a location in the text to the semantic element (see [`Self::at`]) and to apply minimal edits
+/// to the text that keep the rest of the formatting intact (see [`Self::replace`]).
+///
+/// The spans are not checked for the semantic validity of the elements (unknown modifications,
+/// invalid placements etc), use [`Self::peptidoform`] to get the fully validated peptidoform.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProFormaSpans {
+    line: String,
+    spans: Vec<ProFormaSpan>,
+}
+
+/// A single element in a ProForma definition, with its location in the text.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProFormaSpan {
+    /// The kind of element
+    pub kind: ProFormaSpanKind,
+    /// The location in the text, as byte offsets
+    pub span: Range<usize>,
+    /// The peptidoform ion this element belongs to, 0 for global modifications
+    pub peptidoform_ion_index: usize,
+    /// The peptidoform within the peptidoform ion this element belongs to, 0 for global
+    /// modifications and for charge states
+    pub peptidoform_index: usize,
+}
+
+/// The kind of element in a ProForma definition
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProFormaSpanKind {
+    /// A global modification `<[Carbamidomethyl]@C>` or `<13C>`
+    GlobalModification,
+    /// A modification of unknown position `[Phospho]?`, including the copy number if present
+    UnknownPositionModification,
+    /// A labile modification `{Glycan:Hex}`
+    LabileModification,
+    /// An amino acid at the given sequence index
+    AminoAcid(usize),
+    /// A modification on the given position, for N terminal modifications this excludes the
+    /// trailing hyphen
+    Modification(SequencePosition),
+    /// A modification on a range of amino acids `(PEP)[Oxidation]`
+    RangedModification(RangeInclusive<usize>),
+    /// A group of ambiguous amino acids `(?DQ)`, with the range of sequence indices
+    AmbiguousAminoAcids(RangeInclusive<usize>),
+    /// A charge state `/2` or `/2[+2Na+]`
+    ChargeState,
+    /// The separator between cross-linked peptidoforms `//`
+    CrossLinkSeparator,
+    /// The separator between chimeric peptidoform ions `+`
+    ChimericSeparator,
+}
+
+impl ProFormaSpans {
+    /// Determine the spans of all elements in the given ProForma definition. The spans are
+    /// recorded by the ProForma parser in its error tolerant mode (see
+    /// [`CompoundPeptidoformIon::pro_forma_tolerant`]), so elements that contain recoverable
+    /// errors, like an unknown modification, still get a span.
+    /// # Errors
+    /// If the definition contains an error that the parser cannot recover from, for example if a
+    /// bracket is not closed or a charge state is invalid.
+    pub fn parse(line: &str) -> Result<Self, CustomError> {
+        let mut recorder = SpanRecorder::enabled();
+        CompoundPeptidoformIon::pro_forma_inner(line, None, &mut Vec::new(), &mut recorder)?;
+        Ok(Self {
+            line: line.to_string(),
+            spans: recorder.into_spans(),
+        })
+    }
+
+    /// The ProForma definition
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// All spans, in the order in which they appear in the definition
+    pub fn spans(&self) -> &[ProFormaSpan] {
+        &self.spans
+    }
+
+    /// Get the element at the given byte offset, if any. Brackets and separators that are not
+    /// part of any element (eg the hyphen of a C terminal modification) return `None`.
+    pub fn at(&self, offset: usize) -> Option<&ProFormaSpan> {
+        self.spans
+            .iter()
+            .filter(|s| s.span.contains(&offset))
+            .min_by_key(|s| s.span.len())
+    }
+
+    /// Replace the text of the span at the given index (into [`Self::spans`]) with the given
+    /// replacement, without touching any of the other text. This returns the spans of the new
+    /// definition.
+    /// # Errors
+    /// If the new definition is structurally invalid.
+    /// # Panics
+    /// If the span index is out of bounds.
+    pub fn replace(&self, span_index: usize, replacement: &str) -> Result<Self, CustomError> {
+        let mut line = self.line.clone();
+        line.replace_range(self.spans[span_index].span.clone(), replacement);
+        Self::parse(&line)
+    }
+
+    /// Insert the given text at the given byte offset, without touching any of the other text.
+    /// This returns the spans of the new definition.
+    /// # Errors
+    /// If the offset is not on a character boundary of the definition or if the new definition
+    /// is structurally invalid.
+    pub fn insert(&self, offset: usize, text: &str) -> Result<Self, CustomError> {
+        if !self.line.is_char_boundary(offset) {
+            return Err(CustomError::error(
+                "Invalid insertion offset",
+                format!(
+                    "The offset {offset} is not on a character boundary of the definition, which is {} bytes long",
+                    self.line.len()
+                ),
+                Context::show(&self.line),
+            ));
+        }
+        let mut line = self.line.clone();
+        line.insert_str(offset, text);
+        Self::parse(&line)
+    }
+
+    /// Parse the definition as a fully validated peptidoform.
+    /// # Errors
+    /// If the definition is not valid ProForma, see [`CompoundPeptidoformIon::pro_forma`].
+    pub fn peptidoform(
+        &self,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<CompoundPeptidoformIon, CustomError> {
+        CompoundPeptidoformIon::pro_forma(&self.line, custom_database)
+    }
+}
+
+/// Records the spans of the elements found while parsing a ProForma definition, if enabled
+#[derive(Debug, Default)]
+pub(super) struct SpanRecorder {
+    spans: Option<Vec<ProFormaSpan>>,
+    /// The peptidoform ion that is currently being parsed
+    pub peptidoform_ion_index: usize,
+    /// The peptidoform within the peptidoform ion that is currently being parsed
+    pub peptidoform_index: usize,
+}
+
+impl SpanRecorder {
+    /// A recorder that keeps all spans
+    fn enabled() -> Self {
+        Self {
+            spans: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+
+    /// Record an element of the current peptidoform, if enabled
+    pub fn push(&mut self, kind: ProFormaSpanKind, span: Range<usize>) {
+        if let Some(spans) = &mut self.spans {
+            spans.push(ProFormaSpan {
+                // The charge state is defined for the whole peptidoform ion
+                peptidoform_index: if kind == ProFormaSpanKind::ChargeState {
+                    0
+                } else {
+                    self.peptidoform_index
+                },
+                kind,
+                span,
+                peptidoform_ion_index: self.peptidoform_ion_index,
+            });
+        }
+    }
+
+    /// Get all recorded spans in the order in which they appear in the definition
+    fn into_spans(self) -> Vec<ProFormaSpan> {
+        let mut spans = self.spans.unwrap_or_default();
+        spans.sort_by_key(|s| s.span.start);
+        spans
+    }
+}
+
+impl ProFormaSpan {
+    /// Get the text of this span from the definition it was created from
+    /// # Panics
+    /// If the span does not fit on the given line.
+    pub fn text<'a>(&self, line: &'a str) -> &'a str {
+        &line[self.span.clone()]
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::literal_string_with_formatting_args)] // The labile modification
+    fn spans() {
+        let line = "<[Carbamidomethyl]@C>[Phospho]?{Glycan:Hex}[Acetyl]-PEC(?DQ)M[Oxidation]-[Amidated]/2[+2Na+]+(AC)[Oxidation]K[X:DSS#XL1]//MK[#XL1]";
+        let spans = ProFormaSpans::parse(line).unwrap();
+        let kinds = spans
+            .spans()
+            .iter()
+            .map(|s| {
+                (
+                    s.kind.clone(),
+                    s.text(line),
+                    s.peptidoform_ion_index,
+                    s.peptidoform_index,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds[..5],
+            [
+                (
+                    ProFormaSpanKind::GlobalModification,
+                    "<[Carbamidomethyl]@C>",
+                    0,
+                    0
+                ),
+                (
+                    ProFormaSpanKind::UnknownPositionModification,
+                    "[Phospho]",
+                    0,
+                    0
+                ),
+                (ProFormaSpanKind::LabileModification, "{Glycan:Hex}", 0, 0),
+                (
+                    ProFormaSpanKind::Modification(SequencePosition::NTerm),
+                    "[Acetyl]",
+                    0,
+                    0
+                ),
+                (ProFormaSpanKind::AminoAcid(0), "P", 0, 0),
+            ]
+        );
+        assert!(kinds.contains(&(ProFormaSpanKind::AmbiguousAminoAcids(3..=4), "(?DQ)", 0, 0)));
+        assert!(kinds.contains(&(
+            ProFormaSpanKind::Modification(SequencePosition::Index(5)),
+            "[Oxidation]",
+            0,
+            0
+        )));
+        assert!(kinds.contains(&(
+            ProFormaSpanKind::Modification(SequencePosition::CTerm),
+            "[Amidated]",
+            0,
+            0
+        )));
+        assert!(kinds.contains(&(ProFormaSpanKind::ChargeState, "/2[+2Na+]", 0, 0)));
+        assert!(kinds.contains(&(
+            ProFormaSpanKind::RangedModification(0..=1),
+            "[Oxidation]",
+            1,
+            0
+        )));
+        assert!(kinds.contains(&(ProFormaSpanKind::AminoAcid(1), "K", 1, 1)));
+        assert!(ProFormaSpans::parse("PEP[Oxidation").is_err());
+    }
+
+    #[test]
+    fn edit() {
+        let spans = ProFormaSpans::parse("[acetyl]-PEM[Oxidation]TIDE").unwrap();
+        let at = spans.at(14).unwrap();
+        assert_eq!(
+            at.kind,
+            ProFormaSpanKind::Modification(SequencePosition::Index(2))
+        );
+        assert_eq!(at.text(spans.line()), "[Oxidation]");
+        let index = spans.spans().iter().position(|s| s == at).unwrap();
+        let edited = spans.replace(index, "[Dioxidation]").unwrap();
+        assert_eq!(edited.line(), "[acetyl]-PEM[Dioxidation]TIDE");
+        let edited = edited.insert(edited.line().len(), "/2").unwrap();
+        assert_eq!(edited.line(), "[acetyl]-PEM[Dioxidation]TIDE/2");
+        assert_eq!(
+            edited
+                .spans()
+                .last()
+                .map(|s| (&s.kind, s.text(edited.line()))),
+            Some((&ProFormaSpanKind::ChargeState, "/2"))
+        );
+        assert_eq!(
+            edited.peptidoform(None).unwrap(),
+            CompoundPeptidoformIon::pro_forma("[Acetyl]-PEM[Dioxidation]TIDE/2", None).unwrap()
+        );
+        assert_eq!(spans.at(8), None);
+        assert!(spans.insert(spans.line().len() + 1, "K").is_err());
+    }
+
+    #[test]
+    fn recoverable_errors() {
+        // The spans of elements with recoverable errors are still reported
+        let line = "PEM[Nonexistent]K";
+        let spans = ProFormaSpans::parse(line).unwrap();
+        assert!(spans.peptidoform(None).is_err());
+        assert_eq!(
+            spans
+                .spans()
+                .iter()
+                .map(|s| s.text(line))
+                .collect::<Vec<_>>(),
+            ["P", "E", "M", "[Nonexistent]", "K"]
+        );
+        assert!(ProFormaSpans::parse("PEM/").is_err());
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     modification::{self, ModificationId, SimpleModificationInner},
     peptidoform::{
         parse::{global_modifications, parse_charge_state},
-        GlobalModification,
+        GlobalModification, SpanRecorder,
     },
     placement_rule::{self, PlacementRule, Position},
     system::{da, usize::Charge},
@@ -15,7 +15,7 @@ use crate::{
 
 #[test]
 fn parse_global_modifications() {
-    let parse = |str: &str| global_modifications(str, 0, None);
+    let parse = |str: &str| global_modifications(str, 0, None, &mut SpanRecorder::default());
     assert_eq!(
         parse("<[+5]@D>"),
         Ok((
@@ -476,3 +476,142 @@ fn hydrolysed_xl() {
 
     assert_eq!(peptide_xl.formula(), peptide_mod.formula());
 }
+
+#[test]
+fn apply_fixed_modification() {
+    let carbamidomethyl = modification::Ontology::Unimod
+        .find_name("Carbamidomethyl", None)
+        .unwrap();
+    let rule = PlacementRule::AminoAcid(vec![AminoAcid::Cysteine], Position::Anywhere);
+    let mut peptide = Peptidoform::pro_forma("PEPCTCK", None)
+        .unwrap()
+        .into_simple_linear()
+        .unwrap();
+    assert_eq!(
+        peptide.apply_fixed_modification(&carbamidomethyl, Some(&rule)),
+        Ok(vec![
+            crate::SequencePosition::Index(3),
+            crate::SequencePosition::Index(5)
+        ])
+    );
+    assert_eq!(
+        peptide,
+        Peptidoform::pro_forma("PEPC[Carbamidomethyl]TC[Carbamidomethyl]K", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+    );
+    // It is kept as global modification
+    assert_eq!(peptide.to_string(), "<[U:Carbamidomethyl]@C>PEPCTCK");
+    assert_eq!(
+        Peptidoform::pro_forma("<[Carbamidomethyl]@C>PEPCTCK", None)
+            .unwrap()
+            .to_string(),
+        "<[U:Carbamidomethyl]@C>PEPCTCK"
+    );
+    // Applying it again does not place it twice
+    assert_eq!(
+        peptide.apply_fixed_modification(&carbamidomethyl, Some(&rule)),
+        Ok(Vec::new())
+    );
+    // The placement rules of the modification are validated
+    let unchanged = peptide.clone();
+    assert!(peptide
+        .apply_fixed_modification(
+            &carbamidomethyl,
+            Some(&PlacementRule::AminoAcid(
+                vec![AminoAcid::Proline],
+                Position::Anywhere
+            ))
+        )
+        .is_err());
+    assert_eq!(peptide, unchanged);
+    // If the rule would place it on more locations it is displayed on every location
+    let mut peptide = Peptidoform::pro_forma("CPEPCK", None)
+        .unwrap()
+        .into_simple_linear()
+        .unwrap();
+    peptide
+        .apply_fixed_modification(&carbamidomethyl, Some(&rule))
+        .unwrap();
+    assert_eq!(
+        peptide.to_string(),
+        "C[U:Carbamidomethyl]PEPC[U:Carbamidomethyl]K"
+    );
+    // A mass modification needs a rule
+    assert!(peptide
+        .apply_fixed_modification(
+            &Arc::new(SimpleModificationInner::Mass(da(5.0).into())),
+            None
+        )
+        .is_err());
+}
+
+#[test]
+fn tolerant_parsing() {
+    // A valid peptidoform gives the same result without errors
+    let (peptidoform, errors) =
+        CompoundPeptidoformIon::pro_forma_tolerant("[Acetyl]-PEM[Oxidation]TIDE/2", None);
+    assert!(errors.is_empty());
+    assert_eq!(
+        peptidoform,
+        Some(CompoundPeptidoformIon::pro_forma("[Acetyl]-PEM[Oxidation]TIDE/2", None).unwrap())
+    );
+
+    // All recoverable errors are returned with their location
+    let line = "PEP[Oxidationn]TI#DE-";
+    let (peptidoform, errors) = CompoundPeptidoformIon::pro_forma_tolerant(line, None);
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert!(matches!(
+        errors[1].context(),
+        crate::error::Context::Line {
+            offset: 17,
+            length: 1,
+            ..
+        }
+    ));
+    assert_eq!(
+        CompoundPeptidoformIon::pro_forma(line, None),
+        Err(errors[0].clone())
+    );
+    assert_eq!(
+        peptidoform
+            .and_then(|p| p.singular())
+            .and_then(|p| p.singular())
+            .unwrap()
+            .to_string(),
+        "PEPTIDE"
+    );
+
+    // Unrecoverable errors give no peptidoform
+    let (peptidoform, errors) = CompoundPeptidoformIon::pro_forma_tolerant("P#EP[Oxidation", None);
+    assert_eq!(peptidoform, None);
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn formula_difference() {
+    let a = Peptidoform::pro_forma("PEPM[Oxidation]IDE", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let b = Peptidoform::pro_forma("PEPMIDE", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    assert_eq!(
+        a.formula_difference(&b).to_vec(),
+        vec![molecular_formula!(O 1)]
+    );
+    let b = Peptidoform::pro_forma("PEPBIDE", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    assert_eq!(b.formula_difference(&a).len(), 2);
+    let a = CompoundPeptidoformIon::pro_forma("AGK[Acetyl]", None).unwrap();
+    let b = CompoundPeptidoformIon::pro_forma("AGK", None).unwrap();
+    assert_eq!(
+        a.formula_difference(&b).to_vec(),
+        vec![molecular_formula!(C 2 H 2 O 1)]
+    );
+}
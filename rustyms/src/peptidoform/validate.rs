@@ -138,6 +138,7 @@ impl Peptidoform<Linear> {
                     for position in positions {
                         self.add_simple_modification(position.sequence_index, modification.clone());
                     }
+                    self.add_fixed(modification.clone(), rule.clone());
                 }
                 GlobalModification::Isotope(el, isotope) if el.is_valid(*isotope) => {
                     let _ = self.add_global((*el, *isotope)); // Already validated
@@ -2,11 +2,14 @@
 
 use std::str::FromStr;
 
+use itertools::Itertools;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::{Context, CustomError},
     modification::{Modification, ModificationId, Ontology, SimpleModificationInner},
+    peptidoform::ProteinPosition,
     AminoAcid, SequenceElement, SequencePosition,
 };
 
@@ -15,9 +18,21 @@ include!("shared/placement_rule.rs");
 impl PlacementRule {
     /// Check if this rule fits with the given location
     pub fn is_possible<T>(&self, seq: &SequenceElement<T>, position: SequencePosition) -> bool {
+        self.is_possible_in_protein(seq, position, None)
+    }
+
+    /// Check if this rule fits with the given location, if the location of the peptidoform in
+    /// its protein is known protein terminal rules only fit at the termini of the protein
+    pub fn is_possible_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
+    ) -> bool {
         match self {
             Self::AminoAcid(aa, r_pos) => {
-                aa.iter().any(|a| *a == seq.aminoacid.aminoacid()) && r_pos.is_possible(position)
+                aa.iter().any(|a| *a == seq.aminoacid.aminoacid())
+                    && r_pos.is_possible_in_protein(position, protein)
             }
             Self::PsiModification(mod_index, r_pos) => {
                 seq.modifications.iter().any(|m| {
@@ -39,16 +54,39 @@ impl PlacementRule {
                     } else {
                         false
                     }
-                }) && r_pos.is_possible(position)
+                }) && r_pos.is_possible_in_protein(position, protein)
             }
             Self::Terminal(r_pos) => {
-                r_pos.is_possible(position)
+                r_pos.is_possible_in_protein(position, protein)
                     && (position == SequencePosition::NTerm || position == SequencePosition::CTerm)
             }
             Self::Anywhere => true,
         }
     }
 
+    /// The targets of this rule in a ProForma global modification (`<[Oxidation]@M,N-term:C>`),
+    /// or None if this rule cannot be written as such.
+    pub(crate) fn pro_forma_targets(&self) -> Option<Vec<String>> {
+        match self {
+            Self::AminoAcid(aa, position) if !aa.is_empty() => {
+                let prefix = match position {
+                    Position::Anywhere => "",
+                    Position::AnyNTerm => "N-term:",
+                    Position::AnyCTerm => "C-term:",
+                    Position::ProteinNTerm | Position::ProteinCTerm => return None,
+                };
+                Some(
+                    aa.iter()
+                        .map(|aa| format!("{prefix}{}", aa.char()))
+                        .collect(),
+                )
+            }
+            Self::Terminal(Position::AnyNTerm) => Some(vec!["N-term".to_string()]),
+            Self::Terminal(Position::AnyCTerm) => Some(vec!["C-term".to_string()]),
+            _ => None,
+        }
+    }
+
     /// Check if this rule fits with the given location
     pub fn is_possible_aa(&self, aa: AminoAcid, position: Position) -> bool {
         match self {
@@ -72,10 +110,139 @@ impl PlacementRule {
         rules.iter().any(|r| r.is_possible(seq, position))
     }
 
+    /// Check if any of the given rules are possible, see [`Self::is_possible_in_protein`]
+    pub fn any_possible_in_protein<T>(
+        rules: &[Self],
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
+    ) -> bool {
+        rules
+            .iter()
+            .any(|r| r.is_possible_in_protein(seq, position, protein))
+    }
+
     /// Check if any of the given rules are possible
     pub fn any_possible_aa(rules: &[Self], aa: AminoAcid, position: Position) -> bool {
         rules.iter().any(|r| r.is_possible_aa(aa, position))
     }
+
+    /// Explain why this rule does not allow placement on the given element, returns `None` if
+    /// this rule does allow placement (see [`Self::is_possible`]).
+    pub fn mismatch<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+    ) -> Option<PlacementMismatch> {
+        self.mismatch_in_protein(seq, position, None)
+    }
+
+    /// Explain why this rule does not allow placement on the given element, returns `None` if
+    /// this rule does allow placement (see [`Self::is_possible_in_protein`]).
+    pub fn mismatch_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
+    ) -> Option<PlacementMismatch> {
+        if self.is_possible_in_protein(seq, position, protein) {
+            return None;
+        }
+        let position_mismatch = |r_pos: Position| {
+            if r_pos.is_possible(position) {
+                PlacementMismatch::ProteinPosition(r_pos)
+            } else {
+                PlacementMismatch::Position {
+                    found: position,
+                    allowed: r_pos,
+                }
+            }
+        };
+        Some(match self {
+            Self::AminoAcid(aa, r_pos) => {
+                if aa.iter().any(|a| *a == seq.aminoacid.aminoacid()) {
+                    position_mismatch(*r_pos)
+                } else {
+                    PlacementMismatch::AminoAcid {
+                        found: seq.aminoacid.aminoacid(),
+                        allowed: aa.clone(),
+                    }
+                }
+            }
+            Self::PsiModification(mod_index, r_pos) => {
+                if r_pos.is_possible_in_protein(position, protein) {
+                    PlacementMismatch::MissingModification(*mod_index)
+                } else {
+                    position_mismatch(*r_pos)
+                }
+            }
+            Self::Terminal(r_pos) if matches!(position, SequencePosition::Index(_)) => {
+                PlacementMismatch::Position {
+                    found: position,
+                    allowed: *r_pos,
+                }
+            }
+            Self::Terminal(r_pos) => position_mismatch(*r_pos),
+            Self::Anywhere => unreachable!("Anywhere rules can always be placed"),
+        })
+    }
+}
+
+/// The reason a [`PlacementRule`] does not allow a modification to be placed at a certain
+/// location, see [`crate::modification::SimpleModificationInner::validate_placement`].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub enum PlacementMismatch {
+    /// The amino acid is not one of the allowed amino acids
+    AminoAcid {
+        /// The amino acid at the location
+        found: AminoAcid,
+        /// The amino acids allowed by the rule
+        allowed: Vec<AminoAcid>,
+    },
+    /// The location is not at the position required by the rule
+    Position {
+        /// The location
+        found: SequencePosition,
+        /// The position required by the rule
+        allowed: Position,
+    },
+    /// The rule requires another modification (with this PSI-MOD index) to be present at the location
+    MissingModification(usize),
+    /// The rule requires the location to be at a protein terminus, but the peptidoform is not
+    /// located at that terminus of its protein (see [`ProteinPosition`])
+    ProteinPosition(Position),
+}
+
+impl std::fmt::Display for PlacementMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AminoAcid { found, allowed } => write!(
+                f,
+                "the amino acid {found} is not any of {}",
+                allowed.iter().join("")
+            ),
+            Self::Position { found, allowed } => write!(
+                f,
+                "{} is not at the required position {allowed}",
+                match found {
+                    SequencePosition::NTerm => "the N-terminus".to_string(),
+                    SequencePosition::CTerm => "the C-terminus".to_string(),
+                    SequencePosition::Index(index) => format!("the side chain at index {index}"),
+                }
+            ),
+            Self::ProteinPosition(allowed) => write!(
+                f,
+                "the peptidoform is not located at the required position {allowed} in its protein"
+            ),
+            Self::MissingModification(index) => write!(
+                f,
+                "the modification {} is not present",
+                Ontology::Psimod
+                    .find_id(*index, None)
+                    .map_or_else(|| format!("MOD:{index:05}"), |m| m.to_string())
+            ),
+        }
+    }
 }
 
 impl FromStr for PlacementRule {
@@ -130,6 +297,22 @@ impl Position {
         }
     }
 
+    /// See if the given peptide position is a valid position given this [`Position`] as placement
+    /// rule. If the location of the peptidoform in its protein is known the protein terminal
+    /// positions are only valid if the peptidoform is located at that terminus of the protein.
+    pub fn is_possible_in_protein(
+        self,
+        position: SequencePosition,
+        protein: Option<&ProteinPosition>,
+    ) -> bool {
+        self.is_possible(position)
+            && match self {
+                Self::ProteinNTerm => protein.map_or(true, ProteinPosition::is_protein_n_term),
+                Self::ProteinCTerm => protein.map_or(true, ProteinPosition::is_protein_c_term),
+                _ => true,
+            }
+    }
+
     /// See if the given position is a valid position given this [`Position`] as placement rule.
     pub fn is_possible_position(self, position: Self) -> bool {
         match self {
@@ -214,4 +397,42 @@ mod tests {
             "unimod deamidated at end"
         );
     }
+
+    #[test]
+    fn validate_placement() {
+        let deamidated = Ontology::Unimod.find_id(7, None).unwrap();
+        assert!(matches!(
+            deamidated.validate_placement(
+                &SequenceElement::new(CheckedAminoAcid::Q, None),
+                SequencePosition::Index(2)
+            ),
+            Ok(Some(PlacementRule::AminoAcid(_, Position::Anywhere)))
+        ));
+        let reasons = deamidated
+            .validate_placement(
+                &SequenceElement::new(CheckedAminoAcid::Alanine, None),
+                SequencePosition::Index(2),
+            )
+            .unwrap_err();
+        assert!(!reasons.is_empty());
+        assert!(reasons.iter().all(|(_, reason)| matches!(
+            reason,
+            PlacementMismatch::AminoAcid {
+                found: AminoAcid::Alanine,
+                ..
+            }
+        )));
+        assert!(reasons[0].1.to_string().starts_with("the amino acid A"));
+        assert_eq!(
+            Ontology::Unimod
+                .find_name("Carbamidomethyl", None)
+                .unwrap()
+                .validate_placement(
+                    &SequenceElement::new(CheckedAminoAcid::Alanine, None),
+                    SequencePosition::CTerm
+                )
+                .map_err(|_| ()),
+            Err(())
+        );
+    }
 }
@@ -1,11 +1,30 @@
+use std::{fmt::Display, str::FromStr};
+
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-use crate::{AminoAcid, SequenceElement};
+use crate::{
+    error::{Context, CustomError},
+    AminoAcid, SequenceElement,
+};
 
 /// A protease defined by it ability to cut at any site identified by the right amino acids at the n and c terminal.
 /// Each position is identified by an option, a none means that there is no specificity at this position. If there is
 /// a specificity at a certain position any amino acid that is contained in the set is allowed (see
 /// [`crate::CheckedAminoAcid::canonical_identical`]).
+///
+/// A protease can also be parsed from a cleavage rule, where a `|` indicates the cut site and
+/// every position is one of: a single amino acid (`K`), a set of amino acids (`[KR]`), a set of
+/// excluded amino acids (`{P}`), or any amino acid (`X`). So trypsin is written as `[KR]|{P}`.
+/// Additionally the names of common proteases can be parsed, see [`Protease::known`].
+/// ```
+/// # use rustyms::{Protease, AminoAcid};
+/// let trypsin: Protease = "[KR]|{P}".parse().unwrap();
+/// assert_eq!(trypsin.to_string(), "[KR]|{P}");
+/// assert_eq!("Trypsin".parse::<Protease>().unwrap(), trypsin);
+/// assert_eq!("K|".parse::<Protease>().unwrap(), Protease::c_terminal_of(&[AminoAcid::Lysine]));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Protease {
     /// The amino acids n terminal of the cut site.
     pub n_term: Vec<Option<Vec<AminoAcid>>>,
@@ -22,29 +41,55 @@ impl Protease {
         }
     }
 
-    /// Define a protease that cuts on the n terminal side of the provided amino acids.
+    /// Define a protease that cuts on the n terminal side of the provided amino acids, so the
+    /// amino acids end up at the N terminus of the peptides, eg `|D` for Asp-N. The residues are
+    /// matched c terminal of the cut site ([`Self::c_term`]).
+    ///
+    /// Note: this and [`Self::c_terminal_of`] used to place the residues on the opposite side of
+    /// the cut site from what their names say. They now match their names, so code written for
+    /// the old behaviour has to call the other function.
     pub fn n_terminal_of(residues: &[AminoAcid]) -> Self {
         Self {
-            n_term: vec![Some(residues.to_vec())],
-            c_term: Vec::new(),
+            n_term: Vec::new(),
+            c_term: vec![Some(residues.to_vec())],
         }
     }
 
-    /// Define a protease that cuts on the c terminal side of the provided amino acids.
+    /// Define a protease that cuts on the c terminal side of the provided amino acids, so the
+    /// amino acids end up at the C terminus of the peptides, eg `[KR]|` for trypsin without the
+    /// proline rule. The residues are matched n terminal of the cut site ([`Self::n_term`]).
     pub fn c_terminal_of(residues: &[AminoAcid]) -> Self {
         Self {
-            c_term: vec![Some(residues.to_vec())],
-            n_term: Vec::new(),
+            n_term: vec![Some(residues.to_vec())],
+            c_term: Vec::new(),
         }
     }
 
-    /// All locations in the given sequence where this protease could cut
+    /// All locations in the given sequence where this protease could cut, as the index of the
+    /// residue just after the cut site, see [`Self::cuts_at`]. The termini of the sequence are
+    /// never included, and a site is only included if the full pattern of the protease fits in the
+    /// sequence, so this never panics on sequences shorter than the pattern.
     pub fn match_locations<T>(&self, sequence: &[SequenceElement<T>]) -> Vec<usize> {
-        (self.n_term.len()..sequence.len() - self.c_term.len())
-            .filter(|i| self.matches_at(&sequence[i - self.n_term.len()..i + self.c_term.len()]))
+        (self.n_term.len().max(1)..sequence.len())
+            .filter(|i| self.cuts_at(sequence, *i))
             .collect_vec()
     }
 
+    /// Check if this protease could cut the given sequence at the given location (before the
+    /// residue at this index). This is never true for the termini of the sequence.
+    pub fn cuts_at<T>(&self, sequence: &[SequenceElement<T>], index: usize) -> bool {
+        index > 0
+            && index < sequence.len()
+            && index >= self.n_term.len()
+            && index + self.c_term.len() <= sequence.len()
+            && self.matches_at(&sequence[index - self.n_term.len()..index + self.c_term.len()])
+    }
+
+    /// The names and cleavage rules of all commonly used proteases that can be parsed by name.
+    pub const fn known() -> &'static [(&'static str, &'static str)] {
+        KNOWN_PROTEASES
+    }
+
     fn matches_at<T>(&self, slice: &[SequenceElement<T>]) -> bool {
         debug_assert!(slice.len() == self.n_term.len() + self.c_term.len());
         'positions: for (actual, pattern) in slice
@@ -63,3 +108,273 @@ impl Protease {
         true
     }
 }
+
+/// The names (lowercase) and cleavage rules of common proteases
+const KNOWN_PROTEASES: &[(&str, &str)] = &[
+    ("trypsin", "[KR]|{P}"),
+    ("lys-c", "K|"),
+    ("lys-n", "|K"),
+    ("arg-c", "R|"),
+    ("asp-n", "|D"),
+    ("glu-c", "E|"),
+    ("chymotrypsin", "[FWY]|{P}"),
+    ("pepsin", "[FL]|"),
+];
+
+/// All amino acids that are taken into account when an exclusion set (`{P}`) is defined
+const SPECIFIC_AMINO_ACIDS: &[AminoAcid] = &[
+    AminoAcid::Glycine,
+    AminoAcid::Alanine,
+    AminoAcid::Arginine,
+    AminoAcid::Asparagine,
+    AminoAcid::AsparticAcid,
+    AminoAcid::Cysteine,
+    AminoAcid::Glutamine,
+    AminoAcid::GlutamicAcid,
+    AminoAcid::Histidine,
+    AminoAcid::Leucine,
+    AminoAcid::Isoleucine,
+    AminoAcid::Lysine,
+    AminoAcid::Methionine,
+    AminoAcid::Phenylalanine,
+    AminoAcid::Proline,
+    AminoAcid::Serine,
+    AminoAcid::Threonine,
+    AminoAcid::Tryptophan,
+    AminoAcid::Tyrosine,
+    AminoAcid::Valine,
+    AminoAcid::Selenocysteine,
+    AminoAcid::Pyrrolysine,
+];
+
+impl Display for Protease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let position =
+            |f: &mut std::fmt::Formatter<'_>, position: &Option<Vec<AminoAcid>>| match position {
+                None => write!(f, "X"),
+                Some(set) if set.len() == 1 => write!(f, "{}", set[0]),
+                Some(set) => {
+                    let excluded = SPECIFIC_AMINO_ACIDS
+                        .iter()
+                        .filter(|aa| !set.contains(aa))
+                        .collect_vec();
+                    if set.len() > excluded.len() && !excluded.is_empty() {
+                        write!(f, "{{{}}}", excluded.iter().join(""))
+                    } else {
+                        write!(f, "[{}]", set.iter().join(""))
+                    }
+                }
+            };
+        for p in &self.n_term {
+            position(f, p)?;
+        }
+        write!(f, "|")?;
+        for p in &self.c_term {
+            position(f, p)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Protease {
+    type Err = CustomError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if let Some((_, rule)) = KNOWN_PROTEASES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(trimmed))
+        {
+            return rule.parse();
+        }
+        let offset = s.len() - s.trim_start().len();
+        let invalid = |index: usize, length: usize, explanation: &str| {
+            CustomError::error(
+                "Invalid protease",
+                explanation,
+                Context::line(None, s, offset + index, length),
+            )
+        };
+        let mut n_term = Vec::new();
+        let mut c_term = Vec::new();
+        let mut cut = false;
+        let mut chars = trimmed.char_indices();
+        while let Some((index, c)) = chars.next() {
+            let position = match c {
+                '|' if cut => {
+                    return Err(invalid(
+                        index,
+                        1,
+                        "A cleavage rule can only contain a single cut site '|'",
+                    ))
+                }
+                '|' => {
+                    cut = true;
+                    continue;
+                }
+                'X' | 'x' | '.' => None,
+                '[' | '{' => {
+                    let close = if c == '[' { ']' } else { '}' };
+                    let mut set = Vec::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, end)) if end == close => break,
+                            Some((i, aa)) => set.push(AminoAcid::try_from(aa).map_err(|()| {
+                                invalid(i, 1, "Invalid amino acid in cleavage rule")
+                            })?),
+                            None => {
+                                return Err(invalid(
+                                    index,
+                                    trimmed.len() - index,
+                                    &format!(
+                                        "The set of amino acids is not closed, add a '{close}'"
+                                    ),
+                                ))
+                            }
+                        }
+                    }
+                    if set.is_empty() {
+                        return Err(invalid(index, 2, "A set of amino acids cannot be empty"));
+                    }
+                    if c == '{' {
+                        set = SPECIFIC_AMINO_ACIDS
+                            .iter()
+                            .filter(|aa| !set.iter().any(|e| e.canonical_identical(**aa)))
+                            .copied()
+                            .collect();
+                    }
+                    Some(set)
+                }
+                aa => Some(vec![AminoAcid::try_from(aa).map_err(|()| {
+                    invalid(index, 1, "Invalid amino acid in cleavage rule")
+                })?]),
+            };
+            if cut {
+                c_term.push(position);
+            } else {
+                n_term.push(position);
+            }
+        }
+        if cut {
+            Ok(Self { n_term, c_term })
+        } else {
+            Err(CustomError::error(
+                "Invalid protease",
+                "A cleavage rule needs a cut site indicated with '|', or use the name of a known protease",
+                Context::full_line(0, s),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{peptidoform::Linear, Peptidoform};
+
+    fn peptide(sequence: &str) -> Peptidoform<Linear> {
+        Peptidoform::pro_forma(sequence, None)
+            .unwrap()
+            .into_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        for (name, rule) in Protease::known() {
+            let protease: Protease = rule.parse().unwrap();
+            assert_eq!(&protease.to_string(), rule);
+            assert_eq!(name.to_uppercase().parse::<Protease>().unwrap(), protease);
+        }
+        assert_eq!(
+            "X[ST]|.".parse::<Protease>().unwrap(),
+            Protease {
+                n_term: vec![None, Some(vec![AminoAcid::Serine, AminoAcid::Threonine])],
+                c_term: vec![None],
+            }
+        );
+        assert!("KR".parse::<Protease>().is_err());
+        assert!("K||".parse::<Protease>().is_err());
+        assert!("[KR|".parse::<Protease>().is_err());
+        assert!("{}|".parse::<Protease>().is_err());
+        assert!("K|#".parse::<Protease>().is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let trypsin: Protease = "trypsin".parse().unwrap();
+        let json = serde_json::to_string(&trypsin).unwrap();
+        assert_eq!(serde_json::from_str::<Protease>(&json).unwrap(), trypsin);
+    }
+
+    #[test]
+    fn locations() {
+        let trypsin: Protease = "trypsin".parse().unwrap();
+        assert_eq!(
+            trypsin.match_locations(peptide("AKPRGKRA").sequence()),
+            vec![4, 6, 7]
+        );
+        let lys_n: Protease = "lys-n".parse().unwrap();
+        assert_eq!(lys_n.match_locations(peptide("KAKA").sequence()), vec![2]);
+        // The termini and sites where the pattern does not fit are never included
+        assert!(lys_n.match_locations(peptide("K").sequence()).is_empty());
+        let long: Protease = "KXX|XX".parse().unwrap();
+        assert!(long.match_locations(peptide("KA").sequence()).is_empty());
+        assert_eq!(
+            long.match_locations(peptide("KAAAAKAA").sequence()),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn digest() {
+        let trypsin: Protease = "trypsin".parse().unwrap();
+        let sequence = peptide("AAKPAARAAKAA");
+        let digest = |missed| {
+            sequence
+                .digest(&trypsin, missed)
+                .iter()
+                .map(ToString::to_string)
+                .collect_vec()
+        };
+        assert_eq!(digest(0), ["AAKPAAR", "AAK", "AA"]);
+        assert_eq!(digest(1), ["AAKPAAR", "AAKPAARAAK", "AAK", "AAKAA", "AA"]);
+        // No empty peptides, even without any cut site
+        assert!(sequence
+            .digest(&trypsin, 3)
+            .iter()
+            .all(|peptide| !peptide.is_empty()));
+        assert_eq!(
+            peptide("PEPTIDE")
+                .digest(&trypsin, 0)
+                .iter()
+                .map(ToString::to_string)
+                .collect_vec(),
+            ["PEPTIDE"]
+        );
+        let semi = sequence
+            .semi_digest(&trypsin, 0, 4..=6)
+            .iter()
+            .map(ToString::to_string)
+            .collect_vec();
+        assert_eq!(semi, ["AAKP", "AAKPA", "AAKPAA", "AKPAAR", "KPAAR", "PAAR"]);
+        let neo = sequence
+            .neo_n_terminal_digest(&trypsin, 0, 4..=6)
+            .iter()
+            .map(ToString::to_string)
+            .collect_vec();
+        assert_eq!(neo, ["AKPAAR", "KPAAR", "PAAR"]);
+        assert!(trypsin.cuts_at(sequence.sequence(), 7));
+        assert!(!trypsin.cuts_at(sequence.sequence(), 3));
+        assert!(!trypsin.cuts_at(sequence.sequence(), 0));
+        assert!(!trypsin.cuts_at(sequence.sequence(), 12));
+        assert_eq!(
+            sequence.digest_coverage(&trypsin, 0, 3..=10, false),
+            [vec![true; 10], vec![false; 2]].concat()
+        );
+        assert!(sequence
+            .digest_coverage(&trypsin, 0, 2..=10, true)
+            .iter()
+            .all(|c| *c));
+    }
+}
@@ -0,0 +1,218 @@
+//! Provenance metadata that can be embedded in written files, to be able to audit how an output
+//! was generated.
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::provenance::Provenance;
+//! let provenance = Provenance::new()
+//!     .parameter("fdr", 0.01)
+//!     .input_file("peptides.tsv")?;
+//! # Ok(()) }
+//! ```
+
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::Crc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Context, CustomError};
+
+/// The prefix for provenance comment lines in CSV files, lines with this prefix before the header
+/// are skipped when reading CSV files.
+pub const CSV_COMMENT: &str = "##";
+
+/// The provenance of an output file: the software version, the parameters, and the input files
+/// with their checksums. This can be given to the writers to embed it in the written file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The software and its version, defaults to this crate
+    pub software: String,
+    /// All parameters, in the order they were given
+    pub parameters: Vec<(String, String)>,
+    /// All input files
+    pub inputs: Vec<InputFile>,
+}
+
+/// An input file for [`Provenance`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InputFile {
+    /// The path as given
+    pub path: PathBuf,
+    /// The size in bytes
+    pub size: u64,
+    /// The CRC32 checksum of the (possibly compressed) file contents
+    pub crc32: u32,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Provenance {
+    /// Create a provenance with this crate as software and no parameters or inputs
+    pub fn new() -> Self {
+        Self {
+            software: format!("rustyms {}", env!("CARGO_PKG_VERSION")),
+            parameters: Vec::new(),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Set the software, eg the name and version of the tool using this crate
+    #[must_use]
+    pub fn software(self, software: impl Into<String>) -> Self {
+        Self {
+            software: software.into(),
+            ..self
+        }
+    }
+
+    /// Add a parameter
+    #[must_use]
+    pub fn parameter(mut self, name: impl Into<String>, value: impl std::fmt::Display) -> Self {
+        self.parameters.push((name.into(), format!("{value}")));
+        self
+    }
+
+    /// Add an input file, this reads the full file to calculate its checksum.
+    /// # Errors
+    /// If the file could not be read.
+    pub fn input_file(mut self, path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        let path = path.as_ref();
+        let error = |e: std::io::Error| {
+            CustomError::error(
+                "Could not read input file",
+                e,
+                Context::show(path.to_string_lossy()),
+            )
+        };
+        let mut file = File::open(path).map_err(error)?;
+        let mut crc = Crc::new();
+        let mut size = 0;
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer).map_err(error)?;
+            if read == 0 {
+                break;
+            }
+            crc.update(&buffer[..read]);
+            size += read as u64;
+        }
+        self.inputs.push(InputFile {
+            path: path.to_path_buf(),
+            size,
+            crc32: crc.sum(),
+        });
+        Ok(self)
+    }
+
+    /// The CRC32 checksum of all parameters, to easily check if two outputs were generated with
+    /// the same parameters.
+    pub fn parameters_hash(&self) -> u32 {
+        let mut crc = Crc::new();
+        for (name, value) in &self.parameters {
+            crc.update(format!("{name}={value}\n").as_bytes());
+        }
+        crc.sum()
+    }
+
+    /// All provenance information as key value pairs, in the order they should be written
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = vec![
+            ("software".to_string(), self.software.clone()),
+            (
+                "parameters_crc32".to_string(),
+                format!("{:08x}", self.parameters_hash()),
+            ),
+        ];
+        entries.extend(
+            self.parameters
+                .iter()
+                .map(|(name, value)| (format!("parameter[{name}]"), value.clone())),
+        );
+        for (index, input) in self.inputs.iter().enumerate() {
+            entries.push((
+                format!("input[{}]", index + 1),
+                input.path.to_string_lossy().to_string(),
+            ));
+            entries.push((format!("input[{}]_size", index + 1), input.size.to_string()));
+            entries.push((
+                format!("input[{}]_crc32", index + 1),
+                format!("{:08x}", input.crc32),
+            ));
+        }
+        entries
+    }
+
+    /// Write all entries as comment lines for a CSV file, see [`CSV_COMMENT`]
+    /// # Errors
+    /// If writing fails.
+    pub(crate) fn write_csv_comments(&self, mut writer: impl Write) -> std::io::Result<()> {
+        for (key, value) in self.entries() {
+            writeln!(writer, "{CSV_COMMENT} {key}: {value}")?;
+        }
+        Ok(())
+    }
+
+    /// Write all entries as mzTab custom metadata lines, numbered from the given index
+    /// # Errors
+    /// If writing fails.
+    pub(crate) fn write_mztab_metadata(
+        &self,
+        mut writer: impl Write,
+        start: usize,
+    ) -> std::io::Result<()> {
+        for (index, (key, value)) in self.entries().into_iter().enumerate() {
+            writeln!(
+                writer,
+                "MTD\tcustom[{}]\t[, , {key}, {}]",
+                start + index,
+                value.replace(',', ";")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        let provenance = Provenance::new()
+            .software("tool 1.0")
+            .parameter("fdr", 0.01)
+            .input_file("src/identification/test_files/sage_v0_14.tsv")
+            .unwrap();
+        let entries = provenance.entries();
+        assert_eq!(entries[0], ("software".to_string(), "tool 1.0".to_string()));
+        assert_eq!(
+            entries[2],
+            ("parameter[fdr]".to_string(), "0.01".to_string())
+        );
+        assert_eq!(entries[3].0, "input[1]");
+        assert_eq!(
+            entries[4].1,
+            std::fs::metadata("src/identification/test_files/sage_v0_14.tsv")
+                .unwrap()
+                .len()
+                .to_string()
+        );
+        assert_eq!(entries[5].1.len(), 8);
+        assert_ne!(
+            provenance.parameters_hash(),
+            Provenance::new().parameter("fdr", 0.05).parameters_hash()
+        );
+        assert!(Provenance::new()
+            .input_file("src/identification/test_files/missing.tsv")
+            .is_err());
+    }
+}
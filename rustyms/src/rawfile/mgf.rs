@@ -1,6 +1,5 @@
 //! Handle MGF reader reading
 use std::{
-    fs::File,
     io::{BufRead, BufReader},
     path::Path,
 };
@@ -10,8 +9,8 @@ use regex::Regex;
 use uom::num_traits::Zero;
 
 use crate::{
+    compression::open_file,
     error::{Context, CustomError},
-    helper_functions::check_extension,
     spectrum::{PeakSpectrum, RawPeak, RawSpectrum},
     system::{
         charge::e,
@@ -22,7 +21,6 @@ use crate::{
         usize::Charge,
     },
 };
-use flate2::bufread::GzDecoder;
 
 /// Open a MGF file and return the contained spectra.
 ///
@@ -33,19 +31,7 @@ use flate2::bufread::GzDecoder;
 /// * When any expected number in the file is not a number
 /// * When there is only one column (separated by space or tab) on a data row
 pub fn open(path: impl AsRef<Path>) -> Result<Vec<RawSpectrum>, CustomError> {
-    let path = path.as_ref();
-    let file = File::open(path).map_err(|err| {
-        CustomError::error(
-            "Could not open file",
-            format!("Additional info: {err}"),
-            Context::show(path.display()),
-        )
-    })?;
-    if check_extension(path, "gz") {
-        open_raw(GzDecoder::new(BufReader::new(file)))
-    } else {
-        open_raw(file)
-    }
+    open_raw(open_file(path)?)
 }
 
 /// Open a MGF file and return the contained spectra. Open it from a raw buffered reader.
@@ -125,6 +111,13 @@ pub fn open_raw<T: std::io::Read>(reader: T) -> Result<Vec<RawSpectrum>, CustomE
                     }
                     "TITLE" => parse_title(value, &mut current),
                     "SEQUENCE" => current.sequence = Some(value.to_owned()),
+                    // Can be a range or list of scans, use the first one if the title does not define the scan number
+                    "SCANS" if current.raw_scan_number.is_none() => {
+                        current.raw_scan_number = value
+                            .split(['-', ','])
+                            .next()
+                            .and_then(|scan| scan.trim().parse().ok());
+                    }
                     "NUM_SCANS" => {
                         current.num_scans = value.parse().map_err(|_| {
                             base_error
@@ -1,2 +1,3 @@
 //! Handling raw files
 pub mod mgf;
+pub mod mzml;
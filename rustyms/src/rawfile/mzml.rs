@@ -0,0 +1,489 @@
+//! A minimal mzML writer for processed and annotated spectra. This writes centroided MS1 and MS2
+//! spectra with the basic CV params, and does not need the `mzdata` feature.
+//!
+//! ```rust
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::rawfile::{mgf, mzml};
+//! let spectra = mgf::open("data/example.mgf")?;
+//! let mut output = Vec::new();
+//! mzml::write(&mut output, &spectra, true)?;
+//! assert!(String::from_utf8(output).unwrap().contains("<spectrumList count=\"1\""));
+//! # Ok(()) }
+//! ```
+use std::io::Write;
+
+use crate::{
+    error::{Context, CustomError},
+    spectrum::{AnnotatedSpectrum, PeakSpectrum, RawSpectrum},
+    system::{f64::Time, time::s, usize::Charge},
+};
+
+/// A spectrum that can be written as mzML, see [`write`]
+pub trait MzMLSpectrum {
+    /// The title
+    fn title(&self) -> &str;
+    /// The retention time
+    fn retention_time(&self) -> Option<Time>;
+    /// The precursor m/z, charge, and intensity. A spectrum without precursor is written as MS1
+    /// spectrum, with precursor as MS2 spectrum.
+    fn precursor(&self) -> Option<(f64, Option<Charge>, Option<f64>)>;
+    /// The dissociation method used to generate this spectrum as the accession and name of the
+    /// PSI-MS CV term, eg `("MS:1000422", "beam-type collision-induced dissociation")`. The
+    /// activation of the precursor is only written if this is known. Default: unknown.
+    fn dissociation_method(&self) -> Option<(&str, &str)> {
+        None
+    }
+    /// The m/z and intensity of all peaks
+    fn peaks(&self) -> Vec<(f64, f64)>;
+}
+
+impl MzMLSpectrum for RawSpectrum {
+    fn title(&self) -> &str {
+        &self.title
+    }
+    fn retention_time(&self) -> Option<Time> {
+        self.rt
+    }
+    /// The precursor mass is interpreted as m/z (as the PEPMASS in MGF)
+    fn precursor(&self) -> Option<(f64, Option<Charge>, Option<f64>)> {
+        self.mass
+            .map(|mass| (mass.value, self.charge, self.intensity))
+    }
+    fn peaks(&self) -> Vec<(f64, f64)> {
+        self.spectrum()
+            .map(|p| (p.mz.value, p.intensity.0))
+            .collect()
+    }
+}
+
+impl MzMLSpectrum for AnnotatedSpectrum {
+    fn title(&self) -> &str {
+        &self.title
+    }
+    fn retention_time(&self) -> Option<Time> {
+        self.rt
+    }
+    /// The precursor mass is interpreted as m/z (as the PEPMASS in MGF)
+    fn precursor(&self) -> Option<(f64, Option<Charge>, Option<f64>)> {
+        self.mass.map(|mass| (mass.value, self.charge, None))
+    }
+    fn peaks(&self) -> Vec<(f64, f64)> {
+        self.spectrum()
+            .map(|p| (p.experimental_mz.value, p.intensity.0))
+            .collect()
+    }
+}
+
+/// Write the given spectra as mzML. All spectra are written as centroided spectra with 64 bit
+/// float m/z and intensity arrays, optionally zlib compressed. The spectra are identified by
+/// their index (`index=N`), with the title stored as spectrum title.
+/// # Errors
+/// If writing fails.
+pub fn write<'a, S: MzMLSpectrum + 'a>(
+    mut writer: impl Write,
+    spectra: impl IntoIterator<Item = &'a S>,
+    compress: bool,
+) -> Result<(), CustomError> {
+    let spectra: Vec<&S> = spectra.into_iter().collect();
+    write_inner(&mut writer, &spectra, compress)
+        .map_err(|e| CustomError::error("Could not write mzML file", e, Context::None))
+}
+
+/// Write the full mzML file
+/// # Errors
+/// If writing fails.
+fn write_inner<S: MzMLSpectrum>(
+    writer: &mut impl Write,
+    spectra: &[&S],
+    compress: bool,
+) -> std::io::Result<()> {
+    let (ms1, msn) = spectra.iter().fold((false, false), |(ms1, msn), spectrum| {
+        match spectrum.precursor() {
+            Some(_) => (ms1, true),
+            None => (true, msn),
+        }
+    });
+    writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<mzML xmlns="http://psi.hupo.org/ms/mzml" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://psi.hupo.org/ms/mzml http://psidev.info/files/ms/mzML/xsd/mzML1.1.0.xsd" version="1.1.0">"#
+    )?;
+    writeln!(writer, r#"  <cvList count="2">"#)?;
+    writeln!(
+        writer,
+        r#"    <cv id="MS" fullName="Proteomics Standards Initiative Mass Spectrometry Ontology" URI="https://raw.githubusercontent.com/HUPO-PSI/psi-ms-CV/master/psi-ms.obo"/>"#
+    )?;
+    writeln!(
+        writer,
+        r#"    <cv id="UO" fullName="Unit Ontology" URI="http://ontologies.berkeleybop.org/uo.obo"/>"#
+    )?;
+    writeln!(writer, "  </cvList>")?;
+    writeln!(writer, "  <fileDescription>")?;
+    writeln!(writer, "    <fileContent>")?;
+    if ms1 {
+        cv_param(writer, 6, "MS:1000579", "MS1 spectrum", "")?;
+    }
+    if msn {
+        cv_param(writer, 6, "MS:1000580", "MSn spectrum", "")?;
+    }
+    cv_param(writer, 6, "MS:1000127", "centroid spectrum", "")?;
+    writeln!(writer, "    </fileContent>")?;
+    writeln!(writer, "  </fileDescription>")?;
+    writeln!(writer, r#"  <softwareList count="1">"#)?;
+    writeln!(
+        writer,
+        r#"    <software id="rustyms" version="{}">"#,
+        env!("CARGO_PKG_VERSION")
+    )?;
+    cv_param(
+        writer,
+        6,
+        "MS:1000799",
+        "custom unreleased software tool",
+        "rustyms",
+    )?;
+    writeln!(writer, "    </software>")?;
+    writeln!(writer, "  </softwareList>")?;
+    writeln!(writer, r#"  <instrumentConfigurationList count="1">"#)?;
+    writeln!(writer, r#"    <instrumentConfiguration id="IC">"#)?;
+    cv_param(writer, 6, "MS:1000031", "instrument model", "")?;
+    writeln!(writer, "    </instrumentConfiguration>")?;
+    writeln!(writer, "  </instrumentConfigurationList>")?;
+    writeln!(writer, r#"  <dataProcessingList count="1">"#)?;
+    writeln!(writer, r#"    <dataProcessing id="rustyms_processing">"#)?;
+    writeln!(
+        writer,
+        r#"      <processingMethod order="0" softwareRef="rustyms">"#
+    )?;
+    cv_param(writer, 8, "MS:1000544", "Conversion to mzML", "")?;
+    writeln!(writer, "      </processingMethod>")?;
+    writeln!(writer, "    </dataProcessing>")?;
+    writeln!(writer, "  </dataProcessingList>")?;
+    writeln!(
+        writer,
+        r#"  <run id="run" defaultInstrumentConfigurationRef="IC">"#
+    )?;
+    writeln!(
+        writer,
+        r#"    <spectrumList count="{}" defaultDataProcessingRef="rustyms_processing">"#,
+        spectra.len()
+    )?;
+    for (index, spectrum) in spectra.iter().enumerate() {
+        write_spectrum(writer, index, *spectrum, compress)?;
+    }
+    writeln!(writer, "    </spectrumList>")?;
+    writeln!(writer, "  </run>")?;
+    writeln!(writer, "</mzML>")?;
+    Ok(())
+}
+
+/// Write a single spectrum
+/// # Errors
+/// If writing fails.
+fn write_spectrum(
+    writer: &mut impl Write,
+    index: usize,
+    spectrum: &impl MzMLSpectrum,
+    compress: bool,
+) -> std::io::Result<()> {
+    let peaks = spectrum.peaks();
+    let precursor = spectrum.precursor();
+    writeln!(
+        writer,
+        r#"      <spectrum index="{index}" id="index={index}" defaultArrayLength="{}">"#,
+        peaks.len()
+    )?;
+    if precursor.is_some() {
+        cv_param(writer, 8, "MS:1000511", "ms level", "2")?;
+        cv_param(writer, 8, "MS:1000580", "MSn spectrum", "")?;
+    } else {
+        cv_param(writer, 8, "MS:1000511", "ms level", "1")?;
+        cv_param(writer, 8, "MS:1000579", "MS1 spectrum", "")?;
+    }
+    cv_param(writer, 8, "MS:1000127", "centroid spectrum", "")?;
+    if !spectrum.title().is_empty() {
+        cv_param(writer, 8, "MS:1000796", "spectrum title", spectrum.title())?;
+    }
+    writeln!(writer, r#"        <scanList count="1">"#)?;
+    cv_param(writer, 10, "MS:1000795", "no combination", "")?;
+    writeln!(writer, "          <scan>")?;
+    if let Some(rt) = spectrum.retention_time() {
+        writeln!(
+            writer,
+            r#"            <cvParam cvRef="MS" accession="MS:1000016" name="scan start time" value="{}" unitCvRef="UO" unitAccession="UO:0000010" unitName="second"/>"#,
+            rt.get::<s>()
+        )?;
+    }
+    writeln!(writer, "          </scan>")?;
+    writeln!(writer, "        </scanList>")?;
+    if let Some((mz, charge, intensity)) = precursor {
+        writeln!(writer, r#"        <precursorList count="1">"#)?;
+        writeln!(writer, "          <precursor>")?;
+        writeln!(writer, r#"            <selectedIonList count="1">"#)?;
+        writeln!(writer, "              <selectedIon>")?;
+        writeln!(
+            writer,
+            r#"                <cvParam cvRef="MS" accession="MS:1000744" name="selected ion m/z" value="{mz}" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>"#
+        )?;
+        if let Some(charge) = charge {
+            cv_param(
+                writer,
+                16,
+                "MS:1000041",
+                "charge state",
+                &charge.value.to_string(),
+            )?;
+        }
+        if let Some(intensity) = intensity {
+            writeln!(
+                writer,
+                r#"                <cvParam cvRef="MS" accession="MS:1000042" name="peak intensity" value="{intensity}" unitCvRef="MS" unitAccession="MS:1000131" unitName="number of detector counts"/>"#
+            )?;
+        }
+        writeln!(writer, "              </selectedIon>")?;
+        writeln!(writer, "            </selectedIonList>")?;
+        if let Some((accession, name)) = spectrum.dissociation_method() {
+            writeln!(writer, "            <activation>")?;
+            cv_param(writer, 14, accession, name, "")?;
+            writeln!(writer, "            </activation>")?;
+        }
+        writeln!(writer, "          </precursor>")?;
+        writeln!(writer, "        </precursorList>")?;
+    }
+    writeln!(writer, r#"        <binaryDataArrayList count="2">"#)?;
+    write_array(
+        writer,
+        &peaks.iter().map(|p| p.0).collect::<Vec<_>>(),
+        compress,
+        ("MS:1000514", "m/z array"),
+        ("MS:1000040", "m/z"),
+    )?;
+    write_array(
+        writer,
+        &peaks.iter().map(|p| p.1).collect::<Vec<_>>(),
+        compress,
+        ("MS:1000515", "intensity array"),
+        ("MS:1000131", "number of detector counts"),
+    )?;
+    writeln!(writer, "        </binaryDataArrayList>")?;
+    writeln!(writer, "      </spectrum>")?;
+    Ok(())
+}
+
+/// Write a single binary data array
+/// # Errors
+/// If writing or compressing fails.
+fn write_array(
+    writer: &mut impl Write,
+    values: &[f64],
+    compress: bool,
+    array: (&str, &str),
+    unit: (&str, &str),
+) -> std::io::Result<()> {
+    let mut bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    if compress {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&bytes)?;
+        bytes = encoder.finish()?;
+    }
+    let encoded = base64(&bytes);
+    writeln!(
+        writer,
+        r#"          <binaryDataArray encodedLength="{}">"#,
+        encoded.len()
+    )?;
+    cv_param(writer, 12, "MS:1000523", "64-bit float", "")?;
+    if compress {
+        cv_param(writer, 12, "MS:1000574", "zlib compression", "")?;
+    } else {
+        cv_param(writer, 12, "MS:1000576", "no compression", "")?;
+    }
+    writeln!(
+        writer,
+        r#"            <cvParam cvRef="MS" accession="{}" name="{}" value="" unitCvRef="MS" unitAccession="{}" unitName="{}"/>"#,
+        array.0, array.1, unit.0, unit.1
+    )?;
+    writeln!(writer, "            <binary>{encoded}</binary>")?;
+    writeln!(writer, "          </binaryDataArray>")?;
+    Ok(())
+}
+
+/// Write a single cvParam from the MS ontology on its own line
+/// # Errors
+/// If writing fails.
+fn cv_param(
+    writer: &mut impl Write,
+    indent: usize,
+    accession: &str,
+    name: &str,
+    value: &str,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        r#"{:indent$}<cvParam cvRef="MS" accession="{accession}" name="{name}" value="{}"/>"#,
+        "",
+        escape(value)
+    )
+}
+
+/// Escape the special XML characters for use in an attribute value
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Encode the given bytes as standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (u32::from(chunk[0]) << 16)
+            | (u32::from(*chunk.get(1).unwrap_or(&0)) << 8)
+            | u32::from(*chunk.get(2).unwrap_or(&0));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(ALPHABET[(value >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        rawfile::mgf,
+        spectrum::RawPeak,
+        system::{f64::MassOverCharge, mz},
+    };
+
+    fn decode(text: &str) -> Vec<u8> {
+        let value = |c: u8| match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            _ => 63,
+        };
+        let mut result = Vec::new();
+        for chunk in text.as_bytes().chunks(4) {
+            let padding = chunk.iter().filter(|c| **c == b'=').count();
+            let combined = chunk
+                .iter()
+                .map(|c| if *c == b'=' { 0 } else { u32::from(value(*c)) })
+                .fold(0_u32, |acc, v| (acc << 6) | v);
+            result.extend_from_slice(&combined.to_be_bytes()[1..4 - padding]);
+        }
+        result
+    }
+
+    #[test]
+    fn base64_encoding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(decode(&base64(b"rustyms")), b"rustyms");
+    }
+
+    #[test]
+    fn write_spectra() {
+        let mut spectra = mgf::open("data/example.mgf").unwrap();
+        let mut ms1 = RawSpectrum::default();
+        ms1.title = "MS1 <survey> & \"full\"".to_string();
+        ms1.extend([
+            RawPeak {
+                mz: MassOverCharge::new::<mz>(400.5),
+                intensity: 10.0.into(),
+            },
+            RawPeak {
+                mz: MassOverCharge::new::<mz>(800.25),
+                intensity: 20.0.into(),
+            },
+        ]);
+        spectra.push(ms1);
+
+        for compress in [false, true] {
+            let mut output = Vec::new();
+            write(&mut output, &spectra, compress).unwrap();
+            let text = String::from_utf8(output).unwrap();
+            assert!(text.contains(r#"<spectrumList count="2""#));
+            assert!(text.contains(r#"name="ms level" value="2""#));
+            assert!(text.contains(r#"name="ms level" value="1""#));
+            assert!(text.contains("MS1 &lt;survey&gt; &amp; &quot;full&quot;"));
+            assert_eq!(text.matches("<precursor>").count(), 1);
+            assert!(!text.contains("<activation"));
+            assert_eq!(
+                text.matches("zlib compression").count(),
+                if compress { 4 } else { 0 }
+            );
+
+            if !compress {
+                let binary = text
+                    .lines()
+                    .filter_map(|l| {
+                        l.trim()
+                            .strip_prefix("<binary>")
+                            .and_then(|l| l.strip_suffix("</binary>"))
+                    })
+                    .nth(2)
+                    .unwrap();
+                let values = decode(binary)
+                    .chunks(8)
+                    .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+                    .collect::<Vec<_>>();
+                assert_eq!(values, [400.5, 800.25]);
+            }
+        }
+    }
+
+    #[test]
+    fn write_activation() {
+        struct Hcd(RawSpectrum);
+        impl MzMLSpectrum for Hcd {
+            fn title(&self) -> &str {
+                self.0.title()
+            }
+            fn retention_time(&self) -> Option<Time> {
+                self.0.retention_time()
+            }
+            fn precursor(&self) -> Option<(f64, Option<Charge>, Option<f64>)> {
+                self.0.precursor()
+            }
+            fn dissociation_method(&self) -> Option<(&str, &str)> {
+                Some(("MS:1000422", "beam-type collision-induced dissociation"))
+            }
+            fn peaks(&self) -> Vec<(f64, f64)> {
+                self.0.peaks()
+            }
+        }
+
+        let spectra = mgf::open("data/example.mgf")
+            .unwrap()
+            .into_iter()
+            .map(Hcd)
+            .collect::<Vec<_>>();
+        let mut output = Vec::new();
+        write(&mut output, &spectra, false).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.matches("<activation>").count(), 1);
+        assert!(text
+            .contains(r#"accession="MS:1000422" name="beam-type collision-induced dissociation""#));
+    }
+}
@@ -0,0 +1,913 @@
+//! A minimal mass tolerant sequence database search engine with target-decoy FDR estimation.
+//!
+//! This combines the digestion, modification placement, fragment generation, and spectrum scoring
+//! of this crate into a simple but complete database search. It is not meant to compete with
+//! dedicated search engines, but it can be used as a reference implementation or a starting point
+//! for new methods.
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::{identification::FastaData, rawfile::mgf, search::*};
+//! let spectra = mgf::open("data/real_example.mgf")?;
+//! let fasta = FastaData::parse_file("database.fasta")?;
+//! let matches = SearchEngine::search(&spectra, &fasta, SearchSettings::default())?;
+//! for psm in matches.iter().filter(|psm| !psm.decoy && psm.q_value <= 0.01) {
+//!     println!("{} {} {:.2}", psm.spectrum_index, psm.peptidoform, psm.score);
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::RangeInclusive,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::Checkpoint,
+    error::{Context, CustomError},
+    identification::{FastaData, ProteinVariant},
+    modification::SimpleModification,
+    peptidoform::{PeptideTerminus, ProteinPosition, SemiAmbiguous},
+    spectrum::{PeakIndex, Score},
+    system::{usize::Charge, Mass, Ratio},
+    transformation::{
+        AppliedTransformation, PointMutation, SequenceTransformation, TransformationPipeline,
+    },
+    AminoAcid, AnnotatableSpectrum, Chemical, MassMode, Model, MolecularCharge, Peptidoform,
+    Protease, RawSpectrum, SequencePosition, Tolerance,
+};
+
+/// The settings for a database search, see [`SearchEngine`].
+#[derive(Clone, Debug)]
+pub struct SearchSettings {
+    /// The protease used to digest the proteins.
+    ///
+    /// Default: trypsin (C terminal of K and R).
+    pub protease: Protease,
+    /// The maximal number of missed cleavages.
+    ///
+    /// Default: 2.
+    pub max_missed_cleavages: usize,
+    /// Search semi specifically, so also include peptides where only one terminus is a cut site
+    /// of the protease, see [`Peptidoform::semi_digest`].
+    ///
+    /// Default: false.
+    pub semi_enzymatic: bool,
+    /// Search for neo N termini (N terminomics), so include peptides with a C terminus that is a
+    /// cut site of the protease and any N terminus, see [`Peptidoform::neo_n_terminal_digest`].
+    /// This takes precedence over [`Self::semi_enzymatic`]. The origin of the N terminus of all
+    /// matches is given in [`PeptideSpectrumMatch::locations`], see [`protein_n_termini`] for a
+    /// summary.
+    ///
+    /// Default: false.
+    pub neo_n_termini: bool,
+    /// The allowed peptide lengths.
+    ///
+    /// Default: 6..=40.
+    pub peptide_length: RangeInclusive<usize>,
+    /// The modifications that are placed on every possible location. Modifications without
+    /// placement rules (for example mass shifts) are considered possible on every residue.
+    ///
+    /// Default: none.
+    pub fixed_modifications: Vec<SimpleModification>,
+    /// The modifications that can optionally be placed on every possible location. Modifications
+    /// without placement rules (for example mass shifts) are considered possible on every residue.
+    ///
+    /// Default: none.
+    pub variable_modifications: Vec<SimpleModification>,
+    /// The modifications from an N terminal labelling step before digestion (for example
+    /// dimethylation or TMT in TAILS). These are placed as optional modification on the N
+    /// terminus of all peptides where the N terminus is not a cut site of the protease, as those
+    /// N termini were already present before digestion. These do not count towards
+    /// [`Self::max_variable_modifications`].
+    ///
+    /// Default: none.
+    pub n_terminal_labels: Vec<SimpleModification>,
+    /// The maximal number of variable modifications on a single peptide.
+    ///
+    /// Default: 2.
+    pub max_variable_modifications: usize,
+    /// The tolerance for matching the precursor mass.
+    ///
+    /// Default: 10 ppm.
+    pub precursor_tolerance: Tolerance<Mass>,
+    /// The maximal fragment charge, if `None` the precursor charge is used.
+    ///
+    /// Default: `None`.
+    pub max_fragment_charge: Option<Charge>,
+    /// The model used to generate and match fragments.
+    ///
+    /// Default: [`Model::cid_hcd`].
+    pub model: Model,
+    /// The mass mode used for precursors and fragments.
+    ///
+    /// Default: monoisotopic.
+    pub mass_mode: MassMode,
+    /// The transformations used to generate the decoy proteins, all variants are used as decoys.
+    ///
+    /// Default: reverse.
+    pub decoy_generation: TransformationPipeline,
+    /// The single amino acid variants, every variant is searched as a separate variant protein,
+    /// both as target and decoy, see [`FastaData::variant_proteins`].
+    ///
+    /// Default: none.
+    pub variants: Vec<ProteinVariant>,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            protease: Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]),
+            max_missed_cleavages: 2,
+            semi_enzymatic: false,
+            neo_n_termini: false,
+            peptide_length: 6..=40,
+            fixed_modifications: Vec::new(),
+            variable_modifications: Vec::new(),
+            n_terminal_labels: Vec::new(),
+            max_variable_modifications: 2,
+            precursor_tolerance: Tolerance::new_ppm(10.0),
+            max_fragment_charge: None,
+            model: Model::cid_hcd(),
+            mass_mode: MassMode::Monoisotopic,
+            decoy_generation: TransformationPipeline::default()
+                .then(SequenceTransformation::Reverse),
+            variants: Vec::new(),
+        }
+    }
+}
+
+/// A single peptide spectrum match as returned by [`SearchEngine`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeptideSpectrumMatch {
+    /// The index of the spectrum in the searched spectra
+    pub spectrum_index: usize,
+    /// The matched peptidoform
+    pub peptidoform: Peptidoform<SemiAmbiguous>,
+    /// The accessions of all proteins that contain this peptidoform
+    pub proteins: Vec<String>,
+    /// If this is a match to a decoy protein
+    pub decoy: bool,
+    /// The single amino acid variant if this peptidoform is only found in a variant protein
+    #[serde(default)]
+    pub variant: Option<PointMutation>,
+    /// All locations of this peptidoform in the proteins that contain it
+    #[serde(default)]
+    pub locations: Vec<PeptideLocation>,
+    /// The score, defined as `ln(n!) + ln(1 + I)` with `n` the number of annotated peaks and `I`
+    /// the annotated intensity (similar to the X!Tandem hyperscore)
+    pub score: f64,
+    /// The number of peaks that could be annotated
+    pub matched_peaks: u32,
+    /// The fraction of the total intensity that could be annotated
+    pub matched_intensity: f64,
+    /// The signed precursor mass error
+    pub precursor_error: Ratio,
+    /// The q-value, the lowest FDR at which this match is accepted, see [`assign_q_values`]
+    pub q_value: f64,
+}
+
+/// The location of a peptidoform in a protein, with the origin of both termini
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PeptideLocation {
+    /// The accession of the protein
+    pub accession: String,
+    /// The location in the protein
+    pub position: ProteinPosition,
+    /// The origin of the N terminus
+    pub n_terminus: PeptideTerminus,
+    /// The origin of the C terminus
+    pub c_terminus: PeptideTerminus,
+}
+
+/// A protein N terminus that is supported by at least one match, see [`protein_n_termini`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProteinNTerminus {
+    /// The accession of the protein
+    pub accession: String,
+    /// The sequence index in the protein of the first residue
+    pub position: usize,
+    /// The origin of this N terminus, neo N termini are [`PeptideTerminus::NonSpecific`]
+    pub terminus: PeptideTerminus,
+    /// The indices of all supporting matches
+    pub matches: Vec<usize>,
+    /// The number of supporting matches with one of the N terminal labels
+    pub labelled: usize,
+}
+
+/// A peptidoform that can be matched in the search
+#[derive(Clone, Debug)]
+struct Candidate {
+    peptidoform: Peptidoform<SemiAmbiguous>,
+    proteins: Vec<usize>,
+    decoy: bool,
+    variant: Option<PointMutation>,
+    locations: Vec<PeptideLocation>,
+}
+
+/// A database search engine, with all candidate peptidoforms indexed on mass.
+#[derive(Clone, Debug)]
+pub struct SearchEngine {
+    settings: SearchSettings,
+    accessions: Vec<String>,
+    candidates: Vec<Candidate>,
+    /// All masses with the index of their candidate, sorted on mass
+    masses: PeakIndex<(Mass, usize)>,
+}
+
+impl SearchEngine {
+    /// Search all spectra against the given proteins in one go, see [`Self::new`] and
+    /// [`Self::search_spectrum`]. The best match for every spectrum (target or decoy) is returned,
+    /// with the q-values determined over all returned matches.
+    /// # Errors
+    /// If the settings are invalid, see [`Self::new`].
+    pub fn search(
+        spectra: &[RawSpectrum],
+        fasta: &[FastaData],
+        settings: SearchSettings,
+    ) -> Result<Vec<PeptideSpectrumMatch>, CustomError> {
+        let engine = Self::new(fasta, settings)?;
+        let mut matches = spectra
+            .iter()
+            .enumerate()
+            .filter_map(|(index, spectrum)| engine.search_spectrum(index, spectrum))
+            .collect_vec();
+        assign_q_values(&mut matches);
+        Ok(matches)
+    }
+
+    /// Search all spectra with this engine, while saving the progress in the given checkpoint. If
+    /// the checkpoint contains the progress of an earlier (crashed) run over the same spectra,
+    /// those spectra are not searched again. The q-values are assigned over all matches. The
+    /// checkpoint file is left in place, it can be removed once the matches are stored elsewhere.
+    /// # Errors
+    /// If the checkpoint could not be saved.
+    pub fn search_checkpointed(
+        &self,
+        spectra: &[RawSpectrum],
+        mut checkpoint: Checkpoint<usize, PeptideSpectrumMatch>,
+    ) -> Result<Vec<PeptideSpectrumMatch>, CustomError> {
+        checkpoint.run(
+            spectra
+                .iter()
+                .enumerate()
+                .map(|(index, spectrum)| (index, (index, spectrum))),
+            |(index, spectrum)| self.search_spectrum(index, spectrum),
+        )?;
+        let mut matches = checkpoint.into_outputs();
+        assign_q_values(&mut matches);
+        Ok(matches)
+    }
+
+    /// Build the search index by digesting all proteins, their variant proteins, and their decoy
+    /// proteins, and placing all fixed and variable modifications. Peptides that are found in
+    /// both a target and a decoy protein are only kept as target. Peptides that are found in both
+    /// a canonical and a variant protein are kept as canonical.
+    /// # Errors
+    /// If the decoy generation pipeline is empty, as that would use every target as its own decoy.
+    pub fn new(fasta: &[FastaData], settings: SearchSettings) -> Result<Self, CustomError> {
+        if settings.decoy_generation.steps.is_empty() {
+            return Err(CustomError::error(
+                "Invalid decoy generation",
+                "The decoy generation pipeline has no steps, so every decoy would be identical to its target",
+                Context::None,
+            ));
+        }
+        let mut accessions = Vec::with_capacity(fasta.len());
+        let mut candidates: Vec<Candidate> = Vec::new();
+        let mut lookup: HashMap<Peptidoform<SemiAmbiguous>, usize> = HashMap::new();
+
+        for decoy in [false, true] {
+            for (protein_index, protein) in fasta.iter().enumerate() {
+                if !decoy {
+                    accessions.push(protein.identifier().accession().to_string());
+                }
+                let sequences = std::iter::once((protein.peptide().clone(), None))
+                    .chain(
+                        protein
+                            .variant_proteins(&settings.variants)
+                            .into_iter()
+                            .map(|variant| {
+                                let mutation = variant.transformations.first().and_then(|t| {
+                                    if let AppliedTransformation::Mutation(m) = t {
+                                        Some(*m)
+                                    } else {
+                                        None
+                                    }
+                                });
+                                (variant.peptidoform, mutation)
+                            }),
+                    )
+                    .flat_map(|(sequence, mutation)| {
+                        if decoy {
+                            settings
+                                .decoy_generation
+                                .apply(&sequence)
+                                .into_iter()
+                                .map(|variant| (variant.peptidoform, mutation))
+                                .collect()
+                        } else {
+                            vec![(sequence, mutation)]
+                        }
+                    })
+                    .collect_vec();
+                let peptides = sequences.iter().flat_map(|(sequence, mutation)| {
+                    if settings.neo_n_termini {
+                        sequence.neo_n_terminal_digest(
+                            &settings.protease,
+                            settings.max_missed_cleavages,
+                            settings.peptide_length.clone(),
+                        )
+                    } else if settings.semi_enzymatic {
+                        sequence.semi_digest(
+                            &settings.protease,
+                            settings.max_missed_cleavages,
+                            settings.peptide_length.clone(),
+                        )
+                    } else {
+                        sequence.digest(&settings.protease, settings.max_missed_cleavages)
+                    }
+                    .into_iter()
+                    .map(move |peptide| (peptide, *mutation, sequence))
+                });
+                for (peptide, variant, sequence) in peptides {
+                    if !settings.peptide_length.contains(&peptide.len()) {
+                        continue;
+                    }
+                    let location = peptide
+                        .get_protein_position()
+                        .map(|position| PeptideLocation {
+                            accession: accessions[protein_index].clone(),
+                            position: *position,
+                            n_terminus: position
+                                .n_terminus(sequence.sequence(), &settings.protease),
+                            c_terminus: position
+                                .c_terminus(sequence.sequence(), &settings.protease),
+                        });
+                    let labels = location
+                        .as_ref()
+                        .map_or(true, |l| l.n_terminus != PeptideTerminus::Specific);
+                    for peptidoform in modified_forms(peptide, &settings, labels) {
+                        if let Some(index) = lookup.get(&peptidoform) {
+                            let candidate = &mut candidates[*index];
+                            if candidate.decoy != decoy
+                                || (candidate.variant.is_none() && variant.is_some())
+                            {
+                                continue;
+                            }
+                            // A canonical peptide takes precedence over the same peptide from a
+                            // variant protein, also if the variant came from another protein
+                            if candidate.variant.is_some() && variant.is_none() {
+                                candidate.variant = None;
+                                candidate.proteins.clear();
+                                candidate.locations.clear();
+                            }
+                            if !candidate.proteins.contains(&protein_index) {
+                                candidate.proteins.push(protein_index);
+                            }
+                            if let Some(location) = &location {
+                                if !candidate.locations.contains(location) {
+                                    candidate.locations.push(location.clone());
+                                }
+                            }
+                        } else {
+                            lookup.insert(peptidoform.clone(), candidates.len());
+                            candidates.push(Candidate {
+                                peptidoform,
+                                proteins: vec![protein_index],
+                                decoy,
+                                variant,
+                                locations: location.iter().cloned().collect(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let masses = candidates
+            .iter()
+            .enumerate()
+            .flat_map(|(index, candidate)| {
+                candidate
+                    .peptidoform
+                    .formulas()
+                    .iter()
+                    .map(|f| (f.mass(settings.mass_mode), index))
+                    .collect_vec()
+            })
+            .collect();
+
+        Ok(Self {
+            settings,
+            accessions,
+            candidates,
+            masses,
+        })
+    }
+
+    /// The number of unique candidate peptidoforms (targets and decoys) in the index
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Check if the index contains no candidates
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Search a single spectrum and return the best scoring match, if any candidate matches the
+    /// precursor. The precursor mass of the spectrum is interpreted as m/z (as the PEPMASS in MGF)
+    /// and together with the precursor charge it is converted to a neutral mass. Spectra without
+    /// precursor mass or charge are skipped. The q-value of the returned match is not yet set,
+    /// see [`assign_q_values`].
+    pub fn search_spectrum(
+        &self,
+        spectrum_index: usize,
+        spectrum: &RawSpectrum,
+    ) -> Option<PeptideSpectrumMatch> {
+        let charge = spectrum.charge?;
+        let precursor = spectrum.mass? * charge.value as f64
+            - MolecularCharge::proton(isize::try_from(charge.value).ok()?)
+                .formula()
+                .monoisotopic_mass();
+        let max_charge = self.settings.max_fragment_charge.unwrap_or(charge);
+
+        let mut seen = HashSet::new();
+        let mut best: Option<PeptideSpectrumMatch> = None;
+
+        for (mass, index) in self
+            .masses
+            .within(precursor, self.settings.precursor_tolerance)
+        {
+            if !seen.insert(*index) {
+                continue;
+            }
+            let candidate = &self.candidates[*index];
+            let fragments = candidate
+                .peptidoform
+                .generate_theoretical_fragments(max_charge, &self.settings.model);
+            let annotated = spectrum.annotate(
+                candidate.peptidoform.clone().into(),
+                &fragments,
+                &self.settings.model,
+                self.settings.mass_mode,
+            );
+            let (scores, _) =
+                annotated.scores(&fragments, &self.settings.model, self.settings.mass_mode);
+            let (Score::Position {
+                peaks, intensity, ..
+            }
+            | Score::UniqueFormulas {
+                peaks, intensity, ..
+            }) = scores.score;
+            // ln(1 + I) keeps the score finite if the matched peaks have no intensity
+            let score =
+                (1..=peaks.found).map(|n| f64::from(n).ln()).sum::<f64>() + intensity.found.ln_1p();
+
+            if best.as_ref().map_or(true, |b| score > b.score) {
+                best = Some(PeptideSpectrumMatch {
+                    spectrum_index,
+                    peptidoform: candidate.peptidoform.clone(),
+                    proteins: candidate
+                        .proteins
+                        .iter()
+                        .map(|p| self.accessions[*p].clone())
+                        .collect(),
+                    decoy: candidate.decoy,
+                    variant: candidate.variant,
+                    locations: candidate.locations.clone(),
+                    score,
+                    matched_peaks: peaks.found,
+                    matched_intensity: intensity.fraction(),
+                    precursor_error: mass.signed_ppm(precursor),
+                    q_value: 1.0,
+                });
+            }
+        }
+
+        best
+    }
+}
+
+/// Set the q-values for the given matches based on the target-decoy approach. The FDR at a given
+/// score is estimated as the number of decoys divided by the number of targets at or above that
+/// score, the q-value is the lowest FDR at which a match is still accepted. The order of the
+/// matches is left unchanged.
+pub fn assign_q_values(matches: &mut [PeptideSpectrumMatch]) {
+    let mut order = (0..matches.len()).collect_vec();
+    order.sort_unstable_by(|a, b| matches[*b].score.total_cmp(&matches[*a].score));
+    let mut targets = 0_usize;
+    let mut decoys = 0_usize;
+    for index in &order {
+        let psm = &mut matches[*index];
+        if psm.decoy {
+            decoys += 1;
+        } else {
+            targets += 1;
+        }
+        psm.q_value = (decoys as f64 / targets.max(1) as f64).min(1.0);
+    }
+    let mut lowest = 1.0_f64;
+    for index in order.iter().rev() {
+        lowest = lowest.min(matches[*index].q_value);
+        matches[*index].q_value = lowest;
+    }
+}
+
+/// Summarise all protein N termini, including neo N termini, that are supported by target matches
+/// with at most the given q-value. A match supports all N termini of its locations (see
+/// [`PeptideSpectrumMatch::locations`]). The matches are referenced by their index in the given
+/// matches. The matches with one of the given modifications on the N terminus are counted as
+/// labelled, to assess the N terminal labelling chemistry in for example TAILS. The N termini are
+/// sorted on accession and position.
+pub fn protein_n_termini(
+    matches: &[PeptideSpectrumMatch],
+    max_q_value: f64,
+    labels: &[SimpleModification],
+) -> Vec<ProteinNTerminus> {
+    let mut termini: BTreeMap<(&str, usize), ProteinNTerminus> = BTreeMap::new();
+    for (index, psm) in matches.iter().enumerate() {
+        if psm.decoy || psm.q_value > max_q_value {
+            continue;
+        }
+        let labelled = psm
+            .peptidoform
+            .get_simple_n_term()
+            .iter()
+            .any(|m| labels.contains(m));
+        for location in &psm.locations {
+            let terminus = termini
+                .entry((&location.accession, location.position.start))
+                .or_insert_with(|| ProteinNTerminus {
+                    accession: location.accession.clone(),
+                    position: location.position.start,
+                    terminus: location.n_terminus,
+                    matches: Vec::new(),
+                    labelled: 0,
+                });
+            if !terminus.matches.contains(&index) {
+                terminus.matches.push(index);
+                terminus.labelled += usize::from(labelled);
+            }
+        }
+    }
+    termini.into_values().collect()
+}
+
+/// Place all fixed modifications and generate all combinations of variable modifications, and
+/// if `labels` is set all N terminal labels on the forms without N terminal modification
+fn modified_forms(
+    mut peptide: Peptidoform<SemiAmbiguous>,
+    settings: &SearchSettings,
+    labels: bool,
+) -> Vec<Peptidoform<SemiAmbiguous>> {
+    for modification in &settings.fixed_modifications {
+        for position in possible_positions(&peptide, modification) {
+            peptide.add_simple_modification(position, modification.clone());
+        }
+    }
+
+    let sites = settings
+        .variable_modifications
+        .iter()
+        .flat_map(|modification| {
+            possible_positions(&peptide, modification)
+                .into_iter()
+                .map(move |position| (position, modification))
+        })
+        .collect_vec();
+
+    let mut result = vec![peptide.clone()];
+    for number in 1..=settings.max_variable_modifications.min(sites.len()) {
+        for combination in sites.iter().combinations(number) {
+            if !combination
+                .iter()
+                .map(|(position, _)| position)
+                .all_unique()
+            {
+                continue;
+            }
+            let mut modified = peptide.clone();
+            for (position, modification) in combination {
+                modified.add_simple_modification(*position, (*modification).clone());
+            }
+            result.push(modified);
+        }
+    }
+    if labels {
+        let unlabelled = result.len();
+        for label in &settings.n_terminal_labels {
+            if !possible_positions(&peptide, label).contains(&SequencePosition::NTerm) {
+                continue;
+            }
+            for index in 0..unlabelled {
+                if result[index].get_n_term().is_empty() {
+                    let mut labelled = result[index].clone();
+                    labelled.add_simple_modification(SequencePosition::NTerm, label.clone());
+                    result.push(labelled);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// All positions where the given modification can be placed on this peptide
+fn possible_positions(
+    peptide: &Peptidoform<SemiAmbiguous>,
+    modification: &SimpleModification,
+) -> Vec<SequencePosition> {
+    let mut positions = Vec::new();
+    let protein = peptide.get_protein_position();
+    if let Some(first) = peptide.sequence().first() {
+        if modification
+            .is_possible_in_protein(first, SequencePosition::NTerm, protein)
+            .any_possible()
+            && !modification
+                .is_possible_in_protein(first, SequencePosition::Index(0), protein)
+                .any_possible()
+        {
+            positions.push(SequencePosition::NTerm);
+        }
+    }
+    positions.extend(
+        peptide
+            .sequence()
+            .iter()
+            .enumerate()
+            .filter(|(index, element)| {
+                modification
+                    .is_possible_in_protein(element, SequencePosition::Index(*index), protein)
+                    .any_possible()
+            })
+            .map(|(index, _)| SequencePosition::Index(index)),
+    );
+    if let Some(last) = peptide.sequence().last() {
+        if modification
+            .is_possible_in_protein(last, SequencePosition::CTerm, protein)
+            .any_possible()
+            && !modification
+                .is_possible_in_protein(last, SequencePosition::Index(peptide.len() - 1), protein)
+                .any_possible()
+        {
+            positions.push(SequencePosition::CTerm);
+        }
+    }
+    positions
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        rawfile::mgf,
+        spectrum::{PeakSpectrum, RawPeak},
+    };
+
+    #[test]
+    fn search_real_example() {
+        let spectra = mgf::open("data/real_example.mgf").unwrap();
+        let fasta = FastaData::parse_reader(
+            ">sp|P00001|TEST1 Test protein 1\nMSADEEYTKKLNTQGSWLHPAGD\n>sp|P00002|TEST2 Test protein 2\nMKWVTFISLLFLFSSAYSRGVFRRDAHKSEVAHRFKDLGEENFKALVLIAFAQYLQQ\n"
+                .as_bytes(),
+            None,
+        )
+        .unwrap();
+        let settings = SearchSettings {
+            protease: Protease::c_terminal_of(&[AminoAcid::AsparticAcid, AminoAcid::Glutamine]),
+            peptide_length: 4..=30,
+            ..SearchSettings::default()
+        };
+        let engine = SearchEngine::new(&fasta, settings.clone()).unwrap();
+        assert!(!engine.is_empty());
+        let matches = SearchEngine::search(&spectra, &fasta, settings.clone()).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].peptidoform.to_string(), "EEYTKKLNTQ");
+        assert_eq!(matches[0].proteins, ["P00001"]);
+        assert!(!matches[0].decoy);
+        assert!(matches[0].precursor_error.value.abs() < 10.0);
+        assert!(matches[0].q_value.abs() < f64::EPSILON);
+
+        let path = std::env::temp_dir().join(format!(
+            "rustyms_search_test_{}.checkpoint",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let checkpointed = engine
+            .search_checkpointed(&spectra, Checkpoint::open(&path, 1).unwrap())
+            .unwrap();
+        let checkpoint = Checkpoint::<usize, PeptideSpectrumMatch>::open(&path, 1).unwrap();
+        assert_eq!(checkpoint.len(), spectra.len());
+        checkpoint.remove().unwrap();
+        assert_eq!(checkpointed, matches);
+
+        let settings = SearchSettings {
+            decoy_generation: TransformationPipeline::default(),
+            ..settings
+        };
+        assert!(SearchEngine::new(&fasta, settings).is_err());
+    }
+
+    #[test]
+    fn search_zero_intensity() {
+        let spectra = mgf::open("data/real_example.mgf").unwrap();
+        let mut spectrum = RawSpectrum::default();
+        spectrum.title.clone_from(&spectra[0].title);
+        spectrum.charge = spectra[0].charge;
+        spectrum.mass = spectra[0].mass;
+        spectrum.extend(spectra[0].spectrum().map(|peak| RawPeak {
+            mz: peak.mz,
+            intensity: 0.0.into(),
+        }));
+        let fasta = FastaData::parse_reader(
+            ">sp|P00001|TEST1 Test protein 1\nMSADEEYTKKLNTQGSWLHPAGD\n".as_bytes(),
+            None,
+        )
+        .unwrap();
+        let settings = SearchSettings {
+            protease: Protease::c_terminal_of(&[AminoAcid::AsparticAcid, AminoAcid::Glutamine]),
+            peptide_length: 4..=30,
+            ..SearchSettings::default()
+        };
+        let matches = SearchEngine::search(&[spectrum], &fasta, settings).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].matched_peaks > 0);
+        assert!(matches[0].score.is_finite(), "{}", matches[0].score);
+        assert!(matches[0].score > 0.0);
+    }
+
+    #[test]
+    fn search_variants() {
+        let spectra = mgf::open("data/real_example.mgf").unwrap();
+        let fasta = FastaData::parse_reader(
+            ">sp|P00001|TEST1 Test protein 1\nMSADEEYTRKLNTQGSWLHPAGD\n".as_bytes(),
+            None,
+        )
+        .unwrap();
+        let settings = SearchSettings {
+            protease: Protease::c_terminal_of(&[AminoAcid::AsparticAcid, AminoAcid::Glutamine]),
+            peptide_length: 4..=30,
+            variants: vec![ProteinVariant::new("P00001", "R9K".parse().unwrap())],
+            ..SearchSettings::default()
+        };
+        let matches = SearchEngine::search(&spectra, &fasta, settings.clone()).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].peptidoform.to_string(), "EEYTKKLNTQ");
+        assert_eq!(matches[0].proteins, ["P00001"]);
+        assert_eq!(matches[0].variant, Some("R9K".parse().unwrap()));
+        assert!(!matches[0].decoy);
+
+        // The canonical peptide from a later protein takes precedence over the variant
+        let fasta = FastaData::parse_reader(
+            ">sp|P00001|TEST1 Test protein 1\nMSADEEYTRKLNTQGSWLHPAGD\n>sp|P00002|TEST2 Test protein 2\nMSAQEEYTKKLNTQGSW\n".as_bytes(),
+            None,
+        )
+        .unwrap();
+        let matches = SearchEngine::search(&spectra, &fasta, settings).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].peptidoform.to_string(), "EEYTKKLNTQ");
+        assert_eq!(matches[0].proteins, ["P00002"]);
+        assert_eq!(matches[0].variant, None);
+        assert_eq!(matches[0].locations.len(), 1);
+    }
+
+    #[test]
+    fn search_neo_n_termini() {
+        let spectra = mgf::open("data/real_example.mgf").unwrap();
+        let fasta = FastaData::parse_reader(
+            ">sp|P00001|TEST1 Test protein 1\nMSADEEYTKKLNTQGSWLHPAGD\n".as_bytes(),
+            None,
+        )
+        .unwrap();
+        let settings = SearchSettings {
+            protease: Protease::c_terminal_of(&[AminoAcid::Glutamine]),
+            neo_n_termini: true,
+            peptide_length: 4..=30,
+            ..SearchSettings::default()
+        };
+        let matches = SearchEngine::search(&spectra, &fasta, settings).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].peptidoform.to_string(), "EEYTKKLNTQ");
+        assert_eq!(
+            matches[0].locations,
+            [PeptideLocation {
+                accession: "P00001".to_string(),
+                position: ProteinPosition::new(4..14, 23),
+                n_terminus: PeptideTerminus::NonSpecific,
+                c_terminus: PeptideTerminus::Specific,
+            }]
+        );
+        let termini = protein_n_termini(&matches, 1.0, &[]);
+        assert_eq!(termini.len(), 1);
+        assert_eq!(termini[0].position, 4);
+        assert_eq!(termini[0].terminus, PeptideTerminus::NonSpecific);
+        assert_eq!(termini[0].matches, [0]);
+        assert_eq!(termini[0].labelled, 0);
+    }
+
+    #[test]
+    fn n_terminal_labels() {
+        let dimethyl = crate::modification::Ontology::Unimod
+            .find_name("Dimethyl", None)
+            .unwrap();
+        let settings = SearchSettings {
+            n_terminal_labels: vec![dimethyl.clone()],
+            ..SearchSettings::default()
+        };
+        let peptide = Peptidoform::pro_forma("PEPTIDEK", None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        assert_eq!(modified_forms(peptide.clone(), &settings, false).len(), 1);
+        let forms = modified_forms(peptide, &settings, true);
+        assert_eq!(forms.len(), 2);
+        assert_eq!(forms[1].get_simple_n_term(), [dimethyl.clone()]);
+
+        let location = |start| PeptideLocation {
+            accession: "P1".to_string(),
+            position: ProteinPosition::new(start..start + 8, 20),
+            n_terminus: PeptideTerminus::NonSpecific,
+            c_terminus: PeptideTerminus::Specific,
+        };
+        let psm = |peptidoform: &Peptidoform<SemiAmbiguous>, start, decoy| PeptideSpectrumMatch {
+            spectrum_index: 0,
+            peptidoform: peptidoform.clone(),
+            proteins: vec!["P1".to_string()],
+            decoy,
+            variant: None,
+            locations: vec![location(start)],
+            score: 1.0,
+            matched_peaks: 0,
+            matched_intensity: 0.0,
+            precursor_error: Ratio::new::<crate::system::ratio::ppm>(0.0),
+            q_value: 0.0,
+        };
+        let matches = [
+            psm(&forms[0], 5, false),
+            psm(&forms[1], 5, false),
+            psm(&forms[1], 2, false),
+            psm(&forms[1], 8, true),
+        ];
+        let termini = protein_n_termini(&matches, 0.01, &[dimethyl]);
+        assert_eq!(
+            termini
+                .iter()
+                .map(|t| (t.position, t.matches.clone(), t.labelled))
+                .collect_vec(),
+            [(2, vec![2], 1), (5, vec![0, 1], 1)]
+        );
+    }
+
+    #[test]
+    fn protein_terminal_modifications() {
+        let protein = Peptidoform::pro_forma("MPEPTIDEKMAPEK", None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        let settings = SearchSettings {
+            max_missed_cleavages: 0,
+            variable_modifications: vec![crate::modification::Ontology::Unimod
+                .find_name("Met-loss", None)
+                .unwrap()],
+            ..SearchSettings::default()
+        };
+        let forms = protein
+            .digest(&settings.protease, 0)
+            .into_iter()
+            .map(|peptide| modified_forms(peptide, &settings, false).len())
+            .collect_vec();
+        assert_eq!(forms, [2, 1]);
+    }
+
+    #[test]
+    fn q_values() {
+        let psm = |score, decoy| PeptideSpectrumMatch {
+            spectrum_index: 0,
+            peptidoform: Peptidoform::default(),
+            proteins: Vec::new(),
+            decoy,
+            variant: None,
+            locations: Vec::new(),
+            score,
+            matched_peaks: 0,
+            matched_intensity: 0.0,
+            precursor_error: Ratio::new::<crate::system::ratio::ppm>(0.0),
+            q_value: 1.0,
+        };
+        let mut matches = vec![
+            psm(10.0, false),
+            psm(5.0, true),
+            psm(9.0, false),
+            psm(4.0, false),
+            psm(8.0, false),
+        ];
+        assign_q_values(&mut matches);
+        let q = matches.iter().map(|m| m.q_value).collect_vec();
+        assert_eq!(q, [0.0, 1.0 / 4.0, 0.0, 1.0 / 4.0, 0.0]);
+    }
+}
@@ -13,11 +13,12 @@ use crate::{
     CheckedAminoAcid, DiagnosticIon, MolecularFormula, Multi, MultiChemical, Peptidoform,
     SequencePosition,
 };
+use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use thin_vec::ThinVec;
 
 /// One block in a sequence meaning an aminoacid and its accompanying modifications
-#[derive(Default, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug)]
 pub struct SequenceElement<T> {
     /// The aminoacid
     pub aminoacid: CheckedAminoAcid<T>,
@@ -25,6 +26,12 @@ pub struct SequenceElement<T> {
     pub modifications: ThinVec<Modification>,
     /// If this aminoacid is part of an ambiguous sequence group `(QA)?` in ProForma
     pub ambiguous: Option<NonZeroU32>,
+    /// The confidence for this aminoacid, for example the local confidence from de novo tools.
+    /// This is not taken into account for equality and ordering, and is only serialized in human
+    /// readable formats (to keep the binary databases compatible). It is not part of the ProForma
+    /// display, see [`Peptidoform::display_with_confidence`] to show it as INFO tag
+    /// (`[INFO:confidence=0.9]`), which is read back when parsing.
+    pub confidence: Option<OrderedFloat<f64>>,
     /// The marker indicating which level of complexity this sequence element uses as higher bound
     marker: PhantomData<T>,
 }
@@ -35,6 +42,7 @@ impl<T> Clone for SequenceElement<T> {
             aminoacid: self.aminoacid,
             modifications: self.modifications.clone(),
             ambiguous: self.ambiguous,
+            confidence: self.confidence,
             marker: PhantomData,
         }
     }
@@ -58,13 +66,105 @@ impl<T> std::hash::Hash for SequenceElement<T> {
 
 impl<T> Eq for SequenceElement<T> {}
 
+impl<T: PartialOrd> PartialOrd for SequenceElement<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.compared_fields().partial_cmp(&other.compared_fields())
+    }
+}
+
+impl<T: Ord> Ord for SequenceElement<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.compared_fields().cmp(&other.compared_fields())
+    }
+}
+
+impl<T: Serialize> Serialize for SequenceElement<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedSequenceElement {
+            aminoacid: &self.aminoacid,
+            modifications: &self.modifications,
+            ambiguous: self.ambiguous,
+            confidence: serializer.is_human_readable().then_some(self.confidence),
+            marker: PhantomData::<T>,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SequenceElement<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let element = ReadableSequenceElement::<T>::deserialize(deserializer)?;
+            Ok(Self {
+                aminoacid: element.aminoacid,
+                modifications: element.modifications,
+                ambiguous: element.ambiguous,
+                confidence: element.confidence,
+                marker: PhantomData,
+            })
+        } else {
+            let element = BinarySequenceElement::<T>::deserialize(deserializer)?;
+            Ok(Self {
+                aminoacid: element.aminoacid,
+                modifications: element.modifications,
+                ambiguous: element.ambiguous,
+                confidence: None,
+                marker: PhantomData,
+            })
+        }
+    }
+}
+
+/// The serialized form of a [`SequenceElement`], the confidence is skipped for binary formats
+#[derive(Serialize)]
+#[serde(rename = "SequenceElement")]
+struct SerializedSequenceElement<'a, T> {
+    aminoacid: &'a CheckedAminoAcid<T>,
+    modifications: &'a ThinVec<Modification>,
+    ambiguous: Option<NonZeroU32>,
+    #[allow(clippy::option_option)] // The outer None skips the field for binary formats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<Option<OrderedFloat<f64>>>,
+    marker: PhantomData<T>,
+}
+
+/// A [`SequenceElement`] as stored in human readable formats, the confidence is optional
+#[derive(Deserialize)]
+#[serde(rename = "SequenceElement")]
+struct ReadableSequenceElement<T> {
+    aminoacid: CheckedAminoAcid<T>,
+    modifications: ThinVec<Modification>,
+    ambiguous: Option<NonZeroU32>,
+    #[serde(default)]
+    confidence: Option<OrderedFloat<f64>>,
+    #[allow(dead_code)]
+    marker: PhantomData<T>,
+}
+
+/// A [`SequenceElement`] as stored in binary formats, without confidence
+#[derive(Deserialize)]
+#[serde(rename = "SequenceElement")]
+struct BinarySequenceElement<T> {
+    aminoacid: CheckedAminoAcid<T>,
+    modifications: ThinVec<Modification>,
+    ambiguous: Option<NonZeroU32>,
+    #[allow(dead_code)]
+    marker: PhantomData<T>,
+}
+
 impl<T> SequenceElement<T> {
+    /// The fields that are taken into account for comparisons, the confidence is left out
+    fn compared_fields(&self) -> (&CheckedAminoAcid<T>, &[Modification], Option<NonZeroU32>) {
+        (&self.aminoacid, &self.modifications, self.ambiguous)
+    }
+
     /// Mark this sequence element as the following complexity level, the level is not validated
     pub(super) fn mark<M>(self) -> SequenceElement<M> {
         SequenceElement {
             aminoacid: self.aminoacid.mark::<M>(),
             modifications: self.modifications,
             ambiguous: self.ambiguous,
+            confidence: self.confidence,
             marker: PhantomData,
         }
     }
@@ -81,6 +181,7 @@ impl<T> SequenceElement<T> {
             aminoacid,
             modifications: ThinVec::new(),
             ambiguous,
+            confidence: None,
             marker: PhantomData,
         }
     }
@@ -101,6 +202,7 @@ impl<T> SequenceElement<T> {
 impl<T> SequenceElement<T> {
     /// # Errors
     /// If the underlying formatter errors.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn display(
         &self,
         f: &mut impl Write,
@@ -108,6 +210,8 @@ impl<T> SequenceElement<T> {
         preferred_ambiguous_location: &[Option<SequencePosition>],
         index: usize,
         last_ambiguous: Option<NonZeroU32>,
+        hidden: &[&SimpleModification],
+        show_confidence: bool,
         specification_compliant: bool,
     ) -> Result<Vec<usize>, std::fmt::Error> {
         let mut extra_placed = Vec::new();
@@ -118,7 +222,14 @@ impl<T> SequenceElement<T> {
             write!(f, "(?")?;
         }
         write!(f, "{}", self.aminoacid.char())?;
+        let mut hidden = hidden.to_vec();
         for m in &self.modifications {
+            if let Modification::Simple(simple) = m {
+                if let Some(index) = hidden.iter().position(|h| *h == simple) {
+                    hidden.remove(index);
+                    continue;
+                }
+            }
             let mut display_ambiguous = false;
             if let Modification::Ambiguous { id, .. } = m {
                 if !placed_ambiguous.contains(id) && preferred_ambiguous_location[*id].is_none()
@@ -133,6 +244,9 @@ impl<T> SequenceElement<T> {
             m.display(f, specification_compliant, display_ambiguous)?;
             write!(f, "]")?;
         }
+        if let Some(confidence) = self.confidence.filter(|_| show_confidence) {
+            write!(f, "[INFO:confidence={confidence}]")?;
+        }
         Ok(extra_placed)
     }
 
@@ -308,12 +422,8 @@ impl<T> SequenceElement<T> {
                 }
                 Modification::Simple(modification)
                 | Modification::Ambiguous { modification, .. } => match &**modification {
-                    SimpleModificationInner::Database { specificities, .. } => {
-                        for (rules, _, ions) in specificities {
-                            if PlacementRule::any_possible(rules, self, position) {
-                                diagnostic_ions.extend_from_slice(ions);
-                            }
-                        }
+                    SimpleModificationInner::Database { .. } => {
+                        diagnostic_ions.extend(modification.fragmentation(self, position, None).1);
                     }
                     SimpleModificationInner::Linker { specificities, .. } => {
                         for rule in specificities {
@@ -0,0 +1,202 @@
+//! Transparent (de)compression for the file based readers and writers.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder};
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::check_extension,
+};
+
+/// The compression formats that can be read and written. When reading the compression is
+/// detected from the first bytes of the data so compressed files do not need a matching
+/// extension.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[allow(dead_code)]
+pub enum Compression {
+    /// No compression
+    #[default]
+    None,
+    /// Gzip compression (`.gz`)
+    Gzip,
+    /// Zstandard compression (`.zst`), only available with the `zstd` feature
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+#[allow(dead_code)]
+impl Compression {
+    /// The magic bytes that start a gzip stream
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+    /// The magic bytes that start a zstd frame
+    #[cfg(feature = "zstd")]
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+    /// The file extension used for this compression, without the leading dot
+    pub const fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gz"),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Some("zst"),
+        }
+    }
+
+    /// Get the compression from the extension of the given path
+    pub fn from_extension(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if check_extension(path, "gz") {
+            Self::Gzip
+        } else {
+            #[cfg(feature = "zstd")]
+            if check_extension(path, "zst") {
+                return Self::Zstd;
+            }
+            Self::None
+        }
+    }
+
+    /// Detect the compression from the first bytes of the data
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&Self::GZIP_MAGIC) {
+            Self::Gzip
+        } else {
+            #[cfg(feature = "zstd")]
+            if header.starts_with(&Self::ZSTD_MAGIC) {
+                return Self::Zstd;
+            }
+            Self::None
+        }
+    }
+
+    /// Wrap the given reader to decompress it with this compression
+    /// # Errors
+    /// If the decompressor could not be created.
+    #[cfg_attr(not(feature = "zstd"), allow(clippy::unnecessary_wraps))]
+    pub fn reader<'a>(self, reader: impl BufRead + 'a) -> std::io::Result<Box<dyn BufRead + 'a>> {
+        Ok(match self {
+            Self::None => Box::new(reader),
+            Self::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        })
+    }
+
+    /// Wrap the given writer to compress all data written to it with this compression. The
+    /// compressed stream is finished when the writer is dropped.
+    /// # Errors
+    /// If the compressor could not be created.
+    #[cfg_attr(not(feature = "zstd"), allow(clippy::unnecessary_wraps))]
+    pub fn writer<'a>(self, writer: impl Write + 'a) -> std::io::Result<Box<dyn Write + 'a>> {
+        Ok(match self {
+            Self::None => Box::new(writer),
+            Self::Gzip => Box::new(GzEncoder::new(writer, flate2::Compression::default())),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Box::new(zstd::Encoder::new(writer, 0)?.auto_finish()),
+        })
+    }
+}
+
+/// Wrap the given reader to transparently decompress it, the compression is detected from the
+/// first bytes of the data.
+/// # Errors
+/// If the first bytes could not be read.
+#[allow(dead_code)]
+pub fn decompress<'a>(reader: impl Read + 'a) -> std::io::Result<Box<dyn BufRead + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let compression = Compression::detect(reader.fill_buf()?);
+    compression.reader(reader)
+}
+
+/// Open a file for reading and transparently decompress it, the compression is detected from the
+/// first bytes of the file.
+/// # Errors
+/// If the file could not be opened or its first bytes could not be read.
+pub fn open_file(path: impl AsRef<Path>) -> Result<Box<dyn BufRead>, CustomError> {
+    let path = path.as_ref();
+    let error = |e: std::io::Error| {
+        CustomError::error(
+            "Could not open file",
+            e,
+            Context::show(path.to_string_lossy()),
+        )
+    };
+    decompress(File::open(path).map_err(error)?).map_err(error)
+}
+
+/// Create a file for writing with the given compression, or with the compression from the
+/// extension of the path if no compression is given. The compressed stream is finished when the
+/// writer is dropped.
+/// # Errors
+/// If the file or the compressor could not be created.
+#[allow(dead_code)]
+pub fn create_file(
+    path: impl AsRef<Path>,
+    compression: Option<Compression>,
+) -> Result<Box<dyn Write>, CustomError> {
+    let path = path.as_ref();
+    let error = |e: std::io::Error| {
+        CustomError::error(
+            "Could not create file",
+            e,
+            Context::show(path.to_string_lossy()),
+        )
+    };
+    compression
+        .unwrap_or_else(|| Compression::from_extension(path))
+        .writer(BufWriter::new(File::create(path).map_err(error)?))
+        .map_err(error)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = b"PEPTIDE\tPEPTIDER\n";
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+        ] {
+            let mut compressed = Vec::new();
+            {
+                let mut writer = compression.writer(&mut compressed).unwrap();
+                writer.write_all(data).unwrap();
+            }
+            assert_eq!(Compression::detect(&compressed), compression);
+            let mut read = Vec::new();
+            decompress(compressed.as_slice())
+                .unwrap()
+                .read_to_end(&mut read)
+                .unwrap();
+            assert_eq!(read, data);
+        }
+    }
+
+    #[test]
+    fn extension() {
+        assert_eq!(
+            Compression::from_extension("peptides.csv.GZ"),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::from_extension("peptides.csv"),
+            Compression::None
+        );
+        assert_eq!(Compression::Gzip.extension(), Some("gz"));
+        #[cfg(feature = "zstd")]
+        assert_eq!(
+            Compression::from_extension("peptides.csv.zst"),
+            Compression::Zstd
+        );
+    }
+}
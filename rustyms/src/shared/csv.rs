@@ -3,19 +3,17 @@
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::Debug,
-    fs::File,
     io::{BufRead, BufReader, Write},
     ops::Range,
     str::FromStr,
 };
 
-use flate2::bufread::GzDecoder;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    compression::open_file,
     error::{Context, CustomError},
-    helper_functions::check_extension,
 };
 
 /// A single line in a CSV file
@@ -156,24 +154,11 @@ pub fn parse_csv(
     separator: u8,
     provided_header: Option<Vec<String>>,
 ) -> Result<Box<dyn Iterator<Item = Result<CsvLine, CustomError>>>, CustomError> {
-    let file = File::open(path.as_ref()).map_err(|e| {
-        CustomError::error(
-            "Could not open file",
-            e,
-            crate::error::Context::Show {
-                line: path.as_ref().to_string_lossy().to_string(),
-            },
-        )
-    })?;
-    if check_extension(path, "gz") {
-        Ok(Box::new(parse_csv_raw(
-            GzDecoder::new(BufReader::new(file)),
-            separator,
-            provided_header,
-        )?))
-    } else {
-        Ok(Box::new(parse_csv_raw(file, separator, provided_header)?))
-    }
+    Ok(Box::new(parse_csv_raw(
+        open_file(path)?,
+        separator,
+        provided_header,
+    )?))
 }
 
 /// Parse a CSV file from a raw `BufReader`
@@ -181,9 +166,24 @@ pub fn parse_csv(
 /// If no header is provided and the first line could not be read as a header line.
 /// Or if the 'sep=C' uses a character that is more than 1 byte wide in utf8.
 pub fn parse_csv_raw<T: std::io::Read>(
+    reader: T,
+    separator: u8,
+    provided_header: Option<Vec<String>>,
+) -> Result<CsvLineIter<T>, CustomError> {
+    parse_csv_raw_with_comments(reader, separator, provided_header, None)
+}
+
+/// Parse a CSV file from a raw `BufReader`, if a comment prefix is given any lines before the
+/// header that start with this prefix are skipped. Use `##` to skip the provenance written by
+/// the report and library writers.
+/// # Errors
+/// If no header is provided and the first line could not be read as a header line.
+/// Or if the 'sep=C' uses a character that is more than 1 byte wide in utf8.
+pub fn parse_csv_raw_with_comments<T: std::io::Read>(
     reader: T,
     mut separator: u8,
     provided_header: Option<Vec<String>>,
+    comment: Option<&str>,
 ) -> Result<CsvLineIter<T>, CustomError> {
     let reader = BufReader::new(reader);
     let mut lines = reader.lines().enumerate().peekable();
@@ -206,6 +206,15 @@ pub fn parse_csv_raw<T: std::io::Read>(
         // Actually consume this line
         let _ = lines.next();
     }
+    if let Some(comment) = comment {
+        while lines
+            .peek()
+            .and_then(|(_, l)| l.as_ref().ok())
+            .is_some_and(|l| l.starts_with(comment))
+        {
+            let _ = lines.next();
+        }
+    }
     let column_headers = if let Some(header) = provided_header {
         header
     } else {
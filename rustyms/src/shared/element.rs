@@ -788,5 +788,13 @@ pub const COMMON_ELEMENT_PARSE_LIST: &[(&str, Element)] = &[
 ];
 
 #[allow(clippy::redundant_pub_crate)]
-/// The shared type to send the data from all the elements from build time to compile time
-pub(crate) type ElementalData = Vec<(Option<Mass>, Option<Mass>, Vec<(u16, Mass, f64)>)>;
+/// The shared type to send the data from all the elements from build time to compile time:
+/// monoisotopic mass, average weight, isotopes (N, mass, abundance), the uncertainty of the
+/// average weight, and the uncertainty of the mass of all isotopes (N, uncertainty)
+pub(crate) type ElementalData = Vec<(
+    Option<Mass>,
+    Option<Mass>,
+    Vec<(u16, Mass, f64)>,
+    Option<Mass>,
+    Vec<(u16, Mass)>,
+)>;
@@ -14,7 +14,7 @@ use crate::{
     CompoundPeptidoformIon,
 };
 
-use super::{PeakSpectrum, RawPeak};
+use super::{sorted_window, Contaminant, IndexedPeak, PeakSpectrum, RawPeak};
 
 /// An annotated spectrum
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -63,43 +63,7 @@ impl PeakSpectrum for AnnotatedSpectrum {
 
     /// Return the slice of peaks that have experimental mz values within the given tolerance bounds.
     fn binary_search(&self, low: MassOverCharge, high: MassOverCharge) -> &[AnnotatedPeak] {
-        let left_idx = match self
-            .spectrum
-            .binary_search_by(|a| a.experimental_mz.value.total_cmp(&low.value))
-        {
-            Result::Ok(idx) | Result::Err(idx) => {
-                let mut idx = idx.saturating_sub(1);
-                while idx > 0
-                    && self.spectrum[idx]
-                        .experimental_mz
-                        .value
-                        .total_cmp(&low.value)
-                        != Ordering::Less
-                {
-                    idx -= 1;
-                }
-                idx
-            }
-        };
-
-        let right_idx = match self.spectrum[left_idx..]
-            .binary_search_by(|a| a.experimental_mz.value.total_cmp(&high.value))
-        {
-            Result::Ok(idx) | Err(idx) => {
-                let mut idx = idx + left_idx;
-                while idx < self.spectrum.len()
-                    && self.spectrum[idx]
-                        .experimental_mz
-                        .value
-                        .total_cmp(&high.value)
-                        != Ordering::Greater
-                {
-                    idx = idx.saturating_add(1);
-                }
-                idx.min(self.spectrum.len())
-            }
-        };
-        &self.spectrum[left_idx..right_idx]
+        sorted_window(&self.spectrum, low, high)
     }
 
     fn spectrum(&self) -> Self::Iter<'_> {
@@ -123,6 +87,16 @@ pub struct AnnotatedPeak {
     pub annotation: Vec<Fragment>, // Could become Vec<(Fragment, Vec<MatchedIsotopeDistribution>)> when isotope matching is finally in place
     /// Any annotation as isotope from a given fragment
     pub isotope_annotation: Vec<(usize, usize)>,
+    /// The known background ion this peak matches, if any, see [`Contaminant`]
+    #[serde(default)]
+    pub contaminant: Option<Contaminant>,
+}
+
+impl IndexedPeak for AnnotatedPeak {
+    type Position = MassOverCharge;
+    fn position(&self) -> MassOverCharge {
+        self.experimental_mz
+    }
 }
 
 impl AnnotatedPeak {
@@ -133,6 +107,7 @@ impl AnnotatedPeak {
             intensity: peak.intensity,
             annotation: vec![annotation],
             isotope_annotation: Vec::new(),
+            contaminant: None,
         }
     }
 
@@ -143,6 +118,7 @@ impl AnnotatedPeak {
             intensity: peak.intensity,
             annotation: Vec::new(),
             isotope_annotation: Vec::new(),
+            contaminant: None,
         }
     }
 }
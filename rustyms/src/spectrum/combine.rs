@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spectrum::RawPeak,
+    system::{mz, s, MassOverCharge, Time},
+    RawSpectrum, Tolerance, WithinTolerance,
+};
+
+/// How the intensities of merged peaks are combined, see [`RawSpectrum::combine`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SpectrumCombination {
+    /// Sum the intensities of all merged peaks
+    #[default]
+    Sum,
+    /// Average the intensities over all combined spectra, a peak missing from a spectrum counts
+    /// as zero intensity
+    Average,
+}
+
+impl RawSpectrum {
+    /// Combine multiple spectra, for example repeated scans of the same precursor, into a single
+    /// spectrum. All peaks are merged if they are within the tolerance of the intensity weighted
+    /// average m/z of the peaks merged so far, so peaks within the tolerance from a single
+    /// spectrum are merged as well. The merged peak has the intensity weighted average m/z and
+    /// the intensity combined as given.
+    ///
+    /// The metadata is taken from the first spectrum, except for the number of scans which is the
+    /// sum over all spectra, the retention time which is the average of all known retention
+    /// times, and the precursor intensity which is the sum of all known intensities. If no
+    /// spectra are given an empty spectrum is returned.
+    pub fn combine<'a>(
+        spectra: impl IntoIterator<Item = &'a Self>,
+        tolerance: Tolerance<MassOverCharge>,
+        combination: SpectrumCombination,
+    ) -> Self {
+        let spectra: Vec<&Self> = spectra.into_iter().collect();
+        let Some(first) = spectra.first() else {
+            return Self::default();
+        };
+        let mut peaks: Vec<&RawPeak> = spectra
+            .iter()
+            .flat_map(|spectrum| spectrum.spectrum.iter())
+            .collect();
+        peaks.sort_unstable();
+
+        let divisor = match combination {
+            SpectrumCombination::Sum => 1.0,
+            SpectrumCombination::Average => spectra.len() as f64,
+        };
+        let mut merged: Vec<RawPeak> = Vec::new();
+        let mut cluster: Option<Cluster> = None;
+        for peak in peaks {
+            if let Some(current) = &mut cluster {
+                if tolerance.within(&current.mz(), &peak.mz) {
+                    current.add(peak);
+                    continue;
+                }
+                merged.push(current.peak(divisor));
+            }
+            cluster = Some(Cluster::new(peak));
+        }
+        merged.extend(cluster.map(|c| c.peak(divisor)));
+
+        let retention_times: Vec<f64> = spectra
+            .iter()
+            .filter_map(|spectrum| spectrum.rt.map(|rt| rt.get::<s>()))
+            .collect();
+        let intensities: Vec<f64> = spectra
+            .iter()
+            .filter_map(|spectrum| spectrum.intensity)
+            .collect();
+        Self {
+            num_scans: spectra.iter().map(|spectrum| spectrum.num_scans).sum(),
+            rt: (!retention_times.is_empty()).then(|| {
+                Time::new::<s>(retention_times.iter().sum::<f64>() / retention_times.len() as f64)
+            }),
+            intensity: (!intensities.is_empty()).then(|| intensities.iter().sum()),
+            spectrum: merged,
+            ..(*first).clone()
+        }
+    }
+}
+
+/// A cluster of peaks that are merged into a single peak
+struct Cluster {
+    /// The sum of m/z times intensity
+    weighted_mz: f64,
+    /// The sum of m/z
+    mz: f64,
+    /// The sum of intensity
+    intensity: f64,
+    /// The number of peaks
+    count: usize,
+}
+
+impl Cluster {
+    /// Start a new cluster with the given peak
+    fn new(peak: &RawPeak) -> Self {
+        Self {
+            weighted_mz: peak.mz.value * *peak.intensity,
+            mz: peak.mz.value,
+            intensity: *peak.intensity,
+            count: 1,
+        }
+    }
+
+    /// Add a peak to this cluster
+    fn add(&mut self, peak: &RawPeak) {
+        self.weighted_mz += peak.mz.value * *peak.intensity;
+        self.mz += peak.mz.value;
+        self.intensity += *peak.intensity;
+        self.count += 1;
+    }
+
+    /// The intensity weighted average m/z, or the average m/z if all peaks have no intensity
+    fn mz(&self) -> MassOverCharge {
+        MassOverCharge::new::<mz>(if self.intensity > 0.0 {
+            self.weighted_mz / self.intensity
+        } else {
+            self.mz / self.count as f64
+        })
+    }
+
+    /// The merged peak, with the intensity divided by the given divisor
+    fn peak(&self, divisor: f64) -> RawPeak {
+        RawPeak {
+            mz: self.mz(),
+            intensity: (self.intensity / divisor).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::spectrum::PeakSpectrum;
+
+    fn spectrum(peaks: &[(f64, f64)], rt: f64) -> RawSpectrum {
+        let mut spectrum = RawSpectrum::default();
+        spectrum.num_scans = 1;
+        spectrum.rt = Some(Time::new::<s>(rt));
+        spectrum.extend(peaks.iter().map(|(position, intensity)| RawPeak {
+            mz: MassOverCharge::new::<mz>(*position),
+            intensity: (*intensity).into(),
+        }));
+        spectrum
+    }
+
+    #[test]
+    fn combine() {
+        let spectra = [
+            spectrum(&[(100.0, 10.0), (200.0, 4.0)], 10.0),
+            spectrum(&[(100.001, 30.0), (300.0, 2.0)], 20.0),
+        ];
+        let tolerance = Tolerance::new_ppm(20.0);
+        let summed = RawSpectrum::combine(&spectra, tolerance, SpectrumCombination::Sum);
+        let peaks: Vec<_> = summed
+            .spectrum()
+            .map(|p| (p.mz.value, *p.intensity))
+            .collect();
+        assert_eq!(peaks.len(), 3);
+        assert!((peaks[0].0 - 100.000_75).abs() < 1e-9, "{}", peaks[0].0);
+        assert_eq!(peaks[0].1, 40.0);
+        assert_eq!(peaks[1], (200.0, 4.0));
+        assert_eq!(summed.num_scans, 2);
+        assert_eq!(summed.rt, Some(Time::new::<s>(15.0)));
+
+        let averaged = RawSpectrum::combine(&spectra, tolerance, SpectrumCombination::Average);
+        let intensities: Vec<_> = averaged.spectrum().map(|p| *p.intensity).collect();
+        assert_eq!(intensities, [20.0, 2.0, 1.0]);
+
+        assert_eq!(
+            RawSpectrum::combine([], tolerance, SpectrumCombination::Sum),
+            RawSpectrum::default()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    system::{f64::MassOverCharge, mz},
+    MolecularFormula,
+};
+
+use super::{AnnotatedSpectrum, Recovered};
+
+/// The kind of chemical background
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ContaminantCategory {
+    /// Polysiloxanes, from for example air, septa, and siliconized tubes
+    Polysiloxane,
+    /// Phthalate plasticizers, from plastic labware
+    Phthalate,
+    /// Solvent clusters and solvent additives
+    Solvent,
+    /// Any other contaminant
+    Other,
+}
+
+/// A known background ion, matching peaks are labelled as contaminant during annotation if the
+/// contaminants are set in the [model](crate::Model::contaminants).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Contaminant {
+    /// The name, including the adduct
+    pub name: String,
+    /// The kind of contaminant
+    pub category: ContaminantCategory,
+    /// The formula of the ion, including the charge carrier
+    pub formula: MolecularFormula,
+}
+
+impl Contaminant {
+    /// Create a new contaminant, the formula has to be the full ion including the charge carrier
+    pub fn new(
+        name: impl Into<String>,
+        category: ContaminantCategory,
+        formula: MolecularFormula,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            category,
+            formula,
+        }
+    }
+
+    /// The monoisotopic m/z of this ion, an uncharged formula is treated as singly charged
+    pub fn mz(&self) -> MassOverCharge {
+        MassOverCharge::new::<mz>(
+            self.formula.monoisotopic_mass().value
+                / self.formula.charge().value.unsigned_abs().max(1) as f64,
+        )
+    }
+
+    /// A small database of common background ions in positive mode, polysiloxanes, phthalates, and
+    /// solvent clusters, following Keller et al. (2008) Analytica Chimica Acta 627, 71–81.
+    pub fn common() -> Vec<Self> {
+        let protonated = |name: &str, category, formula: MolecularFormula| {
+            Self::new(
+                format!("{name} [M+H]+"),
+                category,
+                formula + molecular_formula!(H 1 Electron -1),
+            )
+        };
+        let sodiated = |name: &str, category, formula: MolecularFormula| {
+            Self::new(
+                format!("{name} [M+Na]+"),
+                category,
+                formula + molecular_formula!(Na 1 Electron -1),
+            )
+        };
+        let ammoniated = |name: &str, category, formula: MolecularFormula| {
+            Self::new(
+                format!("{name} [M+NH4]+"),
+                category,
+                formula + molecular_formula!(N 1 H 4 Electron -1),
+            )
+        };
+        let siloxane = |n: i32| molecular_formula!(C 2 H 6 O 1 Si 1) * n;
+        let acetonitrile = molecular_formula!(C 2 H 3 N 1);
+        let dehp = molecular_formula!(C 24 H 38 O 4);
+        vec![
+            protonated(
+                "Polysiloxane D5",
+                ContaminantCategory::Polysiloxane,
+                siloxane(5),
+            ),
+            protonated(
+                "Polysiloxane D6",
+                ContaminantCategory::Polysiloxane,
+                siloxane(6),
+            ),
+            ammoniated(
+                "Polysiloxane D6",
+                ContaminantCategory::Polysiloxane,
+                siloxane(6),
+            ),
+            protonated(
+                "Polysiloxane D7",
+                ContaminantCategory::Polysiloxane,
+                siloxane(7),
+            ),
+            ammoniated(
+                "Polysiloxane D7",
+                ContaminantCategory::Polysiloxane,
+                siloxane(7),
+            ),
+            protonated(
+                "Polysiloxane D8",
+                ContaminantCategory::Polysiloxane,
+                siloxane(8),
+            ),
+            protonated(
+                "Phthalic anhydride",
+                ContaminantCategory::Phthalate,
+                molecular_formula!(C 8 H 4 O 3),
+            ),
+            protonated(
+                "Dibutyl phthalate",
+                ContaminantCategory::Phthalate,
+                molecular_formula!(C 16 H 22 O 4),
+            ),
+            sodiated(
+                "Dibutyl phthalate",
+                ContaminantCategory::Phthalate,
+                molecular_formula!(C 16 H 22 O 4),
+            ),
+            protonated(
+                "Bis(2-ethylhexyl) phthalate",
+                ContaminantCategory::Phthalate,
+                dehp.clone(),
+            ),
+            sodiated(
+                "Bis(2-ethylhexyl) phthalate",
+                ContaminantCategory::Phthalate,
+                dehp.clone(),
+            ),
+            sodiated(
+                "Bis(2-ethylhexyl) phthalate dimer",
+                ContaminantCategory::Phthalate,
+                dehp * 2,
+            ),
+            protonated(
+                "Acetonitrile",
+                ContaminantCategory::Solvent,
+                acetonitrile.clone(),
+            ),
+            sodiated(
+                "Acetonitrile",
+                ContaminantCategory::Solvent,
+                acetonitrile.clone(),
+            ),
+            protonated(
+                "Acetonitrile dimer",
+                ContaminantCategory::Solvent,
+                acetonitrile.clone() * 2,
+            ),
+            sodiated(
+                "Acetonitrile dimer",
+                ContaminantCategory::Solvent,
+                acetonitrile * 2,
+            ),
+            protonated(
+                "DMSO",
+                ContaminantCategory::Solvent,
+                molecular_formula!(C 2 H 6 O 1 S 1),
+            ),
+            protonated(
+                "Triethylamine",
+                ContaminantCategory::Solvent,
+                molecular_formula!(C 6 H 15 N 1),
+            ),
+        ]
+    }
+}
+
+impl AnnotatedSpectrum {
+    /// All peaks that are labelled as contaminant
+    pub fn contaminants(&self) -> impl Iterator<Item = &super::AnnotatedPeak> {
+        self.spectrum.iter().filter(|p| p.contaminant.is_some())
+    }
+
+    /// The intensity of all peaks labelled as contaminant out of the total intensity, as quality
+    /// control measure for the chemical background
+    pub fn contaminant_intensity(&self) -> Recovered<f64> {
+        Recovered::new(
+            self.contaminants().map(|p| *p.intensity).sum::<f64>(),
+            self.spectrum.iter().map(|p| *p.intensity).sum::<f64>(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        fragment::FragmentType,
+        spectrum::{AnnotatableSpectrum, PeakSpectrum, RawPeak, RawSpectrum},
+        system::{e, usize::Charge},
+        CompoundPeptidoformIon, MassMode, Model,
+    };
+
+    #[test]
+    fn common() {
+        let contaminants = Contaminant::common();
+        let find = |name: &str| {
+            contaminants
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .mz()
+                .value
+        };
+        assert!((find("Polysiloxane D6 [M+H]+") - 445.120_03).abs() < 1e-4);
+        assert!((find("Bis(2-ethylhexyl) phthalate [M+H]+") - 391.284_29).abs() < 1e-4);
+        assert!((find("Bis(2-ethylhexyl) phthalate dimer [M+Na]+") - 803.543_24).abs() < 1e-4);
+        assert!((find("Acetonitrile dimer [M+Na]+") - 105.042_32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn annotate() {
+        let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDEK", None).unwrap();
+        let model = Model::cid_hcd();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &model);
+        let y1 = fragments
+            .iter()
+            .find(|f| f.ion.to_string() == "y1" && f.neutral_loss.is_empty())
+            .unwrap()
+            .mz(MassMode::Monoisotopic)
+            .unwrap();
+        let siloxane = Contaminant::common()[1].mz();
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend([
+            RawPeak {
+                mz: y1,
+                intensity: 1.0.into(),
+            },
+            RawPeak {
+                mz: siloxane,
+                intensity: 3.0.into(),
+            },
+        ]);
+
+        let annotated =
+            spectrum.annotate(peptide.clone(), &fragments, &model, MassMode::Monoisotopic);
+        assert!(annotated.contaminants().next().is_none());
+        let (scores, _) = annotated.scores(&fragments, &model, MassMode::Monoisotopic);
+        let crate::spectrum::Score::UniqueFormulas { peaks, .. } = scores.score else {
+            panic!("Invalid score type")
+        };
+        assert_eq!(peaks.total, 2);
+
+        let model = model.contaminants(Contaminant::common());
+        let annotated = spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic);
+        let contaminants: Vec<_> = annotated.contaminants().collect();
+        assert_eq!(contaminants.len(), 1);
+        assert_eq!(
+            contaminants[0].contaminant.as_ref().unwrap().category,
+            ContaminantCategory::Polysiloxane
+        );
+        assert!(annotated.spectrum().all(|p| p
+            .annotation
+            .iter()
+            .all(|f| matches!(f.ion, FragmentType::y(_)))));
+        assert_eq!(annotated.contaminant_intensity().fraction(), 0.75);
+        let (scores, _) = annotated.scores(&fragments, &model, MassMode::Monoisotopic);
+        let crate::spectrum::Score::UniqueFormulas {
+            peaks, intensity, ..
+        } = scores.score
+        else {
+            panic!("Invalid score type")
+        };
+        assert_eq!(peaks.total, 1);
+        assert_eq!(intensity.fraction(), 1.0);
+        // The FDR uses the same totals as the scores
+        let (fdr, _) = annotated.fdr(&fragments, &model, MassMode::Monoisotopic);
+        assert!((fdr.peaks_actual - 1.0).abs() < f64::EPSILON);
+        assert!((fdr.intensity_actual - 1.0).abs() < f64::EPSILON);
+    }
+}
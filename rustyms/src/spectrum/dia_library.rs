@@ -0,0 +1,427 @@
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::{
+    error::{Context, CustomError},
+    fragment::FragmentType,
+    modification::{Ontology, SimpleModificationInner},
+    peptidoform::Linear,
+    provenance::Provenance,
+    Chemical, MassMode, Modification, MolecularCharge, MolecularFormula, NeutralLoss, Peptidoform,
+};
+
+use super::{AnnotatedSpectrum, PeakSpectrum};
+
+/// The tab separated spectral library formats used by DIA tools, see [`write_dia_library`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiaLibraryFormat {
+    /// The DIA-NN format, modifications are written as `(UniMod:35)` after the modified residue
+    DiaNn,
+    /// The Spectronaut format, the sequence is surrounded by underscores and modifications are
+    /// written as `[Oxidation (M)]` after the modified residue
+    Spectronaut,
+}
+
+/// The settings for [`write_dia_library`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DiaLibraryOptions<'a> {
+    /// The format of the library
+    pub format: DiaLibraryFormat,
+    /// The provenance, written as comment lines before the header, note that not all tools
+    /// accept comments in a library
+    pub provenance: Option<&'a Provenance>,
+}
+
+impl<'a> DiaLibraryOptions<'a> {
+    /// Write a library in the given format without provenance
+    pub const fn new(format: DiaLibraryFormat) -> Self {
+        Self {
+            format,
+            provenance: None,
+        }
+    }
+
+    /// Write the given provenance as comment lines before the header
+    #[must_use]
+    pub const fn provenance(self, provenance: &'a Provenance) -> Self {
+        Self {
+            provenance: Some(provenance),
+            ..self
+        }
+    }
+}
+
+impl DiaLibraryFormat {
+    /// The column headers
+    const fn columns(self) -> &'static [&'static str] {
+        match self {
+            Self::DiaNn => &[
+                "ModifiedPeptide",
+                "StrippedPeptide",
+                "PrecursorCharge",
+                "PrecursorMz",
+                "Tr_recalibrated",
+                "FragmentMz",
+                "RelativeIntensity",
+                "FragmentType",
+                "FragmentSeriesNumber",
+                "FragmentCharge",
+                "FragmentLossType",
+            ],
+            Self::Spectronaut => &[
+                "ModifiedPeptide",
+                "StrippedPeptide",
+                "PrecursorCharge",
+                "PrecursorMz",
+                "iRT",
+                "FragmentMz",
+                "RelativeIntensity",
+                "FragmentType",
+                "FragmentNumber",
+                "FragmentCharge",
+                "FragmentLossType",
+            ],
+        }
+    }
+
+    /// The intensity of the most intense fragment of a precursor
+    const fn max_intensity(self) -> f64 {
+        match self {
+            Self::DiaNn => 1.0,
+            Self::Spectronaut => 100.0,
+        }
+    }
+
+    /// Get the modified sequence for this peptide in this format. Only Unimod modifications are
+    /// supported, on residues and the N and C terminus.
+    /// # Errors
+    /// If the peptide contains any modification that is not a Unimod modification (including
+    /// ambiguous modifications), or if it has labile or global modifications.
+    pub fn modified_sequence(self, peptide: &Peptidoform<Linear>) -> Result<String, CustomError> {
+        if !peptide.get_labile().is_empty() || !peptide.get_global().is_empty() {
+            return Err(CustomError::error(
+                "Unsupported peptide",
+                "Labile and global modifications cannot be written in a DIA library",
+                Context::show(peptide.to_string()),
+            ));
+        }
+        let mut sequence = String::new();
+        if self == Self::Spectronaut {
+            sequence.push('_');
+        }
+        for modification in peptide.get_n_term() {
+            sequence.push_str(&self.modification(peptide, modification, "N-term")?);
+        }
+        for element in peptide.sequence() {
+            sequence.push(element.aminoacid.char());
+            for modification in &element.modifications {
+                sequence.push_str(&self.modification(
+                    peptide,
+                    modification,
+                    &element.aminoacid.char().to_string(),
+                )?);
+            }
+        }
+        for modification in peptide.get_c_term() {
+            sequence.push_str(&self.modification(peptide, modification, "C-term")?);
+        }
+        if self == Self::Spectronaut {
+            sequence.push('_');
+        }
+        Ok(sequence)
+    }
+
+    /// Write a single modification, with the given site for Spectronaut
+    /// # Errors
+    /// If the modification is not a Unimod modification.
+    fn modification(
+        self,
+        peptide: &Peptidoform<Linear>,
+        modification: &Modification,
+        site: &str,
+    ) -> Result<String, CustomError> {
+        if let Some(SimpleModificationInner::Database { id, .. }) =
+            modification.simple().map(AsRef::as_ref)
+        {
+            if id.ontology == Ontology::Unimod {
+                if let Some(accession) = id.id {
+                    return Ok(match self {
+                        Self::DiaNn => format!("(UniMod:{accession})"),
+                        Self::Spectronaut => format!("[{} ({site})]", id.name),
+                    });
+                }
+            }
+        }
+        Err(CustomError::error(
+            "Unsupported modification",
+            format!(
+                "Only Unimod modifications can be written in a DIA library, not '{modification}'"
+            ),
+            Context::show(peptide.to_string()),
+        ))
+    }
+}
+
+/// Write the given annotated spectra as a tab separated spectral library for DIA-NN or
+/// Spectronaut, with the given iRT for each spectrum. Each spectrum is written as a precursor with
+/// the charge of the spectrum (1 if not known) and all peaks annotated as a, b, c, x, y, or z ions
+/// of the first peptidoform as fragments. The m/z values are the theoretical monoisotopic m/z
+/// values, the intensities are relative to the most intense fragment of each precursor. See
+/// [`DiaLibraryOptions`] for the format and the optional provenance.
+/// # Errors
+/// If any of the spectra is not annotated with a single linear peptidoform, if the modified
+/// sequence cannot be written (see [`DiaLibraryFormat::modified_sequence`]), or if writing fails.
+pub fn write_dia_library<'a>(
+    spectra: impl IntoIterator<Item = (&'a AnnotatedSpectrum, f64)>,
+    options: DiaLibraryOptions<'_>,
+    mut writer: impl Write,
+) -> Result<(), CustomError> {
+    let format = options.format;
+    let io_error = |error: std::io::Error| {
+        CustomError::error("Could not write library", error.to_string(), Context::None)
+    };
+    if let Some(provenance) = options.provenance {
+        provenance
+            .write_csv_comments(&mut writer)
+            .map_err(io_error)?;
+    }
+    writeln!(writer, "{}", format.columns().join("\t")).map_err(io_error)?;
+    for (spectrum, irt) in spectra {
+        let peptide = library_peptide(spectrum)?;
+        let modified = format.modified_sequence(&peptide)?;
+        let stripped: String = peptide
+            .sequence()
+            .iter()
+            .map(|e| e.aminoacid.char())
+            .collect();
+        let charge = spectrum.charge.map_or(1, |c| c.value);
+        let precursor_mz = precursor_mz(&peptide, charge);
+        let fragments = spectrum
+            .spectrum()
+            .flat_map(|peak| {
+                peak.annotation
+                    .iter()
+                    .filter(|f| f.peptidoform_index.unwrap_or(0) == 0)
+                    .filter_map(move |f| {
+                        let (kind, position) = match &f.ion {
+                            FragmentType::a(p) => ("a", p),
+                            FragmentType::b(p) => ("b", p),
+                            FragmentType::c(p) => ("c", p),
+                            FragmentType::x(p) => ("x", p),
+                            FragmentType::y(p) => ("y", p),
+                            FragmentType::z(p) => ("z", p),
+                            _ => return None,
+                        };
+                        Some((
+                            f.mz(MassMode::Monoisotopic)?.value,
+                            peak.intensity.0,
+                            kind,
+                            position.series_number,
+                            f.charge.value,
+                            f.neutral_loss.iter().map(loss_type).join(","),
+                        ))
+                    })
+            })
+            .collect_vec();
+        let max = fragments.iter().map(|f| f.1).fold(0.0, f64::max);
+        for (mz, intensity, kind, number, fragment_charge, loss) in fragments {
+            writeln!(
+                writer,
+                "{modified}\t{stripped}\t{charge}\t{precursor_mz}\t{irt}\t{mz}\t{}\t{kind}\t{number}\t{fragment_charge}\t{}",
+                if max > 0.0 {
+                    intensity / max * format.max_intensity()
+                } else {
+                    0.0
+                },
+                if loss.is_empty() { "noloss" } else { &loss }
+            )
+            .map_err(io_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Get the single linear peptidoform a library spectrum is annotated with
+/// # Errors
+/// If the spectrum is not annotated with a single linear peptidoform.
+pub(super) fn library_peptide(
+    spectrum: &AnnotatedSpectrum,
+) -> Result<Peptidoform<Linear>, CustomError> {
+    spectrum
+        .peptide
+        .peptidoforms()
+        .exactly_one()
+        .ok()
+        .and_then(|p| p.clone().into_linear())
+        .ok_or_else(|| {
+            CustomError::error(
+                "Unsupported peptide",
+                "Only spectra annotated with a single linear peptidoform can be used in a DIA library",
+                Context::show(spectrum.peptide.to_string()),
+            )
+        })
+}
+
+/// The theoretical monoisotopic m/z of the given peptide when charged with the given number of protons
+pub(super) fn precursor_mz(peptide: &Peptidoform<Linear>, charge: usize) -> f64 {
+    peptide.formulas().first().map_or(0.0, |f| {
+        (f + &MolecularCharge::proton(isize::try_from(charge).unwrap_or(1)).formula())
+            .monoisotopic_mass()
+            .value
+            / charge as f64
+    })
+}
+
+/// The loss type as used in the DIA library formats, eg `H2O` or `NH3`. Common losses use the
+/// names expected by the DIA tools, other losses are written in Hill notation without counts of 1.
+fn loss_type(loss: &NeutralLoss) -> String {
+    let name = |formula: &MolecularFormula| {
+        [
+            (molecular_formula!(H 2 O 1), "H2O"),
+            (molecular_formula!(H 3 N 1), "NH3"),
+            (molecular_formula!(H 3 O 4 P 1), "H3PO4"),
+            (molecular_formula!(C 1 O 1), "CO"),
+            (molecular_formula!(C 1 O 2), "CO2"),
+        ]
+        .into_iter()
+        .find(|(known, _)| known == formula)
+        .map_or_else(
+            || {
+                let hill: Vec<char> = formula.hill_notation().chars().collect();
+                hill.iter()
+                    .enumerate()
+                    .filter(|(index, c)| {
+                        **c != '1'
+                            || !hill[..*index].last().is_some_and(char::is_ascii_alphabetic)
+                            || hill.get(index + 1).is_some_and(char::is_ascii_digit)
+                    })
+                    .map(|(_, c)| *c)
+                    .collect()
+            },
+            |(_, name)| name.to_string(),
+        )
+    };
+    match loss {
+        NeutralLoss::Loss(formula) => name(formula),
+        NeutralLoss::Gain(formula) => format!("+{}", name(formula)),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        spectrum::{AnnotatableSpectrum, RawPeak, RawSpectrum},
+        system::{e, usize::Charge},
+        CompoundPeptidoformIon, Model,
+    };
+    use ordered_float::OrderedFloat;
+
+    fn annotated(peptide: &str) -> AnnotatedSpectrum {
+        let model = Model::cid_hcd();
+        let peptide = CompoundPeptidoformIon::pro_forma(peptide, None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &model);
+        let mut spectrum = RawSpectrum::default();
+        spectrum.charge = Some(Charge::new::<e>(2));
+        spectrum.extend(
+            fragments
+                .iter()
+                .filter(|f| matches!(f.ion, FragmentType::y(_)) && f.neutral_loss.is_empty())
+                .enumerate()
+                .map(|(i, f)| RawPeak {
+                    mz: f.mz(MassMode::Monoisotopic).unwrap(),
+                    intensity: OrderedFloat((i + 1) as f64),
+                }),
+        );
+        spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic)
+    }
+
+    fn sequence(format: DiaLibraryFormat, peptide: &str) -> Result<String, CustomError> {
+        format.modified_sequence(
+            &Peptidoform::pro_forma(peptide, None)
+                .unwrap()
+                .into_linear()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn modified_sequence() {
+        assert_eq!(
+            sequence(DiaLibraryFormat::DiaNn, "[Acetyl]-PEPM[Oxidation]K").unwrap(),
+            "(UniMod:1)PEPM(UniMod:35)K"
+        );
+        assert_eq!(
+            sequence(DiaLibraryFormat::Spectronaut, "[Acetyl]-PEPM[Oxidation]K").unwrap(),
+            "_[Acetyl (N-term)]PEPM[Oxidation (M)]K_"
+        );
+        assert!(sequence(DiaLibraryFormat::DiaNn, "PEPM[+15.995]K").is_err());
+        assert!(sequence(DiaLibraryFormat::DiaNn, "PEPM[Formula:O]K").is_err());
+    }
+
+    #[test]
+    fn write() {
+        let spectrum = annotated("PEPM[Oxidation]TIDEK");
+        let mut output = Vec::new();
+        write_dia_library(
+            [(&spectrum, 12.5)],
+            DiaLibraryOptions::new(DiaLibraryFormat::DiaNn),
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert!(lines[0].starts_with("ModifiedPeptide\tStrippedPeptide\tPrecursorCharge"));
+        assert!(lines.len() > 5, "{output}");
+        let fields: Vec<_> = lines[1].split('\t').collect();
+        assert_eq!(fields[0], "PEPM(UniMod:35)TIDEK");
+        assert_eq!(fields[1], "PEPMTIDEK");
+        assert_eq!(fields[2], "2");
+        assert!(
+            (fields[3].parse::<f64>().unwrap() - 538.2524).abs() < 0.001,
+            "{}",
+            fields[3]
+        );
+        assert_eq!(fields[4], "12.5");
+        assert_eq!(fields[7], "y");
+        assert_eq!(fields[10], "noloss");
+        assert!(lines[1..].iter().any(|l| l.split('\t').nth(6) == Some("1")));
+        assert!(lines[1..]
+            .iter()
+            .all(|l| ["noloss", "H2O", "NH3"].contains(&l.split('\t').nth(10).unwrap())));
+
+        let provenance = Provenance::new().software("tool 1.0");
+        let mut output = Vec::new();
+        write_dia_library(
+            [(&spectrum, 12.5)],
+            DiaLibraryOptions::new(DiaLibraryFormat::Spectronaut).provenance(&provenance),
+            &mut output,
+        )
+        .unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("## software: tool 1.0\n"));
+    }
+
+    #[test]
+    fn loss_names() {
+        assert_eq!(
+            loss_type(&NeutralLoss::Loss(molecular_formula!(H 3 N 1))),
+            "NH3"
+        );
+        assert_eq!(
+            loss_type(&NeutralLoss::Gain(molecular_formula!(H 2 O 1))),
+            "+H2O"
+        );
+        assert_eq!(
+            loss_type(&NeutralLoss::Loss(molecular_formula!(C 1 H 4 O 1 S 1))),
+            "CH4OS"
+        );
+        assert_eq!(
+            loss_type(&NeutralLoss::Loss(molecular_formula!(C 10 H 11 N 1))),
+            "C10H11N"
+        );
+    }
+}
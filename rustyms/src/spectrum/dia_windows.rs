@@ -0,0 +1,253 @@
+use std::{collections::HashSet, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::CustomError,
+    system::{MassOverCharge, Time},
+};
+
+use super::{
+    dia_library::{library_peptide, precursor_mz},
+    AnnotatedSpectrum,
+};
+
+/// A precursor from a spectral library, as used to design DIA acquisition windows, see
+/// [`partition_dia_windows`].
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DiaPrecursor {
+    /// The peptide, used to count the unique peptides in a window
+    pub peptide: String,
+    /// The charge
+    pub charge: usize,
+    /// The theoretical monoisotopic m/z
+    pub mz: MassOverCharge,
+    /// The (predicted) retention time, if known
+    pub retention_time: Option<Time>,
+    /// The (predicted) ion mobility, if known, in the same unit as the windows
+    pub ion_mobility: Option<f64>,
+}
+
+impl DiaPrecursor {
+    /// Create a precursor from an annotated library spectrum, with the charge of the spectrum
+    /// (1 if not known) and the retention time of the spectrum.
+    /// # Errors
+    /// If the spectrum is not annotated with a single linear peptidoform.
+    pub fn from_spectrum(
+        spectrum: &AnnotatedSpectrum,
+        ion_mobility: Option<f64>,
+    ) -> Result<Self, CustomError> {
+        let peptide = library_peptide(spectrum)?;
+        let charge = spectrum.charge.map_or(1, |c| c.value);
+        Ok(Self {
+            peptide: peptide.to_string(),
+            charge,
+            mz: MassOverCharge::new::<crate::system::mz>(precursor_mz(&peptide, charge)),
+            retention_time: spectrum.rt,
+            ion_mobility,
+        })
+    }
+}
+
+/// A DIA acquisition window, with a precursor m/z range and optionally a retention time and ion
+/// mobility range. All ranges are inclusive.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DiaWindow {
+    /// The precursor m/z range
+    pub mz: RangeInclusive<MassOverCharge>,
+    /// The retention time range, if the window is only acquired for part of the gradient
+    pub retention_time: Option<RangeInclusive<Time>>,
+    /// The ion mobility range
+    pub ion_mobility: Option<RangeInclusive<f64>>,
+}
+
+impl DiaWindow {
+    /// Create a window covering the given precursor m/z range
+    pub const fn new(mz: RangeInclusive<MassOverCharge>) -> Self {
+        Self {
+            mz,
+            retention_time: None,
+            ion_mobility: None,
+        }
+    }
+
+    /// Set the retention time range
+    #[must_use]
+    pub fn retention_time(self, retention_time: RangeInclusive<Time>) -> Self {
+        Self {
+            retention_time: Some(retention_time),
+            ..self
+        }
+    }
+
+    /// Set the ion mobility range
+    #[must_use]
+    pub const fn ion_mobility(self, ion_mobility: RangeInclusive<f64>) -> Self {
+        Self {
+            ion_mobility: Some(ion_mobility),
+            ..self
+        }
+    }
+
+    /// Create windows of a fixed width covering the given m/z range, with the given overlap
+    /// between consecutive windows. The last window ends at the end of the range and could be
+    /// narrower. If the width is not bigger than the overlap no windows are created.
+    pub fn fixed_width(
+        range: RangeInclusive<MassOverCharge>,
+        width: MassOverCharge,
+        overlap: MassOverCharge,
+    ) -> Vec<Self> {
+        let mut windows = Vec::new();
+        if width <= overlap {
+            return windows;
+        }
+        let mut start = *range.start();
+        while start < *range.end() {
+            let end = (start + width).min(*range.end());
+            windows.push(Self::new(start..=end));
+            start = end - overlap;
+            if end >= *range.end() {
+                break;
+            }
+        }
+        windows
+    }
+
+    /// Check if the given precursor is acquired in this window. A precursor without a retention
+    /// time or ion mobility is assumed to be acquired in any retention time or ion mobility range.
+    pub fn contains(&self, precursor: &DiaPrecursor) -> bool {
+        self.mz.contains(&precursor.mz)
+            && self
+                .retention_time
+                .as_ref()
+                .zip(precursor.retention_time)
+                .map_or(true, |(range, rt)| range.contains(&rt))
+            && self
+                .ion_mobility
+                .as_ref()
+                .zip(precursor.ion_mobility)
+                .map_or(true, |(range, im)| range.contains(&im))
+    }
+}
+
+/// The precursors acquired in a single DIA window, see [`partition_dia_windows`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DiaWindowStatistics {
+    /// The window
+    pub window: DiaWindow,
+    /// The indices of all precursors acquired in this window
+    pub precursors: Vec<usize>,
+    /// The number of unique peptides acquired in this window
+    pub peptides: usize,
+    /// The number of precursors in this window that are also acquired in any other window
+    pub shared_precursors: usize,
+}
+
+/// A spectral library partitioned into DIA acquisition windows, see [`partition_dia_windows`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DiaWindowPartition {
+    /// The statistics for each window, in the same order as the windows
+    pub windows: Vec<DiaWindowStatistics>,
+    /// The indices of all precursors that are not acquired in any window
+    pub unassigned: Vec<usize>,
+    /// The indices of all precursors that are acquired in more than one window
+    pub shared: Vec<usize>,
+}
+
+/// Partition the precursors of a spectral library into the given DIA acquisition windows. This
+/// reports for each window which precursors and how many unique peptides are acquired, and which
+/// precursors are acquired in multiple (overlapping) windows or not at all, to assist DIA method
+/// design.
+pub fn partition_dia_windows(
+    precursors: &[DiaPrecursor],
+    windows: &[DiaWindow],
+) -> DiaWindowPartition {
+    let mut usage = vec![0_usize; precursors.len()];
+    let assigned: Vec<Vec<usize>> = windows
+        .iter()
+        .map(|window| {
+            precursors
+                .iter()
+                .enumerate()
+                .filter(|(_, precursor)| window.contains(precursor))
+                .map(|(index, _)| {
+                    usage[index] += 1;
+                    index
+                })
+                .collect()
+        })
+        .collect();
+    DiaWindowPartition {
+        windows: windows
+            .iter()
+            .zip(assigned)
+            .map(|(window, indices)| DiaWindowStatistics {
+                window: window.clone(),
+                peptides: indices
+                    .iter()
+                    .map(|i| precursors[*i].peptide.as_str())
+                    .collect::<HashSet<_>>()
+                    .len(),
+                shared_precursors: indices.iter().filter(|i| usage[**i] > 1).count(),
+                precursors: indices,
+            })
+            .collect(),
+        unassigned: (0..precursors.len()).filter(|i| usage[*i] == 0).collect(),
+        shared: (0..precursors.len()).filter(|i| usage[*i] > 1).collect(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::system::s;
+
+    fn precursor(peptide: &str, mz: f64, rt: Option<f64>, im: Option<f64>) -> DiaPrecursor {
+        DiaPrecursor {
+            peptide: peptide.to_string(),
+            charge: 2,
+            mz: MassOverCharge::new::<crate::system::mz>(mz),
+            retention_time: rt.map(Time::new::<s>),
+            ion_mobility: im,
+        }
+    }
+
+    fn mz(value: f64) -> MassOverCharge {
+        MassOverCharge::new::<crate::system::mz>(value)
+    }
+
+    #[test]
+    fn fixed_width() {
+        let windows = DiaWindow::fixed_width(mz(400.0)..=mz(500.0), mz(25.0), mz(1.0));
+        assert_eq!(windows.len(), 5);
+        assert_eq!(windows[0].mz, mz(400.0)..=mz(425.0));
+        assert_eq!(windows[1].mz, mz(424.0)..=mz(449.0));
+        assert_eq!(*windows[4].mz.end(), mz(500.0));
+        assert!(DiaWindow::fixed_width(mz(400.0)..=mz(500.0), mz(1.0), mz(1.0)).is_empty());
+    }
+
+    #[test]
+    fn partition() {
+        let precursors = [
+            precursor("PEPTIDE", 410.0, None, None),
+            precursor("PEPTIDE", 424.5, Some(60.0), Some(0.9)),
+            precursor("PEPTIDER", 430.0, Some(600.0), Some(1.1)),
+            precursor("PEPTIDEK", 600.0, None, None),
+        ];
+        let windows = [
+            DiaWindow::new(mz(400.0)..=mz(425.0)),
+            DiaWindow::new(mz(424.0)..=mz(449.0))
+                .retention_time(Time::new::<s>(0.0)..=Time::new::<s>(300.0))
+                .ion_mobility(0.8..=1.0),
+        ];
+        let partition = partition_dia_windows(&precursors, &windows);
+        assert_eq!(partition.windows[0].precursors, [0, 1]);
+        assert_eq!(partition.windows[0].peptides, 1);
+        assert_eq!(partition.windows[0].shared_precursors, 1);
+        assert_eq!(partition.windows[1].precursors, [1]);
+        assert_eq!(partition.windows[1].peptides, 1);
+        assert_eq!(partition.unassigned, [2, 3]);
+        assert_eq!(partition.shared, [1]);
+    }
+}
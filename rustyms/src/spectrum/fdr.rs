@@ -2,6 +2,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    spectrum::Recovered,
     system::{MassOverCharge, Ratio},
     AnnotatedSpectrum, Fragment, MassMode, Model, WithinTolerance,
 };
@@ -69,7 +70,8 @@ impl AnnotatedSpectrum {
 
     fn internal_fdr(&self, mzs: &[MassOverCharge], model: &Model) -> Fdr {
         let mut results = Vec::with_capacity(51);
-        let total_intensity = self.spectrum.iter().map(|s| s.intensity.0).sum::<f64>();
+        // Contaminant peaks are not counted, the same as for the scores
+        let (total_peaks, total_intensity) = self.scored_peaks();
 
         for offset in -25..=25 {
             let peaks = self
@@ -107,6 +109,7 @@ impl AnnotatedSpectrum {
                     .tolerance
                     .within(&self.spectrum[closest.0].experimental_mz, mass)
                     && !peak_annotated[closest.0]
+                    && self.spectrum[closest.0].contaminant.is_none()
                 {
                     number_peaks_annotated += 1;
                     intensity_annotated += self.spectrum[closest.0].intensity.0;
@@ -114,7 +117,7 @@ impl AnnotatedSpectrum {
                 }
             }
             results.push((
-                f64::from(number_peaks_annotated) / self.spectrum.len() as f64,
+                f64::from(number_peaks_annotated) / f64::from(total_peaks),
                 intensity_annotated / total_intensity,
             ));
         }
@@ -135,11 +138,11 @@ impl AnnotatedSpectrum {
         let actual: (u32, f64) = self
             .spectrum
             .iter()
-            .filter(|p| !p.annotation.is_empty())
+            .filter(|p| !p.annotation.is_empty() && p.contaminant.is_none())
             .fold((0, 0.0), |acc, p| (acc.0 + 1, acc.1 + p.intensity.0));
 
         Fdr {
-            peaks_actual: f64::from(actual.0) / self.spectrum.len() as f64,
+            peaks_actual: f64::from(actual.0) / f64::from(total_peaks),
             peaks_average_false: peaks_average,
             peaks_standard_deviation_false: peaks_st_dev,
             intensity_actual: actual.1 / total_intensity,
@@ -200,3 +203,86 @@ impl Fdr {
         self.intensity_sigma().log2()
     }
 }
+
+/// The strategy to generate decoy fragments, see
+/// [`crate::CompoundPeptidoformIon::generate_decoy_fragments`].
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum DecoyStrategy {
+    /// Shift all theoretical fragments by the given m/z offset. Use a non integer offset to
+    /// prevent spurious matches from 1 Da isotopes.
+    MassShift(MassOverCharge),
+    /// Generate the theoretical fragments for the reversed sequence of all peptidoforms.
+    Reversed,
+}
+
+/// The matches of decoy fragments to a spectrum, see [`crate::AnnotatableSpectrum::annotate_with_decoys`].
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DecoyMatches {
+    /// The fraction of the decoy fragments (within the m/z range of the model) that matched a peak
+    pub fragments: Recovered<u32>,
+    /// The fraction of the total peaks that matched at least one decoy fragment
+    pub peaks: Recovered<u32>,
+    /// The fraction of the total intensity that matched at least one decoy fragment
+    pub intensity: Recovered<f64>,
+}
+
+impl DecoyMatches {
+    /// Get the decoy match rate (as a fraction), the fraction of decoy fragments that matched a peak.
+    pub fn match_rate(&self) -> f64 {
+        self.fragments.fraction()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{rawfile::mgf, system::usize::Charge, AnnotatableSpectrum, CompoundPeptidoformIon};
+
+    #[test]
+    fn decoy_matches() {
+        let model = Model::cid_hcd();
+        let spectrum = mgf::open("data/real_example.mgf").unwrap();
+        let peptide = CompoundPeptidoformIon::pro_forma("EEYTKKLNTQ/2", None).unwrap();
+        let charge = Charge::new::<crate::system::e>(2);
+        let fragments = peptide.generate_theoretical_fragments(charge, &model);
+
+        // Without a shift the decoys are identical to the targets
+        let decoys = peptide.generate_decoy_fragments(
+            charge,
+            &model,
+            DecoyStrategy::MassShift(MassOverCharge::new::<crate::system::mz>(0.0)),
+        );
+        let (annotated, matches) = spectrum[0].annotate_with_decoys(
+            peptide.clone(),
+            &fragments,
+            &decoys,
+            &model,
+            MassMode::Monoisotopic,
+        );
+        let (scores, _) = annotated.scores(&fragments, &model, MassMode::Monoisotopic);
+        let crate::spectrum::Score::UniqueFormulas {
+            fragments: target_fragments,
+            peaks: target_peaks,
+            ..
+        } = scores.score
+        else {
+            panic!("Combined score should be based on unique formulas")
+        };
+        assert_eq!(matches.fragments, target_fragments);
+        assert_eq!(matches.peaks, target_peaks);
+
+        // A real decoy should match fewer fragments than the target
+        let decoys = peptide.generate_decoy_fragments(charge, &model, DecoyStrategy::Reversed);
+        assert_eq!(decoys.len(), fragments.len());
+        let (_, matches) = spectrum[0].annotate_with_decoys(
+            peptide,
+            &fragments,
+            &decoys,
+            &model,
+            MassMode::Monoisotopic,
+        );
+        assert!(matches.match_rate() < target_fragments.fraction());
+        assert!((0.0..=1.0).contains(&matches.intensity.fraction()));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{system::MassOverCharge, CompoundPeptidoformIon, Fragment, MassMode, Model};
 
-use super::AnnotatedSpectrum;
+use super::{AnnotatedSpectrum, DecoyMatches, Recovered};
 
 /// A spectrum that can be annotated. Within rustyms this is implemented for the build in
 /// [mgf reader](crate::rawfile::mgf) and for mzdata [`SpectrumLike`](mzdata::prelude::SpectrumLike).
@@ -21,7 +21,10 @@ pub trait AnnotatableSpectrum {
     fn search(&self, query: MassOverCharge, tolerance: Self::Tolerance) -> Option<usize>;
 
     /// Annotate this spectrum with the given peptidoform and given fragments see
-    /// [`crate::CompoundPeptidoform::generate_theoretical_fragments`].
+    /// [`crate::CompoundPeptidoform::generate_theoretical_fragments`]. If the model has a
+    /// [noise threshold](Model::noise_threshold) peaks below the local noise level are not
+    /// annotated. Peaks matching any of the [contaminants](Model::contaminants) of the model are
+    /// labelled as such and are not annotated.
     fn annotate(
         &self,
         peptide: CompoundPeptidoformIon,
@@ -31,6 +34,12 @@ pub trait AnnotatableSpectrum {
     ) -> AnnotatedSpectrum {
         let tolerance = model.tolerance.into();
         let mut annotated = Self::empty_annotated(self, peptide);
+        let above_noise = model.noise_threshold.map(|t| annotated.above_noise(t));
+        for contaminant in &model.contaminants {
+            if let Some(index) = Self::search(self, contaminant.mz(), tolerance) {
+                annotated.spectrum[index].contaminant = Some(contaminant.clone());
+            }
+        }
 
         for fragment in theoretical_fragments {
             // Determine fragment mz and see if it is within the model range.
@@ -39,8 +48,12 @@ pub trait AnnotatableSpectrum {
                     continue;
                 }
 
-                // Get the index of the element closest to this value
-                if let Some(index) = Self::search(self, mz, tolerance) {
+                // Get the index of the element closest to this value, ignoring peaks in the noise
+                // and contaminants
+                if let Some(index) = Self::search(self, mz, tolerance).filter(|i| {
+                    above_noise.as_ref().map_or(true, |a| a[*i])
+                        && annotated.spectrum[*i].contaminant.is_none()
+                }) {
                     annotated.spectrum[index].annotation.push(fragment.clone());
                 }
             }
@@ -48,4 +61,57 @@ pub trait AnnotatableSpectrum {
 
         annotated
     }
+
+    /// Annotate this spectrum with the given peptidoform and given fragments, while also matching
+    /// the given decoy fragments see [`crate::CompoundPeptidoformIon::generate_decoy_fragments`].
+    /// The decoy fragments are matched with the same tolerance, m/z range, and noise threshold as
+    /// the targets but are not added as annotations. The returned [`DecoyMatches`] give the rate
+    /// of random matches under these settings, which can be compared with the target scores (see
+    /// [`AnnotatedSpectrum::scores`]).
+    fn annotate_with_decoys(
+        &self,
+        peptide: CompoundPeptidoformIon,
+        theoretical_fragments: &[Fragment],
+        decoy_fragments: &[Fragment],
+        model: &Model,
+        mode: MassMode,
+    ) -> (AnnotatedSpectrum, DecoyMatches) {
+        let tolerance = model.tolerance.into();
+        let annotated = Self::annotate(self, peptide, theoretical_fragments, model, mode);
+        let above_noise = model.noise_threshold.map(|t| annotated.above_noise(t));
+        let mut peak_matched = vec![false; annotated.spectrum.len()];
+        let mut fragments_total = 0_u32;
+        let mut fragments_matched = 0_u32;
+
+        for fragment in decoy_fragments {
+            if let Some(mz) = fragment.mz(mode) {
+                if !model.mz_range.contains(&mz) {
+                    continue;
+                }
+                fragments_total += 1;
+
+                if let Some(index) = Self::search(self, mz, tolerance).filter(|i| {
+                    above_noise.as_ref().map_or(true, |a| a[*i])
+                        && annotated.spectrum[*i].contaminant.is_none()
+                }) {
+                    fragments_matched += 1;
+                    peak_matched[index] = true;
+                }
+            }
+        }
+
+        let (peaks_matched, intensity_matched) = annotated
+            .spectrum
+            .iter()
+            .zip(&peak_matched)
+            .filter(|(_, matched)| **matched)
+            .fold((0_u32, 0.0), |(n, i), (p, _)| (n + 1, i + *p.intensity));
+        let (total_peaks, total_intensity) = annotated.scored_peaks();
+        let matches = DecoyMatches {
+            fragments: Recovered::new(fragments_matched, fragments_total),
+            peaks: Recovered::new(peaks_matched, total_peaks),
+            intensity: Recovered::new(intensity_matched, total_intensity),
+        };
+        (annotated, matches)
+    }
 }
@@ -0,0 +1,287 @@
+//! Scoring of theoretical isotope envelopes against (MS1) spectra
+
+use std::num::NonZeroU16;
+
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spectrum::{AnnotatableSpectrum, PeakSpectrum, RawPeak, RawSpectrum, Recovered},
+    system::f64::{MassOverCharge, Ratio, Time},
+    Chemical, Element, MolecularCharge, MolecularFormula, Tolerance,
+};
+
+impl RawSpectrum {
+    /// Score the theoretical isotope envelope of the given neutral formula with the given charge
+    /// carriers (eg [`MolecularCharge::proton`]) against this (MS1) spectrum. If the charge
+    /// carriers result in a total charge of zero the m/z of the isotopes is not defined and `None`
+    /// is returned. The isotopic distribution is calculated with the given threshold (see
+    /// [`MolecularFormula::isotopic_distribution`]) and any trailing isotopes below this threshold
+    /// are ignored. The isotopes are spaced by the mass difference between ¹³C and ¹²C.
+    ///
+    /// Only available with crate feature 'isotopes'.
+    pub fn isotope_envelope_score(
+        &self,
+        formula: &MolecularFormula,
+        charge_carriers: &MolecularCharge,
+        tolerance: Tolerance<MassOverCharge>,
+        threshold: f64,
+    ) -> Option<IsotopeEnvelopeScore> {
+        envelope_score(formula, charge_carriers, threshold, |mz| {
+            if self.spectrum().len() == 0 {
+                None
+            } else {
+                self.search(mz, tolerance).map(|index| self[index].clone())
+            }
+        })
+    }
+
+    /// Score the theoretical isotope envelope (see [`Self::isotope_envelope_score`]) against all
+    /// given MS1 spectra with a retention time within `window` of the given retention time, for
+    /// example the retention time of a PSM. For every isotope the matched peaks of all these
+    /// spectra are summed into a single peak with the summed intensity and the intensity weighted
+    /// m/z. Spectra without a retention time are ignored. Returns `None` if no spectra are within
+    /// the window or if the charge carriers result in a total charge of zero.
+    ///
+    /// Only available with crate feature 'isotopes'.
+    pub fn isotope_envelope_score_around(
+        spectra: &[Self],
+        retention_time: Time,
+        window: Time,
+        formula: &MolecularFormula,
+        charge_carriers: &MolecularCharge,
+        tolerance: Tolerance<MassOverCharge>,
+        threshold: f64,
+    ) -> Option<IsotopeEnvelopeScore> {
+        let selected: Vec<&Self> = spectra
+            .iter()
+            .filter(|s| {
+                s.rt.is_some_and(|rt| (rt - retention_time).abs() <= window)
+                    && s.spectrum().len() != 0
+            })
+            .collect();
+        if selected.is_empty() {
+            return None;
+        }
+        envelope_score(formula, charge_carriers, threshold, |mz| {
+            let peaks: Vec<RawPeak> = selected
+                .iter()
+                .filter_map(|s| s.search(mz, tolerance).map(|index| s[index].clone()))
+                .collect();
+            if peaks.is_empty() {
+                return None;
+            }
+            let intensity: f64 = peaks.iter().map(|p| *p.intensity).sum();
+            let mz = if intensity == 0.0 {
+                peaks.iter().map(|p| p.mz.value).sum::<f64>() / peaks.len() as f64
+            } else {
+                peaks.iter().map(|p| p.mz.value * *p.intensity).sum::<f64>() / intensity
+            };
+            Some(RawPeak {
+                mz: MassOverCharge::new::<crate::system::mz>(mz),
+                intensity: OrderedFloat(intensity),
+            })
+        })
+    }
+}
+
+/// Score the theoretical isotope envelope against the peaks given by `find`, which returns the
+/// matching peak for the m/z of an isotope (if any).
+fn envelope_score(
+    formula: &MolecularFormula,
+    charge_carriers: &MolecularCharge,
+    threshold: f64,
+    find: impl Fn(MassOverCharge) -> Option<RawPeak>,
+) -> Option<IsotopeEnvelopeScore> {
+    let formula = formula.clone() + charge_carriers.formula();
+    let charge = formula.charge();
+    if charge.value == 0 {
+        return None;
+    }
+    let charge =
+        crate::system::f64::Charge::new::<crate::system::e>(charge.value.unsigned_abs() as f64);
+    let spacing = Element::C.mass(NonZeroU16::new(13))? - Element::C.mass(NonZeroU16::new(12))?;
+    let monoisotopic = formula.monoisotopic_mass();
+
+    let distribution = formula.isotopic_distribution(threshold);
+    let max = distribution.iter().copied().fold(0.0, f64::max);
+    let length = distribution.len()
+        - distribution
+            .iter()
+            .rev()
+            .take_while(|a| **a / max < threshold)
+            .count();
+
+    let isotopes: Vec<MatchedIsotope> = distribution
+        .iter()
+        .take(length)
+        .enumerate()
+        .map(|(offset, abundance)| {
+            let mz: MassOverCharge = (monoisotopic + spacing * offset as f64) / charge;
+            MatchedIsotope {
+                offset,
+                theoretical_mz: mz,
+                theoretical_abundance: abundance / max,
+                peak: find(mz),
+            }
+        })
+        .collect();
+
+    let (dot, theoretical, experimental) =
+        isotopes
+            .iter()
+            .fold((0.0, 0.0, 0.0), |(dot, theoretical, experimental), i| {
+                let intensity = i.peak.as_ref().map_or(0.0, |p| *p.intensity);
+                (
+                    i.theoretical_abundance.mul_add(intensity, dot),
+                    i.theoretical_abundance
+                        .mul_add(i.theoretical_abundance, theoretical),
+                    intensity.mul_add(intensity, experimental),
+                )
+            });
+    let errors: Vec<Ratio> = isotopes
+        .iter()
+        .filter_map(|i| i.peak.as_ref().map(|p| p.mz.signed_ppm(i.theoretical_mz)))
+        .collect();
+
+    Some(IsotopeEnvelopeScore {
+        cosine_similarity: if experimental == 0.0 {
+            0.0
+        } else {
+            dot / (theoretical.sqrt() * experimental.sqrt())
+        },
+        matched: Recovered::new(errors.len() as u32, isotopes.len() as u32),
+        average_ppm_error: (!errors.is_empty())
+            .then(|| errors.iter().copied().sum::<Ratio>() / errors.len() as f64),
+        isotopes,
+    })
+}
+
+/// The score of a theoretical isotope envelope against a spectrum, see
+/// [`RawSpectrum::isotope_envelope_score`].
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IsotopeEnvelopeScore {
+    /// All isotopes of the theoretical envelope with their matched peak (if any)
+    pub isotopes: Vec<MatchedIsotope>,
+    /// The cosine similarity between the theoretical abundances and the matched intensities, with
+    /// unmatched isotopes counting as zero intensity
+    pub cosine_similarity: f64,
+    /// The number of isotopes that matched a peak
+    pub matched: Recovered<u32>,
+    /// The average signed ppm error of the matched peaks (experimental versus theoretical)
+    pub average_ppm_error: Option<Ratio>,
+}
+
+/// A single isotope of a theoretical isotope envelope
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MatchedIsotope {
+    /// The isotope offset, 0 is the monoisotopic peak
+    pub offset: usize,
+    /// The theoretical m/z
+    pub theoretical_mz: MassOverCharge,
+    /// The theoretical abundance relative to the most abundant isotope
+    pub theoretical_abundance: f64,
+    /// The closest peak within the tolerance, if any
+    pub peak: Option<RawPeak>,
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{system::mz, CompoundPeptidoformIon};
+
+    #[test]
+    fn perfect_envelope() {
+        let formula = CompoundPeptidoformIon::pro_forma("PEPTIDEK", None)
+            .unwrap()
+            .formulas()[0]
+            .clone();
+        let charge = MolecularCharge::proton(2);
+        let tolerance = Tolerance::new_ppm(10.0);
+        let theoretical = RawSpectrum::default()
+            .isotope_envelope_score(&formula, &charge, tolerance, 0.01)
+            .unwrap();
+        assert!(theoretical.isotopes.len() > 2);
+        assert_eq!(theoretical.matched.found, 0);
+        assert!(theoretical.cosine_similarity.abs() < f64::EPSILON);
+
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend(theoretical.isotopes.iter().map(|i| RawPeak {
+            mz: i.theoretical_mz + MassOverCharge::new::<mz>(0.0001),
+            intensity: OrderedFloat(i.theoretical_abundance * 1000.0),
+        }));
+        let score = spectrum
+            .isotope_envelope_score(&formula, &charge, tolerance, 0.01)
+            .unwrap();
+        assert_eq!(score.matched.found, score.matched.total);
+        assert!((score.cosine_similarity - 1.0).abs() < 1e-9);
+        assert!(score.average_ppm_error.unwrap().value > 0.0);
+    }
+
+    #[test]
+    fn around_retention_time() {
+        let formula = CompoundPeptidoformIon::pro_forma("PEPTIDEK", None)
+            .unwrap()
+            .formulas()[0]
+            .clone();
+        let charge = MolecularCharge::proton(2);
+        let tolerance = Tolerance::new_ppm(10.0);
+        let theoretical = RawSpectrum::default()
+            .isotope_envelope_score(&formula, &charge, tolerance, 0.01)
+            .unwrap();
+        let scan = |rt: f64, factor: f64, skip: usize| {
+            let mut spectrum = RawSpectrum {
+                rt: Some(Time::new::<crate::system::s>(rt)),
+                ..RawSpectrum::default()
+            };
+            spectrum.extend(theoretical.isotopes.iter().skip(skip).map(|i| RawPeak {
+                mz: i.theoretical_mz,
+                intensity: OrderedFloat(i.theoretical_abundance * factor),
+            }));
+            spectrum
+        };
+        // The scans in the window each miss part of the envelope, the scan outside the window
+        // has a distorted envelope
+        let mut first = scan(100.0, 1000.0, 0);
+        first.relative_noise_filter(0.5);
+        let spectra = [first, scan(102.0, 500.0, 1), scan(200.0, 1.0, 2)];
+        let score = RawSpectrum::isotope_envelope_score_around(
+            &spectra,
+            Time::new::<crate::system::s>(101.0),
+            Time::new::<crate::system::s>(5.0),
+            &formula,
+            &charge,
+            tolerance,
+            0.01,
+        )
+        .unwrap();
+        assert_eq!(score.matched.found, score.matched.total);
+        assert!(score.cosine_similarity > 0.9);
+        assert!(score.cosine_similarity < 1.0);
+        assert!(RawSpectrum::isotope_envelope_score_around(
+            &spectra,
+            Time::new::<crate::system::s>(150.0),
+            Time::new::<crate::system::s>(5.0),
+            &formula,
+            &charge,
+            tolerance,
+            0.01,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn uncharged() {
+        let formula = molecular_formula!(C 6 H 12 O 6);
+        assert_eq!(
+            RawSpectrum::default().isotope_envelope_score(
+                &formula,
+                &MolecularCharge::new(&[]),
+                Tolerance::new_ppm(10.0),
+                0.01
+            ),
+            None
+        );
+    }
+}
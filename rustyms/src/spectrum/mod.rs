@@ -1,17 +1,35 @@
 //! Spectrum related code
 
 mod annotated;
+mod combine;
+mod contaminants;
+mod dia_library;
+mod dia_windows;
 mod fdr;
 mod fragmentation;
+#[cfg(feature = "isotopes")]
+mod isotope_envelope;
 #[cfg(feature = "mzdata")]
 mod mzdata;
+mod noise;
+mod peak_index;
 mod peaks;
 mod raw;
 mod scores;
+mod similarity;
 
 pub use annotated::*;
+pub use combine::*;
+pub use contaminants::*;
+pub use dia_library::*;
+pub use dia_windows::*;
 pub use fdr::*;
 pub use fragmentation::*;
+#[cfg(feature = "isotopes")]
+pub use isotope_envelope::*;
+pub use noise::*;
+pub use peak_index::*;
 pub use peaks::*;
 pub use raw::*;
 pub use scores::*;
+pub use similarity::*;
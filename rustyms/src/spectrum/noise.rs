@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+
+use crate::system::{mz, MassOverCharge};
+
+use super::AnnotatedSpectrum;
+
+/// The local noise level around a peak, see [`noise_levels`]
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct NoiseLevel {
+    /// The median intensity of all peaks in the window
+    pub median: f64,
+    /// The median absolute deviation of the intensity of all peaks in the window
+    pub mad: f64,
+}
+
+impl NoiseLevel {
+    /// The intensity threshold: the median plus `factor` times the median absolute deviation
+    pub fn threshold(self, factor: f64) -> f64 {
+        factor.mul_add(self.mad, self.median)
+    }
+}
+
+/// A dynamic intensity threshold based on the local noise level, see [`noise_levels`]. Peaks
+/// with an intensity below the median plus `factor` times the median absolute deviation of all
+/// peaks within the window around the peak are considered noise.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NoiseThreshold {
+    /// The total width of the sliding window, centred on each peak
+    pub window: MassOverCharge,
+    /// The number of median absolute deviations above the median
+    pub factor: f64,
+}
+
+impl NoiseThreshold {
+    /// Create a new noise threshold
+    pub const fn new(window: MassOverCharge, factor: f64) -> Self {
+        Self { window, factor }
+    }
+
+    /// Get the intensity threshold for each peak, the peaks have to be given as (m/z, intensity)
+    /// sorted on m/z.
+    pub fn thresholds(self, peaks: &[(f64, f64)]) -> Vec<f64> {
+        noise_levels(peaks, self.window)
+            .into_iter()
+            .map(|level| level.threshold(self.factor))
+            .collect()
+    }
+}
+
+impl Default for NoiseThreshold {
+    /// A window of 100 m/z with a factor of 3
+    fn default() -> Self {
+        Self::new(MassOverCharge::new::<mz>(100.0), 3.0)
+    }
+}
+
+/// Estimate the local noise level for each peak as the median and median absolute deviation of
+/// the intensities of all peaks in a sliding window centred on the peak. The peaks have to be
+/// given as (m/z, intensity) sorted on m/z.
+pub fn noise_levels(peaks: &[(f64, f64)], window: MassOverCharge) -> Vec<NoiseLevel> {
+    let half = window.value / 2.0;
+    let mut start = 0;
+    let mut end = 0;
+    let mut intensities = Vec::new();
+    peaks
+        .iter()
+        .map(|(center, _)| {
+            start += peaks[start..].partition_point(|(p, _)| *p < center - half);
+            end += peaks[end..].partition_point(|(p, _)| *p <= center + half);
+            intensities.clear();
+            intensities.extend(peaks[start..end].iter().map(|(_, i)| *i));
+            let level = median(&mut intensities);
+            for intensity in &mut intensities {
+                *intensity = (*intensity - level).abs();
+            }
+            NoiseLevel {
+                median: level,
+                mad: median(&mut intensities),
+            }
+        })
+        .collect()
+}
+
+impl AnnotatedSpectrum {
+    /// The local noise level for each peak, see [`noise_levels`]
+    pub fn noise_levels(&self, window: MassOverCharge) -> Vec<NoiseLevel> {
+        noise_levels(&self.mz_intensity(), window)
+    }
+
+    /// For each peak if it is above the given noise threshold
+    pub fn above_noise(&self, threshold: NoiseThreshold) -> Vec<bool> {
+        threshold
+            .thresholds(&self.mz_intensity())
+            .into_iter()
+            .zip(&self.spectrum)
+            .map(|(threshold, peak)| *peak.intensity >= threshold)
+            .collect()
+    }
+
+    /// All peaks as (m/z, intensity)
+    fn mz_intensity(&self) -> Vec<(f64, f64)> {
+        self.spectrum
+            .iter()
+            .map(|p| (p.experimental_mz.value, *p.intensity))
+            .collect()
+    }
+}
+
+/// The median of the given values, sorts the values in place. Returns 0 for an empty slice.
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_unstable_by(f64::total_cmp);
+    let len = values.len();
+    if len == 0 {
+        0.0
+    } else if len % 2 == 0 {
+        (values[len / 2 - 1] + values[len / 2]) / 2.0
+    } else {
+        values[len / 2]
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        fragment::FragmentType,
+        spectrum::{AnnotatableSpectrum, PeakSpectrum, RawPeak, RawSpectrum},
+        system::{e, usize::Charge},
+        CompoundPeptidoformIon, MassMode, Model,
+    };
+    use ordered_float::OrderedFloat;
+
+    #[test]
+    fn levels() {
+        let peaks = [
+            (100.0, 1.0),
+            (101.0, 2.0),
+            (102.0, 3.0),
+            (103.0, 100.0),
+            (200.0, 10.0),
+        ];
+        let levels = noise_levels(&peaks, MassOverCharge::new::<mz>(4.0));
+        assert_eq!(
+            levels[0],
+            NoiseLevel {
+                median: 2.0,
+                mad: 1.0
+            }
+        );
+        assert_eq!(
+            levels[1],
+            NoiseLevel {
+                median: 2.5,
+                mad: 1.0
+            }
+        );
+        assert_eq!(
+            levels[4],
+            NoiseLevel {
+                median: 10.0,
+                mad: 0.0
+            }
+        );
+        let thresholds =
+            NoiseThreshold::new(MassOverCharge::new::<mz>(4.0), 3.0).thresholds(&peaks);
+        assert!(peaks[3].1 > thresholds[3]);
+        assert!(peaks[0].1 < thresholds[0]);
+        assert!(noise_levels(&[], MassOverCharge::new::<mz>(4.0)).is_empty());
+    }
+
+    #[test]
+    fn filter_and_annotate() {
+        let model = Model::cid_hcd();
+        let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDEK", None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &model);
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend(
+            fragments
+                .iter()
+                .filter(|f| matches!(f.ion, FragmentType::y(_)) && f.neutral_loss.is_empty())
+                .enumerate()
+                .map(|(i, f)| RawPeak {
+                    mz: f.mz(MassMode::Monoisotopic).unwrap(),
+                    intensity: OrderedFloat((i + 1) as f64),
+                }),
+        );
+        let threshold = NoiseThreshold::new(MassOverCharge::new::<mz>(10_000.0), 1000.0);
+        let annotated =
+            spectrum.annotate(peptide.clone(), &fragments, &model, MassMode::Monoisotopic);
+        assert!(annotated.spectrum().all(|p| !p.annotation.is_empty()));
+        let annotated = spectrum.annotate(
+            peptide,
+            &fragments,
+            &model.noise_threshold(Some(threshold)),
+            MassMode::Monoisotopic,
+        );
+        assert!(annotated.spectrum().all(|p| p.annotation.is_empty()));
+
+        let mut filtered = spectrum.clone();
+        filtered.dynamic_noise_filter(threshold);
+        assert!(filtered.spectrum().next().is_none());
+        let mut filtered = spectrum.clone();
+        filtered.dynamic_noise_filter(NoiseThreshold::new(
+            MassOverCharge::new::<mz>(10_000.0),
+            0.0,
+        ));
+        assert_eq!(
+            filtered.spectrum().count(),
+            spectrum.spectrum().count() / 2 + 1
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! A sorted index of peaks, for fast tolerance window queries
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    system::{Mass, MassOverCharge, Ratio},
+    Tolerance,
+};
+
+/// Anything that has a position on a mass axis (for example m/z or mass) and so can be stored in
+/// a [`PeakIndex`] or queried with [`sorted_window`] and [`sorted_closest`].
+pub trait IndexedPeak {
+    /// The quantity the peaks are sorted on, for example [`MassOverCharge`] or [`Mass`]
+    type Position: Copy + PartialOrd + std::ops::Sub<Output = Self::Position>;
+
+    /// The position of this peak
+    fn position(&self) -> Self::Position;
+}
+
+impl<T> IndexedPeak for (MassOverCharge, T) {
+    type Position = MassOverCharge;
+    fn position(&self) -> MassOverCharge {
+        self.0
+    }
+}
+
+impl<T> IndexedPeak for (Mass, T) {
+    type Position = Mass;
+    fn position(&self) -> Mass {
+        self.0
+    }
+}
+
+/// A set of peaks sorted on their position, to be reused for many tolerance window queries. This
+/// is the owned version of the queries [`sorted_window`] and [`sorted_closest`], which can be used
+/// on any slice that is already sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PeakIndex<T> {
+    peaks: Vec<T>,
+}
+
+impl<T: IndexedPeak> PeakIndex<T> {
+    /// Create a new index, this sorts the given peaks.
+    pub fn new(peaks: impl IntoIterator<Item = T>) -> Self {
+        let mut peaks: Vec<T> = peaks.into_iter().collect();
+        peaks.sort_by(|a, b| {
+            a.position()
+                .partial_cmp(&b.position())
+                .unwrap_or(Ordering::Equal)
+        });
+        Self { peaks }
+    }
+
+    /// All peaks, sorted on position.
+    pub fn peaks(&self) -> &[T] {
+        &self.peaks
+    }
+
+    /// The number of peaks.
+    pub fn len(&self) -> usize {
+        self.peaks.len()
+    }
+
+    /// Check if there are no peaks.
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+
+    /// All peaks with a position between `low` and `high` (inclusive), see [`sorted_window`].
+    pub fn window(&self, low: T::Position, high: T::Position) -> &[T] {
+        sorted_window(&self.peaks, low, high)
+    }
+
+    /// All peaks within the tolerance around the given value.
+    pub fn within(&self, value: T::Position, tolerance: Tolerance<T::Position>) -> &[T]
+    where
+        T::Position: std::ops::Mul<Ratio, Output = T::Position>
+            + std::ops::Add<T::Position, Output = T::Position>,
+    {
+        let (low, high) = tolerance.bounds(value);
+        sorted_window(&self.peaks, low, high)
+    }
+
+    /// The index of the peak closest to the given value, if there is any within the tolerance, see
+    /// [`sorted_closest`].
+    pub fn closest(&self, value: T::Position, tolerance: Tolerance<T::Position>) -> Option<usize>
+    where
+        T::Position: std::ops::Mul<Ratio, Output = T::Position>
+            + std::ops::Add<T::Position, Output = T::Position>,
+    {
+        sorted_closest(&self.peaks, value, tolerance)
+    }
+}
+
+impl<T: IndexedPeak> FromIterator<T> for PeakIndex<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+impl<T> std::ops::Index<usize> for PeakIndex<T> {
+    type Output = T;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.peaks[index]
+    }
+}
+
+/// Get all peaks with a position between `low` and `high` (inclusive). The peaks have to be
+/// sorted on position.
+pub fn sorted_window<T: IndexedPeak>(peaks: &[T], low: T::Position, high: T::Position) -> &[T] {
+    let start = peaks.partition_point(|p| p.position() < low);
+    let end = start + peaks[start..].partition_point(|p| p.position() <= high);
+    &peaks[start..end]
+}
+
+/// Get the index of the peak closest to the given value, if there is any within the tolerance.
+/// The peaks have to be sorted on position.
+pub fn sorted_closest<T: IndexedPeak>(
+    peaks: &[T],
+    value: T::Position,
+    tolerance: Tolerance<T::Position>,
+) -> Option<usize>
+where
+    T::Position: std::ops::Mul<Ratio, Output = T::Position>
+        + std::ops::Add<T::Position, Output = T::Position>,
+{
+    let (low, high) = tolerance.bounds(value);
+    let start = peaks.partition_point(|p| p.position() < low);
+    let distance = |p: &T| {
+        let position = p.position();
+        if position > value {
+            position - value
+        } else {
+            value - position
+        }
+    };
+    sorted_window(&peaks[start..], low, high)
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            distance(a)
+                .partial_cmp(&distance(b))
+                .unwrap_or(Ordering::Equal)
+        })
+        .map(|(index, _)| start + index)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::system::{dalton, mz};
+
+    #[test]
+    fn window_and_closest() {
+        let index: PeakIndex<(MassOverCharge, usize)> = [500.0, 100.0, 300.0, 300.01, 200.0]
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (MassOverCharge::new::<mz>(*m), i))
+            .collect();
+        assert_eq!(
+            index.peaks().iter().map(|p| p.1).collect::<Vec<_>>(),
+            vec![1, 4, 2, 3, 0]
+        );
+        assert_eq!(
+            index
+                .window(
+                    MassOverCharge::new::<mz>(200.0),
+                    MassOverCharge::new::<mz>(300.0)
+                )
+                .len(),
+            2
+        );
+        assert!(index
+            .window(
+                MassOverCharge::new::<mz>(600.0),
+                MassOverCharge::new::<mz>(700.0)
+            )
+            .is_empty());
+        let tolerance = Tolerance::new_absolute(MassOverCharge::new::<mz>(0.1));
+        assert_eq!(
+            index
+                .within(MassOverCharge::new::<mz>(300.0), tolerance)
+                .len(),
+            2
+        );
+        assert_eq!(
+            index.closest(MassOverCharge::new::<mz>(300.008), tolerance),
+            Some(3)
+        );
+        assert_eq!(
+            index.closest(MassOverCharge::new::<mz>(300.002), Tolerance::new_ppm(10.0)),
+            Some(2)
+        );
+        assert_eq!(
+            index.closest(MassOverCharge::new::<mz>(400.0), tolerance),
+            None
+        );
+        assert_eq!(
+            PeakIndex::<(Mass, ())>::default()
+                .closest(Mass::new::<dalton>(400.0), Tolerance::new_ppm(10.0)),
+            None
+        );
+    }
+}
@@ -7,12 +7,15 @@ use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    spectrum::{AnnotatableSpectrum, AnnotatedPeak, PeakSpectrum},
+    spectrum::{
+        noise_levels, sorted_closest, sorted_window, AnnotatableSpectrum, AnnotatedPeak,
+        IndexedPeak, NoiseLevel, NoiseThreshold, PeakSpectrum,
+    },
     system::{
         f64::{Mass, MassOverCharge, Ratio, Time},
         usize::Charge,
     },
-    AnnotatedSpectrum, CompoundPeptidoformIon, Tolerance, WithinTolerance,
+    AnnotatedSpectrum, CompoundPeptidoformIon, Tolerance,
 };
 
 /// A raw spectrum (meaning not annotated yet)
@@ -31,12 +34,12 @@ pub struct RawSpectrum {
     /// The found precursor intensity
     pub intensity: Option<f64>,
     /// The peaks of which this spectrum consists
-    spectrum: Vec<RawPeak>,
+    pub(super) spectrum: Vec<RawPeak>,
     /// MGF: if present the SEQUENCE line
     pub sequence: Option<String>,
     /// MGF TITLE: if present the raw file where this mgf was made from
     pub raw_file: Option<String>,
-    /// MGF TITLE: if present the raw file scan number
+    /// MGF TITLE or SCANS: if present the raw file scan number
     pub raw_scan_number: Option<usize>,
     /// MGF TITLE: index number
     pub raw_index: Option<usize>,
@@ -77,6 +80,30 @@ impl RawSpectrum {
         self.spectrum.shrink_to_fit();
     }
 
+    /// Filter the spectrum to retain all peaks above the local noise level, see [`NoiseThreshold`].
+    pub fn dynamic_noise_filter(&mut self, threshold: NoiseThreshold) {
+        let thresholds = threshold.thresholds(&self.mz_intensity());
+        let mut index = 0;
+        self.spectrum.retain(|p| {
+            index += 1;
+            *p.intensity >= thresholds[index - 1]
+        });
+        self.spectrum.shrink_to_fit();
+    }
+
+    /// The local noise level for each peak, see [`noise_levels`]
+    pub fn noise_levels(&self, window: MassOverCharge) -> Vec<NoiseLevel> {
+        noise_levels(&self.mz_intensity(), window)
+    }
+
+    /// All peaks as (m/z, intensity)
+    fn mz_intensity(&self) -> Vec<(f64, f64)> {
+        self.spectrum
+            .iter()
+            .map(|p| (p.mz.value, *p.intensity))
+            .collect()
+    }
+
     /// Filter a spectrum by dividing it in windows and within each window only retain the `top` number of peaks.
     #[allow(clippy::missing_panics_doc)] // Cannot panic as it checks with peek first
     pub fn top_x_filter(&mut self, window_size: f64, top: usize) {
@@ -133,23 +160,7 @@ impl AnnotatableSpectrum for RawSpectrum {
     }
 
     fn search(&self, query: MassOverCharge, tolerance: Self::Tolerance) -> Option<usize> {
-        let index = self
-            .spectrum
-            .binary_search_by(|p| p.mz.value.total_cmp(&query.value))
-            .unwrap_or_else(|i| i);
-
-        // Check index-1, index and index+1 (if existing) to find the one with the lowest ppm
-        let mut closest = (0, f64::INFINITY);
-        for i in if index == 0 { 0 } else { index - 1 }..=(index + 1).min(self.spectrum.len() - 1) {
-            let ppm = self.spectrum[i].ppm(query).value;
-            if ppm < closest.1 {
-                closest = (i, ppm);
-            }
-        }
-
-        tolerance
-            .within(&self.spectrum[closest.0].mz, &query)
-            .then_some(closest.0)
+        sorted_closest(&self.spectrum, query, tolerance)
     }
 }
 
@@ -181,34 +192,7 @@ impl PeakSpectrum for RawSpectrum {
 
     /// Return the slice of peaks that is within the given tolerance bounds.
     fn binary_search(&self, low: MassOverCharge, high: MassOverCharge) -> &[RawPeak] {
-        let left_idx = match self
-            .spectrum
-            .binary_search_by(|a| a.mz.value.total_cmp(&low.value))
-        {
-            Result::Ok(idx) | Result::Err(idx) => {
-                let mut idx = idx.saturating_sub(1);
-                while idx > 0 && self.spectrum[idx].mz.value.total_cmp(&low.value) != Ordering::Less
-                {
-                    idx -= 1;
-                }
-                idx
-            }
-        };
-
-        let right_idx = match self.spectrum[left_idx..]
-            .binary_search_by(|a| a.mz.value.total_cmp(&high.value))
-        {
-            Result::Ok(idx) | Err(idx) => {
-                let mut idx = idx + left_idx;
-                while idx < self.spectrum.len()
-                    && self.spectrum[idx].mz.value.total_cmp(&high.value) != Ordering::Greater
-                {
-                    idx = idx.saturating_add(1);
-                }
-                idx.min(self.spectrum.len())
-            }
-        };
-        &self.spectrum[left_idx..right_idx]
+        sorted_window(&self.spectrum, low, high)
     }
 
     fn spectrum(&self) -> Self::Iter<'_> {
@@ -253,6 +237,13 @@ impl PartialEq for RawPeak {
 
 impl Eq for RawPeak {}
 
+impl IndexedPeak for RawPeak {
+    type Position = MassOverCharge;
+    fn position(&self) -> MassOverCharge {
+        self.mz
+    }
+}
+
 impl RawPeak {
     /// Determine the ppm error for the given fragment
     pub fn ppm(&self, mz: MassOverCharge) -> Ratio {
@@ -26,7 +26,7 @@ impl AnnotatedSpectrum {
                     .is_some_and(|mz| model.mz_range.contains(&mz))
             })
             .collect_vec();
-        let total_intensity: f64 = self.spectrum.iter().map(|p| *p.intensity).sum();
+        let (_, total_intensity) = self.scored_peaks();
         let individual_peptides = self
             .peptide
             .peptidoform_ions()
@@ -90,6 +90,15 @@ impl AnnotatedSpectrum {
         )
     }
 
+    /// The number of peaks and the total intensity of all peaks that are not labelled as
+    /// contaminant, these are the totals for the scores
+    pub(super) fn scored_peaks(&self) -> (u32, f64) {
+        self.spectrum
+            .iter()
+            .filter(|p| p.contaminant.is_none())
+            .fold((0, 0.0), |(n, i), p| (n + 1, i + *p.intensity))
+    }
+
     /// Get the base score of this spectrum
     /// (Fragments, peaks, intensity)
     fn filtered_base_score(
@@ -131,7 +140,7 @@ impl AnnotatedSpectrum {
             .count() as u32;
         (
             Recovered::new(fragments_found, total_fragments),
-            Recovered::new(peaks_annotated, self.spectrum.len() as u32),
+            Recovered::new(peaks_annotated, self.scored_peaks().0),
             intensity_annotated,
         )
     }
@@ -359,7 +368,7 @@ pub struct Recovered<T> {
 
 impl<T> Recovered<T> {
     /// Create a new recovered statistic
-    fn new(found: impl Into<T>, total: impl Into<T>) -> Self {
+    pub(crate) fn new(found: impl Into<T>, total: impl Into<T>) -> Self {
         Self {
             found: found.into(),
             total: total.into(),
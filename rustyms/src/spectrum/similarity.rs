@@ -0,0 +1,220 @@
+//! Binned spectral similarity, to score one query spectrum against many library spectra
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spectrum::{PeakSpectrum, RawSpectrum},
+    system::mz,
+};
+
+/// The settings for binning a spectrum, see [`BinnedSpectrum`]. Spectra can only be compared if
+/// they were binned with the same parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BinningParameters {
+    /// The width of a single bin in m/z. The default is the average distance between the centres
+    /// of peptide fragment clusters.
+    ///
+    /// Default: 1.0005079.
+    pub bin_width: f64,
+    /// The offset of the bin boundaries, as fraction of the bin width.
+    ///
+    /// Default: 0.4.
+    pub bin_offset: f64,
+    /// The lowest m/z that is taken into account.
+    ///
+    /// Default: 100.0.
+    pub min_mz: f64,
+    /// The highest m/z that is taken into account.
+    ///
+    /// Default: 2000.0.
+    pub max_mz: f64,
+    /// Use the square root of the intensities, this lowers the influence of the most intense peaks.
+    ///
+    /// Default: true.
+    pub sqrt_intensity: bool,
+}
+
+impl Default for BinningParameters {
+    fn default() -> Self {
+        Self {
+            bin_width: 1.000_507_9,
+            bin_offset: 0.4,
+            min_mz: 100.0,
+            max_mz: 2000.0,
+            sqrt_intensity: true,
+        }
+    }
+}
+
+impl BinningParameters {
+    /// The total number of bins for these parameters.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn number_of_bins(&self) -> usize {
+        ((self.max_mz - self.min_mz) / self.bin_width + self.bin_offset)
+            .ceil()
+            .max(0.0) as usize
+    }
+
+    /// The bin for this m/z, if it falls within the range.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn bin(&self, value: f64) -> Option<usize> {
+        (value >= self.min_mz && value <= self.max_mz)
+            .then(|| ((value - self.min_mz) / self.bin_width + self.bin_offset).floor() as usize)
+    }
+}
+
+/// A spectrum binned into fixed width bins and normalised to unit length, so that the dot product
+/// of two binned spectra is their cosine similarity. The dense representation is what allows the
+/// similarity to be calculated as a single vectorised loop, see [`batch_cosine`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BinnedSpectrum {
+    bins: Vec<f32>,
+}
+
+impl BinnedSpectrum {
+    /// Bin the given spectrum, all intensity within a bin is summed.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(spectrum: &RawSpectrum, parameters: &BinningParameters) -> Self {
+        let mut bins = vec![0.0_f32; parameters.number_of_bins()];
+        for peak in spectrum.spectrum() {
+            if let Some(bin) = parameters.bin(peak.mz.get::<mz>()) {
+                if let Some(value) = bins.get_mut(bin) {
+                    *value += if parameters.sqrt_intensity {
+                        peak.intensity.sqrt()
+                    } else {
+                        *peak.intensity
+                    } as f32;
+                }
+            }
+        }
+        let length = dot(&bins, &bins).sqrt();
+        if length > 0.0 {
+            for value in &mut bins {
+                *value /= length;
+            }
+        }
+        Self { bins }
+    }
+
+    /// The normalised intensity in every bin.
+    pub fn bins(&self) -> &[f32] {
+        &self.bins
+    }
+
+    /// Get the cosine similarity of these two binned spectra. This is 0.0 if either spectrum is
+    /// empty.
+    /// # Panics
+    /// In debug builds if the spectra were binned with a different number of bins.
+    pub fn cosine(&self, other: &Self) -> f64 {
+        debug_assert_eq!(self.bins.len(), other.bins.len());
+        f64::from(dot(&self.bins, &other.bins))
+    }
+}
+
+/// Calculate the cosine similarity of a query against all given library spectra, the scores are
+/// in the same order as the library. With the feature `simd` this uses explicit SIMD
+/// instructions, otherwise it relies on the compiler to vectorise the scalar loop, see
+/// [`batch_cosine_scalar`].
+pub fn batch_cosine(query: &BinnedSpectrum, library: &[BinnedSpectrum]) -> Vec<f64> {
+    library
+        .iter()
+        .map(|spectrum| query.cosine(spectrum))
+        .collect()
+}
+
+/// Only available with feature `rayon`.
+/// Calculate the cosine similarity of a query against all given library spectra in parallel, see
+/// [`batch_cosine`]. The scores are in the same order as the library, regardless of the number of
+/// threads used.
+#[cfg(feature = "rayon")]
+pub fn par_batch_cosine(query: &BinnedSpectrum, library: &[BinnedSpectrum]) -> Vec<f64> {
+    use rayon::prelude::*;
+    library
+        .par_iter()
+        .map(|spectrum| query.cosine(spectrum))
+        .collect()
+}
+
+/// Calculate the cosine similarity of a query against all given library spectra with the scalar
+/// implementation, regardless of the `simd` feature. This is the reference for [`batch_cosine`].
+pub fn batch_cosine_scalar(query: &BinnedSpectrum, library: &[BinnedSpectrum]) -> Vec<f64> {
+    library
+        .iter()
+        .map(|spectrum| f64::from(dot_scalar(&query.bins, &spectrum.bins)))
+        .collect()
+}
+
+/// The dot product of the two slices
+#[cfg(not(feature = "simd"))]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    dot_scalar(a, b)
+}
+
+/// The dot product of the two slices, using 8 wide SIMD lanes
+#[cfg(feature = "simd")]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    use wide::f32x8;
+
+    let chunks_a = a.chunks_exact(8);
+    let chunks_b = b.chunks_exact(8);
+    let tail = dot_scalar(chunks_a.remainder(), chunks_b.remainder());
+    let mut sum = f32x8::ZERO;
+    for (a, b) in chunks_a.zip(chunks_b) {
+        let a: [f32; 8] = std::array::from_fn(|i| a[i]);
+        let b: [f32; 8] = std::array::from_fn(|i| b[i]);
+        sum += f32x8::from(a) * f32x8::from(b);
+    }
+    sum.reduce_add() + tail
+}
+
+/// The dot product of the two slices, as a plain loop
+fn dot_scalar(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{spectrum::RawPeak, system::MassOverCharge};
+    use ordered_float::OrderedFloat;
+
+    fn spectrum(peaks: &[(f64, f64)]) -> RawSpectrum {
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend(peaks.iter().map(|(m, i)| RawPeak {
+            mz: MassOverCharge::new::<mz>(*m),
+            intensity: OrderedFloat(*i),
+        }));
+        spectrum
+    }
+
+    #[test]
+    fn batch_cosine() {
+        let parameters = BinningParameters::default();
+        let query = BinnedSpectrum::new(
+            &spectrum(&[(200.1, 4.0), (300.2, 9.0), (1010.5, 1.0)]),
+            &parameters,
+        );
+        let library: Vec<_> = [
+            spectrum(&[(200.1, 4.0), (300.2, 9.0), (1010.5, 1.0)]),
+            spectrum(&[(200.15, 4.0), (300.25, 9.0)]),
+            spectrum(&[(500.0, 4.0), (600.0, 9.0)]),
+            spectrum(&[]),
+        ]
+        .iter()
+        .map(|s| BinnedSpectrum::new(s, &parameters))
+        .collect();
+        let scores = super::batch_cosine(&query, &library);
+        let scalar = batch_cosine_scalar(&query, &library);
+        assert_eq!(scores.len(), 4);
+        assert!((scores[0] - 1.0).abs() < 1e-6, "{scores:?}");
+        assert!(scores[1] > 0.9 && scores[1] < 1.0, "{scores:?}");
+        assert!(scores[2].abs() < 1e-6, "{scores:?}");
+        assert!(scores[3].abs() < 1e-6, "{scores:?}");
+        for (simd, scalar) in scores.iter().zip(&scalar) {
+            assert!((simd - scalar).abs() < 1e-5);
+        }
+        #[cfg(feature = "rayon")]
+        assert_eq!(par_batch_cosine(&query, &library), scores);
+    }
+}
@@ -0,0 +1,457 @@
+//! Composable sequence transformations to generate variants of peptidoforms and proteins, for
+//! example decoys (reversed or shuffled sequences), single nucleotide variant peptides from a
+//! list of point mutations, or substituted sequences for robustness testing. Every variant keeps
+//! track of the transformations that were applied to generate it.
+//!
+//! ```rust
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! use rustyms::{transformation::*, *};
+//! let protein = Peptidoform::pro_forma("MPEPTIDEKAMPEK", None)?.into_semi_ambiguous().unwrap();
+//! let pipeline = TransformationPipeline::default()
+//!     .then(SequenceTransformation::Mutations(vec!["K9R".parse()?]))
+//!     .then(SequenceTransformation::Reverse);
+//! let variants = pipeline.apply(&protein);
+//! assert_eq!(variants[0].peptidoform.to_string(), "KEPMAREDITPEPM");
+//! assert_eq!(variants[0].transformations.len(), 2);
+//! # Ok(()) }
+//! ```
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    peptidoform::SemiAmbiguous,
+    AminoAcid, CheckedAminoAcid, Peptidoform, SequenceElement, SequencePosition,
+};
+
+/// A single transformation step, see [`TransformationPipeline`]. Every transformation generates
+/// zero or more variants from a single peptidoform.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SequenceTransformation {
+    /// Reverse the sequence, generates a single variant
+    Reverse,
+    /// Shuffle the sequence, while keeping the given amino acids in place, for example K and R
+    /// to keep the tryptic cleavage sites. The shuffle is generated from the given seed with a
+    /// `ChaCha` generator, so it is reproducible across platforms. Generates a single variant. Only
+    /// available with feature `rand`.
+    #[cfg(feature = "rand")]
+    Shuffle {
+        /// The amino acids that are kept in place
+        fixed: Vec<AminoAcid>,
+        /// The seed for the random generator
+        seed: u64,
+    },
+    /// Apply point mutations, for example from a list of known variants. Generates a variant for
+    /// every mutation that applies to this peptidoform. The positions are interpreted as
+    /// positions in the protein if the location of the peptidoform in its protein is known (see
+    /// [`Peptidoform::get_protein_position`]), otherwise as positions in the peptidoform.
+    /// Mutations where the original amino acid does not match the sequence are skipped.
+    Mutations(Vec<PointMutation>),
+    /// Substitute a single amino acid for any other canonical amino acid that has at least the
+    /// given score in the substitution matrix. Generates a variant for every possible
+    /// substitution. Only available with feature `align`.
+    #[cfg(feature = "align")]
+    Substitutions {
+        /// The substitution matrix
+        matrix: crate::align::ScoringMatrix,
+        /// The minimal score for a substitution
+        min_score: i8,
+    },
+}
+
+/// A point mutation, written as the original amino acid, the 1 based position, and the new
+/// amino acid, eg `K9R`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PointMutation {
+    /// The 0 based sequence index
+    pub index: usize,
+    /// The original amino acid
+    pub from: AminoAcid,
+    /// The new amino acid
+    pub to: AminoAcid,
+}
+
+/// A transformation that was applied to generate a variant
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AppliedTransformation {
+    /// The sequence was reversed
+    Reversed,
+    /// The sequence was shuffled with the given seed
+    Shuffled(u64),
+    /// The point mutation was applied, with the position as given in the mutation list
+    Mutation(PointMutation),
+    /// An amino acid was substituted, with the position as index in the peptidoform
+    Substitution(PointMutation),
+}
+
+/// A variant generated by a [`TransformationPipeline`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TransformedPeptidoform {
+    /// The variant
+    pub peptidoform: Peptidoform<SemiAmbiguous>,
+    /// All transformations that were applied to generate this variant, in order
+    pub transformations: Vec<AppliedTransformation>,
+}
+
+/// A sequence of transformations that are applied after each other. Every step is applied to
+/// all variants generated by the previous step.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TransformationPipeline {
+    /// The transformation steps
+    pub steps: Vec<SequenceTransformation>,
+}
+
+impl TransformationPipeline {
+    /// Add a step to the end of this pipeline
+    #[must_use]
+    pub fn then(mut self, step: SequenceTransformation) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Apply all steps to the given peptidoform. If there are no steps this returns the
+    /// peptidoform itself.
+    pub fn apply(&self, peptidoform: &Peptidoform<SemiAmbiguous>) -> Vec<TransformedPeptidoform> {
+        self.steps.iter().fold(
+            vec![TransformedPeptidoform {
+                peptidoform: peptidoform.clone(),
+                transformations: Vec::new(),
+            }],
+            |variants, step| {
+                variants
+                    .iter()
+                    .flat_map(|variant| {
+                        step.apply(&variant.peptidoform).into_iter().map(
+                            |(peptidoform, applied)| TransformedPeptidoform {
+                                peptidoform,
+                                transformations: variant
+                                    .transformations
+                                    .iter()
+                                    .cloned()
+                                    .chain(std::iter::once(applied))
+                                    .collect(),
+                            },
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+}
+
+impl SequenceTransformation {
+    /// Apply this transformation to the given peptidoform
+    pub fn apply(
+        &self,
+        peptidoform: &Peptidoform<SemiAmbiguous>,
+    ) -> Vec<(Peptidoform<SemiAmbiguous>, AppliedTransformation)> {
+        match self {
+            Self::Reverse => vec![(peptidoform.reverse(), AppliedTransformation::Reversed)],
+            #[cfg(feature = "rand")]
+            Self::Shuffle { fixed, seed } => {
+                use rand::{seq::SliceRandom, SeedableRng};
+
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(*seed);
+                let movable: Vec<usize> = peptidoform
+                    .sequence()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, s)| !fixed.contains(&s.aminoacid.aminoacid()))
+                    .map(|(index, _)| index)
+                    .collect();
+                let mut shuffled = movable.clone();
+                shuffled.shuffle(&mut rng);
+                let mut result = peptidoform.clone();
+                for (target, source) in movable.iter().zip(&shuffled) {
+                    result.sequence_mut()[*target] = peptidoform.sequence()[*source].clone();
+                }
+                vec![(result, AppliedTransformation::Shuffled(*seed))]
+            }
+            Self::Mutations(mutations) => {
+                let offset = peptidoform.get_protein_position().map(|p| p.start);
+                mutations
+                    .iter()
+                    .filter_map(|mutation| {
+                        let index = mutation.index.checked_sub(offset.unwrap_or_default())?;
+                        (peptidoform.sequence().get(index)?.aminoacid.aminoacid() == mutation.from)
+                            .then(|| {
+                                (
+                                    substitute(peptidoform, index, mutation.to),
+                                    AppliedTransformation::Mutation(*mutation),
+                                )
+                            })
+                    })
+                    .collect()
+            }
+            #[cfg(feature = "align")]
+            Self::Substitutions { matrix, min_score } => {
+                let matrix = matrix.matrix();
+                peptidoform
+                    .sequence()
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, element)| {
+                        let from = element.aminoacid.aminoacid();
+                        AminoAcid::CANONICAL_AMINO_ACIDS
+                            .iter()
+                            .filter(move |to| {
+                                **to != from && matrix[from as usize][**to as usize] >= *min_score
+                            })
+                            .map(move |to| {
+                                (
+                                    substitute(peptidoform, index, *to),
+                                    AppliedTransformation::Substitution(PointMutation {
+                                        index,
+                                        from,
+                                        to: *to,
+                                    }),
+                                )
+                            })
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Substitute the amino acid at the given index, all modifications on this residue that are
+/// not possible on the new amino acid are removed
+fn substitute(
+    peptidoform: &Peptidoform<SemiAmbiguous>,
+    index: usize,
+    to: AminoAcid,
+) -> Peptidoform<SemiAmbiguous> {
+    let mut result = peptidoform.clone();
+    let original = &peptidoform.sequence()[index];
+    let mut element = SequenceElement::new(CheckedAminoAcid::new(to), original.ambiguous);
+    element.confidence = original.confidence;
+    element.modifications = original
+        .modifications
+        .iter()
+        .filter(|m| {
+            m.is_possible_in_protein(
+                &element,
+                SequencePosition::Index(index),
+                peptidoform.get_protein_position(),
+            )
+            .any_possible()
+        })
+        .cloned()
+        .collect();
+    result.sequence_mut()[index] = element;
+    result
+}
+
+impl std::fmt::Display for PointMutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.from.char(),
+            self.index + 1,
+            self.to.char()
+        )
+    }
+}
+
+impl FromStr for PointMutation {
+    type Err = CustomError;
+    /// Parse a point mutation, eg `K9R`, the position is 1 based. The HGVS protein notation with
+    /// three letter codes is also supported, eg `p.Lys9Arg` or `p.(Lys9Arg)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            CustomError::error(
+                "Invalid point mutation",
+                "A point mutation should be the original amino acid, the 1 based position, and the new amino acid, eg 'K9R' or 'p.Lys9Arg'",
+                Context::full_line(0, s),
+            )
+        };
+        let trimmed = s.trim();
+        if let Some(hgvs) = trimmed.strip_prefix("p.") {
+            let hgvs = hgvs
+                .strip_prefix('(')
+                .and_then(|h| h.strip_suffix(')'))
+                .unwrap_or(hgvs);
+            let code = |code: Option<&str>| {
+                code.and_then(|code| {
+                    AminoAcid::CANONICAL_AMINO_ACIDS
+                        .iter()
+                        .chain(&[AminoAcid::Selenocysteine, AminoAcid::Pyrrolysine])
+                        .find(|aa| aa.code().eq_ignore_ascii_case(code))
+                        .copied()
+                })
+            };
+            if let (Some(from), Some(position), Some(to)) = (
+                code(hgvs.get(..3)),
+                hgvs.get(3..hgvs.len().saturating_sub(3)),
+                code(hgvs.get(hgvs.len().saturating_sub(3)..)),
+            ) {
+                let position = position.parse::<usize>().map_err(|_| invalid())?;
+                return Ok(Self {
+                    index: position.checked_sub(1).ok_or_else(invalid)?,
+                    from,
+                    to,
+                });
+            }
+            return hgvs.parse().map_err(|_| invalid());
+        }
+        let mut chars = trimmed.chars();
+        let from = chars.next().ok_or_else(invalid)?;
+        let to = chars.next_back().ok_or_else(invalid)?;
+        let position = chars.as_str().parse::<usize>().map_err(|_| invalid())?;
+        Ok(Self {
+            index: position.checked_sub(1).ok_or_else(invalid)?,
+            from: AminoAcid::try_from(from).map_err(|()| invalid())?,
+            to: AminoAcid::try_from(to).map_err(|()| invalid())?,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::Protease;
+
+    fn peptide(sequence: &str) -> Peptidoform<SemiAmbiguous> {
+        Peptidoform::pro_forma(sequence, None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap()
+    }
+
+    #[test]
+    fn point_mutation() {
+        let mutation: PointMutation = "K9R".parse().unwrap();
+        assert_eq!(
+            mutation,
+            PointMutation {
+                index: 8,
+                from: AminoAcid::Lysine,
+                to: AminoAcid::Arginine
+            }
+        );
+        assert_eq!(mutation.to_string(), "K9R");
+        assert!("K0R".parse::<PointMutation>().is_err());
+        assert!("KR".parse::<PointMutation>().is_err());
+        assert!("K9".parse::<PointMutation>().is_err());
+        assert_eq!("p.Lys9Arg".parse::<PointMutation>().unwrap(), mutation);
+        assert_eq!("p.(Lys9Arg)".parse::<PointMutation>().unwrap(), mutation);
+        assert_eq!("p.K9R".parse::<PointMutation>().unwrap(), mutation);
+        assert!("p.Lys9Ter".parse::<PointMutation>().is_err());
+        assert!("p.Lys9".parse::<PointMutation>().is_err());
+    }
+
+    #[test]
+    fn mutations_in_digested_peptides() {
+        let protein = peptide("MPEPTIDEKAMPEKTTK");
+        let trypsin: Protease = "trypsin".parse().unwrap();
+        let mutations = SequenceTransformation::Mutations(vec![
+            "E3Q".parse().unwrap(),
+            "M11L".parse().unwrap(),
+            "P12A".parse().unwrap(),
+            "T15A".parse().unwrap(),
+        ]);
+        let variants = protein
+            .digest(&trypsin, 0)
+            .iter()
+            .map(|peptide| {
+                mutations
+                    .apply(peptide)
+                    .into_iter()
+                    .map(|(p, _)| p.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variants,
+            [vec!["MPQPTIDEK"], vec!["ALPEK", "AMAEK"], vec!["ATK"]]
+        );
+    }
+
+    #[test]
+    fn pipeline() {
+        let pipeline = TransformationPipeline::default();
+        assert_eq!(pipeline.apply(&peptide("PEPTIDE")).len(), 1);
+        let pipeline = pipeline
+            .then(SequenceTransformation::Mutations(vec![
+                "P1A".parse().unwrap(),
+                "T4S".parse().unwrap(),
+                "I5L".parse().unwrap(),
+            ]))
+            .then(SequenceTransformation::Reverse);
+        let variants = pipeline.apply(&peptide("PEPTIDE"));
+        assert_eq!(variants.len(), 3);
+        assert_eq!(variants[1].peptidoform.to_string(), "EDISPEP");
+        assert_eq!(
+            variants[1].transformations,
+            [
+                AppliedTransformation::Mutation("T4S".parse().unwrap()),
+                AppliedTransformation::Reversed
+            ]
+        );
+    }
+
+    #[test]
+    fn modifications_on_substituted_residues() {
+        let variants =
+            SequenceTransformation::Mutations(vec!["S2A".parse().unwrap(), "C4A".parse().unwrap()])
+                .apply(&peptide("AS[Phospho]AC[Carbamidomethyl]K"));
+        assert_eq!(variants[0].0.to_string(), "AAAC[U:Carbamidomethyl]K");
+        assert_eq!(variants[1].0.to_string(), "AS[U:Phospho]AAK");
+        let variants = SequenceTransformation::Mutations(vec!["C4S".parse().unwrap()])
+            .apply(&peptide("AMAC[+57.021]K"));
+        assert_eq!(variants[0].0.to_string(), "AMAS[+57.021]K");
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn shuffle() {
+        let original = peptide("PEPTIDEKAMPEKR");
+        let shuffle = SequenceTransformation::Shuffle {
+            fixed: vec![AminoAcid::Lysine, AminoAcid::Arginine],
+            seed: 42,
+        };
+        let shuffled = shuffle.apply(&original)[0].0.clone();
+        assert_eq!(shuffle.apply(&original)[0].0, shuffled);
+        assert_ne!(shuffled, original);
+        for (a, b) in original.sequence().iter().zip(shuffled.sequence()) {
+            if matches!(
+                a.aminoacid.aminoacid(),
+                AminoAcid::Lysine | AminoAcid::Arginine
+            ) {
+                assert_eq!(a, b);
+            }
+        }
+        let mut a = original
+            .sequence()
+            .iter()
+            .map(|s| s.aminoacid.aminoacid())
+            .collect::<Vec<_>>();
+        let mut b = shuffled
+            .sequence()
+            .iter()
+            .map(|s| s.aminoacid.aminoacid())
+            .collect::<Vec<_>>();
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "align")]
+    #[test]
+    fn substitutions() {
+        let variants = SequenceTransformation::Substitutions {
+            matrix: crate::align::ScoringMatrix::Blosum62,
+            min_score: 2,
+        }
+        .apply(&peptide("IK"));
+        let variants = variants
+            .iter()
+            .map(|(p, _)| p.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(variants, ["LK", "VK", "IR"]);
+    }
+}
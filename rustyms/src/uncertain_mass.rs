@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use crate::system::{da, Mass, Ratio};
+
+/// A mass with its (standard) uncertainty, see [`crate::MolecularFormula::mass_with_uncertainty`].
+/// Adding or subtracting uncertain masses assumes the uncertainties are independent, so these
+/// are combined in quadrature.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UncertainMass {
+    /// The mass
+    pub value: Mass,
+    /// The uncertainty of the mass
+    pub uncertainty: Mass,
+}
+
+impl UncertainMass {
+    /// Create a new mass with the given uncertainty
+    pub fn new(value: Mass, uncertainty: Mass) -> Self {
+        Self {
+            value,
+            uncertainty: uncertainty.abs(),
+        }
+    }
+
+    /// Create a mass as stated with a limited number of decimals (in dalton), for example the mass
+    /// of a mass only modification. The uncertainty is half of the last stated decimal, so
+    /// `15.995` results in an uncertainty of 0.0005 Da.
+    pub fn from_stated(value: f64) -> Self {
+        Self::new(da(value), da(stated_precision(value)))
+    }
+
+    /// The relative uncertainty
+    pub fn relative_uncertainty(&self) -> Ratio {
+        if self.value.value == 0.0 {
+            Ratio::new::<crate::system::ratio::fraction>(0.0)
+        } else {
+            (self.uncertainty / self.value).abs()
+        }
+    }
+}
+
+impl std::ops::Add for UncertainMass {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value + rhs.value,
+            da(self.uncertainty.value.hypot(rhs.uncertainty.value)),
+        )
+    }
+}
+
+impl std::ops::Sub for UncertainMass {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.value - rhs.value,
+            da(self.uncertainty.value.hypot(rhs.uncertainty.value)),
+        )
+    }
+}
+
+impl std::ops::Mul<f64> for UncertainMass {
+    type Output = Self;
+    /// Scale the mass, the uncertainty is scaled as well (fully correlated)
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self::new(self.value * rhs, self.uncertainty * rhs)
+    }
+}
+
+impl std::fmt::Display for UncertainMass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ± {} Da", self.value.value, self.uncertainty.value)
+    }
+}
+
+/// The precision a number is stated in: half of the last decimal that is needed to represent the
+/// number. Any floating point noise (from adding numbers) is ignored, and the precision is never
+/// given as more than 10 decimals. Note that trailing zeros cannot be detected, so the sum of
+/// multiple stated numbers can be given a lower precision than the original numbers.
+pub fn stated_precision(value: f64) -> f64 {
+    if value == 0.0 {
+        return 0.0;
+    }
+    let decimals = (0..10)
+        .find(|decimals| {
+            let factor = 10.0_f64.powi(*decimals);
+            ((value * factor).round() / factor - value).abs() <= value.abs() * 1e-12
+        })
+        .unwrap_or(10);
+    0.5 * 10.0_f64.powi(-decimals)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        system::{e, usize::Charge},
+        MassMode, Model, MolecularFormula, Peptidoform,
+    };
+
+    #[test]
+    fn precision() {
+        assert!((stated_precision(15.995) - 0.0005).abs() < 1e-12);
+        assert!((stated_precision(15.995 + 0.984) - 0.0005).abs() < 1e-12);
+        assert!((stated_precision(42.0) - 0.5).abs() < 1e-12);
+        assert!((stated_precision(-0.98) - 0.005).abs() < 1e-12);
+        assert!(stated_precision(0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn propagation() {
+        let water = molecular_formula!(H 2 O 1).mass_with_uncertainty(MassMode::Monoisotopic);
+        assert!((water.value.value - 18.010_565).abs() < 1e-6);
+        // 2 × 1.4e-11 (H, correlated) and 3.2e-10 (O) combined in quadrature
+        assert!(
+            (water.uncertainty.value - 3.212e-10).abs() < 1e-13,
+            "{water}"
+        );
+        // Natural variation dominates the average weight
+        let water = molecular_formula!(H 2 O 1).mass_with_uncertainty(MassMode::Average);
+        assert!(water.uncertainty.value > 1e-4);
+
+        let mass_only = MolecularFormula::with_additional_mass(15.995);
+        assert!((mass_only.mass_uncertainty(MassMode::Monoisotopic).value - 0.0005).abs() < 1e-12);
+
+        let sum = water + UncertainMass::from_stated(1.0);
+        assert!((sum.uncertainty.value - water.uncertainty.value.hypot(0.5)).abs() < 1e-12);
+        assert!(sum.relative_uncertainty().value > 0.0);
+
+        let peptide = Peptidoform::pro_forma("PEP[+15.995]TIDE", None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(2), &Model::all());
+        for fragment in &fragments {
+            let uncertainty = fragment.mz_uncertainty(MassMode::Monoisotopic).unwrap();
+            assert!(uncertainty.value > 0.0);
+            // The precursor side chain loss has 31.99 (2 × 15.995) so two stated decimals, at charge 2
+            assert!(uncertainty.value <= 0.0025 + 1e-9);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! End to end pipelines (read PSMs, find their spectra, annotate, score, and export) on the small
+//! inputs in `tests/pipelines`, compared to the golden outputs in `tests/pipelines/golden`. These
+//! only use the public API, so refactors that change the output of any step are caught here. If a
+//! change in the output is intended, run the tests with the `RUSTYMS_BLESS` environment variable
+//! set to overwrite the golden files and review the changes in the diff.
+#![cfg(feature = "identification")]
+#![allow(clippy::missing_panics_doc)]
+
+use std::{fmt::Write, path::Path};
+
+use rustyms::{
+    identification::{
+        peptide_report, write_peptide_report, GenericFormat, IdentifiedPeptide, PeptideReportFormat,
+    },
+    rawfile::mgf,
+    spectrum::{write_dia_library, DiaLibraryFormat, DiaLibraryOptions, Score},
+    system::{e, usize::Charge},
+    AnnotatableSpectrum, AnnotatedSpectrum, MassMode, Model,
+};
+
+/// The environment variable to set to overwrite the golden files
+const BLESS_VARIABLE: &str = "RUSTYMS_BLESS";
+
+#[test]
+fn psms_to_reports_and_library() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/pipelines");
+    let format = GenericFormat {
+        separator: ',',
+        charge: Some("z".to_string()),
+        scan: Some("Scan".to_string()),
+        score: Some("Score".to_string()),
+        score_scale: Some(100.0),
+        raw_file: Some("File".to_string()),
+        ..GenericFormat::new("Sequence")
+    };
+    let psms: Vec<IdentifiedPeptide> = format
+        .parse_file(directory.join("psms.csv"), None)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let spectra = mgf::open(directory.join("spectra.mgf")).unwrap();
+    let model = Model::cid_hcd();
+
+    let mut scores = String::from("id\tpeptide\tspectrum\tfragments\tpeaks\tintensity\n");
+    let mut annotated: Vec<AnnotatedSpectrum> = Vec::new();
+    for psm in &psms {
+        let peptide = psm.peptide().unwrap().compound_peptidoform().into_owned();
+        let found = psm.find_spectra(&spectra);
+        if found.is_empty() {
+            writeln!(scores, "{}\t{peptide}\tnot found\t\t\t", psm.id()).unwrap();
+        }
+        for spectrum in found {
+            let fragments = peptide.generate_theoretical_fragments(
+                psm.charge().unwrap_or(Charge::new::<e>(1)),
+                &model,
+            );
+            let spectrum =
+                spectrum.annotate(peptide.clone(), &fragments, &model, MassMode::Monoisotopic);
+            let (combined, _) = spectrum.scores(&fragments, &model, MassMode::Monoisotopic);
+            let (Score::Position {
+                fragments,
+                peaks,
+                intensity,
+                ..
+            }
+            | Score::UniqueFormulas {
+                fragments,
+                peaks,
+                intensity,
+                ..
+            }) = combined.score;
+            writeln!(
+                scores,
+                "{}\t{peptide}\t{}\t{}/{}\t{}/{}\t{:.4}",
+                psm.id(),
+                spectrum.title,
+                fragments.found,
+                fragments.total,
+                peaks.found,
+                peaks.total,
+                intensity.fraction(),
+            )
+            .unwrap();
+            annotated.push(spectrum);
+        }
+    }
+    check_golden(&directory, "scores.tsv", scores.as_bytes());
+
+    let report = peptide_report(&psms);
+    for (format, name) in [
+        (PeptideReportFormat::Csv, "peptide_report.csv"),
+        (PeptideReportFormat::MzTab, "peptide_report.mztab"),
+    ] {
+        let mut output = Vec::new();
+        write_peptide_report(&report, format, None, &mut output).unwrap();
+        check_golden(&directory, name, &output);
+    }
+
+    let mut output = Vec::new();
+    write_dia_library(
+        annotated.iter().map(|spectrum| (spectrum, 0.0)),
+        DiaLibraryOptions::new(DiaLibraryFormat::DiaNn),
+        &mut output,
+    )
+    .unwrap();
+    check_golden(&directory, "library_diann.tsv", &output);
+}
+
+/// Compare the output to the golden file with the given name, or overwrite the golden file if the
+/// bless environment variable is set.
+fn check_golden(directory: &Path, name: &str, output: &[u8]) {
+    let path = directory.join("golden").join(name);
+    if std::env::var_os(BLESS_VARIABLE).is_some() {
+        std::fs::write(&path, output).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|error| {
+        panic!(
+            "Could not read golden file '{}' (run with {BLESS_VARIABLE} set to create it): {error}",
+            path.display()
+        )
+    });
+    let output = String::from_utf8_lossy(output);
+    for (index, (expected, found)) in expected.lines().zip(output.lines()).enumerate() {
+        assert_eq!(
+            found,
+            expected,
+            "Line {} of '{name}' differs from the golden file (run with {BLESS_VARIABLE} set to update it)",
+            index + 1
+        );
+    }
+    assert_eq!(
+        output.lines().count(),
+        expected.lines().count(),
+        "The number of lines of '{name}' differs from the golden file (run with {BLESS_VARIABLE} set to update it)"
+    );
+}